@@ -0,0 +1,17 @@
+// Generate the gRPC service from `proto/` only with the `grpc` feature, by vendored protoc so
+// that no protoc needs to be installed.
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/japanese_mahjong_theory.proto");
+        std::env::set_var(
+            "PROTOC",
+            protoc_bin_vendored::protoc_bin_path().expect("Vendored protoc not found."),
+        );
+        // Clients are generated by users in their own languages.
+        tonic_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/japanese_mahjong_theory.proto"], &["proto"])
+            .expect("Failed to compile proto.");
+    }
+}
@@ -0,0 +1,135 @@
+//! User configuration in TOML, giving defaults of command line arguments and rules.
+//!
+//! Keys are the same as command line arguments, which override the configuration.
+//!
+//! ```toml
+//! format = "table"
+//! player = 3
+//! color = "always"
+//! lang = "ja"
+//! mistake_threshold = 0.2
+//! order = "acceptance"
+//! deal_in_table = "/home/user/rates.txt"
+//!
+//! [rules]
+//! aotenjou = false
+//! kuikae = true
+//! kuitan_nashi = false
+//! ```
+//!
+//! `mistake_threshold`, from 0 to 1, is the ratio of acceptance lost from which a discard is
+//! a mistake in review. `order` is the order of conditions in analysis, see
+//! `ConditionOrder`. `deal_in_table` is a file of deal-in rates replacing the default,
+//! see `DealInTable::parse()`.
+
+use jmt_core::game::Rules;
+use std::{env, fs, path::PathBuf};
+
+/// Defaults loaded from the configuration file.
+///
+/// # Member
+/// * format: Output format, `standard`, `table`, `json` or `csv`.
+/// * player: Number of players, 3 or 4.
+/// * color: When to color output, `auto`, `always` or `never`.
+/// * lang: Language of messages, `en`, `ja` or `zh`.
+/// * mistake_threshold: Threshold of mistakes in review.
+/// * order: Order of conditions in analysis, `speed`, `acceptance`, `hai` or `safety`.
+/// * deal_in_table: Path of the table of deal-in rates.
+/// * rules: Optional rules, which are only settable here.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub format: Option<String>,
+    pub player: Option<String>,
+    pub color: Option<String>,
+    pub lang: Option<String>,
+    pub mistake_threshold: Option<f64>,
+    pub order: Option<String>,
+    pub deal_in_table: Option<String>,
+    pub rules: Rules,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/jmt/config.toml`, or `~/.config/jmt/config.toml` if the variable is
+    /// not set.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = match env::var_os("XDG_CONFIG_HOME") {
+            Some(config_home) if !config_home.is_empty() => PathBuf::from(config_home),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_home.join("jmt").join("config.toml"))
+    }
+
+    /// Load the configuration file. If `path` is not given, load the default path if it
+    /// exists, otherwise use defaults.
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let string = fs::read_to_string(&path)
+            .map_err(|error| format!("Failed to read {}: {}.", path.display(), error))?;
+        Self::parse(&string).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    /// Parse the configuration from TOML. Unknown keys are errors, so that typos are not
+    /// ignored silently.
+    pub fn parse(string: &str) -> Result<Self, String> {
+        let table = match string.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => return Err("Configuration must be a table.".to_string()),
+            Err(error) => return Err(format!("Invalid TOML: {}.", error)),
+        };
+
+        let mut config = Self::default();
+        for (key, value) in &table {
+            match &**key {
+                "format" | "color" | "lang" | "order" | "deal_in_table" => {
+                    let string = value
+                        .as_str()
+                        .ok_or(format!("'{}' must be a string.", key))?
+                        .to_string();
+                    match &**key {
+                        "format" => config.format = Some(string),
+                        "color" => config.color = Some(string),
+                        "order" => config.order = Some(string),
+                        "deal_in_table" => config.deal_in_table = Some(string),
+                        _ => config.lang = Some(string),
+                    }
+                }
+                "player" => {
+                    let player = value
+                        .as_integer()
+                        .ok_or("'player' must be an integer.".to_string())?;
+                    config.player = Some(player.to_string());
+                }
+                "mistake_threshold" => match value.as_float() {
+                    Some(threshold) if (0.0..=1.0).contains(&threshold) => {
+                        config.mistake_threshold = Some(threshold)
+                    }
+                    _ => return Err("'mistake_threshold' must be a float from 0 to 1.".to_string()),
+                },
+                "rules" => {
+                    let rules = value
+                        .as_table()
+                        .ok_or("'rules' must be a table.".to_string())?;
+                    for (key, value) in rules {
+                        let enabled = value
+                            .as_bool()
+                            .ok_or(format!("'rules.{}' must be a boolean.", key))?;
+                        match &**key {
+                            "aotenjou" => config.rules.aotenjou = enabled,
+                            "kuikae" => config.rules.kuikae = enabled,
+                            "kuitan_nashi" => config.rules.kuitan_nashi = enabled,
+                            _ => return Err(format!("Unknown rule '{}'.", key)),
+                        }
+                    }
+                }
+                _ => return Err(format!("Unknown key '{}'.", key)),
+            }
+        }
+        Ok(config)
+    }
+}
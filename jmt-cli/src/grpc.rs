@@ -0,0 +1,183 @@
+//! gRPC service of `proto/japanese_mahjong_theory.proto`, for backends not written in Rust.
+
+use jmt_core::game::{
+    self, analyzer,
+    simulate::{RolloutCache, Simulation},
+    Cancellation, Haiyama, MachiCondition, PlayerNumber, Rules, Tehai,
+};
+use std::{collections::BTreeMap, time::Duration};
+use tonic::{transport::Server, Request, Response, Status};
+
+/// Messages and the service trait generated from the proto.
+pub mod proto {
+    tonic::include_proto!("japanese_mahjong_theory");
+}
+
+use proto::analyzer_server::{Analyzer, AnalyzerServer};
+
+/// Most iterations of a simulation, larger requests are clamped to it.
+const MAX_ITERATIONS: u32 = 100_000;
+/// Most draws in an iteration, more than any player can draw in a hand.
+const MAX_DRAWS: u32 = 30;
+/// Time budget of a simulation, which returns iterations completed in it.
+const SIMULATION_BUDGET: Duration = Duration::from_secs(10);
+
+/// Cancel the token when dropped, so that a simulation stops once its request is dropped,
+/// e.g. by the client going away.
+struct CancelOnDrop(Cancellation);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Implementation of the service, each request analyzes tehai alone.
+///
+/// # Member
+/// * rollout_cache: Steps of rollouts shared by all simulations requested.
+#[derive(Clone, Debug, Default)]
+pub struct AnalyzerService {
+    rollout_cache: RolloutCache,
+}
+
+/// Serve at `address` such as `127.0.0.1:50051` until the server fails.
+pub fn serve(address: &str) -> Result<(), String> {
+    let address = address
+        .parse()
+        .map_err(|_| format!("Invalid address: {}.", address))?;
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|error| format!("Failed to start runtime: {}.", error))?;
+    runtime
+        .block_on(
+            Server::builder()
+                .add_service(AnalyzerServer::new(AnalyzerService::default()))
+                .serve(address),
+        )
+        .map_err(|error| format!("Failed to serve: {}.", error))
+}
+
+#[tonic::async_trait]
+impl Analyzer for AnalyzerService {
+    async fn analyze(
+        &self,
+        request: Request<proto::AnalyzeRequest>,
+    ) -> Result<Response<proto::AnalyzeResponse>, Status> {
+        let request = request.into_inner();
+        let player_number = player_number(request.player_number)?;
+        let tehai = Tehai::new(request.tehai, player_number).map_err(Status::invalid_argument)?;
+        // Analysis is CPU bound, so keep it off the threads of the runtime.
+        let report =
+            tokio::task::spawn_blocking(move || analyzer::report_tehai(&tehai, player_number))
+                .await
+                .map_err(|error| Status::internal(error.to_string()))?
+                .map_err(Status::invalid_argument)?;
+
+        Ok(Response::new(proto::AnalyzeResponse {
+            shanten: report.shanten,
+            conditions: report.conditions.iter().map(condition).collect(),
+            report_json: report.to_json().to_string(),
+        }))
+    }
+
+    async fn score(
+        &self,
+        request: Request<proto::ScoreRequest>,
+    ) -> Result<Response<proto::ScoreResponse>, Status> {
+        let request = request.into_inner();
+        let rules = Rules {
+            aotenjou: request.aotenjou,
+            ..Rules::default()
+        };
+        let score = game::Score::new(request.han, request.fu, request.oya, &rules)
+            .map_err(Status::invalid_argument)?;
+        Ok(Response::new(proto::ScoreResponse {
+            base: score.base,
+            ron: score.ron,
+            tsumo_ko: score.tsumo_ko,
+            tsumo_oya: score.tsumo_oya,
+        }))
+    }
+
+    async fn simulate(
+        &self,
+        request: Request<proto::SimulateRequest>,
+    ) -> Result<Response<proto::SimulateResponse>, Status> {
+        let request = request.into_inner();
+        let player_number = player_number(request.player_number)?;
+        let mut tehai =
+            Tehai::new(request.tehai, player_number).map_err(Status::invalid_argument)?;
+        // Hai on hand, including sutehai, can not be drawn.
+        let mut haiyama = Haiyama::new(player_number);
+        haiyama
+            .discard_with_vec(&tehai.juntehai, true)
+            .map_err(Status::invalid_argument)?;
+        if !request.sutehai.is_empty() {
+            let hai_vec = game::Hai::from_string_unordered(&request.sutehai, player_number)
+                .map_err(Status::invalid_argument)?;
+            if hai_vec.len() != 1 {
+                return Err(Status::invalid_argument("Can only discard one hai."));
+            }
+            tehai
+                .discard(&hai_vec[0])
+                .map_err(Status::invalid_argument)?;
+        }
+
+        let mut simulation = Simulation::default();
+        if request.iterations != 0 {
+            simulation.iterations = request.iterations.min(MAX_ITERATIONS) as usize;
+        }
+        if request.draws != 0 {
+            simulation.draws = request.draws.min(MAX_DRAWS) as usize;
+        }
+        simulation.seed = request.seed;
+        let cache = self.rollout_cache.clone();
+        let guard = CancelOnDrop(Cancellation::with_budget(SIMULATION_BUDGET));
+        let cancellation = guard.0.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            simulation.run_with_cache(&tehai, &haiyama, &cache, None, Some(&cancellation))
+        })
+        .await
+        .map_err(|error| Status::internal(error.to_string()))?
+        .map_err(Status::invalid_argument)?;
+
+        Ok(Response::new(proto::SimulateResponse {
+            iterations: result.iterations as u32,
+            tenpai: result.tenpai as u32,
+            hora: result.hora as u32,
+            tenpai_rate: result.tenpai_rate(),
+            hora_rate: result.hora_rate(),
+            average_hora_draws: result.average_hora_draws(),
+        }))
+    }
+}
+
+fn player_number(number: u32) -> Result<PlayerNumber, Status> {
+    match number {
+        0 | 4 => Ok(PlayerNumber::Four),
+        3 => Ok(PlayerNumber::Three),
+        _ => Err(Status::invalid_argument(format!(
+            "Not support {}-players mode.",
+            number
+        ))),
+    }
+}
+
+fn condition(condition: &MachiCondition) -> proto::Condition {
+    proto::Condition {
+        sutehai: condition.sutehai.to_string(),
+        machihai: machihai(&condition.machihai),
+        nokori: condition.nokori() as u32,
+        kairyouhai: machihai(&condition.kairyouhai),
+    }
+}
+
+fn machihai(hai_map: &BTreeMap<game::Hai, u8>) -> Vec<proto::Machihai> {
+    hai_map
+        .iter()
+        .map(|(hai, nokori)| proto::Machihai {
+            hai: hai.to_string(),
+            nokori: *nokori as u32,
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+//! ANSI escape sequences for colored terminal output.
+
+const MANZU: &str = "\x1b[31m";
+const PINZU: &str = "\x1b[34m";
+const SOUZU: &str = "\x1b[32m";
+const JIHAI: &str = "\x1b[1m";
+const RESET_COLOR: &str = "\x1b[39m";
+const RESET_BOLD: &str = "\x1b[22m";
+const HIGHLIGHT: &str = "\x1b[7m";
+const RESET_HIGHLIGHT: &str = "\x1b[27m";
+
+/// Color hai in text by its type: manzu red, pinzu blue, souzu green and jihai bold.
+///
+/// Hai are recognized as numbers followed by a type letter, such as `5m` or `123p`.
+pub fn colorize(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut colored = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        // Keep escape sequences already in text.
+        if chars[index] == '\x1b' {
+            while index < chars.len() && !chars[index].is_ascii_alphabetic() {
+                colored.push(chars[index]);
+                index += 1;
+            }
+            if index < chars.len() {
+                colored.push(chars[index]);
+                index += 1;
+            }
+            continue;
+        }
+
+        let start = index;
+        while index < chars.len() && chars[index].is_ascii_digit() {
+            index += 1;
+        }
+        if index == start {
+            colored.push(chars[index]);
+            index += 1;
+            continue;
+        }
+
+        let numbers: String = chars[start..index].iter().collect();
+        let style = match chars.get(index) {
+            Some('m') => Some((MANZU, RESET_COLOR)),
+            Some('p') => Some((PINZU, RESET_COLOR)),
+            Some('s') => Some((SOUZU, RESET_COLOR)),
+            Some('z') => Some((JIHAI, RESET_BOLD)),
+            _ => None,
+        };
+        match style {
+            Some((begin, end)) => {
+                colored += &format!("{}{}{}{}", begin, numbers, chars[index], end);
+                index += 1;
+            }
+            None => colored += &numbers,
+        }
+    }
+    colored
+}
+
+/// Highlight text by reversing colors, used for the recommended sutehai.
+pub fn highlight(text: &str) -> String {
+    format!("{}{}{}", HIGHLIGHT, text, RESET_HIGHLIGHT)
+}
@@ -0,0 +1,1017 @@
+use super::OutputFormat;
+use jmt_core::{game, i18n};
+
+pub enum Command {
+    Interactive,
+    Theory,
+    Noninteractive,
+    OutputFormat(OutputFormat),
+    Color(bool),
+    Locale(i18n::Locale),
+    PlayerNumber(game::PlayerNumber),
+    TehaiInput(game::Tehai),
+    Scene(Box<game::GameManager>),
+    GameOperation(game::Operation),
+    Back {
+        haiyama_sensitive: bool,
+    },
+    State,
+    Display {
+        explain: bool,
+    },
+    AllSutehai,
+    History,
+    Kifu,
+    LoadKifu(String),
+    Annotate(game::Annotation),
+    React {
+        seat: game::Seat,
+        hai: game::Hai,
+    },
+    Defense,
+    Wall,
+    Validate,
+    MachiSearch(game::Hai),
+    Riichi,
+    OpponentRiichi {
+        seat: game::Seat,
+        sutehai: game::Hai,
+    },
+    OpponentDiscard {
+        seat: game::Seat,
+        hai_vec: Vec<game::Hai>,
+    },
+    SafeAgainst(game::Seat),
+    DealInTable(Option<String>),
+    OpponentCall {
+        seat: game::Seat,
+        mentsu: game::Mentsu,
+    },
+    OpponentValue,
+    Ledger(LedgerOperation),
+    Ryuukyoku(Vec<game::Kaze>),
+    NextHand,
+    Kaze(game::Kaze, game::Kaze),
+    Dora(Vec<game::Hai>),
+    Fork,
+    Branch(Option<usize>),
+    Rate,
+    Review(Option<usize>),
+    Agreement(Vec<String>),
+    MistakeThreshold(f64),
+    Simulate(game::Hai),
+    Compare(game::Hai, game::Hai),
+    Why(game::Hai, game::Hai),
+    Sync(game::Tehai),
+    Agari,
+    Search(usize),
+    Hanchan(u64),
+    Enumerate(usize, i32),
+    TenhouUrl,
+    Help(Option<&'static CommandSpec>),
+    Exit,
+}
+
+/// Group of commands in help.
+///
+/// # Member
+/// * Common: Available at both interactive mode and non-interactive mode.
+/// * Interactive: Only available at interactive mode.
+/// * IgnoringHaiyama: Variants of interactive commands ignoring haiyama errors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandGroup {
+    Common,
+    Interactive,
+    IgnoringHaiyama,
+}
+
+/// Definition of a command, from which help is generated.
+///
+/// # Member
+/// * names: Names and aliases, or the prefix of an operation like `+`. Empty if the input
+/// is recognized by its arguments alone, such as tehai.
+/// * arguments: Syntax of arguments, empty if none.
+/// * description: What the command does.
+/// * examples: Valid inputs of the command.
+/// * group: Group in help.
+#[derive(Copy, Clone, Debug)]
+pub struct CommandSpec {
+    pub names: &'static [&'static str],
+    pub arguments: &'static str,
+    pub description: &'static str,
+    pub examples: &'static [&'static str],
+    pub group: CommandGroup,
+}
+
+/// All commands in the order of help. Add a definition here when adding a command, so it
+/// is documented in help.
+pub const COMMAND_SPECS: &[CommandSpec] = &[
+    CommandSpec {
+        names: &["i", "interactive"],
+        arguments: "",
+        description: "Interactive mode. Reinitialize if already at interactive mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["ni", "noninteractive"],
+        arguments: "",
+        description: "Exit interactive mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["th", "theory"],
+        arguments: "",
+        description: "Interactive mode for tehai alone, where hai seen elsewhere are not \
+                      tracked and only tehai is taken out of haiyama.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["3pl", "3-player"],
+        arguments: "",
+        description: "3 players mahjong. Reinitialize if interactive mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["4pl", "4-player"],
+        arguments: "",
+        description: "4 players mahjong. Reinitialize if interactive mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["std", "standard"],
+        arguments: "",
+        description: "Standard output mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["table"],
+        arguments: "",
+        description: "Like standard output mode, but print analysis results as an aligned table.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["color", "nocolor"],
+        arguments: "",
+        description: "Color hai by type and highlight the recommended sutehai or not. \
+            Default is color if output is a terminal.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["json"],
+        arguments: "",
+        description: "JSON output mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["csv"],
+        arguments: "",
+        description: "CSV output mode, printing analysis results as CSV with one row for each \
+            sutehai. Other results are printed as standard output mode.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["lang"],
+        arguments: "<en|ja|zh>",
+        description: "Set language of messages. Default is \"en\", whose results keep Japanese \
+            terms.",
+        examples: &["lang ja"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["q", "quit", "exit"],
+        arguments: "",
+        description: "Exit program.",
+        examples: &[],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["h", "help"],
+        arguments: "[command]",
+        description: "Print command list, or syntax and examples of a command.",
+        examples: &["help", "help wind"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &[],
+        arguments: "<tehai>",
+        description: "Input tehai of 13 or 14 hai, and analyze it if full with hai. Fuuro is \
+            written in brackets.",
+        examples: &["123m456p789s11z", "45p8s144m[111z]25m44p3m"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &[],
+        arguments: "<tenhou url>",
+        description: "Input tehai from a tenhou hand analysis URL.",
+        examples: &["https://tenhou.net/2/?q=123m456p789s11z"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &[],
+        arguments: "<scene>",
+        description: "Start interactive mode at a situation of hand, dora indicators, winds, \
+            turn and visible hai, and analyze it if full with hai. Only hand is required.",
+        examples: &[
+            "hand=123m456p78s1155z37z; dora=3p; seat=S; round=E; turn=6; river=19m4z; seen=666z",
+        ],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["load"],
+        arguments: "<file>",
+        description: "Load kifu from a file and replay it as current game.",
+        examples: &["load game.txt"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["agreement"],
+        arguments: "<file...>",
+        description: "Review kifu files and print how often discards agree with the first \
+            choice and the first three choices of analysis, for each game and each player \
+            named by 'player <name>' in kifu.",
+        examples: &["agreement game1.txt game2.txt"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["+"],
+        arguments: "<hai>",
+        description: "Add a hai to tehai.",
+        examples: &["+4m"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["-"],
+        arguments: "<hai>",
+        description: "Discard a hai from tehai.",
+        examples: &["-1s"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["r"],
+        arguments: "<hai>",
+        description: "Discard a hai declaring riichi. After riichi, missing machihai in \
+            sutehai of others makes tehai furiten permanently.",
+        examples: &["r1s"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["*+"],
+        arguments: "<hai...>",
+        description: "Add some hai to haiyama. Limit is 4 for each type of hai.",
+        examples: &["*+1s777z"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["*-"],
+        arguments: "<hai...>",
+        description: "Discard some hai from haiyama. Note that you shouldn't use \"*-\" for \
+            nakihai.",
+        examples: &["*-1s777z"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["*d"],
+        arguments: "<hai>",
+        description: "Reveal a dora indicator such as kan-dora, which is discarded from haiyama \
+            and added to dora indicators. Asked for right after kan.",
+        examples: &["*d3p"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &[">"],
+        arguments: "<mentsu>[rinshanhai]",
+        description: "Naku. It means chii, pon or kan. Third hai will be regarded as nakihai \
+            if chii. The order of hai does not matter. \">4444p\" is daiminkan, \"+4p\" then \
+            \">4444p\" is kakan or ankan, and rinshanhai can be given after kan or added by \
+            \"+\" later.",
+        examples: &[">456m", ">555z", ">4444p5s"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["b", "back"],
+        arguments: "",
+        description: "Undo last operation.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["s", "state"],
+        arguments: "",
+        description: "Print current game state, including haiyama, types of sutehai, tehai.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["d", "display"],
+        arguments: "",
+        description: "Normally program will print tehai analysis result after operation if \
+            tehai full with hai. You can use this command print again.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["e", "explain"],
+        arguments: "",
+        description: "Like display, but also print which shapes in tehai accept each machihai.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["all"],
+        arguments: "",
+        description: "Like display, but rank every type of hai in tehai as sutehai, including \
+            those increasing shanten, grouped by shanten after discarding.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["log", "history"],
+        arguments: "",
+        description: "Print operation history.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["kifu"],
+        arguments: "",
+        description: "Print operation history as kifu, one operation each line in the \
+            notation of commands.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["react"],
+        arguments: "<kami|toimen|shimo> <hai>",
+        description: "Enumerate pass, chii, pon, kan and ron on sutehai of another player \
+            when lack one hai, and recommend the best.",
+        examples: &["react kami 5s"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["sute"],
+        arguments: "<kami|toimen|shimo> <hai>",
+        description: "Discard sutehai of another player from haiyama like \"*-\", recording \
+            who discarded it, so that it is genbutsu against the player.",
+        examples: &["sute shimo 1z", "sute toimen 19m"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["safe"],
+        arguments: "<kami|toimen|shimo>",
+        description: "List genbutsu against another player, which are sutehai of the player, \
+            and after riichi marked, hai discarded by anyone.",
+        examples: &["safe toimen"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["call"],
+        arguments: "<kami|toimen|shimo> <mentsu>",
+        description: "Discard fuuro of another player from haiyama like \"*-\", including \
+            the nakihai, recording it for estimating the hand of the player.",
+        examples: &["call toimen 555z", "call kami 345p"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["opp", "opponents"],
+        arguments: "",
+        description: "Estimate hands of other players from riichi, fuuro, dora discarded and \
+            the turn, with han, points of dealing in and the probability of tenpai.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["dealin"],
+        arguments: "[file]",
+        description: "Print the table of deal-in rates by category of sutehai against a \
+            riichi, or load one from a file in the same form, one '<category> <rate>' each \
+            line. Categories not given keep default rates.",
+        examples: &["dealin", "dealin rates.txt"],
+        group: CommandGroup::Common,
+    },
+    CommandSpec {
+        names: &["note"],
+        arguments: "<text|#tag>",
+        description: "Annotate the last operation with free text, or a tag of one word \
+            starting with '#'. Annotations are kept in kifu and shown by rate.",
+        examples: &["note #misclick", "note considering pon here"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["wall"],
+        arguments: "",
+        description: "Print remaining number of each type of hai in haiyama as a grid, a row \
+            for each suit.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["validate"],
+        arguments: "",
+        description: "Check whether tehai, kawa and hai seen by operations on haiyama are \
+            consistent with haiyama, and report each type of hai over- or under-represented.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["def", "defense"],
+        arguments: "",
+        description: "Classify each type of hai in tehai as no-chance or one-chance by unseen \
+            kabe hai, sorted from the safest.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["machi"],
+        arguments: "<hai>",
+        description: "Enumerate shapes waiting for a hai, which can be made from unseen hai, \
+            with the number of combinations.",
+        examples: &["machi 5p"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["riichi"],
+        arguments: "[<kami|toimen|shimo> <hai>]",
+        description: "Check whether riichi can be declared, and list sutehai keeping tenpai. \
+            With a seat, mark the player as riichi declared by the sutehai, then analysis \
+            shows safety of each sutehai against it.",
+        examples: &["riichi", "riichi toimen 5m"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["ledger"],
+        arguments: "[set <seat> <points> | honba <number> | \
+            ron <winner> <loser> <han> <fu> | tsumo <winner> <han> <fu>]",
+        description: "Print points of all seats, riichi sticks and honba, or set points and \
+            honba, or settle ron and tsumo. Seats are given by jikaze, 1z is oya.",
+        examples: &[
+            "ledger",
+            "ledger set 2z 30000",
+            "ledger honba 1",
+            "ledger ron 1z 2z 3 30",
+            "ledger tsumo 2z 2 40",
+        ],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["ryuukyoku"],
+        arguments: "[seat...]",
+        description: "End the hand by exhaustive draw and settle noten bappu. Tenpai of self \
+            is judged by tehai, where waiting only for hai all in tehai is noten, give seats \
+            of others in tenpai.",
+        examples: &["ryuukyoku 2z 3z"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["next"],
+        arguments: "",
+        description: "Start the next hand after ryuukyoku, keeping points, riichi sticks and \
+            honba. Jikaze moves on without renchan.",
+        examples: &["next"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["wind"],
+        arguments: "<bakaze> <jikaze>",
+        description: "Set wind of round and seat, which decide yakuhai. Discards breaking \
+            yakuhai toitsu are ranked lower when equal.",
+        examples: &["wind ton nan", "wind 1z 2z"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["dora"],
+        arguments: "[hai...]",
+        description: "Set dora indicators, or clear them without hai. If the best sutehai \
+            keeps less han of yakuhai and dora, it is compared with the one keeping the most \
+            han.",
+        examples: &["dora 1s"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["fork"],
+        arguments: "",
+        description: "Save a copy of current state as a new branch, to explore another line.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["branch"],
+        arguments: "[index]",
+        description: "List current state and all branches with their best sutehai, or switch \
+            to a branch, and current state is kept as the branch instead.",
+        examples: &["branch", "branch 0"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["url"],
+        arguments: "",
+        description: "Print tenhou hand analysis URL of current tehai.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["rate"],
+        arguments: "",
+        description: "Rate every discard in operation history by comparing it with the \
+            analysis at that moment.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["review"],
+        arguments: "[step]",
+        description: "Review operation history, such as a loaded kifu, turn by turn: \
+            re-analyze tehai at every discard and print the ranking next to the actual \
+            choice. With a step number from 1, print only that step. Discards losing \
+            acceptance by the threshold or more are marked as mistakes and counted for each \
+            hand; set the threshold in percent by 'review threshold', 20 by default.",
+        examples: &["review", "review 3", "review threshold 30"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["sync"],
+        arguments: "<tehai>",
+        description: "Bring tehai to the given one by the fewest draws, discards and calls, \
+            and print them, for following a client which only reports tehai. Initialize tehai \
+            if not yet.",
+        examples: &["sync 123m456p789s1125z"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["why"],
+        arguments: "<hai><hai>",
+        description: "Compare two sutehai by analysis, and print which is ranked first and \
+            what differs: shanten, acceptance, expected draws until tenpai, kairyouhai, \
+            yakuhai broken, tenpai without yaku and safety.",
+        examples: &["why 9s2m"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["agari"],
+        arguments: "",
+        description: "Print every machihai of tenpai tehai lacking one hai, with yaku, han, fu \
+            and points of the hand won by ron and by tsumo.",
+        examples: &[],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["sim"],
+        arguments: "<hai>[hai]",
+        description: "Simulate drawing after discarding a hai, and print the rate of tenpai \
+            and winning. Give two hai to compare them on identical draws.",
+        examples: &["sim 5m", "sim 8s5m"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["search"],
+        arguments: "[depth]",
+        description: "Search every discard by expectimax over draws from haiyama, and print \
+            the expected shanten after some draws, 2 by default and 3 at most. Slow for 3 \
+            draws from 2 or more shanten. With less than 3 draws left, solve them exactly \
+            instead, and print the rate of winning and tenpai for each discard, and whether to \
+            push, keep tenpai or fold.",
+        examples: &["search", "search 3"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["hanchan"],
+        arguments: "[seed]",
+        description: "Simulate a whole hanchan of greedy players, rotating oya with honba and \
+            renchan, and print each hand and final placements with uma and oka.",
+        examples: &["hanchan", "hanchan 7"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["enumerate"],
+        arguments: "<size> <shanten>",
+        description: "Count all hands with given size and shanten, as classes same after \
+            swapping suits or jihai, and print a few. Slow for more than 8 hai.",
+        examples: &["enumerate 5 0"],
+        group: CommandGroup::Interactive,
+    },
+    CommandSpec {
+        names: &["+!"],
+        arguments: "<hai>",
+        description: "Add a hai to tehai ignoring haiyama error.",
+        examples: &["+!4m"],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+    CommandSpec {
+        names: &["-!"],
+        arguments: "<hai>",
+        description: "Equal with -, no difference.",
+        examples: &["-!1s"],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+    CommandSpec {
+        names: &["*+!"],
+        arguments: "<hai...>",
+        description: "Add some hai to haiyama ignoring haiyama error.",
+        examples: &["*+!1s777z"],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+    CommandSpec {
+        names: &["*-!"],
+        arguments: "<hai...>",
+        description: "Discard some hai from haiyama ignoring haiyama error.",
+        examples: &["*-!1s777z"],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+    CommandSpec {
+        names: &["*d!"],
+        arguments: "<hai>",
+        description: "Reveal a dora indicator ignoring haiyama error.",
+        examples: &["*d!3p"],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+    CommandSpec {
+        names: &[">!"],
+        arguments: "<mentsu>[rinshanhai]",
+        description: "Naku ignoring haiyama error.",
+        examples: &[">!456m"],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+    CommandSpec {
+        names: &["b!", "back!"],
+        arguments: "",
+        description: "Undo operation ignoring haiyama error. Note if you use \"back\" for \
+            operations who ignored haiyama error, \"back\" will keep reporting haiyama errors.",
+        examples: &[],
+        group: CommandGroup::IgnoringHaiyama,
+    },
+];
+
+impl CommandSpec {
+    /// Find the definition of a command by one of its names.
+    pub fn find(name: &str) -> Option<&'static CommandSpec> {
+        COMMAND_SPECS.iter().find(|spec| spec.names.contains(&name))
+    }
+
+    /// Syntax of the command like `wind <bakaze> <jikaze>`.
+    pub fn usage(&self) -> String {
+        match (self.names.first(), self.arguments.is_empty()) {
+            (Some(name), true) => name.to_string(),
+            // Operations are written without space, like `+4m`.
+            (Some(name), false) if !name.starts_with(char::is_alphanumeric) => {
+                format!("{}{}", name, self.arguments)
+            }
+            (Some(name), false) => format!("{} {}", name, self.arguments),
+            (None, _) => self.arguments.to_string(),
+        }
+    }
+
+    /// Error of wrong arguments, showing the syntax.
+    pub fn usage_error(name: &str) -> String {
+        match CommandSpec::find(name) {
+            Some(spec) => format!("Usage: {}.", spec.usage()),
+            None => format!("Unknown command: {}.", name),
+        }
+    }
+
+    /// Help of all commands, grouped as common commands, commands for interactive mode and
+    /// those ignoring haiyama errors.
+    pub fn help() -> String {
+        let mut help_string = String::new();
+        for (group, title) in [
+            (CommandGroup::Common, "Common command:"),
+            (CommandGroup::Interactive, "Command for interactive mode:"),
+            (
+                CommandGroup::IgnoringHaiyama,
+                "Haiyama errors will cause operation failure and game state recovery. \
+                If you don't care errors from haiyama, you can use following command. \
+                If the number of a type of hai is 0, discard from haiyama will keep 0; \
+                if 4, add to haiyama will keep 4:",
+            ),
+        ]
+        .iter()
+        {
+            if !help_string.is_empty() {
+                help_string += "\n\n";
+            }
+            help_string += title;
+            for spec in COMMAND_SPECS.iter().filter(|spec| spec.group == *group) {
+                help_string += &format!("\n{}", spec);
+            }
+        }
+        help_string += "\n\nUse \"help <command>\" for syntax and examples of a command.";
+        help_string
+    }
+}
+
+impl std::fmt::Display for CommandSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Alternate flag `{:#}` prints syntax and every example in lines.
+        if f.alternate() {
+            let mut spec_string = format!("Usage: {}\n{}", self.usage(), self.description);
+            if self.names.len() > 1 {
+                spec_string += &format!("\nAliases: {}", self.names.join(", "));
+            }
+            if self.group != CommandGroup::Common {
+                spec_string += "\nOnly available at interactive mode.";
+            }
+            for example in self.examples {
+                spec_string += &format!("\nExample: {}", example);
+            }
+            return write!(f, "{}", spec_string);
+        }
+        let name_string = if self.names.is_empty() {
+            self.arguments.to_string()
+        } else {
+            self.names.join(",")
+        };
+        write!(f, "* {} -- {}", name_string, self.description)?;
+        // The last example is the most complete.
+        match self.examples.last() {
+            Some(example) => write!(f, " For an example, \"{}\".", example),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Operation on points of all seats, seats are given by jikaze.
+pub enum LedgerOperation {
+    Show,
+    Set(game::Kaze, i32),
+    Honba(u32),
+    /// Winner, loser, han and fu.
+    Ron(game::Kaze, game::Kaze, u32, u32),
+    /// Winner, han and fu.
+    Tsumo(game::Kaze, u32, u32),
+}
+
+impl Command {
+    pub fn parse(command: String, player_number: game::PlayerNumber) -> Result<Command, String> {
+        match &*command {
+            "ni" | "noninteractive" => Ok(Command::Noninteractive),
+            "i" | "interactive" => Ok(Command::Interactive),
+            "th" | "theory" => Ok(Command::Theory),
+            "q" | "quit" | "exit" => Ok(Command::Exit),
+            "s" | "state" => Ok(Command::State),
+            "b" | "back" => Ok(Command::Back {
+                haiyama_sensitive: true,
+            }),
+            "b!" | "back!" => Ok(Command::Back {
+                haiyama_sensitive: false,
+            }),
+            "d" | "display" => Ok(Command::Display { explain: false }),
+            "e" | "explain" => Ok(Command::Display { explain: true }),
+            "all" => Ok(Command::AllSutehai),
+            "log" | "history" => Ok(Command::History),
+            "kifu" => Ok(Command::Kifu),
+            "def" | "defense" => Ok(Command::Defense),
+            "wall" => Ok(Command::Wall),
+            "validate" => Ok(Command::Validate),
+            "riichi" => Ok(Command::Riichi),
+            "dealin" => Ok(Command::DealInTable(None)),
+            "opp" | "opponents" => Ok(Command::OpponentValue),
+            "ledger" => Ok(Command::Ledger(LedgerOperation::Show)),
+            "ryuukyoku" => Ok(Command::Ryuukyoku(vec![])),
+            "next" => Ok(Command::NextHand),
+            "fork" => Ok(Command::Fork),
+            "branch" => Ok(Command::Branch(None)),
+            "rate" => Ok(Command::Rate),
+            "review" => Ok(Command::Review(None)),
+            "hanchan" => Ok(Command::Hanchan(0)),
+            "search" => Ok(Command::Search(game::expectimax::DEFAULT_DEPTH)),
+            "url" => Ok(Command::TenhouUrl),
+            "agari" => Ok(Command::Agari),
+            "h" | "help" => Ok(Command::Help(None)),
+            "3pl" | "3-player" => Ok(Command::PlayerNumber(game::PlayerNumber::Three)),
+            "4pl" | "4-player" => Ok(Command::PlayerNumber(game::PlayerNumber::Four)),
+            "std" | "standard" => Ok(Command::OutputFormat(OutputFormat::Standard)),
+            "table" => Ok(Command::OutputFormat(OutputFormat::Table)),
+            "json" => Ok(Command::OutputFormat(OutputFormat::Json)),
+            "csv" => Ok(Command::OutputFormat(OutputFormat::Csv)),
+            "color" => Ok(Command::Color(true)),
+            "nocolor" => Ok(Command::Color(false)),
+            _ if command.starts_with("help ") => match CommandSpec::find(command[5..].trim()) {
+                Some(spec) => Ok(Command::Help(Some(spec))),
+                None => Err(format!("Unknown command: {}.", command[5..].trim())),
+            },
+            _ if command.starts_with("lang ") => Ok(Command::Locale(i18n::Locale::from_string(
+                command[5..].trim(),
+            )?)),
+            _ if command.starts_with("branch ") => match command[7..].trim().parse::<usize>() {
+                Ok(index) => Ok(Command::Branch(Some(index))),
+                Err(_) => Err(format!(
+                    "Unresolved branch number: {}.",
+                    command[7..].trim()
+                )),
+            },
+            _ if command.starts_with("review threshold ") => {
+                let percent = command[17..].trim();
+                match percent.parse::<f64>() {
+                    Ok(percent) if (0.0..=100.0).contains(&percent) => {
+                        Ok(Command::MistakeThreshold(percent / 100.0))
+                    }
+                    _ => Err(format!("Unresolved threshold percent: {}.", percent)),
+                }
+            }
+            _ if command.starts_with("review ") => match command[7..].trim().parse::<usize>() {
+                Ok(step) if step > 0 => Ok(Command::Review(Some(step))),
+                _ => Err(format!("Unresolved review step: {}.", command[7..].trim())),
+            },
+            _ if command.starts_with("search ") => match command[7..].trim().parse::<usize>() {
+                Ok(depth) => Ok(Command::Search(depth)),
+                Err(_) => Err(format!("Unresolved depth: {}.", command[7..].trim())),
+            },
+            _ if command.starts_with("hanchan ") => match command[8..].trim().parse::<u64>() {
+                Ok(seed) => Ok(Command::Hanchan(seed)),
+                Err(_) => Err(format!("Unresolved seed: {}.", command[8..].trim())),
+            },
+            _ if command.starts_with("enumerate ") => {
+                let arguments: Vec<&str> = command[10..].split_whitespace().collect();
+                match (
+                    arguments.len(),
+                    arguments.get(0).map(|size| size.parse::<usize>()),
+                    arguments.get(1).map(|shanten| shanten.parse::<i32>()),
+                ) {
+                    (2, Some(Ok(size)), Some(Ok(shanten))) => Ok(Command::Enumerate(size, shanten)),
+                    _ => Err(CommandSpec::usage_error("enumerate")),
+                }
+            }
+            _ if command.starts_with("react ") => {
+                let arguments: Vec<&str> = command[6..].split_whitespace().collect();
+                if arguments.len() != 2 {
+                    return Err(CommandSpec::usage_error("react"));
+                }
+                let seat = game::Seat::from_string(arguments[0])?;
+                let hai_vec = game::Hai::from_string_unordered(arguments[1], player_number)?;
+                if hai_vec.len() == 1 {
+                    Ok(Command::React {
+                        seat,
+                        hai: hai_vec[0],
+                    })
+                } else {
+                    Err("Can only react to one hai.".to_string())
+                }
+            }
+            _ if command.starts_with("sute ") => {
+                let arguments: Vec<&str> = command[5..].split_whitespace().collect();
+                if arguments.len() != 2 {
+                    return Err(CommandSpec::usage_error("sute"));
+                }
+                Ok(Command::OpponentDiscard {
+                    seat: game::Seat::from_string(arguments[0])?,
+                    hai_vec: game::Hai::from_string_unordered(arguments[1], player_number)?,
+                })
+            }
+            _ if command.starts_with("call ") => {
+                let arguments: Vec<&str> = command[5..].split_whitespace().collect();
+                if arguments.len() != 2 {
+                    return Err(CommandSpec::usage_error("call"));
+                }
+                let seat = game::Seat::from_string(arguments[0])?;
+                let hai_vec = game::Hai::from_string_unordered(arguments[1], player_number)?;
+                let mentsu = game::Mentsu::new(&hai_vec, player_number)
+                    .ok_or(format!("'{}' is not a mentsu.", arguments[1]))?;
+                Ok(Command::OpponentCall { seat, mentsu })
+            }
+            _ if command.starts_with("dealin ") => {
+                Ok(Command::DealInTable(Some(command[7..].trim().to_string())))
+            }
+            _ if command.starts_with("safe ") => Ok(Command::SafeAgainst(game::Seat::from_string(
+                command[5..].trim(),
+            )?)),
+            _ if command.starts_with("riichi ") => {
+                let arguments: Vec<&str> = command[7..].split_whitespace().collect();
+                if arguments.len() != 2 {
+                    return Err(CommandSpec::usage_error("riichi"));
+                }
+                let seat = game::Seat::from_string(arguments[0])?;
+                let hai_vec = game::Hai::from_string_unordered(arguments[1], player_number)?;
+                if hai_vec.len() == 1 {
+                    Ok(Command::OpponentRiichi {
+                        seat,
+                        sutehai: hai_vec[0],
+                    })
+                } else {
+                    Err("Riichi is declared by one sutehai.".to_string())
+                }
+            }
+            _ if command.starts_with("ledger ") => {
+                let arguments: Vec<&str> = command[7..].split_whitespace().collect();
+                let number = |index: usize| -> Result<u32, String> {
+                    arguments[index]
+                        .parse::<u32>()
+                        .map_err(|_| format!("Unresolved number: {}.", arguments[index]))
+                };
+                let kaze = |index: usize| game::Kaze::from_string(arguments[index]);
+                match (arguments.first(), arguments.len()) {
+                    (Some(&"set"), 3) => match arguments[2].parse::<i32>() {
+                        Ok(points) => Ok(Command::Ledger(LedgerOperation::Set(kaze(1)?, points))),
+                        Err(_) => Err(format!("Unresolved points: {}.", arguments[2])),
+                    },
+                    (Some(&"honba"), 2) => Ok(Command::Ledger(LedgerOperation::Honba(number(1)?))),
+                    (Some(&"ron"), 5) => Ok(Command::Ledger(LedgerOperation::Ron(
+                        kaze(1)?,
+                        kaze(2)?,
+                        number(3)?,
+                        number(4)?,
+                    ))),
+                    (Some(&"tsumo"), 4) => Ok(Command::Ledger(LedgerOperation::Tsumo(
+                        kaze(1)?,
+                        number(2)?,
+                        number(3)?,
+                    ))),
+                    _ => Err(CommandSpec::usage_error("ledger")),
+                }
+            }
+            _ if command.starts_with("ryuukyoku ") => {
+                let mut tenpai = vec![];
+                for argument in command[10..].split_whitespace() {
+                    tenpai.push(game::Kaze::from_string(argument)?);
+                }
+                Ok(Command::Ryuukyoku(tenpai))
+            }
+            _ if command.starts_with("wind ") => {
+                let arguments: Vec<&str> = command[5..].split_whitespace().collect();
+                if arguments.len() != 2 {
+                    return Err(CommandSpec::usage_error("wind"));
+                }
+                Ok(Command::Kaze(
+                    game::Kaze::from_string(arguments[0])?,
+                    game::Kaze::from_string(arguments[1])?,
+                ))
+            }
+            "dora" => Ok(Command::Dora(vec![])),
+            _ if command.starts_with("dora ") => {
+                let hai_string = command[5..].trim().to_string();
+                Ok(Command::Dora(game::Hai::from_string_unordered(
+                    &hai_string,
+                    player_number,
+                )?))
+            }
+            _ if command.starts_with("machi ") => {
+                let hai_string = command[6..].trim().to_string();
+                let hai_vec = game::Hai::from_string_unordered(&hai_string, player_number)?;
+                if hai_vec.len() == 1 {
+                    Ok(Command::MachiSearch(hai_vec[0]))
+                } else {
+                    Err("Can only search shapes waiting for one hai.".to_string())
+                }
+            }
+            _ if command.starts_with("note ") => {
+                Ok(Command::Annotate(game::Annotation::new(&command[5..])?))
+            }
+            _ if command.starts_with("agreement ") => Ok(Command::Agreement(
+                command[10..]
+                    .split_whitespace()
+                    .map(|path| path.to_string())
+                    .collect(),
+            )),
+            _ if command.starts_with("load ") => {
+                Ok(Command::LoadKifu(command[5..].trim().to_string()))
+            }
+            _ if command.starts_with("sync ") => Ok(Command::Sync(game::Tehai::new(
+                command[5..].trim().to_string(),
+                player_number,
+            )?)),
+            _ if command.starts_with("why ") => {
+                let hai_string = command[4..].trim().to_string();
+                let hai_vec = game::Hai::from_string_unordered(&hai_string, player_number)?;
+                match hai_vec.len() {
+                    2 => Ok(Command::Why(hai_vec[0], hai_vec[1])),
+                    _ => Err(CommandSpec::usage_error("why")),
+                }
+            }
+            _ if command.starts_with("sim ") => {
+                let hai_string = command[4..].trim().to_string();
+                let hai_vec = game::Hai::from_string_unordered(&hai_string, player_number)?;
+                match hai_vec.len() {
+                    1 => Ok(Command::Simulate(hai_vec[0])),
+                    2 => Ok(Command::Compare(hai_vec[0], hai_vec[1])),
+                    _ => Err("Can only simulate discarding one hai, or compare two.".to_string()),
+                }
+            }
+            _ if command.contains("tenhou.net/2/") => Ok(Command::TehaiInput(
+                game::Tehai::from_tenhou_url(&command, player_number)?,
+            )),
+            _ if command.contains('=') => Ok(Command::Scene(Box::new(
+                game::GameManager::from_scene(&command, player_number)?,
+            ))),
+            _ => Command::parse_with_argument(command, player_number),
+        }
+    }
+
+    fn parse_with_argument(
+        command: String,
+        player_number: game::PlayerNumber,
+    ) -> Result<Command, String> {
+        match game::Operation::parse(&command, player_number)? {
+            game::Operation::Tehai(game::TehaiOperation::Initialize(tehai)) => {
+                Ok(Command::TehaiInput(tehai))
+            }
+            op => Ok(Command::GameOperation(op)),
+        }
+    }
+}
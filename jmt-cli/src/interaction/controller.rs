@@ -0,0 +1,1531 @@
+use super::{color, csv, Command, CommandSpec, LedgerOperation, Table};
+use jmt_core::{
+    game,
+    i18n::{self, Message},
+};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct Controller {
+    game_manager: Option<game::GameManager>,
+    player_number: game::PlayerNumber,
+    output_format: OutputFormat,
+    color: bool,
+    rules: game::Rules,
+    deal_in_table: game::DealInTable,
+    mistake_threshold: f64,
+    analyze_options: game::AnalyzeOptions,
+    branches: Vec<game::GameManager>,
+    rollout_cache: game::simulate::RolloutCache,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum OutputFormat {
+    Standard,
+    Table,
+    Json,
+    Csv,
+}
+
+impl Controller {
+    pub fn new(
+        output_format: OutputFormat,
+        player_number: game::PlayerNumber,
+        interactive: bool,
+        color: bool,
+    ) -> Self {
+        Self {
+            game_manager: if interactive {
+                Some(game::GameManager::new(player_number))
+            } else {
+                None
+            },
+            player_number,
+            output_format,
+            color,
+            rules: game::Rules::default(),
+            deal_in_table: game::DealInTable::default(),
+            mistake_threshold: game::MISTAKE_THRESHOLD,
+            analyze_options: game::AnalyzeOptions::default(),
+            branches: vec![],
+            rollout_cache: game::simulate::RolloutCache::new(),
+        }
+    }
+
+    /// Set optional rules, kept over reinitializing the game.
+    pub fn set_rules(&mut self, rules: game::Rules) -> &mut Self {
+        self.rules = rules;
+        if let Some(game_manager) = &mut self.game_manager {
+            game_manager.set_rules(rules);
+        }
+        self
+    }
+
+    /// Set the table of deal-in rates, kept over reinitializing the game.
+    pub fn set_deal_in_table(&mut self, table: game::DealInTable) -> &mut Self {
+        if let Some(game_manager) = &mut self.game_manager {
+            game_manager.set_deal_in_table(table.clone());
+        }
+        self.deal_in_table = table;
+        self
+    }
+
+    /// Set the ratio of acceptance lost from which a discard is a mistake in review.
+    pub fn set_mistake_threshold(&mut self, threshold: f64) -> &mut Self {
+        self.mistake_threshold = threshold;
+        self
+    }
+
+    /// Set options of analysis, such as the order of conditions.
+    pub fn set_analyze_options(&mut self, options: game::AnalyzeOptions) -> &mut Self {
+        self.analyze_options = options;
+        self
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    /// Prompt before each input, none for JSON and CSV output to keep output parsable.
+    pub fn prompt(&self) -> &'static str {
+        match self.output_format {
+            OutputFormat::Standard | OutputFormat::Table => ">>> ",
+            OutputFormat::Json | OutputFormat::Csv => "",
+        }
+    }
+
+    /// Execute a command and print its output, returning whether it succeeded.
+    pub fn execute(&mut self, command: String, exit: &mut bool) -> bool {
+        let result = self.execute_core(command, exit);
+        let succeeded = result.is_ok();
+
+        match result {
+            Ok(Some(output)) => {
+                if !*exit {
+                    if let OutputFormat::Standard | OutputFormat::Table = self.output_format {
+                        println!(
+                            "<<< [{},{}]",
+                            self.player_number,
+                            match &self.game_manager {
+                                Some(_) => "I",
+                                None => "NI",
+                            }
+                        )
+                    }
+                }
+                println!("{}", output);
+            }
+            Err(error) => match self.output_format {
+                OutputFormat::Standard | OutputFormat::Table => {
+                    println!(
+                        "<<< [{},{}]",
+                        self.player_number,
+                        match &self.game_manager {
+                            Some(_) => "I",
+                            None => "NI",
+                        }
+                    );
+                    println!("{}", error);
+                }
+                OutputFormat::Json => println!("{}", json!({ "error": error })),
+                // Errors go to stderr, so that stdout stays CSV.
+                OutputFormat::Csv => eprintln!("{}", error),
+            },
+            _ => (),
+        }
+        succeeded
+    }
+
+    fn execute_core(&mut self, command: String, exit: &mut bool) -> Result<Option<String>, String> {
+        /// Print the analysis, and time of each phase to stderr with the `profiling` feature.
+        #[cfg_attr(not(feature = "profiling"), allow(unused_variables))]
+        fn print_machi(
+            report: &game::AnalysisReport,
+            format: OutputFormat,
+            explain: bool,
+            color: bool,
+            parsing: Duration,
+        ) -> String {
+            #[cfg(feature = "profiling")]
+            let start = Instant::now();
+            let output = format_machi(report, format, explain, color);
+            #[cfg(feature = "profiling")]
+            eprintln!(
+                "{}",
+                game::Timing {
+                    parsing,
+                    formatting: start.elapsed(),
+                    ..report.timing
+                }
+            );
+            output
+        }
+
+        fn format_machi(
+            report: &game::AnalysisReport,
+            format: OutputFormat,
+            explain: bool,
+            color: bool,
+        ) -> String {
+            let (tehai, shanten, conditions) = (&report.tehai, report.shanten, &report.conditions);
+            let output = match format {
+                OutputFormat::Standard => format!(
+                    "{}：{}\n{}",
+                    i18n::text(Message::Tehai),
+                    tehai,
+                    if shanten == -1 {
+                        i18n::text(Message::Agari).to_string()
+                    } else {
+                        let mut conditions_string = String::new();
+                        for (index, i) in conditions.iter().enumerate() {
+                            let mut condition_string = if explain {
+                                format!("{:#}", i)
+                            } else {
+                                format!("{}", i)
+                            };
+                            // Conditions are sorted, the first is recommended.
+                            if color && index == 0 {
+                                let sutehai_string = i.sutehai.to_string();
+                                condition_string = condition_string.replacen(
+                                    &sutehai_string,
+                                    &color::highlight(&sutehai_string),
+                                    1,
+                                );
+                            }
+                            let riichi_safety_string = report.riichi_safety_string(&i.sutehai);
+                            if !riichi_safety_string.is_empty() {
+                                condition_string += &format!(" [{}]", riichi_safety_string);
+                            }
+                            conditions_string += "\n";
+                            conditions_string += &condition_string;
+                        }
+                        format!(
+                            "{}\n--------{}",
+                            if shanten == 0 {
+                                i18n::text(Message::Tenpai).to_string()
+                            } else {
+                                format!("{}：{}", i18n::text(Message::Shanten), shanten)
+                            },
+                            conditions_string
+                        )
+                    }
+                ),
+                OutputFormat::Table => format!(
+                    "{}：{}\n{}",
+                    i18n::text(Message::Tehai),
+                    tehai,
+                    if shanten == -1 {
+                        i18n::text(Message::Agari).to_string()
+                    } else {
+                        let mut header = vec![
+                            "打".to_string(),
+                            "摸".to_string(),
+                            "残り".to_string(),
+                            "種類".to_string(),
+                            "次巡".to_string(),
+                            "平均".to_string(),
+                            "備考".to_string(),
+                        ];
+                        // Only shown against riichi, so that the table stays narrow.
+                        if !report.riichi_safety.is_empty() {
+                            header.push("安全度".to_string());
+                        }
+                        let mut table = Table::new(header);
+                        for (index, i) in conditions.iter().enumerate() {
+                            let mut machihai_string_vec = vec![];
+                            for machihai in i.machihai.keys() {
+                                machihai_string_vec.push(machihai.to_string());
+                            }
+                            let mut notes_string_vec = vec![];
+                            if i.furiten {
+                                notes_string_vec.push("振り聴".to_string());
+                            }
+                            if i.no_yaku {
+                                notes_string_vec.push("役なし".to_string());
+                            }
+                            if i.dead_machihai.len() > 0 {
+                                let mut dead_machihai_string_vec = vec![];
+                                for hai in i.dead_machihai.iter() {
+                                    dead_machihai_string_vec.push(hai.to_string());
+                                }
+                                notes_string_vec
+                                    .push(format!("純カラ {}", dead_machihai_string_vec.join(" ")));
+                            }
+                            if i.kairyouhai.len() > 0 {
+                                let mut kairyouhai_string_vec = vec![];
+                                for kairyouhai in i.kairyouhai.keys() {
+                                    kairyouhai_string_vec.push(kairyouhai.to_string());
+                                }
+                                notes_string_vec.push(format!(
+                                    "改良 {} 残り{}枚",
+                                    kairyouhai_string_vec.join(" "),
+                                    i.kairyouhai.values().map(|n| *n as usize).sum::<usize>()
+                                ));
+                            }
+                            let sutehai_string = if color && index == 0 {
+                                color::highlight(&i.sutehai.to_string())
+                            } else {
+                                i.sutehai.to_string()
+                            };
+                            let mut row = vec![
+                                sutehai_string,
+                                machihai_string_vec.join(" "),
+                                format!("{}枚", i.nokori()),
+                                i.machihai.len().to_string(),
+                                format!("{:.1}%", i.advance_rate(1) * 100.0),
+                                i.expected_advance_draws()
+                                    .map_or("-".to_string(), |draws| format!("{:.1}巡", draws)),
+                                notes_string_vec.join(" "),
+                            ];
+                            if !report.riichi_safety.is_empty() {
+                                row.push(report.riichi_safety_string(&i.sutehai));
+                            }
+                            table.push(row);
+                        }
+                        format!(
+                            "{}\n{}",
+                            if shanten == 0 {
+                                i18n::text(Message::Tenpai).to_string()
+                            } else {
+                                format!("{}：{}", i18n::text(Message::Shanten), shanten)
+                            },
+                            table
+                        )
+                    }
+                ),
+                OutputFormat::Json => return report.to_json().to_string(),
+                OutputFormat::Csv => return csv::analysis(report),
+            };
+            // Flag yakuman potential below analysis, so big hands are not abandoned.
+            let mut output = output;
+            if shanten != -1 && report.yakuman.len() > 0 {
+                output += "\n--------";
+                for chance in &report.yakuman {
+                    output += &format!("\n{}", chance);
+                }
+            }
+            if let Some(trade_off) = &report.trade_off {
+                output += &format!("\n--------\n{}", trade_off);
+            }
+            for warning in &report.warnings {
+                output += &format!("\n警告：{}", warning);
+            }
+            if color {
+                color::colorize(&output)
+            } else {
+                output
+            }
+        }
+
+        /// Summary of a branch, with the best sutehai if full with hai.
+        fn branch_summary(game_manager: &game::GameManager) -> (String, serde_json::Value) {
+            let tehai = match game_manager.tehai() {
+                Some(tehai) => tehai,
+                None => return (i18n::text(Message::NotInitialized).to_string(), json!(null)),
+            };
+            let best = match game_manager.state {
+                game::State::FullHai => match game_manager.tehai_analyze() {
+                    Ok((shanten, conditions)) => Some((shanten, conditions.into_iter().next())),
+                    Err(_) => None,
+                },
+                _ => None,
+            };
+            match best {
+                Some((shanten, Some(condition))) => (
+                    format!(
+                        "手牌 {} 向聴 {} 最善 打 {} 残り{}枚",
+                        tehai,
+                        shanten,
+                        condition.sutehai,
+                        condition.nokori()
+                    ),
+                    json!({
+                        "tehai": tehai.to_json(),
+                        "shanten_number": shanten,
+                        "best_condition": condition.to_json(),
+                    }),
+                ),
+                Some((shanten, None)) => (
+                    format!("手牌 {} 向聴 {}", tehai, shanten),
+                    json!({
+                        "tehai": tehai.to_json(),
+                        "shanten_number": shanten,
+                    }),
+                ),
+                None => (
+                    format!("手牌 {}", tehai),
+                    json!({
+                        "tehai": tehai.to_json(),
+                    }),
+                ),
+            }
+        }
+
+        *exit = false;
+        let start = Instant::now();
+        let command = Command::parse(command, self.player_number)?;
+        let parsing = start.elapsed();
+        match command {
+            Command::Exit => *exit = true,
+            Command::Noninteractive => {
+                self.game_manager = None;
+                self.branches.clear();
+            }
+            Command::Interactive | Command::Theory => {
+                let mut game_manager = match command {
+                    Command::Theory => game::GameManager::theory(self.player_number),
+                    _ => game::GameManager::new(self.player_number),
+                };
+                game_manager
+                    .set_rules(self.rules)
+                    .set_deal_in_table(self.deal_in_table.clone());
+                self.game_manager = Some(game_manager);
+                self.branches.clear();
+            }
+            Command::OutputFormat(output_format) => self.output_format = output_format,
+            Command::Color(color) => self.color = color,
+            Command::Locale(locale) => {
+                i18n::set_locale(locale);
+                return Ok(Some(i18n::text(Message::LocaleName).to_string()));
+            }
+            Command::PlayerNumber(player_number) => {
+                self.player_number = player_number;
+                if let Some(game_manager) = &mut self.game_manager {
+                    game_manager
+                        .reinitialize(player_number)
+                        .set_rules(self.rules)
+                        .set_deal_in_table(self.deal_in_table.clone());
+                }
+                self.branches.clear();
+            }
+            Command::GameOperation(op) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    let kan_or_rinshan = game_manager.state == game::State::WaitForRinshanhai
+                        || matches!(
+                            op,
+                            game::Operation::Tehai(game::TehaiOperation::Naku {
+                                kind: game::Naku::Kan(_),
+                                ..
+                            })
+                        );
+                    game_manager.operate(op)?;
+                    // Right after kan, ask for rinshanhai and then the kan-dora indicator.
+                    let mut prompt_vec = vec![];
+                    if let game::State::WaitForRinshanhai = game_manager.state {
+                        prompt_vec.push(i18n::text(Message::RinshanhaiPrompt));
+                    }
+                    if kan_or_rinshan && game_manager.kan_dora_pending() {
+                        prompt_vec.push(i18n::text(Message::KanDoraPrompt));
+                    }
+                    let mut output_vec = vec![];
+                    if let game::State::FullHai = game_manager.state {
+                        let report =
+                            game::analyzer::report_with(game_manager, &self.analyze_options)?;
+                        output_vec.push(print_machi(
+                            &report,
+                            self.output_format,
+                            false,
+                            self.color,
+                            parsing,
+                        ));
+                    }
+                    if let OutputFormat::Json = self.output_format {
+                        if !prompt_vec.is_empty() && output_vec.is_empty() {
+                            output_vec.push(json!({ "prompt": prompt_vec }).to_string());
+                        }
+                    } else {
+                        output_vec.extend(prompt_vec.iter().map(|prompt| prompt.to_string()));
+                    }
+                    if !output_vec.is_empty() {
+                        return Ok(Some(output_vec.join("\n")));
+                    }
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::TehaiInput(tehai) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.operate(game::Operation::Tehai(
+                        game::TehaiOperation::Initialize(tehai),
+                    ))?;
+                    if let game::State::FullHai = game_manager.state {
+                        let report =
+                            game::analyzer::report_with(game_manager, &self.analyze_options)?;
+                        return Ok(Some(print_machi(
+                            &report,
+                            self.output_format,
+                            false,
+                            self.color,
+                            parsing,
+                        )));
+                    }
+                }
+                None => {
+                    let report = game::analyzer::report_tehai_with(
+                        &tehai,
+                        self.player_number,
+                        &self.analyze_options,
+                    )?;
+                    return Ok(Some(print_machi(
+                        &report,
+                        self.output_format,
+                        false,
+                        self.color,
+                        parsing,
+                    )));
+                }
+            },
+            Command::Scene(game_manager) => {
+                let mut game_manager = *game_manager;
+                game_manager
+                    .set_rules(self.rules)
+                    .set_deal_in_table(self.deal_in_table.clone());
+                self.branches.clear();
+                let game_manager = self.game_manager.insert(game_manager);
+                if let game::State::FullHai = game_manager.state {
+                    let report = game::analyzer::report_with(game_manager, &self.analyze_options)?;
+                    return Ok(Some(print_machi(
+                        &report,
+                        self.output_format,
+                        false,
+                        self.color,
+                        parsing,
+                    )));
+                }
+            }
+            Command::Back { haiyama_sensitive } => match &mut self.game_manager {
+                Some(game_manager) => {
+                    let (op, state) = game_manager.back(haiyama_sensitive)?;
+                    return Ok(Some(format!(
+                        "Undo operation: {:?}\nBack to state: {:?}",
+                        op, state
+                    )));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::State => match &self.game_manager {
+                Some(game_manager) => {
+                    return Ok(Some(format!(
+                        "{}",
+                        match self.output_format {
+                            OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv
+                                if self.color =>
+                            {
+                                color::colorize(&game_manager.to_string())
+                            }
+                            OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                                game_manager.to_string()
+                            }
+                            OutputFormat::Json => game_manager.to_json().to_string(),
+                        }
+                    )))
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Display { explain } => match &self.game_manager {
+                Some(game_manager) => {
+                    if let game::State::FullHai = game_manager.state {
+                        let report =
+                            game::analyzer::report_with(game_manager, &self.analyze_options)?;
+                        return Ok(Some(print_machi(
+                            &report,
+                            self.output_format,
+                            explain,
+                            self.color,
+                            parsing,
+                        )));
+                    } else {
+                        return Err(i18n::text(Message::NotFullHai).to_string());
+                    }
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::AllSutehai => match &self.game_manager {
+                Some(game_manager) => {
+                    if let game::State::FullHai = game_manager.state {
+                        let tehai = game_manager
+                            .tehai()
+                            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+                        let result_vec =
+                            tehai.analyze_all_sutehai(self.player_number, Some(game_manager))?;
+                        return Ok(Some(match self.output_format {
+                            OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                                let mut string = format!("手牌：{}", tehai);
+                                let mut last_shanten = None;
+                                for (shanten, condition) in &result_vec {
+                                    // Group conditions by shanten after discarding.
+                                    if last_shanten != Some(*shanten) {
+                                        string += &if *shanten == 0 {
+                                            format!("\n聴牌\n--------")
+                                        } else {
+                                            format!("\n向聴：{}\n--------", shanten)
+                                        };
+                                        last_shanten = Some(*shanten);
+                                    }
+                                    string += &format!("\n{}", condition);
+                                }
+                                string
+                            }
+                            OutputFormat::Json => {
+                                let mut json_vec = vec![];
+                                for (shanten, condition) in &result_vec {
+                                    json_vec.push(json!({
+                                        "shanten_number": shanten,
+                                        "condition": condition.to_json(),
+                                    }));
+                                }
+                                json!({ "tehai": tehai.to_json(), "sutehai": json_vec }).to_string()
+                            }
+                        }));
+                    } else {
+                        return Err(i18n::text(Message::NotFullHai).to_string());
+                    }
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::History => match &self.game_manager {
+                Some(game_manager) => {
+                    let iter = game_manager.history().iter();
+                    match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            let mut string = String::from("Operation History");
+                            for (id, (op, state, _)) in iter.enumerate() {
+                                string += &format!(
+                                    "\n[{}]:\t\tState: {:?}\n\t\tOperation: {:?}",
+                                    id, state, op
+                                );
+                                for annotation in
+                                    game_manager.annotations().get(&id).into_iter().flatten()
+                                {
+                                    string += &format!("\n\t\tAnnotation: {}", annotation);
+                                }
+                            }
+                            return Ok(Some(string));
+                        }
+                        OutputFormat::Json => {
+                            let mut json_vec = vec![];
+                            for (id, (op, state, _)) in iter.enumerate() {
+                                let annotations: Vec<serde_json::Value> = game_manager
+                                    .annotations()
+                                    .get(&id)
+                                    .into_iter()
+                                    .flatten()
+                                    .map(game::Annotation::to_json)
+                                    .collect();
+                                json_vec.push(json!({
+                                    "operation": op.to_json(),
+                                    "state": format!("{:?}", state),
+                                    "annotations": annotations,
+                                }))
+                            }
+                            return Ok(Some(json!({ "history": json_vec }).to_string()));
+                        }
+                    }
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Annotate(annotation) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    let id = game_manager.history().len().saturating_sub(1);
+                    game_manager.annotate(annotation.clone())?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("[{}] @ {}", id, annotation)
+                        }
+                        OutputFormat::Json => json!({
+                            "operation": id,
+                            "annotation": annotation.to_json(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Kifu => match &self.game_manager {
+                Some(game_manager) => {
+                    let kifu = game::Kifu::from_game_manager(game_manager);
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            kifu.to_string()
+                        }
+                        OutputFormat::Json => kifu.to_json().to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::LoadKifu(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|error| format!("Failed to read '{}': {}", path, error))?;
+                let kifu = game::Kifu::parse(&text)?;
+                let game_manager = kifu.replay()?;
+                // History of the replay must record the game again, or saving it loses some.
+                game::Kifu::from_game_manager(&game_manager)
+                    .verify(&game_manager)
+                    .map_err(|error| i18n::format(Message::KifuNotReplayed, &[&path, &error]))?;
+                self.player_number = kifu.player_number;
+                self.game_manager = Some(game_manager);
+                self.branches.clear();
+                return Ok(Some(match self.output_format {
+                    OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                        format!("Replayed {} operations.", kifu.operations.len())
+                    }
+                    OutputFormat::Json => json!({ "replayed": kifu.operations.len() }).to_string(),
+                }));
+            }
+            Command::Agreement(paths) => {
+                let mut games = vec![];
+                let mut players: Vec<(String, game::Agreement)> = vec![];
+                let mut total = game::Agreement::default();
+                for path in &paths {
+                    let text = std::fs::read_to_string(path)
+                        .map_err(|error| format!("Failed to read '{}': {}", path, error))?;
+                    let kifu = game::Kifu::parse(&text)?;
+                    let review =
+                        game::Review::new(&kifu.operations, &kifu.annotations, kifu.player_number)
+                            .map_err(|error| format!("{}: {}", path, error))?;
+                    let agreement = game::Agreement::from_review(&review);
+                    let player = kifu.player.unwrap_or("-".to_string());
+                    match players.iter_mut().find(|(name, _)| *name == player) {
+                        Some((_, player_agreement)) => {
+                            player_agreement.add(&agreement);
+                        }
+                        None => players.push((player.clone(), agreement)),
+                    }
+                    total.add(&agreement);
+                    games.push((path.clone(), player, agreement));
+                }
+                return Ok(Some(match self.output_format {
+                    OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                        let mut lines = vec![];
+                        for (path, player, agreement) in &games {
+                            lines.push(format!("対局 {}（{}）：{}", path, player, agreement));
+                        }
+                        for (player, agreement) in &players {
+                            lines.push(format!("打ち手 {}：{}", player, agreement));
+                        }
+                        lines.push(format!("総合：{}", total));
+                        lines.join("\n")
+                    }
+                    OutputFormat::Json => {
+                        let mut game_json_vec = vec![];
+                        for (path, player, agreement) in &games {
+                            let mut game_json = agreement.to_json();
+                            game_json["path"] = json!(path);
+                            game_json["player"] = json!(player);
+                            game_json_vec.push(game_json);
+                        }
+                        let mut player_json_vec = vec![];
+                        for (player, agreement) in &players {
+                            let mut player_json = agreement.to_json();
+                            player_json["player"] = json!(player);
+                            player_json_vec.push(player_json);
+                        }
+                        json!({
+                            "games": game_json_vec,
+                            "players": player_json_vec,
+                            "total": total.to_json(),
+                        })
+                        .to_string()
+                    }
+                }));
+            }
+            Command::React { seat, hai } => match &self.game_manager {
+                Some(game_manager) => {
+                    let results = game::Reaction::analyze(game_manager, hai, seat)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            let mut output = String::new();
+                            if let Some(best) = results.first() {
+                                output += &format!("推奨：{}\n--------", best);
+                            }
+                            for result in &results {
+                                output += &format!("\n{}", result);
+                            }
+                            if self.color {
+                                color::colorize(&output)
+                            } else {
+                                output
+                            }
+                        }
+                        OutputFormat::Json => {
+                            let mut result_json_vec = vec![];
+                            for result in &results {
+                                result_json_vec.push(result.to_json());
+                            }
+                            json!({ "reactions": result_json_vec }).to_string()
+                        }
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Wall => match &self.game_manager {
+                Some(game_manager) => {
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("{:#}", game_manager.haiyama())
+                        }
+                        OutputFormat::Json => {
+                            json!({ "grid": game_manager.haiyama().to_grid() }).to_string()
+                        }
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Validate => match &self.game_manager {
+                Some(game_manager) => {
+                    let result = game_manager.validate();
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            match result {
+                                Ok(_) => "整合".to_string(),
+                                Err(error) => error,
+                            }
+                        }
+                        OutputFormat::Json => match result {
+                            Ok(_) => json!({ "valid": true }).to_string(),
+                            Err(error) => json!({
+                                "valid": false,
+                                "errors": error.lines().collect::<Vec<_>>(),
+                            })
+                            .to_string(),
+                        },
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Defense => match &self.game_manager {
+                Some(game_manager) => {
+                    let safety_vec = game::Safety::analyze(game_manager)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            let mut safety_string_vec = vec![];
+                            for safety in &safety_vec {
+                                safety_string_vec.push(safety.to_string());
+                            }
+                            let output = safety_string_vec.join("\n");
+                            if self.color {
+                                color::colorize(&output)
+                            } else {
+                                output
+                            }
+                        }
+                        OutputFormat::Json => {
+                            let mut safety_json_vec = vec![];
+                            for safety in &safety_vec {
+                                safety_json_vec.push(safety.to_json());
+                            }
+                            json!({ "safety": safety_json_vec }).to_string()
+                        }
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::MachiSearch(machihai) => {
+                // Without game manager, all hai are unseen.
+                let haiyama = match &self.game_manager {
+                    Some(game_manager) => game_manager.haiyama().clone(),
+                    None => game::Haiyama::new(self.player_number),
+                };
+                let shape_vec = game::MachiShape::search(machihai, &haiyama);
+                return Ok(Some(match self.output_format {
+                    OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                        let total: u32 = shape_vec.iter().map(|shape| shape.combinations).sum();
+                        let mut output_vec = vec![format!("{}待ち 計{}通り", machihai, total)];
+                        for shape in &shape_vec {
+                            output_vec.push(shape.to_string());
+                        }
+                        output_vec.join("\n")
+                    }
+                    OutputFormat::Json => {
+                        let mut shape_json_vec = vec![];
+                        for shape in &shape_vec {
+                            shape_json_vec.push(shape.to_json());
+                        }
+                        json!({
+                            "machihai": machihai.to_string(),
+                            "shapes": shape_json_vec,
+                        })
+                        .to_string()
+                    }
+                }));
+            }
+            Command::Riichi => match &self.game_manager {
+                Some(game_manager) => {
+                    let sutehai_vec = game::riichi::can_riichi(game_manager)
+                        .map_err(|reason| reason.to_string())?;
+                    let mut sutehai_string_vec = vec![];
+                    for sutehai in &sutehai_vec {
+                        sutehai_string_vec.push(sutehai.to_string());
+                    }
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            let output = format!("立直可能：打 {}", sutehai_string_vec.join(" "));
+                            if self.color {
+                                color::colorize(&output)
+                            } else {
+                                output
+                            }
+                        }
+                        OutputFormat::Json => json!({ "sutehai": sutehai_string_vec }).to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::OpponentRiichi { seat, sutehai } => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.mark_opponent_riichi(seat, sutehai)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("{}立直：宣言牌 {}", seat, sutehai)
+                        }
+                        OutputFormat::Json => json!({
+                            "riichi": game_manager
+                                .opponent_riichi()
+                                .iter()
+                                .map(|riichi| json!({
+                                    "seat": riichi.seat.name(),
+                                    "sutehai": riichi.sutehai.to_string(),
+                                }))
+                                .collect::<Vec<_>>(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::DealInTable(path) => {
+                if let Some(path) = path {
+                    let text = std::fs::read_to_string(&path)
+                        .map_err(|error| format!("Failed to read {}: {}.", path, error))?;
+                    self.set_deal_in_table(game::DealInTable::parse(&text)?);
+                }
+                return Ok(Some(match self.output_format {
+                    OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                        self.deal_in_table.to_string()
+                    }
+                    OutputFormat::Json => self.deal_in_table.to_json().to_string(),
+                }));
+            }
+            Command::OpponentCall { seat, mentsu } => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.call_by(seat, mentsu)?;
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::OpponentValue => match &self.game_manager {
+                Some(game_manager) => {
+                    let values = game::OpponentValue::estimate_all(game_manager)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => values
+                            .iter()
+                            .map(|value| value.to_string())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        OutputFormat::Json => json!({
+                            "opponents": values
+                                .iter()
+                                .map(|value| value.to_json())
+                                .collect::<Vec<_>>(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::OpponentDiscard { seat, hai_vec } => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.discard_by(seat, hai_vec)?;
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::SafeAgainst(seat) => match &self.game_manager {
+                Some(game_manager) => {
+                    let mut genbutsu: Vec<game::Hai> =
+                        game_manager.safe_against(seat).into_iter().collect();
+                    genbutsu.sort();
+                    let genbutsu_string_vec: Vec<String> =
+                        genbutsu.iter().map(|hai| hai.to_string()).collect();
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("{}の現物：{}", seat, genbutsu_string_vec.join(" "))
+                        }
+                        OutputFormat::Json => json!({
+                            "seat": seat.name(),
+                            "genbutsu": genbutsu_string_vec,
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Ledger(ledger_operation) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    let rules = game_manager.rules();
+                    let ledger = game_manager.ledger_mut();
+                    let score = |winner, han, fu| {
+                        game::Score::new(han, fu, winner == game::Kaze::Ton, &rules)
+                    };
+                    match ledger_operation {
+                        LedgerOperation::Show => (),
+                        LedgerOperation::Set(seat, points) => ledger.set_points(seat, points)?,
+                        LedgerOperation::Honba(honba) => ledger.set_honba(honba),
+                        LedgerOperation::Ron(winner, loser, han, fu) => {
+                            ledger.ron(winner, loser, &score(winner, han, fu)?)?
+                        }
+                        LedgerOperation::Tsumo(winner, han, fu) => {
+                            ledger.tsumo(winner, &score(winner, han, fu)?)?
+                        }
+                    }
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            ledger.to_string()
+                        }
+                        OutputFormat::Json => ledger.to_json().to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Ryuukyoku(tenpai) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    let ryuukyoku = game_manager.ryuukyoku(&tenpai)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("{}\n--------\n{}", ryuukyoku, game_manager.ledger())
+                        }
+                        OutputFormat::Json => json!({
+                            "ryuukyoku": ryuukyoku.to_json(),
+                            "ledger": game_manager.ledger().to_json(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::NextHand => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.next_hand()?;
+                    self.branches.clear();
+                    let (bakaze, jikaze) = (game_manager.bakaze(), game_manager.jikaze());
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!(
+                                "{}場 {}家\n--------\n{}",
+                                bakaze,
+                                jikaze,
+                                game_manager.ledger()
+                            )
+                        }
+                        OutputFormat::Json => json!({
+                            "bakaze": bakaze.to_hai().to_string(),
+                            "jikaze": jikaze.to_hai().to_string(),
+                            "ledger": game_manager.ledger().to_json(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Kaze(bakaze, jikaze) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.set_kaze(jikaze, bakaze);
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("{}場 {}家", bakaze, jikaze)
+                        }
+                        OutputFormat::Json => json!({
+                            "bakaze": bakaze.to_hai().to_string(),
+                            "jikaze": jikaze.to_hai().to_string(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Dora(dora_indicators) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    game_manager.set_dora_indicators(dora_indicators);
+                    let mut indicator_string_vec = vec![];
+                    for hai in game_manager.dora_indicators() {
+                        indicator_string_vec.push(hai.to_string());
+                    }
+                    let mut dora_string_vec = vec![];
+                    for hai in game_manager.dora() {
+                        dora_string_vec.push(hai.to_string());
+                    }
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!(
+                                "ドラ表示牌：{} ドラ：{}",
+                                indicator_string_vec.join(""),
+                                dora_string_vec.join("")
+                            )
+                        }
+                        OutputFormat::Json => json!({
+                            "dora_indicators": indicator_string_vec,
+                            "dora": dora_string_vec,
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Fork => match &self.game_manager {
+                Some(game_manager) => {
+                    self.branches.push(game_manager.fork());
+                    let index = self.branches.len() - 1;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("Current state is saved as branch {}.", index)
+                        }
+                        OutputFormat::Json => json!({ "branch": index }).to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Branch(index) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    // Switch by swapping, so that current state is kept as that branch.
+                    if let Some(index) = index {
+                        let branch = self
+                            .branches
+                            .get_mut(index)
+                            .ok_or(format!("No branch {}.", index))?;
+                        std::mem::swap(game_manager, branch);
+                    }
+
+                    let current = branch_summary(game_manager);
+                    let mut branches = vec![];
+                    for branch in &self.branches {
+                        branches.push(branch_summary(branch));
+                    }
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            let mut output = format!("[現在] {}", current.0);
+                            for (index, (summary, _)) in branches.iter().enumerate() {
+                                output += &format!("\n[{}] {}", index, summary);
+                            }
+                            if self.color {
+                                color::colorize(&output)
+                            } else {
+                                output
+                            }
+                        }
+                        OutputFormat::Json => {
+                            let mut branch_json_vec = vec![];
+                            for (_, summary_json) in branches {
+                                branch_json_vec.push(summary_json);
+                            }
+                            json!({
+                                "current": current.1,
+                                "branches": branch_json_vec,
+                            })
+                            .to_string()
+                        }
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Rate => match &self.game_manager {
+                Some(game_manager) => {
+                    let operations: Vec<game::Operation> = game_manager
+                        .history()
+                        .iter()
+                        .map(|(op, _, _)| op.clone())
+                        .collect();
+                    let report = game::EfficiencyReport::rate(
+                        &operations,
+                        game_manager.annotations(),
+                        self.player_number,
+                    )?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv
+                            if self.color =>
+                        {
+                            color::colorize(&report.to_string())
+                        }
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            report.to_string()
+                        }
+                        OutputFormat::Json => report.to_json().to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::MistakeThreshold(threshold) => {
+                self.mistake_threshold = threshold;
+                return Ok(Some(format!(
+                    "ミス判定：受け入れ -{:.1}%以上",
+                    threshold * 100.0
+                )));
+            }
+            Command::Review(step) => match &self.game_manager {
+                Some(game_manager) => {
+                    let operations: Vec<game::Operation> = game_manager
+                        .history()
+                        .iter()
+                        .map(|(op, _, _)| op.clone())
+                        .collect();
+                    let mut review = game::Review::new(
+                        &operations,
+                        game_manager.annotations(),
+                        self.player_number,
+                    )?;
+                    review.threshold = self.mistake_threshold;
+                    review.evaluate(game::REVIEW_ITERATIONS)?;
+                    let output = match step {
+                        Some(step) => {
+                            let review_step = review.steps.get(step - 1).ok_or(format!(
+                                "No step {} to review, only {} steps.",
+                                step,
+                                review.steps.len()
+                            ))?;
+                            match self.output_format {
+                                OutputFormat::Standard
+                                | OutputFormat::Table
+                                | OutputFormat::Csv => {
+                                    format!("[{}/{}] {}", step, review.steps.len(), review_step)
+                                }
+                                OutputFormat::Json => review_step.to_json().to_string(),
+                            }
+                        }
+                        None => match self.output_format {
+                            OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                                review.to_string()
+                            }
+                            OutputFormat::Json => review.to_json().to_string(),
+                        },
+                    };
+                    return Ok(Some(if self.color {
+                        color::colorize(&output)
+                    } else {
+                        output
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Simulate(sutehai) => match &self.game_manager {
+                Some(game_manager) => {
+                    match game_manager.state {
+                        game::State::FullHai => (),
+                        _ => return Err("Can only simulate when full with hai.".to_string()),
+                    }
+                    let mut tehai = game_manager
+                        .tehai()
+                        .ok_or(i18n::text(Message::NotInitialized).to_string())?
+                        .clone();
+                    tehai.discard(&sutehai)?;
+
+                    let output_format = self.output_format;
+                    let mut progress = |progress: game::simulate::Progress| {
+                        // Render progress bar only for standard output, and not too often.
+                        if let OutputFormat::Json = output_format {
+                            return;
+                        }
+                        if progress.completed % 20 != 0 && progress.completed != progress.total {
+                            return;
+                        }
+                        let width = 30;
+                        let filled = width * progress.completed / progress.total;
+                        eprint!(
+                            "\r[{}{}] {}/{} 残り {}秒",
+                            "#".repeat(filled),
+                            " ".repeat(width - filled),
+                            progress.completed,
+                            progress.total,
+                            progress.eta.map_or(0, |eta| eta.as_secs())
+                        );
+                        if progress.completed == progress.total {
+                            eprintln!();
+                        }
+                    };
+                    let result = game::simulate::Simulation::default().run_with_cache(
+                        &tehai,
+                        game_manager.haiyama(),
+                        &self.rollout_cache,
+                        Some(&mut progress),
+                        None,
+                    )?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            format!("打 {} {}", sutehai, result)
+                        }
+                        OutputFormat::Json => json!({
+                            "sutehai": sutehai.to_string(),
+                            "result": result.to_json(),
+                        })
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Compare(discard_a, discard_b) => match &self.game_manager {
+                Some(game_manager) => {
+                    match game_manager.state {
+                        game::State::FullHai => (),
+                        _ => {
+                            return Err("Can only compare discards when full with hai.".to_string())
+                        }
+                    }
+                    let tehai = game_manager
+                        .tehai()
+                        .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+                    let comparison = game::simulate::compare_with_cache(
+                        tehai,
+                        &discard_a,
+                        &discard_b,
+                        game_manager.haiyama(),
+                        game::simulate::Simulation::default().iterations,
+                        &self.rollout_cache,
+                    )?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            comparison.to_string()
+                        }
+                        OutputFormat::Json => comparison.to_json().to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Sync(tehai) => match &mut self.game_manager {
+                Some(game_manager) => {
+                    let operations = game_manager.sync_tehai(&tehai)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            let string_vec: Vec<String> =
+                                operations.iter().map(ToString::to_string).collect();
+                            string_vec.join(" ")
+                        }
+                        OutputFormat::Json => {
+                            let json_vec: Vec<serde_json::Value> =
+                                operations.iter().map(game::Operation::to_json).collect();
+                            json!(json_vec).to_string()
+                        }
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Agari => match &self.game_manager {
+                Some(game_manager) => {
+                    let table = game::AgariTable::analyze(game_manager)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            table.to_string()
+                        }
+                        OutputFormat::Json => table.to_json().to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Why(lhs, rhs) => match &self.game_manager {
+                Some(game_manager) => {
+                    let report = game::analyzer::report_with(game_manager, &self.analyze_options)?;
+                    let comparison = game::ConditionComparison::analyze(
+                        &report,
+                        lhs,
+                        rhs,
+                        game_manager.player_number(),
+                        Some(game_manager),
+                    )?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            comparison.to_string()
+                        }
+                        OutputFormat::Json => comparison.to_json().to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Search(depth) => match &self.game_manager {
+                Some(game_manager) => {
+                    match game_manager.state {
+                        game::State::FullHai => (),
+                        _ => return Err("Can only search discards when full with hai.".to_string()),
+                    }
+                    let tehai = game_manager
+                        .tehai()
+                        .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+                    // Few draws are left to solve exactly, whatever the depth.
+                    let draws = game_manager.draws_remaining();
+                    if draws < game::endgame::EXACT_DRAWS {
+                        let endgame = game::endgame::solve(tehai, game_manager.haiyama(), draws)?;
+                        return Ok(Some(match self.output_format {
+                            OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                                endgame.to_string()
+                            }
+                            OutputFormat::Json => endgame.to_json().to_string(),
+                        }));
+                    }
+                    let value_vec = game::expectimax::search(tehai, game_manager.haiyama(), depth)?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                            value_vec
+                                .iter()
+                                .map(|value| value.to_string())
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        }
+                        OutputFormat::Json => json!(value_vec
+                            .iter()
+                            .map(|value| value.to_json())
+                            .collect::<Vec<_>>())
+                        .to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Hanchan(seed) => {
+                let mut hanchan = game::Hanchan::new(
+                    self.player_number,
+                    game::MatchRules::new(self.player_number),
+                );
+                // Yaku are not judged in simulation, every winning is scored as 3 han 30 fu.
+                let placements = hanchan.simulate(3, 30, seed)?;
+                return Ok(Some(match self.output_format {
+                    OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                        let mut output_vec = vec![];
+                        for record in hanchan.records() {
+                            output_vec.push(record.to_string());
+                        }
+                        output_vec.push("--------".to_string());
+                        for placement in placements {
+                            output_vec.push(placement.to_string());
+                        }
+                        output_vec.join("\n")
+                    }
+                    OutputFormat::Json => hanchan.to_json().to_string(),
+                }));
+            }
+            Command::Enumerate(size, shanten) => {
+                let mut class_number = 0;
+                let mut hand_number = 0;
+                let mut example_vec = vec![];
+                for class in game::enumerate::classes(size, shanten, self.player_number)? {
+                    class_number += 1;
+                    hand_number += class.weight;
+                    // Only a few classes are printed as examples.
+                    if example_vec.len() < 10 {
+                        example_vec.push(class);
+                    }
+                }
+                return Ok(Some(match self.output_format {
+                    OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => {
+                        let mut output_vec = vec![format!(
+                            "{}枚 {}向聴 {}種類 {}通り",
+                            size, shanten, class_number, hand_number
+                        )];
+                        for class in example_vec {
+                            let mut hand_string = String::new();
+                            for hai in game::Hai::from_counts(&class.counts) {
+                                hand_string += &hai.to_string();
+                            }
+                            output_vec.push(format!("{} ×{}", hand_string, class.weight));
+                        }
+                        output_vec.join("\n")
+                    }
+                    OutputFormat::Json => {
+                        let mut example_json_vec = vec![];
+                        for class in example_vec {
+                            example_json_vec.push(class.to_json());
+                        }
+                        json!({
+                            "size": size,
+                            "shanten": shanten,
+                            "classes": class_number,
+                            "hands": hand_number,
+                            "examples": example_json_vec,
+                        })
+                        .to_string()
+                    }
+                }));
+            }
+            Command::TenhouUrl => match &self.game_manager {
+                Some(game_manager) => {
+                    let tehai = game_manager
+                        .tehai()
+                        .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+                    let url = tehai.to_tenhou_url()?;
+                    return Ok(Some(match self.output_format {
+                        OutputFormat::Standard | OutputFormat::Table | OutputFormat::Csv => url,
+                        OutputFormat::Json => json!({ "url": url }).to_string(),
+                    }));
+                }
+                None => {
+                    return Err(i18n::text(Message::NonInteractive).to_string());
+                }
+            },
+            Command::Help(spec) => {
+                return Ok(Some(match spec {
+                    Some(spec) => format!("{:#}", spec),
+                    None => CommandSpec::help(),
+                }));
+            }
+        };
+        Ok(None)
+    }
+}
@@ -0,0 +1,114 @@
+//! CSV of analysis, one row for each sutehai, for spreadsheets and data analysis.
+//!
+//! Hai in a cell are separated by spaces, rates are in 0 to 1, and flags are `true` or
+//! `false`. Yaku flags judge hai after discarding including fuuro, telling which yaku the
+//! sutehai keeps possible rather than which yaku is complete. Safety against other players
+//! declared riichi or likely tenpai is like `kamicha:genbutsu toimen:suji`, and the deal-in
+//! rate is to any of them, both empty if there is no such player.
+
+use jmt_core::game::{AnalysisReport, Hai, MachiCondition};
+
+const HEADER: [&str; 20] = [
+    "sutehai",
+    "shanten",
+    "machihai",
+    "machihai_types",
+    "nokori",
+    "advance_rate",
+    "expected_advance_draws",
+    "expected_tenpai_draws",
+    "furiten",
+    "no_yaku",
+    "dead_machihai",
+    "kairyouhai",
+    "kairyouhai_nokori",
+    "menzen",
+    "tanyao",
+    "yakuhai",
+    "honitsu",
+    "chinitsu",
+    "riichi_safety",
+    "deal_in_rate",
+];
+
+/// Print analysis as CSV with header. Only the header if already won.
+pub fn analysis(report: &AnalysisReport) -> String {
+    let mut lines = vec![HEADER.join(",")];
+    for condition in &report.conditions {
+        lines.push(row(report, condition).join(","));
+    }
+    lines.join("\n")
+}
+
+fn row(report: &AnalysisReport, condition: &MachiCondition) -> Vec<String> {
+    let mut counts = report.tehai.to_counts();
+    counts[condition.sutehai.to_index()] -= 1;
+    for mentsu in &report.tehai.fuuro {
+        for hai in mentsu.to_vec() {
+            counts[hai.to_index()] += 1;
+        }
+    }
+    let has = |range: std::ops::Range<usize>| range.into_iter().any(|index| counts[index] > 0);
+    let suit_number = (0..3).filter(|suit| has(suit * 9..suit * 9 + 9)).count();
+    let jihai = has(27..34);
+    let tanyao = (0..34)
+        .filter(|index| counts[*index] > 0)
+        .all(|index| index < 27 && index % 9 != 0 && index % 9 != 8);
+    let yakuhai = report
+        .yakuhai_type
+        .iter()
+        .any(|hai| counts[hai.to_index()] >= 2);
+
+    vec![
+        condition.sutehai.to_string(),
+        report.shanten.to_string(),
+        hai_cell(condition.machihai.keys()),
+        condition.machihai.len().to_string(),
+        condition.nokori().to_string(),
+        format!("{:.4}", condition.advance_rate(1)),
+        optional_cell(condition.expected_advance_draws()),
+        optional_cell(condition.tenpai_draws),
+        condition.furiten.to_string(),
+        condition.no_yaku.to_string(),
+        hai_cell(condition.dead_machihai.iter()),
+        hai_cell(condition.kairyouhai.keys()),
+        condition
+            .kairyouhai
+            .values()
+            .map(|nokori| *nokori as usize)
+            .sum::<usize>()
+            .to_string(),
+        report.tehai.is_menzen().to_string(),
+        tanyao.to_string(),
+        yakuhai.to_string(),
+        (suit_number == 1 && jihai).to_string(),
+        (suit_number == 1 && !jihai).to_string(),
+        report
+            .riichi_safety
+            .get(&condition.sutehai)
+            .map_or(vec![], |seat_safety_vec| {
+                seat_safety_vec
+                    .iter()
+                    .map(|(seat, safety)| format!("{}:{}", seat.name(), safety.name()))
+                    .collect()
+            })
+            .join(" "),
+        report
+            .deal_in
+            .get(&condition.sutehai)
+            .map_or(String::new(), |rate| format!("{:.4}", rate)),
+    ]
+}
+
+/// Hai separated by spaces, which need no quoting in CSV.
+fn hai_cell<'a>(hai_iter: impl Iterator<Item = &'a Hai>) -> String {
+    hai_iter
+        .map(|hai| hai.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Empty if there is no value.
+fn optional_cell(value: Option<f64>) -> String {
+    value.map_or(String::new(), |value| format!("{:.2}", value))
+}
@@ -0,0 +1,117 @@
+//! Reading input lines, with history and completion when editing in a terminal.
+
+use super::command::COMMAND_SPECS;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, Context, Editor, Helper,
+};
+use std::io::{stdin, stdout, Write};
+
+/// Source of input lines.
+///
+/// # Member
+/// * Editor: Lines edited in a terminal, with arrow-key history and tab completion.
+/// * Stdin: Lines read from stdin as is, such as from a pipe.
+pub enum LineReader {
+    Editor(Editor<InputHelper>),
+    Stdin,
+}
+
+/// Completion of names of commands and types of hai.
+pub struct InputHelper;
+
+impl LineReader {
+    /// Create a reader editing lines if `editing`, which should be whether stdin is a
+    /// terminal.
+    pub fn new(editing: bool) -> Self {
+        if editing {
+            let mut editor = Editor::new();
+            editor.set_helper(Some(InputHelper));
+            LineReader::Editor(editor)
+        } else {
+            LineReader::Stdin
+        }
+    }
+
+    /// Read a line after printing `prompt`, `Ok(None)` at the end of input.
+    pub fn read_line(&mut self, prompt: &str) -> Result<Option<String>, String> {
+        match self {
+            LineReader::Editor(editor) => match editor.readline(prompt) {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        editor.add_history_entry(line.trim());
+                    }
+                    Ok(Some(line))
+                }
+                // Ctrl-C drops the line being edited, like a shell.
+                Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
+                Err(ReadlineError::Eof) => Ok(None),
+                Err(error) => Err(format!("Failed to read input: {}.", error)),
+            },
+            LineReader::Stdin => {
+                print!("{}", prompt);
+                stdout().flush().unwrap();
+                let mut input = String::new();
+                match stdin().read_line(&mut input) {
+                    Ok(0) => Ok(None),
+                    Ok(_) => Ok(Some(input)),
+                    Err(_) => Err("Failed to read input.".to_string()),
+                }
+            }
+        }
+    }
+}
+
+impl Completer for InputHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(' ').map_or(0, |index| index + 1);
+        let word = &line[start..pos];
+
+        // Numbers waiting for a type are completed with types of hai, like `123` to `123m`.
+        if word.ends_with(|chr: char| chr.is_ascii_digit()) {
+            let numbers: Vec<char> = word
+                .chars()
+                .rev()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            let mut candidate_vec = vec![];
+            for hai_type in ['m', 'p', 's', 'z'].iter() {
+                if *hai_type == 'z' && numbers.iter().any(|number| *number > '7') {
+                    continue;
+                }
+                candidate_vec.push(format!("{}{}", word, hai_type));
+            }
+            return Ok((start, candidate_vec));
+        }
+
+        // The first word is completed with names of commands.
+        let mut candidate_vec = vec![];
+        if start == 0 && !word.is_empty() {
+            for spec in COMMAND_SPECS {
+                for name in spec.names {
+                    if name.starts_with(word) && name.starts_with(char::is_alphabetic) {
+                        candidate_vec.push(name.to_string());
+                    }
+                }
+            }
+        }
+        Ok((start, candidate_vec))
+    }
+}
+
+impl Hinter for InputHelper {
+    type Hint = String;
+}
+
+impl Highlighter for InputHelper {}
+
+impl Validator for InputHelper {}
+
+impl Helper for InputHelper {}
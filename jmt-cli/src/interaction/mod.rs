@@ -0,0 +1,11 @@
+mod color;
+mod command;
+mod controller;
+mod csv;
+mod editor;
+mod table;
+
+use command::{Command, CommandSpec, LedgerOperation};
+pub use controller::{Controller, OutputFormat};
+pub use editor::LineReader;
+use table::Table;
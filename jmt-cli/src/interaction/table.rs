@@ -0,0 +1,94 @@
+/// Text table whose columns are aligned by display width, so that CJK characters taking
+/// two columns in terminal do not break the alignment.
+#[derive(Clone, Debug)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(header: Vec<String>) -> Self {
+        Self {
+            header,
+            rows: vec![],
+        }
+    }
+
+    /// Add a row. Missing cells are printed empty.
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut widths: Vec<usize> = self.header.iter().map(|cell| display_width(cell)).collect();
+        for row in &self.rows {
+            for (index, cell) in row.iter().enumerate() {
+                if index < widths.len() {
+                    widths[index] = widths[index].max(display_width(cell));
+                }
+            }
+        }
+
+        let format_row = |row: &Vec<String>| -> String {
+            let mut cells = vec![];
+            for (index, width) in widths.iter().enumerate() {
+                let cell = row.get(index).map_or("", |cell| cell.as_str());
+                cells.push(format!(
+                    "{}{}",
+                    cell,
+                    " ".repeat(width - display_width(cell))
+                ));
+            }
+            cells.join(" | ").trim_end().to_string()
+        };
+
+        let mut separator = vec![];
+        for width in &widths {
+            separator.push("-".repeat(*width));
+        }
+
+        let mut table_string = format_row(&self.header);
+        table_string += "\n";
+        table_string += &separator.join("-+-");
+        for row in &self.rows {
+            table_string += "\n";
+            table_string += &format_row(row);
+        }
+        write!(f, "{}", table_string)
+    }
+}
+
+/// Width of string in terminal, in which east asian wide and fullwidth characters take
+/// two columns, and ANSI escape sequences take none.
+pub fn display_width(string: &str) -> usize {
+    let mut escaping = false;
+    string
+        .chars()
+        .map(|c| match c as u32 {
+            _ if escaping => {
+                escaping = !c.is_ascii_alphabetic();
+                0
+            }
+            0x1B => {
+                escaping = true;
+                0
+            }
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
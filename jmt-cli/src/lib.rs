@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+
+//! Command line interface of `jmt-core`, with the services on it as features.
+//!
+//! * server: WebSocket endpoint streaming analysis, see `server`.
+//! * grpc: gRPC service of `proto/japanese_mahjong_theory.proto`, see `grpc`.
+
+pub mod config;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod interaction;
+#[cfg(feature = "server")]
+pub mod server;
@@ -0,0 +1,242 @@
+#![forbid(unsafe_code)]
+
+use clap::{App, Arg};
+use jmt_cli::{config, interaction};
+use jmt_core::{game, i18n};
+use std::{
+    io::{stdin, stdout, IsTerminal},
+    process,
+};
+
+fn main() {
+    process::exit(match run_application() {
+        Ok(_) => 0,
+        Err(_) => 1,
+    })
+}
+
+fn run_application() -> Result<(), ()> {
+    // Handle program arguments.
+    let app = App::new("Japanese Mahjong Theory")
+        .version("1.18")
+        .author("Nichts Hsu <NichtsVonChaos@gmail.com>")
+        .arg(
+            Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .takes_value(true)
+                .value_name("format_type")
+                .help("Set output format: standard, table, json, csv"),
+        )
+        .arg(
+            Arg::with_name("players")
+                .short("p")
+                .long("player")
+                .takes_value(true)
+                .value_name("players_number")
+                .help("Set players number, 3 or 4"),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .takes_value(true)
+                .value_name("when")
+                .help(
+                    "Color output: auto, always, never. Auto colors only if output is a terminal",
+                ),
+        )
+        .arg(
+            Arg::with_name("lang")
+                .long("lang")
+                .takes_value(true)
+                .value_name("locale")
+                .help("Set language of messages: en, ja, zh"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .value_name("path")
+                .help("Load configuration file instead of ~/.config/jmt/config.toml"),
+        )
+        .arg(
+            Arg::with_name("scene")
+                .long("scene")
+                .takes_value(true)
+                .value_name("scene")
+                .help("Analyze a situation like 'hand=123m456p78s1155z37z; dora=3p' and exit"),
+        )
+        .arg(
+            Arg::with_name("interactive")
+                .short("i")
+                .long("interactive")
+                .help("Start with interactive mode"),
+        );
+    #[cfg(feature = "server")]
+    let app = app.arg(
+        Arg::with_name("serve")
+            .long("serve")
+            .takes_value(true)
+            .value_name("address")
+            .help("Serve WebSocket analysis at the address, such as 127.0.0.1:9000"),
+    );
+    #[cfg(feature = "grpc")]
+    let app = app.arg(
+        Arg::with_name("grpc")
+            .long("grpc")
+            .takes_value(true)
+            .value_name("address")
+            .help("Serve gRPC at the address, such as 127.0.0.1:50051"),
+    );
+    let matches = app.get_matches();
+
+    // Arguments override the configuration.
+    let config = match config::Config::load(matches.value_of("config")) {
+        Ok(config) => config,
+        Err(error) => {
+            println!("{}", error);
+            return Err(());
+        }
+    };
+
+    let output_format =
+        if let Some(format_type) = matches.value_of("format").or(config.format.as_deref()) {
+            if format_type == "standard" {
+                interaction::OutputFormat::Standard
+            } else if format_type == "table" {
+                interaction::OutputFormat::Table
+            } else if format_type == "json" {
+                interaction::OutputFormat::Json
+            } else if format_type == "csv" {
+                interaction::OutputFormat::Csv
+            } else {
+                println!("Unknown format type: {}.", format_type);
+                return Err(());
+            }
+        } else {
+            interaction::OutputFormat::Standard
+        };
+
+    let player_number =
+        if let Some(players_number) = matches.value_of("players").or(config.player.as_deref()) {
+            if let Ok(players_number) = players_number.parse::<u32>() {
+                match players_number {
+                    3 => game::PlayerNumber::Three,
+                    4 => game::PlayerNumber::Four,
+                    _ => {
+                        println!("Not support {}-players mode.", players_number);
+                        return Err(());
+                    }
+                }
+            } else {
+                println!("Unparsed argument: {}.", players_number);
+                return Err(());
+            }
+        } else {
+            game::PlayerNumber::Four
+        };
+
+    let color = match matches.value_of("color").or(config.color.as_deref()) {
+        None | Some("auto") => stdout().is_terminal(),
+        Some("always") => true,
+        Some("never") => false,
+        Some(when) => {
+            println!("Unknown color option: {}.", when);
+            return Err(());
+        }
+    };
+
+    if let Some(locale) = matches.value_of("lang").or(config.lang.as_deref()) {
+        match i18n::Locale::from_string(locale) {
+            Ok(locale) => i18n::set_locale(locale),
+            Err(error) => {
+                println!("{}", error);
+                return Err(());
+            }
+        }
+    }
+
+    #[cfg(feature = "server")]
+    if let Some(address) = matches.value_of("serve") {
+        return jmt_cli::server::serve(address, player_number)
+            .map_err(|error| println!("{}", error));
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(address) = matches.value_of("grpc") {
+        return jmt_cli::grpc::serve(address).map_err(|error| println!("{}", error));
+    }
+
+    let interactive = matches.is_present("interactive");
+
+    // Initialize controller.
+    let mut controller =
+        interaction::Controller::new(output_format, player_number, interactive, color);
+    controller.set_rules(config.rules);
+    if let Some(threshold) = config.mistake_threshold {
+        controller.set_mistake_threshold(threshold);
+    }
+    if let Some(order) = &config.order {
+        match order.parse::<game::ConditionOrder>() {
+            Ok(order) => {
+                controller.set_analyze_options(game::AnalyzeOptions { order });
+            }
+            Err(error) => {
+                println!("{}", error);
+                return Err(());
+            }
+        }
+    }
+    if let Some(path) = &config.deal_in_table {
+        match std::fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read {}: {}.", path, error))
+            .and_then(|text| game::DealInTable::parse(&text))
+        {
+            Ok(table) => {
+                controller.set_deal_in_table(table);
+            }
+            Err(error) => {
+                println!("{}", error);
+                return Err(());
+            }
+        }
+    }
+
+    if let Some(scene) = matches.value_of("scene") {
+        let mut exit = false;
+        return if controller.execute(scene.to_string(), &mut exit) {
+            Ok(())
+        } else {
+            Err(())
+        };
+    }
+
+    // Edit lines only if typed in a terminal, so piped input is read as is.
+    let mut line_reader = interaction::LineReader::new(stdin().is_terminal());
+
+    // Main loop
+    loop {
+        let input = match line_reader.read_line(controller.prompt()) {
+            Ok(Some(input)) => input,
+            // End of input is the same as exit.
+            Ok(None) => break Ok(()),
+            Err(error) => {
+                match controller.output_format() {
+                    interaction::OutputFormat::Standard | interaction::OutputFormat::Table => {
+                        println!("{}", error)
+                    }
+                    interaction::OutputFormat::Json => {
+                        println!("{}", serde_json::json!({ "error": error }))
+                    }
+                    interaction::OutputFormat::Csv => eprintln!("{}", error),
+                }
+                break Err(());
+            }
+        };
+        let mut exit = false;
+        controller.execute(input.trim().to_string(), &mut exit);
+        if exit {
+            break Ok(());
+        }
+    }
+}
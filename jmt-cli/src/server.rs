@@ -0,0 +1,156 @@
+//! WebSocket endpoint streaming analysis to browser GUIs.
+//!
+//! Each message from a client is a JSON object `{"operation": "+4m"}`, in the notation of
+//! interactive commands, and is answered with the state of the game after it, with analysis
+//! when tehai is full with hai:
+//!
+//! ```json
+//! {"session": "5f0c...", "events": [...], "game": {...}, "analysis": {...}}
+//! ```
+//!
+//! Errors are answered with `{"error": "..."}`, leaving the game unchanged. Connect with
+//! `?session=<id>` to resume a session after reconnecting, otherwise a new session starts,
+//! whose ID is in every answer. IDs are random, since anyone knowing one can take over the
+//! session.
+
+use jmt_core::game::{self, PlayerNumber, SessionManager, State};
+use serde_json::{json, Value};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+use tungstenite::{
+    handshake::server::{Request, Response},
+    Message,
+};
+
+/// Sessions without connection for this long are dropped.
+const SESSION_LIFETIME: Duration = Duration::from_secs(30 * 60);
+
+/// Serve the endpoint at `address` such as `127.0.0.1:9000`, each connection on its own
+/// thread, until the listener fails.
+pub fn serve(address: &str, player_number: PlayerNumber) -> Result<(), String> {
+    let listener = TcpListener::bind(address)
+        .map_err(|error| format!("Failed to listen on {}: {}.", address, error))?;
+    let sessions = Arc::new(Mutex::new(SessionManager::new(SESSION_LIFETIME)));
+    let next_id = Arc::new(AtomicU64::new(1));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        sessions.lock().unwrap().expire();
+        let sessions = sessions.clone();
+        let next_id = next_id.clone();
+        thread::spawn(move || {
+            // A connection closed by the client is not an error of the server.
+            let _ = connect(stream, &sessions, &next_id, player_number);
+        });
+    }
+    Ok(())
+}
+
+/// Handle a connection until it is closed.
+fn connect(
+    stream: TcpStream,
+    sessions: &Mutex<SessionManager>,
+    next_id: &AtomicU64,
+    player_number: PlayerNumber,
+) -> Result<(), String> {
+    let mut requested_id = None;
+    let mut socket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        requested_id = request.uri().query().and_then(|query| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("session="))
+                .map(|id| id.to_string())
+        });
+        Ok(response)
+    })
+    .map_err(|error| error.to_string())?;
+
+    let id = {
+        let mut sessions = sessions.lock().unwrap();
+        match requested_id {
+            Some(id) if sessions.get_mut(&id).is_ok() => id,
+            _ => {
+                let id = session_id(next_id.fetch_add(1, Ordering::Relaxed));
+                sessions.create(&id, player_number)?.enable_events();
+                id
+            }
+        }
+    };
+
+    loop {
+        let answer = match socket.read().map_err(|error| error.to_string())? {
+            Message::Text(text) => answer(&text, &id, sessions),
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        socket
+            .send(Message::Text(answer.to_string()))
+            .map_err(|error| error.to_string())?;
+    }
+}
+
+/// Unguessable ID of the `count`-th session, hashing it by keys which std draws from the
+/// randomness of the OS. The count keeps IDs in the same process different.
+fn session_id(count: u64) -> String {
+    let hash = |half: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(count);
+        hasher.write_u64(half);
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", hash(0), hash(1))
+}
+
+/// Operate the game of the session by a message, and answer the result.
+fn answer(text: &str, id: &str, sessions: &Mutex<SessionManager>) -> Value {
+    let operation = match serde_json::from_str::<Value>(text) {
+        Ok(message) => match message.get("operation").and_then(Value::as_str) {
+            Some(operation) => operation.to_string(),
+            None => return json!({ "error": "Message must have 'operation' string." }),
+        },
+        Err(error) => return json!({ "error": format!("Invalid JSON: {}.", error) }),
+    };
+
+    // Analyze a snapshot after releasing the lock, so slow analysis of one session does not
+    // block others.
+    let (game_manager, events) = {
+        let mut sessions = sessions.lock().unwrap();
+        let game_manager = match sessions.get_mut(id) {
+            Ok(game_manager) => game_manager,
+            Err(error) => return json!({ "error": error }),
+        };
+        let result = game::Operation::parse(&operation, game_manager.player_number())
+            .and_then(|operation| game_manager.operate(operation));
+        if let Err(error) = result {
+            return json!({ "error": error });
+        }
+        let events = game_manager.drain_events();
+        (game_manager.clone(), events)
+    };
+
+    let analysis = match game_manager.state {
+        State::FullHai => match game::analyzer::report(&game_manager) {
+            Ok(report) => report.to_json(),
+            Err(error) => json!({ "error": error }),
+        },
+        _ => Value::Null,
+    };
+    json!({
+        "session": id,
+        "events": events.iter().map(|event| event.to_json()).collect::<Vec<_>>(),
+        "game": game_manager.to_json(),
+        "analysis": analysis,
+    })
+}
@@ -0,0 +1,552 @@
+//! Han, fu and points of every machihai of a tenpai tehai, by ron and by tsumo, so that what
+//! each machihai is worth can be told exactly.
+//!
+//! Yaku of situations other than riichi and menzen tsumo, such as ippatsu, haitei and rinshan
+//! kaihou, are ignored, and so are akadora, honba and riichi sticks. Each yakuman counts as 13
+//! han, so multiple yakuman only score more with `aotenjou`. When a hand can be read in more
+//! than one way, the way scoring the most is taken.
+
+use super::{
+    yaku::{self, Block, Yaku, YakuContext},
+    GameManager, Hai, Hourakei, Kaze, Score, State, Tehai,
+};
+use crate::i18n::{self, Message};
+use serde_json::json;
+
+/// A hand won with a machihai, read in the way scoring the most.
+///
+/// # Japanese
+/// * Agari: 和了
+///
+/// # Member
+/// * agarihai: The hai won with.
+/// * tsumo: Won by tsumo, otherwise by ron.
+/// * yaku: Yaku of the hand, empty if it has none and so cannot win.
+/// * dora: Number of dora, which only counts with some yaku other than yakuman.
+/// * han: Han of yaku and dora.
+/// * fu: Fu before rounding up.
+/// * score: Points of the hand, `None` without yaku.
+#[derive(Clone, Debug)]
+pub struct Agari {
+    pub agarihai: Hai,
+    pub tsumo: bool,
+    pub yaku: Vec<Yaku>,
+    pub dora: u32,
+    pub han: u32,
+    pub fu: u32,
+    pub score: Option<Score>,
+}
+
+/// Value of a machihai.
+///
+/// # Member
+/// * machihai: The hai to win with.
+/// * nokori: Number of the machihai remaining in haiyama.
+/// * agari_tehai: Tehai after winning, including the machihai.
+/// * ron: The hand won by ron.
+/// * tsumo: The hand won by tsumo.
+#[derive(Clone, Debug)]
+pub struct MachiValue {
+    pub machihai: Hai,
+    pub nokori: u8,
+    pub agari_tehai: Tehai,
+    pub ron: Agari,
+    pub tsumo: Agari,
+}
+
+/// Value of every machihai of tenpai tehai, in order of hai.
+#[derive(Clone, Debug)]
+pub struct AgariTable {
+    pub tehai: Tehai,
+    pub machi: Vec<MachiValue>,
+}
+
+/// How the agarihai completes the hand.
+///
+/// # Japanese
+/// * Ryanmen: 両面
+/// * Kanchan: 嵌張
+/// * Penchan: 辺張
+/// * Shanpon: 双碰
+/// * Tanki: 単騎
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Wait {
+    Ryanmen,
+    Kanchan,
+    Penchan,
+    Shanpon,
+    Tanki,
+}
+
+impl Agari {
+    /// Read tehai won with `agarihai` in every way, and keep the one scoring the most.
+    ///
+    /// # Parameters
+    /// * tehai: Tehai with `3k + 1` juntehai, which wins with `agarihai`.
+    /// * game_manager: Source of kaze, dora, riichi and rules.
+    pub fn new(
+        tehai: &Tehai,
+        agarihai: &Hai,
+        tsumo: bool,
+        game_manager: &GameManager,
+    ) -> Result<Self, String> {
+        let mut agari_tehai = tehai.clone();
+        agari_tehai.juntehai.push(*agarihai);
+        agari_tehai.juntehai.sort();
+        let no_yaku = Self {
+            agarihai: *agarihai,
+            tsumo,
+            yaku: vec![],
+            dora: 0,
+            han: 0,
+            fu: 0,
+            score: None,
+        };
+
+        let fuuro_blocks = Block::fuuro_vec(tehai);
+        let mut best = no_yaku.clone();
+        for decomposer in agari_tehai.decompositions(game_manager.player_number()) {
+            if decomposer.shanten_number() != -1 {
+                continue;
+            }
+            let readings = match decomposer.hourakei() {
+                Hourakei::Mentsute => {
+                    let pair = match decomposer.toitsu_vec().first() {
+                        Some(toitsu) => toitsu.0.to_index(),
+                        None => continue,
+                    };
+                    let mut blocks = fuuro_blocks.clone();
+                    blocks.extend(decomposer.mentsu_vec().iter().map(Block::new));
+                    // The agarihai completes the pair or one of the mentsu in juntehai.
+                    let mut readings = vec![];
+                    if pair == agarihai.to_index() {
+                        readings.push(no_yaku.read(
+                            &agari_tehai,
+                            pair,
+                            &blocks,
+                            None,
+                            game_manager,
+                        )?);
+                    }
+                    for index in fuuro_blocks.len()..blocks.len() {
+                        if blocks[index].contains(agarihai.to_index()) {
+                            readings.push(no_yaku.read(
+                                &agari_tehai,
+                                pair,
+                                &blocks,
+                                Some(index),
+                                game_manager,
+                            )?);
+                        }
+                    }
+                    readings
+                }
+                hourakei => vec![no_yaku.read_special(&agari_tehai, hourakei, game_manager)?],
+            };
+            for reading in readings {
+                if reading.key() > best.key() {
+                    best = reading;
+                }
+            }
+        }
+        Ok(best)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let yaku_json: Vec<serde_json::Value> = self.yaku.iter().map(Yaku::to_json).collect();
+        json!({
+            "agarihai": self.agarihai.to_string(),
+            "tsumo": self.tsumo,
+            "yaku": yaku_json,
+            "dora": self.dora,
+            "han": self.han,
+            "fu": self.fu,
+            "score": self.score.map(|score| score.to_json()),
+        })
+    }
+
+    /// Order of value, by base points and then han and fu.
+    fn key(&self) -> (u64, u32, u32) {
+        match &self.score {
+            Some(score) => (score.base, self.han, self.fu),
+            None => (0, 0, 0),
+        }
+    }
+
+    /// Read a mentsute hand completed at `winning`, the index of a block, or the pair if
+    /// `None`.
+    fn read(
+        &self,
+        agari_tehai: &Tehai,
+        pair: usize,
+        blocks: &[Block],
+        winning: Option<usize>,
+        game_manager: &GameManager,
+    ) -> Result<Self, String> {
+        let menzen = agari_tehai.is_menzen();
+        let agari = self.agarihai.to_index();
+        let wait = match winning.map(|index| blocks[index]) {
+            None => Wait::Tanki,
+            Some(block) if !block.juntsu => Wait::Shanpon,
+            Some(block) if agari == block.index + 1 => Wait::Kanchan,
+            Some(block)
+                if (agari == block.index + 2 && block.index % 9 == 0)
+                    || (agari == block.index && block.index % 9 == 6) =>
+            {
+                Wait::Penchan
+            }
+            Some(_) => Wait::Ryanmen,
+        };
+        // Koutsu completed by ron is not concealed.
+        let mut blocks = blocks.to_vec();
+        if let (Some(index), false) = (winning, self.tsumo) {
+            if !blocks[index].juntsu {
+                blocks[index].concealed = false;
+            }
+        }
+
+        let counts = yaku::all_counts(agari_tehai);
+        let context = self.context(game_manager);
+        let yaku = yaku::mentsute_yaku(
+            &counts,
+            pair,
+            &blocks,
+            menzen,
+            wait == Wait::Ryanmen,
+            &context,
+        );
+        let pinfu = yaku.contains(&Yaku::Pinfu);
+        let fu = self.fu(&blocks, pair, wait, menzen, pinfu, game_manager);
+        self.scored(yaku, dora(&counts, game_manager), fu, menzen, game_manager)
+    }
+
+    /// Read a hand of chiitoitsu or kokushimusou.
+    fn read_special(
+        &self,
+        agari_tehai: &Tehai,
+        hourakei: Hourakei,
+        game_manager: &GameManager,
+    ) -> Result<Self, String> {
+        let counts = yaku::all_counts(agari_tehai);
+        let yaku = yaku::special_yaku(&counts, hourakei, &self.context(game_manager));
+        // Fu of yakuman only matters under aotenjou, where 30 is usual.
+        let fu = if hourakei == Hourakei::Kokushimusou {
+            30
+        } else {
+            25
+        };
+        self.scored(yaku, dora(&counts, game_manager), fu, true, game_manager)
+    }
+
+    /// Fu of a mentsute hand, or of the base alone with empty `blocks` when `pinfu` is set.
+    fn fu(
+        &self,
+        blocks: &[Block],
+        pair: usize,
+        wait: Wait,
+        menzen: bool,
+        pinfu: bool,
+        game_manager: &GameManager,
+    ) -> u32 {
+        if pinfu {
+            return if self.tsumo { 20 } else { 30 };
+        }
+        let mut fu = 20;
+        if self.tsumo {
+            fu += 2;
+        } else if menzen {
+            fu += 10;
+        }
+        for block in blocks.iter().filter(|block| !block.juntsu) {
+            let mut block_fu = 2;
+            if block.has_yaochuu() {
+                block_fu *= 2;
+            }
+            if block.concealed {
+                block_fu *= 2;
+            }
+            if block.kantsu {
+                block_fu *= 4;
+            }
+            fu += block_fu;
+        }
+        let jikaze = game_manager.jikaze().to_hai().to_index();
+        let bakaze = game_manager.bakaze().to_hai().to_index();
+        if pair >= 31 {
+            fu += 2;
+        }
+        if pair == jikaze {
+            fu += 2;
+        }
+        if pair == bakaze {
+            fu += 2;
+        }
+        if let Wait::Kanchan | Wait::Penchan | Wait::Tanki = wait {
+            fu += 2;
+        }
+        // Ron of a hand with fuuro and no other fu is 30 fu.
+        if fu == 20 {
+            30
+        } else {
+            fu
+        }
+    }
+
+    /// Facts of the game for yaku of this hand.
+    fn context(&self, game_manager: &GameManager) -> YakuContext {
+        YakuContext {
+            tsumo: self.tsumo,
+            riichi: game_manager.kawa().is_riichi(),
+            jikaze: Some(game_manager.jikaze()),
+            bakaze: Some(game_manager.bakaze()),
+            kuitan_nashi: game_manager.rules().kuitan_nashi,
+        }
+    }
+
+    /// Score of the hand with `yaku`, where dora only count without yakuman.
+    fn scored(
+        &self,
+        yaku: Vec<Yaku>,
+        dora: u32,
+        fu: u32,
+        menzen: bool,
+        game_manager: &GameManager,
+    ) -> Result<Self, String> {
+        if yaku.is_empty() {
+            return Ok(Self { fu, ..self.clone() });
+        }
+        let dora = if yaku.iter().any(Yaku::is_yakuman) {
+            0
+        } else {
+            dora
+        };
+        let han = yaku.iter().map(|yaku| yaku.han(menzen)).sum::<u32>() + dora;
+        let oya = game_manager.jikaze() == Kaze::Ton;
+        Ok(Self {
+            yaku,
+            dora,
+            han,
+            fu,
+            score: Some(Score::new(han, fu, oya, &game_manager.rules())?),
+            ..self.clone()
+        })
+    }
+}
+
+impl AgariTable {
+    /// Value of every machihai of tehai lacking one hai, which must be tenpai. Machihai all in
+    /// own tehai are skipped.
+    pub fn analyze(game_manager: &GameManager) -> Result<Self, String> {
+        let tehai = game_manager
+            .tehai()
+            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+        if game_manager.state != State::LackOneHai {
+            return Err(i18n::text(Message::AgariNotLackOneHai).to_string());
+        }
+
+        let player_number = game_manager.player_number();
+        let mut machi = vec![];
+        for hai in Hai::iter_all(player_number) {
+            if tehai.count(&hai) >= 4 {
+                continue;
+            }
+            let mut agari_tehai = tehai.clone();
+            agari_tehai.juntehai.push(hai);
+            agari_tehai.juntehai.sort();
+            if !agari_tehai
+                .decompositions(player_number)
+                .any(|decomposer| decomposer.shanten_number() == -1)
+            {
+                continue;
+            }
+            machi.push(MachiValue {
+                machihai: hai,
+                nokori: game_manager.haiyama()[&hai],
+                agari_tehai,
+                ron: Agari::new(tehai, &hai, false, game_manager)?,
+                tsumo: Agari::new(tehai, &hai, true, game_manager)?,
+            });
+        }
+        if machi.is_empty() {
+            return Err(i18n::text(Message::NotTenpai).to_string());
+        }
+        Ok(Self {
+            tehai: tehai.clone(),
+            machi,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let machi_json: Vec<serde_json::Value> = self
+            .machi
+            .iter()
+            .map(|value| {
+                json!({
+                    "machihai": value.machihai.to_string(),
+                    "nokori": value.nokori,
+                    "agari_tehai": value.agari_tehai.to_json(),
+                    "ron": value.ron.to_json(),
+                    "tsumo": value.tsumo.to_json(),
+                })
+            })
+            .collect();
+        json!({
+            "tehai": self.tehai.to_json(),
+            "machi": machi_json,
+        })
+    }
+}
+
+/// Number of dora in juntehai and fuuro.
+fn dora(counts: &[u8; 34], game_manager: &GameManager) -> u32 {
+    game_manager
+        .dora()
+        .iter()
+        .map(|hai| counts[hai.to_index()] as u32)
+        .sum()
+}
+
+impl std::fmt::Display for Agari {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let way = if self.tsumo { "自摸" } else { "栄和" };
+        let score = match &self.score {
+            Some(score) => score,
+            None => return write!(f, "{} 役無し", way),
+        };
+        let points = if !self.tsumo {
+            format!("{}点", score.ron)
+        } else if score.oya {
+            format!("{}点オール", score.tsumo_ko)
+        } else {
+            format!("{}-{}点", score.tsumo_ko, score.tsumo_oya)
+        };
+        let mut string = format!("{} {}飜{}符 {}", way, score.han, score.fu, points);
+        for yaku in &self.yaku {
+            string += &format!(" {}", yaku);
+        }
+        if self.dora > 0 {
+            string += &format!(" ドラ{}", self.dora);
+        }
+        write!(f, "{}", string)
+    }
+}
+
+impl std::fmt::Display for AgariTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut string = format!("手牌：{}", self.tehai);
+        for value in &self.machi {
+            string += &format!(
+                "\n待ち {} 残り{}枚\n  {}\n  {}",
+                value.machihai, value.nokori, value.ron, value.tsumo
+            );
+        }
+        write!(f, "{}", string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{PlayerNumber, Rules};
+
+    fn agari(tehai: &str, agarihai: Hai, tsumo: bool, game_manager: &GameManager) -> Agari {
+        let tehai = Tehai::new(tehai.to_string(), PlayerNumber::Four).unwrap();
+        Agari::new(&tehai, &agarihai, tsumo, game_manager).unwrap()
+    }
+
+    /// Ko in nanba of tonpuu.
+    fn game_manager() -> GameManager {
+        let mut game_manager = GameManager::new(PlayerNumber::Four);
+        game_manager.set_kaze(Kaze::Nan, Kaze::Ton);
+        game_manager
+    }
+
+    #[test]
+    fn pinfu_tsumo_20_fu() {
+        let agari = agari("234m567p345s99m23s", Hai::Souzu(1), true, &game_manager());
+        assert_eq!(agari.yaku, vec![Yaku::MenzenTsumo, Yaku::Pinfu]);
+        assert_eq!(agari.fu, 20);
+        assert_eq!(agari.score.unwrap().fu, 20);
+    }
+
+    #[test]
+    fn open_hand_without_fu() {
+        let game_manager = game_manager();
+        let ron = agari("[234m]567p345s88m34s", Hai::Souzu(5), false, &game_manager);
+        assert_eq!(ron.yaku, vec![Yaku::Tanyao]);
+        assert_eq!(ron.fu, 30);
+        // Tsumo has 2 fu, rounded up to 30 as well.
+        let tsumo = agari("[234m]567p345s88m34s", Hai::Souzu(5), true, &game_manager);
+        assert_eq!(tsumo.fu, 22);
+        assert_eq!(tsumo.score.unwrap().fu, 30);
+    }
+
+    #[test]
+    fn ron_on_shanpon_breaks_suuankou() {
+        let game_manager = game_manager();
+        let ron = agari("111m333p555s99s77z", Hai::Souzu(9), false, &game_manager);
+        assert!(ron.yaku.contains(&Yaku::Sanankou));
+        assert!(ron.yaku.contains(&Yaku::Toitoi));
+        assert!(!ron.yaku.contains(&Yaku::Suuankou));
+        // 20 + 10 of menzen ron, 8 of concealed 111m, 4 of each concealed 333p and 555s, 4 of
+        // 999s completed by ron and 2 of the pair of sangenpai.
+        assert_eq!(ron.fu, 20 + 10 + 8 + 4 + 4 + 4 + 2);
+        let tsumo = agari("111m333p555s99s77z", Hai::Souzu(9), true, &game_manager);
+        assert_eq!(tsumo.yaku, vec![Yaku::Suuankou]);
+    }
+
+    #[test]
+    fn concealed_yaochuu_kantsu_32_fu() {
+        let game_manager = game_manager();
+        let tehai = "234p567p88s34s[(1111m)]";
+        let tsumo = agari(tehai, Hai::Souzu(2), true, &game_manager);
+        assert_eq!(tsumo.yaku, vec![Yaku::MenzenTsumo]);
+        assert_eq!(tsumo.fu, 20 + 2 + 32);
+        assert_eq!(tsumo.score.unwrap().fu, 60);
+        // No yaku by ron without riichi.
+        let ron = agari(tehai, Hai::Souzu(2), false, &game_manager);
+        assert!(ron.score.is_none());
+    }
+
+    #[test]
+    fn double_wind_pair() {
+        let mut game_manager = game_manager();
+        game_manager.set_kaze(Kaze::Ton, Kaze::Ton);
+        let agari = agari("234m567p345s23s11z", Hai::Souzu(1), true, &game_manager);
+        // Not pinfu with a pair of yakuhai.
+        assert_eq!(agari.yaku, vec![Yaku::MenzenTsumo]);
+        assert_eq!(agari.fu, 20 + 2 + 4);
+        // Ton is jikaze and so oya.
+        assert_eq!(agari.score.unwrap().fu, 30);
+        assert_eq!(agari.score.unwrap().tsumo_ko, 500);
+    }
+
+    #[test]
+    fn kuitan_nashi() {
+        let mut game_manager = game_manager();
+        let tehai = "[234m]567p345s88m34s";
+        assert!(agari(tehai, Hai::Souzu(5), false, &game_manager)
+            .score
+            .is_some());
+        game_manager.set_rules(Rules {
+            kuitan_nashi: true,
+            ..Rules::default()
+        });
+        let agari = agari(tehai, Hai::Souzu(5), false, &game_manager);
+        assert!(agari.yaku.is_empty());
+        assert!(agari.score.is_none());
+    }
+
+    #[test]
+    fn ryanpeikou_over_chiitoitsu() {
+        let ryanpeikou = agari("223344m556677p5s", Hai::Souzu(5), false, &game_manager());
+        assert_eq!(ryanpeikou.yaku, vec![Yaku::Tanyao, Yaku::Ryanpeikou]);
+        assert_eq!(ryanpeikou.han, 4);
+        // 20 + 10 of menzen ron and 2 of tanki.
+        assert_eq!(ryanpeikou.fu, 32);
+        // Read only as chiitoitsu without two iipeikou.
+        let chiitoitsu = agari("113344m556677p5s", Hai::Souzu(5), false, &game_manager());
+        assert_eq!(chiitoitsu.yaku, vec![Yaku::Chiitoitsu]);
+        assert_eq!(chiitoitsu.fu, 25);
+    }
+}
@@ -0,0 +1,436 @@
+//! Analysis of tehai full with hai in one structure, bundling shanten, conditions and
+//! everything judged from the game, instead of calling each analysis and stitching results.
+
+use super::{
+    opponent, riichi, yaku, Cancellation, DealInTable, GameManager, Hai, Haiyama, Kaze,
+    MachiCondition, PlayerNumber, RiichiSafety, Rules, Safety, Seat, Situation, State, Tehai,
+    TradeOff, Warning, YakumanChance,
+};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Time budget of kairyouhai analysis.
+pub const KAIRYOU_TIME_BUDGET: Duration = Duration::from_secs(3);
+
+/// Number of draws in a whole game, used if the game is unknown.
+const GAME_DRAWS: usize = 18;
+
+/// Order of conditions in a report. Conditions equal by the order keep the order by speed.
+///
+/// # Member
+/// * Speed: Expected draws until tenpai, then acceptance, as `Tehai::analyze()` sorts.
+/// * Acceptance: Number of unseen machihai, the most first.
+/// * Hai: Order of sutehai, like `1m` before `9s` before `1z`.
+/// * Safety: Order of `AnalysisReport::safety`, the safest first. Same as speed without
+/// the game, where safety is unknown.
+/// * Custom: Any comparison of conditions given by front-ends.
+#[derive(Clone, Default)]
+pub enum ConditionOrder {
+    #[default]
+    Speed,
+    Acceptance,
+    Hai,
+    Safety,
+    Custom(Arc<CompareConditions>),
+}
+
+/// Comparison of conditions for a custom order.
+pub type CompareConditions = dyn Fn(&MachiCondition, &MachiCondition) -> Ordering + Send + Sync;
+
+/// Options of analysis, which only change the presentation of a report.
+///
+/// # Member
+/// * order: Order of conditions in the report.
+#[derive(Clone, Debug, Default)]
+pub struct AnalyzeOptions {
+    pub order: ConditionOrder,
+}
+
+/// Result of analyzing tehai full with hai.
+///
+/// # Japanese
+/// * dora: ドラ
+/// * furiten: 振り聴
+///
+/// # Member
+/// * tehai: The tehai analyzed.
+/// * shanten: Shanten after the best sutehai, -1 if won.
+/// * conditions: Conditions of sutehai sorted from the best, with kairyouhai if close to
+/// tenpai, and whether the tenpai has no yaku.
+/// * yakuman: Chances of yakuman with potential.
+/// * trade_off: Comparison of the fastest and the most valuable sutehai if they differ.
+/// * dora: Number of dora in tehai including fuuro.
+/// * riichi_furiten: Whether a machihai is missed after riichi, so every wait is furiten.
+/// * warnings: Problems of the game found by `GameManager::validate()`, followed by
+/// sutehai likely to be mistakes.
+/// * safety: Safety of each type of hai in juntehai, sorted from the safest.
+/// * threats: Other players declared riichi or likely tenpai, with the probability of tenpai,
+/// see `opponent::threats()`.
+/// * riichi_safety: Safety of each type of hai in juntehai against every threat.
+/// * deal_in: Rate of dealing in to any threat by each type of hai in juntehai, estimated by
+/// `DealInTable`, empty if there is no threat.
+/// * yakuhai_type: Types of yakuhai, only sangenpai if kaze is unknown.
+/// * timing: Time of decomposition and evaluating machihai, with the `profiling` feature.
+#[derive(Clone, Debug)]
+pub struct AnalysisReport {
+    pub tehai: Tehai,
+    pub shanten: i32,
+    pub conditions: Vec<MachiCondition>,
+    pub yakuman: Vec<YakumanChance>,
+    pub trade_off: Option<TradeOff>,
+    pub dora: u32,
+    pub riichi_furiten: bool,
+    pub warnings: Vec<Warning>,
+    pub safety: Vec<Safety>,
+    pub threats: Vec<(Seat, f64)>,
+    pub riichi_safety: BTreeMap<Hai, Vec<(Seat, RiichiSafety)>>,
+    pub deal_in: BTreeMap<Hai, f64>,
+    pub yakuhai_type: BTreeSet<Hai>,
+    #[cfg(feature = "profiling")]
+    pub timing: super::Timing,
+}
+
+/// Analyze tehai of the game manager, which must be full with hai.
+pub fn report(game_manager: &GameManager) -> Result<AnalysisReport, String> {
+    report_with(game_manager, &AnalyzeOptions::default())
+}
+
+/// Same as `report()`, with given options.
+pub fn report_with(
+    game_manager: &GameManager,
+    options: &AnalyzeOptions,
+) -> Result<AnalysisReport, String> {
+    if game_manager.state != State::FullHai {
+        return Err(i18n::text(Message::NotFullHai).to_string());
+    }
+    let tehai = game_manager
+        .tehai()
+        .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+    let player_number = game_manager.player_number();
+    let mut report = analyze(tehai, player_number, Some(game_manager))?;
+
+    let player_count = match player_number {
+        PlayerNumber::Four => 4,
+        PlayerNumber::Three => 3,
+    };
+    let draws = riichi::tsumo_number(game_manager).div_ceil(player_count);
+    report.yakuman = YakumanChance::analyze(tehai, game_manager.haiyama(), draws);
+    report.trade_off = TradeOff::analyze(tehai, report.shanten, &report.conditions, game_manager)?;
+    report.dora = game_manager
+        .dora()
+        .iter()
+        .map(|dora| tehai.count(dora) as u32)
+        .sum();
+    report.riichi_furiten = game_manager.kawa().is_riichi_furiten();
+    if let Err(error) = game_manager.validate() {
+        report.warnings = error
+            .lines()
+            .map(|line| Warning::Inconsistent(line.to_string()))
+            .collect();
+    }
+    report.safety = Safety::analyze(game_manager)?;
+    report.threats = opponent::threats(game_manager);
+    report.riichi_safety = RiichiSafety::analyze(game_manager)?;
+    report.deal_in = DealInTable::analyze(game_manager)?;
+    report.yakuhai_type = game_manager.yakuhai_type();
+    judge_yaku(
+        &mut report,
+        player_number,
+        Some(game_manager.jikaze()),
+        Some(game_manager.bakaze()),
+        &game_manager.rules(),
+    );
+    report.warnings.extend(Warning::judge_conditions(&report));
+    if report.shanten == 0 {
+        report
+            .warnings
+            .extend(Warning::judge_riichi(&report.conditions, game_manager));
+    }
+    report.sort_conditions(&options.order);
+    Ok(report)
+}
+
+/// Analyze tehai alone, where all hai except tehai are unseen and nothing of the game is
+/// known.
+pub fn report_tehai(tehai: &Tehai, player_number: PlayerNumber) -> Result<AnalysisReport, String> {
+    report_tehai_with(tehai, player_number, &AnalyzeOptions::default())
+}
+
+/// Same as `report_tehai()`, with given options.
+pub fn report_tehai_with(
+    tehai: &Tehai,
+    player_number: PlayerNumber,
+    options: &AnalyzeOptions,
+) -> Result<AnalysisReport, String> {
+    let mut report = analyze(tehai, player_number, None)?;
+    let mut haiyama = Haiyama::new(player_number);
+    haiyama.discard_with_vec(&tehai.juntehai, true)?;
+    for mentsu in &tehai.fuuro {
+        haiyama.discard_with_vec(&mentsu.to_vec(), true)?;
+    }
+    report.yakuman = YakumanChance::analyze(tehai, &haiyama, GAME_DRAWS);
+    judge_yaku(&mut report, player_number, None, None, &Rules::default());
+    report.warnings = Warning::judge_conditions(&report);
+    report.sort_conditions(&options.order);
+    Ok(report)
+}
+
+/// Analyze each tehai alone by `report_tehai()`, in parallel with the `parallel` feature,
+/// and return reports in the same order as given.
+pub fn batch(
+    tehai_slice: &[Tehai],
+    player_number: PlayerNumber,
+) -> Vec<Result<AnalysisReport, String>> {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        tehai_slice
+            .par_iter()
+            .map(|tehai| report_tehai(tehai, player_number))
+            .collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        tehai_slice
+            .iter()
+            .map(|tehai| report_tehai(tehai, player_number))
+            .collect()
+    }
+}
+
+/// Mark tenpai conditions where no machihai gives a yaku, including dead machihai. Kaze are
+/// `None` if unknown.
+fn judge_yaku(
+    report: &mut AnalysisReport,
+    player_number: PlayerNumber,
+    jikaze: Option<Kaze>,
+    bakaze: Option<Kaze>,
+    rules: &Rules,
+) {
+    if report.shanten != 0 {
+        return;
+    }
+    for condition in &mut report.conditions {
+        let mut tehai = report.tehai.clone();
+        if tehai.discard(&condition.sutehai).is_err() {
+            continue;
+        }
+        let mut machihai = condition
+            .machihai
+            .keys()
+            .chain(condition.dead_machihai.iter())
+            .peekable();
+        condition.no_yaku = machihai.peek().is_some()
+            && machihai
+                .all(|hai| !yaku::has_yaku(&tehai, hai, player_number, jikaze, bakaze, rules));
+    }
+}
+
+/// Shanten and conditions, leaving results judged from the game empty.
+fn analyze(
+    tehai: &Tehai,
+    player_number: PlayerNumber,
+    situation: Option<&dyn Situation>,
+) -> Result<AnalysisReport, String> {
+    #[cfg(not(feature = "profiling"))]
+    let (shanten, mut conditions) = tehai.analyze(player_number, situation)?;
+    #[cfg(feature = "profiling")]
+    let (shanten, mut conditions, mut timing) = tehai.analyze_timed(player_number, situation)?;
+    #[cfg(feature = "profiling")]
+    let start = std::time::Instant::now();
+    // Kairyouhai is only worth analyzing when close to tenpai. Show what has been found if
+    // it takes too long.
+    if shanten == 0 || shanten == 1 {
+        let cancellation = Cancellation::with_budget(KAIRYOU_TIME_BUDGET);
+        tehai.analyze_kairyou(
+            &mut conditions,
+            player_number,
+            situation,
+            Some(&cancellation),
+        )?;
+    }
+    #[cfg(feature = "profiling")]
+    {
+        timing.machi += start.elapsed();
+    }
+    Ok(AnalysisReport {
+        tehai: tehai.clone(),
+        shanten,
+        conditions,
+        yakuman: vec![],
+        trade_off: None,
+        dora: 0,
+        riichi_furiten: false,
+        warnings: vec![],
+        safety: vec![],
+        threats: vec![],
+        riichi_safety: BTreeMap::new(),
+        deal_in: BTreeMap::new(),
+        yakuhai_type: (5..=7).map(Hai::Jihai).collect(),
+        #[cfg(feature = "profiling")]
+        timing,
+    })
+}
+
+impl AnalysisReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut condition_json_vec = vec![];
+        for condition in &self.conditions {
+            condition_json_vec.push(condition.to_json());
+        }
+        let mut yakuman_json_vec = vec![];
+        for chance in &self.yakuman {
+            yakuman_json_vec.push(chance.to_json());
+        }
+        let mut safety_json_vec = vec![];
+        for safety in &self.safety {
+            safety_json_vec.push(safety.to_json());
+        }
+        json!({
+            "tehai": self.tehai.to_json(),
+            "shanten_number": self.shanten,
+            "conditions": condition_json_vec,
+            "yakuman": yakuman_json_vec,
+            "trade_off": self.trade_off.map(|trade_off| trade_off.to_json()),
+            "dora": self.dora,
+            "riichi_furiten": self.riichi_furiten,
+            "warnings": self
+                .warnings
+                .iter()
+                .map(|warning| warning.to_json())
+                .collect::<Vec<_>>(),
+            "safety": safety_json_vec,
+            "threats": self
+                .threats
+                .iter()
+                .map(|(seat, tenpai)| json!({ "seat": seat.name(), "tenpai": tenpai }))
+                .collect::<Vec<_>>(),
+            "riichi_safety": self
+                .riichi_safety
+                .iter()
+                .map(|(hai, seat_safety_vec)| {
+                    (
+                        hai.to_string(),
+                        seat_safety_vec
+                            .iter()
+                            .map(|(seat, safety)| {
+                                json!({ "seat": seat.name(), "safety": safety.name() })
+                            })
+                            .collect::<Vec<_>>()
+                            .into(),
+                    )
+                })
+                .collect::<serde_json::Map<String, serde_json::Value>>(),
+            "deal_in": self
+                .deal_in
+                .iter()
+                .map(|(hai, rate)| (hai.to_string(), json!(rate)))
+                .collect::<serde_json::Map<String, serde_json::Value>>(),
+            "yakuhai_type": self
+                .yakuhai_type
+                .iter()
+                .map(|hai| hai.to_string())
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Sort conditions by given order, which are sorted by speed from analysis. Only called
+    /// after everything judged from conditions, since some take the first as the best.
+    pub fn sort_conditions(&mut self, order: &ConditionOrder) {
+        let safety_vec = &self.safety;
+        let safety_rank = |condition: &MachiCondition| {
+            safety_vec
+                .iter()
+                .position(|safety| safety.hai == condition.sutehai)
+                .unwrap_or(safety_vec.len())
+        };
+        // Stable sort keeps the order by speed among equal conditions.
+        match order {
+            ConditionOrder::Speed => {}
+            ConditionOrder::Acceptance => self
+                .conditions
+                .sort_by_key(|condition| Reverse(condition.nokori())),
+            ConditionOrder::Hai => self.conditions.sort_by_key(|condition| condition.sutehai),
+            ConditionOrder::Safety => self.conditions.sort_by_key(safety_rank),
+            ConditionOrder::Custom(compare) => {
+                self.conditions.sort_by(|lhs, rhs| compare(lhs, rhs))
+            }
+        }
+    }
+
+    /// Probability of tenpai of a threat, `None` if the player is not a threat.
+    pub fn tenpai_of(&self, seat: Seat) -> Option<f64> {
+        self.threats
+            .iter()
+            .find(|(threat, _)| *threat == seat)
+            .map(|(_, tenpai)| *tenpai)
+    }
+
+    /// Safety of a sutehai against every threat with the deal-in rate, like
+    /// `上家:現物 対面(聴牌45%):筋 放銃3.0%`, where the probability of tenpai is shown for
+    /// players without riichi. Empty if there is no threat.
+    pub fn riichi_safety_string(&self, sutehai: &Hai) -> String {
+        let mut string_vec: Vec<String> =
+            self.riichi_safety
+                .get(sutehai)
+                .map_or(vec![], |seat_safety_vec| {
+                    seat_safety_vec
+                        .iter()
+                        .map(|(seat, safety)| match self.tenpai_of(*seat) {
+                            Some(tenpai) if tenpai < 1.0 => {
+                                format!("{}(聴牌{:.0}%):{}", seat, tenpai * 100.0, safety)
+                            }
+                            _ => format!("{}:{}", seat, safety),
+                        })
+                        .collect()
+                });
+        if let Some(rate) = self.deal_in.get(sutehai) {
+            string_vec.push(format!("放銃{:.1}%", rate * 100.0));
+        }
+        string_vec.join(" ")
+    }
+}
+
+impl ConditionOrder {
+    /// Value of `order` in the config file of the CLI, read back by `from_str()`, and the
+    /// `Debug` form. `custom` is only shown, since a comparison cannot be parsed.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConditionOrder::Speed => "speed",
+            ConditionOrder::Acceptance => "acceptance",
+            ConditionOrder::Hai => "hai",
+            ConditionOrder::Safety => "safety",
+            ConditionOrder::Custom(_) => "custom",
+        }
+    }
+}
+
+impl std::fmt::Debug for ConditionOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Parse an order by its name except custom.
+impl std::str::FromStr for ConditionOrder {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string {
+            "speed" => Ok(ConditionOrder::Speed),
+            "acceptance" => Ok(ConditionOrder::Acceptance),
+            "hai" => Ok(ConditionOrder::Hai),
+            "safety" => Ok(ConditionOrder::Safety),
+            _ => Err(format!(
+                "Unknown order '{}', expected speed, acceptance, hai or safety.",
+                string
+            )),
+        }
+    }
+}
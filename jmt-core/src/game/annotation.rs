@@ -0,0 +1,52 @@
+//! Annotations attached to operations in the history, such as "misclick" or "considering pon
+//! here", which are kept in kifu and shown when rating discards.
+
+use serde_json::json;
+
+/// An annotation of an operation.
+///
+/// # Member
+/// * Tag: A single word written with a leading `#`, like `#misclick`, for grouping.
+/// * Note: Free text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    Tag(String),
+    Note(String),
+}
+
+impl Annotation {
+    /// Create an annotation from text, which is a tag if it is a single word starting with
+    /// `#`, otherwise a note.
+    pub fn new(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Annotation can not be empty.".to_string());
+        }
+        if text.contains('\n') {
+            return Err("Annotation must be one line.".to_string());
+        }
+        match text.strip_prefix('#') {
+            Some(tag) if !tag.is_empty() && !tag.contains(char::is_whitespace) => {
+                Ok(Annotation::Tag(tag.to_string()))
+            }
+            _ => Ok(Annotation::Note(text.to_string())),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Annotation::Tag(tag) => json!({ "kind": "tag", "text": tag }),
+            Annotation::Note(note) => json!({ "kind": "note", "text": note }),
+        }
+    }
+}
+
+/// Written in the same form as `Annotation::new()` accepts.
+impl std::fmt::Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Annotation::Tag(tag) => write!(f, "#{}", tag),
+            Annotation::Note(note) => write!(f, "{}", note),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Token to abort long-running analysis, by calling `cancel()` from anywhere holding a
+/// clone of it, or by running out of time budget.
+///
+/// Analysis checks the token between steps and returns what has been found so far.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Create a token which is cancelled only by `cancel()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token which is also cancelled after given time budget.
+    pub fn with_budget(budget: Duration) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: Some(Instant::now() + budget),
+        }
+    }
+
+    /// Cancel analysis using this token or any clone of it.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether cancelled or out of time budget.
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) => Instant::now() >= deadline,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Tables of deal-in rates by category of sutehai against a riichi, from which defense
+//! estimates the risk of each sutehai.
+//!
+//! The default table has rounded rates of dealing in to one riichi, close to those commonly
+//! cited from statistics of online games. They are a rough guide regardless of the turn and
+//! kawa of the riichi player, so replace them with your own by `DealInTable::parse()`, whose
+//! text form is the same as printed:
+//!
+//! ```text
+//! # category rate
+//! musuji_456 0.12
+//! suji_28 0.03
+//! jihai_0 0.06
+//! ```
+//!
+//! # Japanese
+//! * deal in: 放銃
+//! * kata suji: 片筋, only one of both suji is genbutsu.
+//! * ryou suji: 両筋, both suji are genbutsu.
+
+use super::{opponent, GameManager, Hai, Haiyama};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+
+/// Category of a sutehai against a riichi.
+///
+/// # Member
+/// * Genbutsu: Genbutsu, which never deals in.
+/// * Suji: Suji of 1 or 9, 2 or 8, 3 or 7, given by the distance to the terminal from 1.
+/// * KataSuji: 4, 5 or 6 where only one suji is genbutsu.
+/// * RyouSuji: 4, 5 or 6 where both suji are genbutsu.
+/// * Musuji: Musuji by the distance to the terminal from 1 to 4, where 4 stands for 4, 5
+/// and 6.
+/// * Jihai: Jihai by the number visible including tehai, from 0 to 3.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DealInCategory {
+    Genbutsu,
+    Suji(u8),
+    KataSuji,
+    RyouSuji,
+    Musuji(u8),
+    Jihai(u8),
+}
+
+impl DealInCategory {
+    /// All categories in the order of tables.
+    pub fn all() -> Vec<Self> {
+        let mut categories = vec![DealInCategory::Genbutsu];
+        categories.extend((1..=3).map(DealInCategory::Suji));
+        categories.push(DealInCategory::KataSuji);
+        categories.push(DealInCategory::RyouSuji);
+        categories.extend((1..=4).map(DealInCategory::Musuji));
+        categories.extend((0..=3).map(DealInCategory::Jihai));
+        categories
+    }
+
+    /// Categorize a sutehai against a riichi with its genbutsu.
+    pub fn classify(hai: Hai, genbutsu: &HashSet<Hai>, haiyama: &Haiyama) -> Self {
+        if genbutsu.contains(&hai) {
+            return DealInCategory::Genbutsu;
+        }
+        let index = hai.to_index();
+        if hai.is_jihai() {
+            let unseen = haiyama.to_counts()[index];
+            return DealInCategory::Jihai(haiyama.capacity(&hai).saturating_sub(unseen).min(3));
+        }
+        // Suji not in the game, such as 4m of 3 players, can never be waited by ryanmen.
+        let number = index % 9;
+        let is_suji = |suji: usize| {
+            let suji = Hai::from_index(suji).unwrap();
+            genbutsu.contains(&suji) || haiyama.capacity(&suji) == 0
+        };
+        let distance = number.min(8 - number) as u8 + 1;
+        match number {
+            3..=5 => match (is_suji(index - 3), is_suji(index + 3)) {
+                (true, true) => DealInCategory::RyouSuji,
+                (false, false) => DealInCategory::Musuji(4),
+                _ => DealInCategory::KataSuji,
+            },
+            0..=2 if is_suji(index + 3) => DealInCategory::Suji(distance),
+            6..=8 if is_suji(index - 3) => DealInCategory::Suji(distance),
+            _ => DealInCategory::Musuji(distance),
+        }
+    }
+
+    /// Key of the category in JSON and in each line of the text form of tables, read back by
+    /// `from_name()`.
+    pub fn name(&self) -> String {
+        match self {
+            DealInCategory::Genbutsu => "genbutsu".to_string(),
+            DealInCategory::Suji(distance) => format!("suji_{}", terminal_name(*distance)),
+            DealInCategory::KataSuji => "kata_suji_456".to_string(),
+            DealInCategory::RyouSuji => "ryou_suji_456".to_string(),
+            DealInCategory::Musuji(distance) => format!("musuji_{}", terminal_name(*distance)),
+            DealInCategory::Jihai(visible) => format!("jihai_{}", visible),
+        }
+    }
+
+    /// Parse a category from its name.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        Self::all()
+            .into_iter()
+            .find(|category| category.name() == name)
+            .ok_or(format!("Unknown category of deal-in rate: {}.", name))
+    }
+}
+
+impl std::fmt::Display for DealInCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DealInCategory::Genbutsu => write!(f, "現物"),
+            DealInCategory::Suji(distance) => write!(f, "筋{}", terminal_name(*distance)),
+            DealInCategory::KataSuji => write!(f, "片筋456"),
+            DealInCategory::RyouSuji => write!(f, "両筋456"),
+            DealInCategory::Musuji(distance) => write!(f, "無筋{}", terminal_name(*distance)),
+            DealInCategory::Jihai(visible) => write!(f, "字牌{}枚見え", visible),
+        }
+    }
+}
+
+/// Numbers at the distance to the terminal, like `28` for 2.
+fn terminal_name(distance: u8) -> &'static str {
+    match distance {
+        1 => "19",
+        2 => "28",
+        3 => "37",
+        _ => "456",
+    }
+}
+
+/// Deal-in rate of each category against one riichi.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DealInTable {
+    rates: BTreeMap<DealInCategory, f64>,
+}
+
+impl Default for DealInTable {
+    fn default() -> Self {
+        let rates = [
+            (DealInCategory::Genbutsu, 0.0),
+            (DealInCategory::Suji(1), 0.018),
+            (DealInCategory::Suji(2), 0.03),
+            (DealInCategory::Suji(3), 0.053),
+            (DealInCategory::KataSuji, 0.076),
+            (DealInCategory::RyouSuji, 0.025),
+            (DealInCategory::Musuji(1), 0.055),
+            (DealInCategory::Musuji(2), 0.075),
+            (DealInCategory::Musuji(3), 0.086),
+            (DealInCategory::Musuji(4), 0.12),
+            (DealInCategory::Jihai(0), 0.06),
+            (DealInCategory::Jihai(1), 0.03),
+            (DealInCategory::Jihai(2), 0.013),
+            (DealInCategory::Jihai(3), 0.002),
+        ];
+        Self {
+            rates: rates.iter().copied().collect(),
+        }
+    }
+}
+
+impl DealInTable {
+    /// Parse a table in text form, one `<category> <rate>` each line with rates from 0 to 1.
+    /// Lines starting with `#` are comments, and categories not given keep default rates.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut table = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() != 2 {
+                return Err(format!(
+                    "Line {}: Expected '<category> <rate>', but '{}' found.",
+                    index + 1,
+                    line
+                ));
+            }
+            let category = DealInCategory::from_name(words[0])
+                .map_err(|error| format!("Line {}: {}", index + 1, error))?;
+            let rate = words[1]
+                .parse::<f64>()
+                .map_err(|_| format!("Line {}: Invalid rate '{}'.", index + 1, words[1]))?;
+            table
+                .set(category, rate)
+                .map_err(|error| format!("Line {}: {}", index + 1, error))?;
+        }
+        Ok(table)
+    }
+
+    /// Rate of dealing in to any threat by each type of hai in juntehai, from the table of
+    /// the game manager, where rates against a player without riichi are weighted by the
+    /// probability of tenpai. Empty if there is no threat, see `opponent::threats()`.
+    pub fn analyze(game_manager: &GameManager) -> Result<BTreeMap<Hai, f64>, String> {
+        let mut rates = BTreeMap::new();
+        let threats = opponent::threats(game_manager);
+        let tehai = match game_manager.tehai() {
+            Some(tehai) if !threats.is_empty() => tehai,
+            _ => return Ok(rates),
+        };
+        let genbutsu_vec: Vec<(HashSet<Hai>, f64)> = threats
+            .iter()
+            .map(|(seat, tenpai)| (game_manager.safe_against(*seat), *tenpai))
+            .collect();
+        let table = game_manager.deal_in_table();
+        for hai in &tehai.juntehai {
+            let categories: Vec<(DealInCategory, f64)> = genbutsu_vec
+                .iter()
+                .map(|(genbutsu, tenpai)| {
+                    (
+                        DealInCategory::classify(*hai, genbutsu, game_manager.haiyama()),
+                        *tenpai,
+                    )
+                })
+                .collect();
+            rates.insert(*hai, table.combined_rate(&categories));
+        }
+        Ok(rates)
+    }
+
+    /// Deal-in rate of a category against one riichi.
+    pub fn rate(&self, category: DealInCategory) -> f64 {
+        *self.rates.get(&category).unwrap_or(&0.0)
+    }
+
+    /// Replace the rate of a category, which must be from 0 to 1.
+    pub fn set(&mut self, category: DealInCategory, rate: f64) -> Result<&mut Self, String> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!(
+                "Deal-in rate must be from 0 to 1, but {} given.",
+                rate
+            ));
+        }
+        self.rates.insert(category, rate);
+        Ok(self)
+    }
+
+    /// Rate of dealing in to any of some players, each given by the category and the
+    /// probability of tenpai, regarding them as independent.
+    pub fn combined_rate(&self, categories: &[(DealInCategory, f64)]) -> f64 {
+        1.0 - categories
+            .iter()
+            .map(|(category, tenpai)| 1.0 - self.rate(*category) * tenpai)
+            .product::<f64>()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!(self
+            .rates
+            .iter()
+            .map(|(category, rate)| (category.name(), json!(rate)))
+            .collect::<serde_json::Map<String, serde_json::Value>>())
+    }
+}
+
+/// Written in the text form `DealInTable::parse()` accepts.
+impl std::fmt::Display for DealInTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut line_vec = vec![];
+        for category in DealInCategory::all() {
+            line_vec.push(format!("{} {}", category.name(), self.rate(category)));
+        }
+        write!(f, "{}", line_vec.join("\n"))
+    }
+}
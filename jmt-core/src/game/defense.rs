@@ -0,0 +1,262 @@
+use super::{opponent, GameManager, Hai, Haiyama, Seat};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+
+/// Chance of ryanmen waiting for a hai, judged by the number of kabe hai unseen.
+///
+/// # Japanese
+/// * NoChance: ノーチャンス, all hai of a kabe are visible, so no ryanmen can wait for it.
+/// * OneChance: ワンチャンス, only one hai of a kabe is unseen.
+/// * kabe: 壁
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Chance {
+    NoChance,
+    OneChance,
+    Normal,
+}
+
+/// Safety of a type of hai in tehai against ryanmen.
+///
+/// # Member
+/// * hai: The hai to discard.
+/// * chance: `None` for hai which can never be waited by ryanmen, such as jihai.
+/// * kabe: Unseen number of each hai in ryanmen waiting for this hai, only hai with at
+/// most one unseen are listed.
+/// * nokori: Unseen number of this hai, less for safer against tanki and shanpon.
+#[derive(Clone, Debug)]
+pub struct Safety {
+    pub hai: Hai,
+    pub chance: Option<Chance>,
+    pub kabe: Vec<(Hai, u8)>,
+    pub nokori: u8,
+}
+
+/// Riichi of another player, marked by the user.
+///
+/// # Member
+/// * seat: Seat of the player.
+/// * sutehai: Sutehai declaring riichi.
+/// * since: Number of operations in history when marked. Hai discarded after it by anyone
+/// are genbutsu, since missing them is furiten, see `GameManager::safe_against()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OpponentRiichi {
+    pub seat: Seat,
+    pub sutehai: Hai,
+    pub since: usize,
+}
+
+/// Safety of a hai against a riichi, from the safest.
+///
+/// # Japanese
+/// * Genbutsu: 現物, sutehai of the riichi player or passed after riichi, never ron.
+/// * Suji: 筋, hai whose ryanmen would wait for genbutsu too, which is furiten.
+/// * Jihai: 字牌, only waited by tanki or shanpon.
+/// * NoChance: ノーチャンス, no ryanmen can wait for it, see `Chance`.
+/// * Musuji: 無筋, none of the above.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiichiSafety {
+    Genbutsu,
+    Suji,
+    Jihai,
+    NoChance,
+    Musuji,
+}
+
+impl RiichiSafety {
+    /// Safety of each type of hai in juntehai against every threat, see `opponent::threats()`.
+    /// Empty if no one has declared riichi or is likely tenpai.
+    pub fn analyze(
+        game_manager: &GameManager,
+    ) -> Result<BTreeMap<Hai, Vec<(Seat, RiichiSafety)>>, String> {
+        let tehai = game_manager
+            .tehai()
+            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+        let mut safety_map: BTreeMap<Hai, Vec<(Seat, RiichiSafety)>> = BTreeMap::new();
+        for (threat, _) in opponent::threats(game_manager) {
+            let genbutsu = game_manager.safe_against(threat);
+            for hai in &tehai.juntehai {
+                let safety = RiichiSafety::classify(*hai, &genbutsu, game_manager.haiyama());
+                let seat_safety_vec = safety_map.entry(*hai).or_default();
+                if !seat_safety_vec.iter().any(|(seat, _)| *seat == threat) {
+                    seat_safety_vec.push((threat, safety));
+                }
+            }
+        }
+        Ok(safety_map)
+    }
+
+    /// Classify a hai against a riichi with its genbutsu.
+    pub fn classify(hai: Hai, genbutsu: &HashSet<Hai>, haiyama: &Haiyama) -> Self {
+        if genbutsu.contains(&hai) {
+            return RiichiSafety::Genbutsu;
+        }
+        if hai.is_jihai() {
+            return RiichiSafety::Jihai;
+        }
+        let index = hai.to_index();
+        // Ryanmen waiting for hai also waits for the hai 3 apart, on either side.
+        let number = index % 9;
+        let mut suji_vec = vec![];
+        if number >= 3 {
+            suji_vec.push(index - 3);
+        }
+        if number <= 5 {
+            suji_vec.push(index + 3);
+        }
+        if suji_vec.iter().all(|suji| {
+            let suji = Hai::from_index(*suji).unwrap();
+            genbutsu.contains(&suji) || haiyama.capacity(&suji) == 0
+        }) {
+            return RiichiSafety::Suji;
+        }
+        match Safety::classify(hai, haiyama).chance {
+            Some(Chance::NoChance) | None => RiichiSafety::NoChance,
+            _ => RiichiSafety::Musuji,
+        }
+    }
+
+    /// Key of the safety in JSON, CSV and snapshots, which stays the same while `Display`
+    /// is for people.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RiichiSafety::Genbutsu => "genbutsu",
+            RiichiSafety::Suji => "suji",
+            RiichiSafety::Jihai => "jihai",
+            RiichiSafety::NoChance => "no_chance",
+            RiichiSafety::Musuji => "musuji",
+        }
+    }
+}
+
+impl std::fmt::Display for RiichiSafety {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiichiSafety::Genbutsu => write!(f, "現物"),
+            RiichiSafety::Suji => write!(f, "筋"),
+            RiichiSafety::Jihai => write!(f, "字牌"),
+            RiichiSafety::NoChance => write!(f, "ノーチャンス"),
+            RiichiSafety::Musuji => write!(f, "無筋"),
+        }
+    }
+}
+
+impl Safety {
+    /// Classify each type of hai in juntehai, sorted from the safest.
+    pub fn analyze(game_manager: &GameManager) -> Result<Vec<Safety>, String> {
+        let tehai = game_manager
+            .tehai()
+            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+        let haiyama = game_manager.haiyama();
+        let mut hai_vec = tehai.juntehai.clone();
+        hai_vec.sort();
+        hai_vec.dedup();
+
+        let mut safety_vec = vec![];
+        for hai in hai_vec {
+            safety_vec.push(Safety::classify(hai, haiyama));
+        }
+        safety_vec.sort_by(|lhs, rhs| {
+            // Hai never waited by ryanmen is the safest.
+            let rank = |safety: &Safety| match safety.chance {
+                None => Chance::NoChance,
+                Some(chance) => chance,
+            };
+            rank(lhs)
+                .cmp(&rank(rhs))
+                .then(lhs.nokori.cmp(&rhs.nokori))
+                .then(lhs.hai.cmp(&rhs.hai))
+        });
+        Ok(safety_vec)
+    }
+
+    /// Classify a hai by unseen hai in haiyama.
+    pub fn classify(hai: Hai, haiyama: &Haiyama) -> Self {
+        let index = hai.to_index();
+        let counts = haiyama.to_counts();
+        let mut kabe = vec![];
+        let mut max_chance = None;
+        if index < 27 {
+            let number = index % 9;
+            // Ryanmen waiting for hai is on its right side or left side, and both sides of
+            // ryanmen must be in 2 to 8.
+            let mut taatsu_vec = vec![];
+            if number <= 5 {
+                taatsu_vec.push([index + 1, index + 2]);
+            }
+            if number >= 3 {
+                taatsu_vec.push([index - 2, index - 1]);
+            }
+            for taatsu in taatsu_vec {
+                let taatsu_hai: Vec<Hai> = taatsu
+                    .iter()
+                    // Index is always less than 27 here.
+                    .map(|index| Hai::from_index(*index).unwrap())
+                    .collect();
+                // Hai not in the game, such as 2m to 8m in 3-player mode.
+                if taatsu_hai.iter().any(|hai| haiyama.capacity(hai) == 0) {
+                    continue;
+                }
+                let mut chance = u8::MAX;
+                for (hai, index) in taatsu_hai.iter().zip(taatsu.iter()) {
+                    if counts[*index] <= 1 {
+                        kabe.push((*hai, counts[*index]));
+                    }
+                    chance = chance.min(counts[*index]);
+                }
+                max_chance = Some(max_chance.map_or(chance, |max: u8| max.max(chance)));
+            }
+        }
+
+        Self {
+            hai,
+            chance: max_chance.map(|chance| match chance {
+                0 => Chance::NoChance,
+                1 => Chance::OneChance,
+                _ => Chance::Normal,
+            }),
+            kabe,
+            nokori: counts[index],
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut kabe_json_vec = vec![];
+        for (hai, number) in &self.kabe {
+            kabe_json_vec.push(json!({
+                "tile": hai.to_string(),
+                "number": number,
+            }));
+        }
+        json!({
+            "tile": self.hai.to_string(),
+            "chance": match self.chance {
+                Some(Chance::NoChance) => json!("no_chance"),
+                Some(Chance::OneChance) => json!("one_chance"),
+                Some(Chance::Normal) => json!("normal"),
+                None => json!(null),
+            },
+            "kabe": kabe_json_vec,
+            "number": self.nokori,
+        })
+    }
+}
+
+impl std::fmt::Display for Safety {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut safety_string = format!("{} 残り{}枚", self.hai, self.nokori);
+        match self.chance {
+            Some(Chance::NoChance) => safety_string += " ノーチャンス",
+            Some(Chance::OneChance) => safety_string += " ワンチャンス",
+            Some(Chance::Normal) => {}
+            None => safety_string += " 両面無し",
+        }
+        if self.kabe.len() > 0 {
+            safety_string += " 壁";
+            for (hai, number) in &self.kabe {
+                safety_string += &format!(" {}:{}", hai, number);
+            }
+        }
+        write!(f, "{}", safety_string)
+    }
+}
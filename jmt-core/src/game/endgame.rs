@@ -0,0 +1,297 @@
+//! Exact solver of discards at the end of a hand, when so few draws are left that every
+//! order of them can be enumerated.
+//!
+//! Draws are taken from unseen hai without replacement, each type by its number in
+//! haiyama, which is exact as long as hai of others are unknown. Every sutehai is tried
+//! after every draw, so the best discard maximizes the probability of winning by tsumo,
+//! then that of tenpai at ryuukyoku, without any heuristic. Tehai which can no longer
+//! reach tenpai are cut, and so are sutehai increasing shanten before the last draw, which
+//! can never be better. Positions met again are looked up by their zobrist hash, as are
+//! shanten and machihai of tehai met again with other hai unseen. Ron and calls are not
+//! considered.
+
+use super::{kernel, shanten, zobrist, Hai, Haiyama, Tehai};
+use serde_json::json;
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Number of draws of self below which analysis switches to the exact solver. Solving 2
+/// draws takes up to a second, and each more draw about twenty times as long.
+pub const EXACT_DRAWS: usize = 3;
+
+/// Value of a sutehai by the exact solver.
+///
+/// # Japanese
+/// * hora: 和了
+/// * tenpai: 聴牌
+///
+/// # Member
+/// * sutehai: The sutehai.
+/// * hora: Probability of winning by tsumo with the best play afterwards.
+/// * tenpai: Probability of tenpai at ryuukyoku or winning before it.
+#[derive(Copy, Clone, Debug)]
+pub struct EndgameValue {
+    pub sutehai: Hai,
+    pub hora: f64,
+    pub tenpai: f64,
+}
+
+/// What the best play aims at.
+///
+/// # Member
+/// * Push: Some sutehai still wins with a chance.
+/// * KeepTenpai: No sutehai can win, but some reach or keep tenpai for ryuukyoku.
+/// * Fold: No sutehai can even reach tenpai, so discard safely instead.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EndgamePlan {
+    Push,
+    KeepTenpai,
+    Fold,
+}
+
+/// Result of the exact solver.
+///
+/// # Member
+/// * draws: Number of draws of self solved.
+/// * plan: What the best play aims at.
+/// * values: Values of all types of hai in juntehai, the best first.
+#[derive(Clone, Debug)]
+pub struct Endgame {
+    pub draws: usize,
+    pub plan: EndgamePlan,
+    pub values: Vec<EndgameValue>,
+}
+
+/// State of the solver, where the position is changed in place as the tree is walked.
+struct Solver {
+    position: zobrist::Position,
+    fuuro_count: usize,
+    tables: Vec<HashMap<u64, (f64, f64), zobrist::BuildZobristHasher>>,
+    shanten_table: HashMap<u64, i32, zobrist::BuildZobristHasher>,
+    machi_table: HashMap<u64, [bool; 34], zobrist::BuildZobristHasher>,
+}
+
+/// Solve each type of sutehai of tehai full with hai exactly, with `draws` draws of self
+/// left from haiyama, which must be less than `EXACT_DRAWS`.
+pub fn solve(tehai: &Tehai, haiyama: &Haiyama, draws: usize) -> Result<Endgame, String> {
+    if tehai.juntehai.len() % 3 != 2 {
+        return Err(format!(
+            "The number of hai on hand must be 3*k+2 to solve, but {} provided.",
+            tehai.juntehai.len()
+        ));
+    }
+    if draws >= EXACT_DRAWS {
+        return Err(format!(
+            "Can only solve exactly with less than {} draws, but {} given.",
+            EXACT_DRAWS, draws
+        ));
+    }
+
+    let counts = tehai.to_counts();
+    let mut solver = Solver {
+        position: zobrist::Position::new(counts, haiyama.to_counts()),
+        fuuro_count: tehai.fuuro.len(),
+        tables: vec![HashMap::default(); draws + 1],
+        shanten_table: HashMap::default(),
+        machi_table: HashMap::default(),
+    };
+    let mut values = vec![];
+    for (index, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        solver.position.remove(index);
+        let (hora, tenpai) = solver.wait(draws);
+        solver.position.add(index);
+        values.push(EndgameValue {
+            sutehai: Hai::from_index(index).unwrap(),
+            hora,
+            tenpai,
+        });
+    }
+    // Stable sort keeps the order of hai among the same value.
+    values.sort_by(|a, b| compare(&(b.hora, b.tenpai), &(a.hora, a.tenpai)));
+    let plan = match values.first() {
+        Some(best) if best.hora > 0.0 => EndgamePlan::Push,
+        Some(best) if best.tenpai > 0.0 => EndgamePlan::KeepTenpai,
+        _ => EndgamePlan::Fold,
+    };
+    Ok(Endgame {
+        draws,
+        plan,
+        values,
+    })
+}
+
+/// Compare values by hora, then by tenpai.
+fn compare(a: &(f64, f64), b: &(f64, f64)) -> Ordering {
+    a.0.partial_cmp(&b.0)
+        .unwrap()
+        .then(a.1.partial_cmp(&b.1).unwrap())
+}
+
+impl Solver {
+    /// Probabilities of hora and tenpai of tehai waiting for a draw with `draws` left.
+    fn wait(&mut self, draws: usize) -> (f64, f64) {
+        let shanten = self.shanten();
+        if shanten as usize > draws {
+            return (0.0, 0.0);
+        }
+        let total = kernel::total(&self.position.unseen);
+        if draws == 0 || total == 0 {
+            return (0.0, if shanten == 0 { 1.0 } else { 0.0 });
+        }
+        if draws == 1 {
+            // The last draw wins or reaches tenpai only if it is machihai.
+            let machi = self.machi(shanten);
+            let rate = kernel::acceptance(&machi, &self.position.unseen) as f64 / total as f64;
+            return if shanten == 0 {
+                (rate, 1.0)
+            } else {
+                (0.0, rate)
+            };
+        }
+        let hash = self.position.hash();
+        if let Some(value) = self.tables[draws].get(&hash) {
+            return *value;
+        }
+
+        let value = {
+            let (mut hora, mut tenpai) = (0.0, 0.0);
+            for index in 0..34 {
+                let number = self.position.unseen[index];
+                if number == 0 {
+                    continue;
+                }
+                self.position.move_hai(index, true);
+                let (draw_hora, draw_tenpai) = self.choose(draws - 1);
+                self.position.move_hai(index, false);
+                hora += number as f64 * draw_hora;
+                tenpai += number as f64 * draw_tenpai;
+            }
+            (hora / total as f64, tenpai / total as f64)
+        };
+        self.tables[draws].insert(hash, value);
+        value
+    }
+
+    /// Probabilities of the best sutehai of tehai full with hai with `draws` left.
+    fn choose(&mut self, draws: usize) -> (f64, f64) {
+        // Shanten of tehai full with hai is the least after any sutehai.
+        let shanten = self.shanten();
+        if shanten == -1 {
+            return (1.0, 1.0);
+        }
+        if shanten as usize > draws {
+            return (0.0, 0.0);
+        }
+        if draws == 0 {
+            return (0.0, if shanten == 0 { 1.0 } else { 0.0 });
+        }
+        let mut best = (0.0, 0.0);
+        for index in 0..34 {
+            if self.position.counts[index] == 0 {
+                continue;
+            }
+            self.position.remove(index);
+            // Before the last draw, tehai of more shanten wins and reaches tenpai less.
+            let value = if draws == 1 && self.shanten() > shanten {
+                (0.0, 0.0)
+            } else {
+                self.wait(draws)
+            };
+            self.position.add(index);
+            if compare(&value, &best) == Ordering::Greater {
+                best = value;
+            }
+        }
+        best
+    }
+
+    fn shanten(&mut self) -> i32 {
+        let (counts, fuuro_count) = (&self.position.counts, self.fuuro_count);
+        *self
+            .shanten_table
+            .entry(self.position.tehai_hash)
+            .or_insert_with(|| shanten::calculate_counts(counts, fuuro_count))
+    }
+
+    /// Machihai of tehai waiting for a draw with `shanten`, by shanten after each draw,
+    /// which is shared with tehai full with hai reached by other paths.
+    fn machi(&mut self, shanten: i32) -> [bool; 34] {
+        if let Some(machi) = self.machi_table.get(&self.position.tehai_hash) {
+            return *machi;
+        }
+        let mut machi = [false; 34];
+        for (index, is_machi) in machi.iter_mut().enumerate() {
+            if self.position.counts[index] >= 4 {
+                continue;
+            }
+            self.position.add(index);
+            *is_machi = self.shanten() < shanten;
+            self.position.remove(index);
+        }
+        self.machi_table.insert(self.position.tehai_hash, machi);
+        machi
+    }
+}
+
+impl Endgame {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "draws": self.draws,
+            "plan": self.plan.name(),
+            "values": self
+                .values
+                .iter()
+                .map(|value| json!({
+                    "sutehai": value.sutehai.to_string(),
+                    "hora": value.hora,
+                    "tenpai": value.tenpai,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl EndgamePlan {
+    /// Value of `"plan"` in JSON of the endgame.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EndgamePlan::Push => "push",
+            EndgamePlan::KeepTenpai => "keep_tenpai",
+            EndgamePlan::Fold => "fold",
+        }
+    }
+}
+
+impl std::fmt::Display for EndgamePlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndgamePlan::Push => write!(f, "押し"),
+            EndgamePlan::KeepTenpai => write!(f, "形式聴牌"),
+            EndgamePlan::Fold => write!(f, "降り"),
+        }
+    }
+}
+
+impl std::fmt::Display for EndgameValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "打 {} 和了率 {:.1}% 聴牌率 {:.1}%",
+            self.sutehai,
+            self.hora * 100.0,
+            self.tenpai * 100.0
+        )
+    }
+}
+
+impl std::fmt::Display for Endgame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "残り{}巡 {}", self.draws, self.plan)?;
+        for value in &self.values {
+            write!(f, "\n{}", value)?;
+        }
+        Ok(())
+    }
+}
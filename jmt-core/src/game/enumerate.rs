@@ -0,0 +1,327 @@
+//! Enumeration of hands by shanten, for validating algorithms and statistical studies.
+//!
+//! Hands are juntehai only, and the number of fuuro is decided by the size, so that a hand
+//! of 13 or 14 hai has no fuuro, and a hand of 7 or 8 hai has 2 fuuro.
+//!
+//! Shanten does not change when suits are swapped or jihai are swapped, so hands are
+//! enumerated as classes of these swaps. Each class is represented by the hand with
+//! manzu, pinzu and souzu in descending order, and jihai counts in descending order.
+
+use super::{shanten, simulate::Rng, PlayerNumber};
+use serde_json::json;
+use std::rc::Rc;
+
+/// Max size of a hand.
+pub const MAX_SIZE: usize = 14;
+
+/// A class of hands which are the same after swapping suits or jihai.
+///
+/// # Member
+/// * counts: 34-index counts of the representative hand.
+/// * weight: Number of different hands in the class.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HandClass {
+    pub counts: [u8; 34],
+    pub weight: u64,
+}
+
+/// Number of fuuro for a hand of `size` hai.
+pub fn fuuro_count(size: usize) -> Result<usize, String> {
+    if size == 0 || size > MAX_SIZE || size.is_multiple_of(3) {
+        return Err(format!(
+            "The number of hai must be 3*k+1 or 3*k+2 and at most {}, but {} provided.",
+            MAX_SIZE, size
+        ));
+    }
+    Ok((MAX_SIZE - size) / 3)
+}
+
+/// Enumerate all classes of hands with `size` hai and given shanten lazily.
+///
+/// The number of classes grows fast with the size, which is feasible up to about 8 hai.
+pub fn classes(
+    size: usize,
+    shanten: i32,
+    player_number: PlayerNumber,
+) -> Result<Box<dyn Iterator<Item = HandClass>>, String> {
+    let fuuro_count = fuuro_count(size)?;
+    let suit_blocks = Rc::new(blocks(size, &[0, 1, 2, 3, 4, 5, 6, 7, 8]));
+    let jihai_blocks = Rc::new(jihai_partitions(size));
+    let iterator: Box<dyn Iterator<Item = HandClass>> = match player_number {
+        // Manzu is the same as the other suits.
+        PlayerNumber::Four => Box::new((0..=size).flat_map(move |manzu_sum| {
+            let suit_blocks = suit_blocks.clone();
+            let jihai_blocks = jihai_blocks.clone();
+            (0..=manzu_sum.min(size - manzu_sum)).flat_map(move |pinzu_sum| {
+                let suit_blocks = suit_blocks.clone();
+                let jihai_blocks = jihai_blocks.clone();
+                let rest = size - manzu_sum - pinzu_sum;
+                (0..=pinzu_sum.min(rest)).flat_map(move |souzu_sum| {
+                    let jihai_sum = rest - souzu_sum;
+                    let jihai_blocks = jihai_blocks.clone();
+                    suits(suit_blocks.clone(), Some(manzu_sum), pinzu_sum, souzu_sum).flat_map(
+                        move |(manzu, pinzu, souzu)| {
+                            let jihai_blocks = jihai_blocks.clone();
+                            (0..jihai_blocks[jihai_sum].len()).map(move |index| {
+                                class(
+                                    &manzu,
+                                    &pinzu,
+                                    &souzu,
+                                    &jihai_blocks[jihai_sum][index],
+                                    true,
+                                )
+                            })
+                        },
+                    )
+                })
+            })
+        })),
+        // Manzu has only 1m and 9m, which cannot be swapped with other suits.
+        PlayerNumber::Three => {
+            let manzu_blocks = Rc::new(blocks(size, &[0, 8]));
+            Box::new((0..=size).flat_map(move |manzu_sum| {
+                let suit_blocks = suit_blocks.clone();
+                let jihai_blocks = jihai_blocks.clone();
+                let manzu_blocks = manzu_blocks.clone();
+                (0..manzu_blocks[manzu_sum].len()).flat_map(move |manzu_index| {
+                    let manzu = manzu_blocks[manzu_sum][manzu_index];
+                    let suit_blocks = suit_blocks.clone();
+                    let jihai_blocks = jihai_blocks.clone();
+                    let rest = size - manzu_sum;
+                    (0..=rest).flat_map(move |pinzu_sum| {
+                        let suit_blocks = suit_blocks.clone();
+                        let jihai_blocks = jihai_blocks.clone();
+                        (0..=pinzu_sum.min(rest - pinzu_sum)).flat_map(move |souzu_sum| {
+                            let jihai_sum = rest - pinzu_sum - souzu_sum;
+                            let jihai_blocks = jihai_blocks.clone();
+                            suits(suit_blocks.clone(), None, pinzu_sum, souzu_sum).flat_map(
+                                move |(_, pinzu, souzu)| {
+                                    let jihai_blocks = jihai_blocks.clone();
+                                    (0..jihai_blocks[jihai_sum].len()).map(move |index| {
+                                        class(
+                                            &manzu,
+                                            &pinzu,
+                                            &souzu,
+                                            &jihai_blocks[jihai_sum][index],
+                                            false,
+                                        )
+                                    })
+                                },
+                            )
+                        })
+                    })
+                })
+            }))
+        }
+    };
+    Ok(Box::new(iterator.filter(move |class| {
+        shanten::calculate_counts(&class.counts, fuuro_count) == shanten
+    })))
+}
+
+/// Sample hands with `size` hai and given shanten, each drawn from a full haiyama, so a hand
+/// is sampled by the probability of being dealt.
+///
+/// Stop after `max_attempts` draws, so the result may have less than `number` hands.
+pub fn sample(
+    size: usize,
+    shanten: i32,
+    player_number: PlayerNumber,
+    number: usize,
+    max_attempts: usize,
+    seed: u64,
+) -> Result<Vec<[u8; 34]>, String> {
+    let fuuro_count = fuuro_count(size)?;
+    let mut yama = vec![];
+    for index in 0..34 {
+        // 2m to 8m are not in 3-player mode.
+        if let PlayerNumber::Three = player_number {
+            if index > 0 && index < 8 {
+                continue;
+            }
+        }
+        for _ in 0..4 {
+            yama.push(index);
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut hand_vec = vec![];
+    for _ in 0..max_attempts {
+        if hand_vec.len() >= number {
+            break;
+        }
+        // Partial shuffle is enough to draw the first `size` hai.
+        for drawn in 0..size {
+            let index = drawn + rng.below(yama.len() - drawn);
+            yama.swap(drawn, index);
+        }
+        let mut counts = [0u8; 34];
+        for index in &yama[..size] {
+            counts[*index] += 1;
+        }
+        if shanten::calculate_counts(&counts, fuuro_count) == shanten {
+            hand_vec.push(counts);
+        }
+    }
+    Ok(hand_vec)
+}
+
+impl HandClass {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "counts": self.counts.to_vec(),
+            "weight": self.weight,
+        })
+    }
+}
+
+/// All counts of a suit in given positions, grouped by sum up to `size`, each group in
+/// descending order.
+fn blocks(size: usize, positions: &[usize]) -> Vec<Vec<[u8; 9]>> {
+    let mut blocks = vec![vec![]; size + 1];
+    let mut block = [0u8; 9];
+    fill_block(&mut block, positions, 0, size, &mut blocks);
+    for group in blocks.iter_mut() {
+        group.sort_by(|lhs, rhs| rhs.cmp(lhs));
+    }
+    blocks
+}
+
+fn fill_block(
+    block: &mut [u8; 9],
+    positions: &[usize],
+    sum: usize,
+    size: usize,
+    blocks: &mut Vec<Vec<[u8; 9]>>,
+) {
+    match positions.split_first() {
+        None => blocks[sum].push(*block),
+        Some((position, rest)) => {
+            for count in 0..=4.min(size - sum) {
+                block[*position] = count as u8;
+                fill_block(block, rest, sum + count, size, blocks);
+            }
+            block[*position] = 0;
+        }
+    }
+}
+
+/// All counts of jihai in descending order, grouped by sum up to `size`.
+fn jihai_partitions(size: usize) -> Vec<Vec<[u8; 7]>> {
+    let mut partitions = vec![vec![]; size + 1];
+    let mut partition = [0u8; 7];
+    fill_partition(&mut partition, 0, 4, 0, size, &mut partitions);
+    partitions
+}
+
+fn fill_partition(
+    partition: &mut [u8; 7],
+    position: usize,
+    max: u8,
+    sum: usize,
+    size: usize,
+    partitions: &mut Vec<Vec<[u8; 7]>>,
+) {
+    if position == 7 {
+        partitions[sum].push(*partition);
+        return;
+    }
+    for count in (0..=max.min((size - sum) as u8)).rev() {
+        partition[position] = count;
+        fill_partition(
+            partition,
+            position + 1,
+            count,
+            sum + count as usize,
+            size,
+            partitions,
+        );
+    }
+    partition[position] = 0;
+}
+
+/// Blocks of manzu, pinzu and souzu of a hand.
+type SuitBlocks = ([u8; 9], [u8; 9], [u8; 9]);
+
+/// Blocks of suits in descending order, where manzu is not compared if `manzu_sum` is
+/// `None`.
+fn suits(
+    suit_blocks: Rc<Vec<Vec<[u8; 9]>>>,
+    manzu_sum: Option<usize>,
+    pinzu_sum: usize,
+    souzu_sum: usize,
+) -> Box<dyn Iterator<Item = SuitBlocks>> {
+    let manzu_range = match manzu_sum {
+        Some(manzu_sum) => 0..suit_blocks[manzu_sum].len(),
+        None => 0..1,
+    };
+    Box::new(manzu_range.flat_map(move |manzu_index| {
+        let suit_blocks = suit_blocks.clone();
+        let manzu = manzu_sum.map_or([0u8; 9], |sum| suit_blocks[sum][manzu_index]);
+        // Blocks with the same sum are in descending order, so later blocks are less.
+        let pinzu_start = match manzu_sum {
+            Some(manzu_sum) if manzu_sum == pinzu_sum => manzu_index,
+            _ => 0,
+        };
+        (pinzu_start..suit_blocks[pinzu_sum].len()).flat_map(move |pinzu_index| {
+            let suit_blocks = suit_blocks.clone();
+            let souzu_start = if pinzu_sum == souzu_sum {
+                pinzu_index
+            } else {
+                0
+            };
+            (souzu_start..suit_blocks[souzu_sum].len()).map(move |souzu_index| {
+                (
+                    manzu,
+                    suit_blocks[pinzu_sum][pinzu_index],
+                    suit_blocks[souzu_sum][souzu_index],
+                )
+            })
+        })
+    }))
+}
+
+/// Build the class from blocks, and count hands by swapping suits and jihai.
+fn class(
+    manzu: &[u8; 9],
+    pinzu: &[u8; 9],
+    souzu: &[u8; 9],
+    jihai: &[u8; 7],
+    manzu_swappable: bool,
+) -> HandClass {
+    let mut counts = [0u8; 34];
+    counts[0..9].copy_from_slice(manzu);
+    counts[9..18].copy_from_slice(pinzu);
+    counts[18..27].copy_from_slice(souzu);
+    counts[27..34].copy_from_slice(jihai);
+
+    let suit_weight = if manzu_swappable {
+        if manzu == pinzu && pinzu == souzu {
+            1
+        } else if manzu == pinzu || pinzu == souzu {
+            3
+        } else {
+            6
+        }
+    } else if pinzu == souzu {
+        1
+    } else {
+        2
+    };
+    // Ways to assign counts to 7 jihai, which is 7! divided by factorials of repeats.
+    let mut jihai_weight = 5040;
+    let mut repeat = 1;
+    for index in 1..7 {
+        if jihai[index] == jihai[index - 1] {
+            repeat += 1;
+            jihai_weight /= repeat;
+        } else {
+            repeat = 1;
+        }
+    }
+    HandClass {
+        counts,
+        weight: suit_weight * jihai_weight,
+    }
+}
@@ -0,0 +1,170 @@
+//! Depth-limited expectimax search of sutehai over draws from known haiyama, which looks a
+//! few draws ahead instead of acceptance of the next draw alone, for critical decisions.
+//!
+//! The tree alternates nodes of self discarding, which take the best sutehai, and chance
+//! nodes of drawing, which average over unseen hai by their numbers in haiyama. Draws of
+//! other players are not known, so they are a part of the chance node, which thins
+//! haiyama evenly and leaves the probability of each draw as it is.
+//!
+//! Tehai are valued by expected shanten after the draws, -1 if won. Only draws of machihai
+//! branch, which decrease shanten, and then only sutehai keeping the new shanten are
+//! searched. Other draws are regarded as discarded at once, leaving tehai and haiyama as
+//! they are, so better shapes of the same shanten are only valued by the acceptance of the
+//! last draw. Positions met again are looked up by their zobrist hash.
+
+use super::{kernel, shanten, zobrist, Hai, Haiyama, Tehai};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Default number of draws to look ahead.
+pub const DEFAULT_DEPTH: usize = 2;
+
+/// Max number of draws to look ahead, since the cost grows by about a hundred times with
+/// each draw, to seconds at 3 draws from 2 shanten.
+pub const MAX_DEPTH: usize = 3;
+
+/// Value of a sutehai by expectimax.
+///
+/// # Member
+/// * sutehai: The sutehai.
+/// * shanten: Shanten after discarding it.
+/// * expected_shanten: Expected shanten after `depth` draws, lower is better.
+/// * depth: Number of draws looked ahead.
+#[derive(Copy, Clone, Debug)]
+pub struct DiscardValue {
+    pub sutehai: Hai,
+    pub shanten: i32,
+    pub expected_shanten: f64,
+    pub depth: usize,
+}
+
+/// State of a search, where the position is changed in place as the tree is walked.
+struct Search {
+    position: zobrist::Position,
+    fuuro_count: usize,
+    tables: Vec<HashMap<u64, f64, zobrist::BuildZobristHasher>>,
+}
+
+/// Value each type of sutehai of tehai full with hai by expectimax of `depth` draws from
+/// haiyama, from 1 to `MAX_DEPTH`.
+///
+/// # Return
+/// * Vec<DiscardValue>: Values of all types of hai in juntehai, the best first, and those
+/// with the same expected shanten in the order of hai.
+pub fn search(tehai: &Tehai, haiyama: &Haiyama, depth: usize) -> Result<Vec<DiscardValue>, String> {
+    if tehai.juntehai.len() % 3 != 2 {
+        return Err(format!(
+            "The number of hai on hand must be 3*k+2 to search, but {} provided.",
+            tehai.juntehai.len()
+        ));
+    }
+    if depth == 0 || depth > MAX_DEPTH {
+        return Err(format!(
+            "Depth of search must be from 1 to {}, but {} given.",
+            MAX_DEPTH, depth
+        ));
+    }
+
+    let counts = tehai.to_counts();
+    let mut search = Search {
+        position: zobrist::Position::new(counts, haiyama.to_counts()),
+        fuuro_count: tehai.fuuro.len(),
+        tables: vec![HashMap::default(); depth + 1],
+    };
+    let mut value_vec = vec![];
+    for (index, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        search.position.remove(index);
+        value_vec.push(DiscardValue {
+            sutehai: Hai::from_index(index).unwrap(),
+            shanten: shanten::calculate_counts(&search.position.counts, search.fuuro_count),
+            expected_shanten: search.expected_shanten(depth),
+            depth,
+        });
+        search.position.add(index);
+    }
+    // Stable sort keeps the order of hai among the same value.
+    value_vec.sort_by(|a, b| a.expected_shanten.partial_cmp(&b.expected_shanten).unwrap());
+    Ok(value_vec)
+}
+
+impl Search {
+    /// Expected shanten of tehai waiting for a draw after `depth` draws.
+    fn expected_shanten(&mut self, depth: usize) -> f64 {
+        let hash = self.position.hash();
+        if let Some(expected) = self.tables[depth].get(&hash) {
+            return *expected;
+        }
+        let shanten = shanten::calculate_counts(&self.position.counts, self.fuuro_count);
+        let total = kernel::total(&self.position.unseen);
+        if depth == 0 || total == 0 {
+            return shanten as f64;
+        }
+        let machi = shanten::machi_counts(&self.position.counts, self.fuuro_count);
+        let acceptance = kernel::acceptance(&machi, &self.position.unseen);
+        let expected = if depth == 1 {
+            // Any sutehai keeps the decreased shanten for the last draw.
+            shanten as f64 - acceptance as f64 / total as f64
+        } else {
+            let mut sum = 0.0;
+            for (index, &is_machi) in machi.iter().enumerate() {
+                let number = self.position.unseen[index];
+                if !is_machi || number == 0 {
+                    continue;
+                }
+                self.position.move_hai(index, true);
+                sum += number as f64 * self.choose(shanten - 1, depth - 1);
+                self.position.move_hai(index, false);
+            }
+            if acceptance < total {
+                sum += (total - acceptance) as f64 * self.expected_shanten(depth - 1);
+            }
+            sum / total as f64
+        };
+        self.tables[depth].insert(hash, expected);
+        expected
+    }
+
+    /// Expected shanten after discarding the best sutehai keeping `shanten` of tehai full
+    /// with hai, with `depth` draws left.
+    fn choose(&mut self, shanten: i32, depth: usize) -> f64 {
+        if shanten == -1 {
+            return -1.0;
+        }
+        let mut best = f64::INFINITY;
+        for index in 0..34 {
+            if self.position.counts[index] == 0 {
+                continue;
+            }
+            self.position.remove(index);
+            if shanten::calculate_counts(&self.position.counts, self.fuuro_count) == shanten {
+                best = best.min(self.expected_shanten(depth));
+            }
+            self.position.add(index);
+        }
+        best
+    }
+}
+
+impl DiscardValue {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "sutehai": self.sutehai.to_string(),
+            "shanten": self.shanten,
+            "expected_shanten": self.expected_shanten,
+            "depth": self.depth,
+        })
+    }
+}
+
+impl std::fmt::Display for DiscardValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "打 {} {}向聴 {}巡後の期待向聴数 {:.3}",
+            self.sutehai, self.shanten, self.depth, self.expected_shanten
+        )
+    }
+}
@@ -0,0 +1,217 @@
+//! Comparison of two sutehai in a report, explaining why analysis ranks one above the other,
+//! such as why cutting 9s is better than 2m, for trainers to put into words.
+
+use super::{AnalysisReport, Hai, MachiCondition, PlayerNumber, Situation};
+use serde_json::json;
+use std::cmp::Ordering;
+
+/// Differences of two sutehai, each numeric one as `lhs` minus `rhs`.
+///
+/// # Japanese
+/// * kairyouhai: 改良牌
+/// * yakuhai: 役牌
+///
+/// # Member
+/// * lhs: The first sutehai.
+/// * rhs: The second sutehai.
+/// * preferred: The sutehai ranked first by speed, see `ConditionOrder::Speed`.
+/// * shanten: Shanten after each sutehai.
+/// * acceptance: Difference of unseen machihai.
+/// * tenpai_draws: Difference of expected draws until tenpai, negative is faster. `None`
+/// unless known for both.
+/// * kairyou: Difference of unseen kairyouhai, only known at 0 or 1 shanten.
+/// * breaks_yakuhai: Whether each sutehai breaks toitsu or koutsu of yakuhai, losing yaku.
+/// * no_yaku: Whether each sutehai leaves tenpai without yaku.
+/// * safer: The sutehai ranked safer by `AnalysisReport::safety`, `None` if the same or
+/// unknown.
+/// * deal_in: Difference of the rate of dealing in to threats, `None` without threats.
+#[derive(Copy, Clone, Debug)]
+pub struct ConditionComparison {
+    pub lhs: Hai,
+    pub rhs: Hai,
+    pub preferred: Hai,
+    pub shanten: (i32, i32),
+    pub acceptance: i64,
+    pub tenpai_draws: Option<f64>,
+    pub kairyou: i64,
+    pub breaks_yakuhai: (bool, bool),
+    pub no_yaku: (bool, bool),
+    pub safer: Option<Hai>,
+    pub deal_in: Option<f64>,
+}
+
+impl ConditionComparison {
+    /// Compare two sutehai of tehai in the report. Sutehai increasing shanten are not in
+    /// conditions of the report, so they are analyzed again with the situation, if any.
+    pub fn analyze(
+        report: &AnalysisReport,
+        lhs: Hai,
+        rhs: Hai,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> Result<ConditionComparison, String> {
+        for sutehai in &[lhs, rhs] {
+            if !report.tehai.juntehai.contains(sutehai) {
+                return Err(format!("{} is not in tehai.", sutehai));
+            }
+        }
+        if lhs == rhs {
+            return Err("Can only compare two different sutehai.".to_string());
+        }
+
+        let mut all_sutehai = None;
+        let mut condition_of = |sutehai: Hai| -> Result<(i32, MachiCondition), String> {
+            if let Some(condition) = report
+                .conditions
+                .iter()
+                .find(|condition| condition.sutehai == sutehai)
+            {
+                return Ok((report.shanten, condition.clone()));
+            }
+            if all_sutehai.is_none() {
+                all_sutehai = Some(report.tehai.analyze_all_sutehai(player_number, situation)?);
+            }
+            all_sutehai
+                .iter()
+                .flatten()
+                .find(|(_, condition)| condition.sutehai == sutehai)
+                .cloned()
+                .ok_or(format!("Logic error: no condition of sutehai {}.", sutehai))
+        };
+        let (lhs_shanten, lhs_condition) = condition_of(lhs)?;
+        let (rhs_shanten, rhs_condition) = condition_of(rhs)?;
+
+        let breaks_yakuhai = |sutehai: &Hai| {
+            report.yakuhai_type.contains(sutehai) && report.tehai.count(sutehai) >= 2
+        };
+        let kairyou = |condition: &MachiCondition| {
+            condition
+                .kairyouhai
+                .values()
+                .map(|number| *number as i64)
+                .sum::<i64>()
+        };
+        let safety_rank = |sutehai: &Hai| {
+            report
+                .safety
+                .iter()
+                .position(|safety| safety.hai == *sutehai)
+        };
+        // Same as the order of `Tehai::analyze_all_sutehai()`, breaking ties by yakuhai.
+        let tenpai_draws = |condition: &MachiCondition| condition.tenpai_draws.unwrap_or(f64::MAX);
+        let ordering = lhs_shanten
+            .cmp(&rhs_shanten)
+            .then(
+                tenpai_draws(&lhs_condition)
+                    .partial_cmp(&tenpai_draws(&rhs_condition))
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then(
+                lhs_condition
+                    .nokori()
+                    .cmp(&rhs_condition.nokori())
+                    .reverse(),
+            )
+            .then(breaks_yakuhai(&lhs).cmp(&breaks_yakuhai(&rhs)))
+            .then(lhs.cmp(&rhs));
+
+        Ok(ConditionComparison {
+            lhs,
+            rhs,
+            preferred: if ordering == Ordering::Greater {
+                rhs
+            } else {
+                lhs
+            },
+            shanten: (lhs_shanten, rhs_shanten),
+            acceptance: lhs_condition.nokori() as i64 - rhs_condition.nokori() as i64,
+            tenpai_draws: match (lhs_condition.tenpai_draws, rhs_condition.tenpai_draws) {
+                (Some(lhs_draws), Some(rhs_draws)) => Some(lhs_draws - rhs_draws),
+                _ => None,
+            },
+            kairyou: kairyou(&lhs_condition) - kairyou(&rhs_condition),
+            breaks_yakuhai: (breaks_yakuhai(&lhs), breaks_yakuhai(&rhs)),
+            no_yaku: (lhs_condition.no_yaku, rhs_condition.no_yaku),
+            safer: match (safety_rank(&lhs), safety_rank(&rhs)) {
+                (Some(lhs_rank), Some(rhs_rank)) if lhs_rank < rhs_rank => Some(lhs),
+                (Some(lhs_rank), Some(rhs_rank)) if lhs_rank > rhs_rank => Some(rhs),
+                _ => None,
+            },
+            deal_in: match (report.deal_in.get(&lhs), report.deal_in.get(&rhs)) {
+                (Some(lhs_rate), Some(rhs_rate)) => Some(lhs_rate - rhs_rate),
+                _ => None,
+            },
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "lhs": self.lhs.to_string(),
+            "rhs": self.rhs.to_string(),
+            "preferred": self.preferred.to_string(),
+            "shanten_number": [self.shanten.0, self.shanten.1],
+            "acceptance": self.acceptance,
+            "tenpai_draws": self.tenpai_draws,
+            "kairyou": self.kairyou,
+            "breaks_yakuhai": [self.breaks_yakuhai.0, self.breaks_yakuhai.1],
+            "no_yaku": [self.no_yaku.0, self.no_yaku.1],
+            "safer": self.safer.map(|hai| hai.to_string()),
+            "deal_in": self.deal_in,
+        })
+    }
+}
+
+/// Print only what differs, like `打 9s 対 打 2m 優先 打 9s` followed by `受け入れ +4枚`,
+/// numbers of `lhs` relative to `rhs`.
+impl std::fmt::Display for ConditionComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "打 {} 対 打 {} 優先 打 {}",
+            self.lhs, self.rhs, self.preferred
+        )?;
+        let mut line_vec = vec![];
+        if self.shanten.0 != self.shanten.1 {
+            line_vec.push(format!("向聴 {} 対 {}", self.shanten.0, self.shanten.1));
+        }
+        if self.acceptance != 0 {
+            line_vec.push(format!("受け入れ {:+}枚", self.acceptance));
+        }
+        if let Some(draws) = self.tenpai_draws {
+            if draws.abs() >= 0.005 {
+                line_vec.push(format!("聴牌まで {:+.2}巡", draws));
+            }
+        }
+        if self.kairyou != 0 {
+            line_vec.push(format!("改良 {:+}枚", self.kairyou));
+        }
+        for (sutehai, breaks) in &[
+            (self.lhs, self.breaks_yakuhai.0),
+            (self.rhs, self.breaks_yakuhai.1),
+        ] {
+            if *breaks {
+                line_vec.push(format!("打 {} 役牌を崩す", sutehai));
+            }
+        }
+        for (sutehai, no_yaku) in &[(self.lhs, self.no_yaku.0), (self.rhs, self.no_yaku.1)] {
+            if *no_yaku {
+                line_vec.push(format!("打 {} 役なし", sutehai));
+            }
+        }
+        if let Some(safer) = self.safer {
+            line_vec.push(format!("打 {} がより安全", safer));
+        }
+        if let Some(rate) = self.deal_in {
+            if rate.abs() >= 0.0005 {
+                line_vec.push(format!("放銃率 {:+.1}%", rate * 100.0));
+            }
+        }
+        if line_vec.is_empty() {
+            line_vec.push("差なし".to_string());
+        }
+        for line in line_vec {
+            write!(f, "\n{}", line)?;
+        }
+        Ok(())
+    }
+}
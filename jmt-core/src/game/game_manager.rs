@@ -0,0 +1,1955 @@
+use super::{
+    riichi, shanten, zobrist, Annotation, DealInTable, Hai, Haiyama, Kan, Kawa, Kaze, Ledger,
+    MachiCondition, Mentsu, OpponentRiichi, PlayerNumber, Rules, Ryuukyoku, Seat, Situation, Tehai,
+};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// The game manager.
+/// Include everything that a complete mahjong game need.
+///
+/// It is `Send + Sync` and owns all its state without shared pointers or caches, so a server
+/// can keep one for each session behind a lock. Keep it so, which is checked at compile time.
+#[derive(Clone, Debug)]
+pub struct GameManager {
+    haiyama: Haiyama,
+    tehai: Option<Tehai>,
+    kawa: Kawa,
+    pub state: State,
+    jikaze: Kaze,
+    bakaze: Kaze,
+    dora_indicators: Vec<Hai>,
+    rules: Rules,
+    deal_in_table: DealInTable,
+    ledger: Ledger,
+    initial_haiyama: Haiyama,
+    player_number: PlayerNumber,
+    history: Vec<(Operation, State, Kawa)>,
+    annotations: BTreeMap<usize, Vec<Annotation>>,
+    opponent_riichi: Vec<OpponentRiichi>,
+    sutehai_seats: BTreeMap<usize, Seat>,
+    opponent_fuuro: BTreeMap<usize, (Seat, Mentsu)>,
+    riichi_payouts: BTreeMap<usize, u32>,
+    events: Option<Vec<Event>>,
+    turn: Option<(usize, usize)>,
+    tehai_zobrist: u64,
+    theory: bool,
+}
+
+/// Type of naku.
+///
+/// # Japanese
+/// * Naku: 鳴く
+/// * Chii: チー
+/// * Pon: ポン
+/// * Kan: カン
+/// * nakihai: 鳴き牌
+#[derive(Clone, Debug)]
+pub enum Naku {
+    Chii { juntsu: Mentsu, nakihai: Hai },
+    Pon(Mentsu),
+    Kan(Kan),
+}
+
+/// Operation on haiyama.
+///
+/// # Japanese
+/// * Dora: ドラ表示牌をめくる
+///
+/// # Member
+/// * Dora: A dora indicator revealed, such as kan-dora after kan, which is discarded from
+/// haiyama and added to dora indicators.
+#[derive(Clone, Debug)]
+pub enum HaiyamaOperation {
+    Add(Vec<Hai>),
+    Discard(Vec<Hai>),
+    Dora(Hai),
+}
+
+/// Operation on tehai.
+#[derive(Clone, Debug)]
+pub enum TehaiOperation {
+    Initialize(Tehai),
+    Add { hai: Hai, haiyama_sensitive: bool },
+    Discard(Hai),
+    Riichi(Hai),
+    Naku { kind: Naku, haiyama_sensitive: bool },
+}
+
+/// Valid operation for game manager.
+#[derive(Clone, Debug)]
+pub enum Operation {
+    Haiyama {
+        kind: HaiyamaOperation,
+        haiyama_sensitive: bool,
+    },
+    Tehai(TehaiOperation),
+}
+
+/// Game state.
+///
+/// # Member
+/// * Ryuukyoku: The hand is over by exhaustive draw, and whether oya keeps the seat.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum State {
+    WaitToInit,
+    FullHai,
+    LackOneHai,
+    WaitForRinshanhai,
+    Ryuukyoku { renchan: bool },
+}
+
+/// Event emitted when game manager is changed, recorded only after `enable_events()`.
+///
+/// # Member
+/// * Operated: An operation succeeded and moved state from `last_state` to `state`.
+/// * Undone: An operation was undone and state was restored to `state`.
+/// * Reinitialized: Game manager was reset to a fresh game.
+/// * NextHand: The next hand started after ryuukyoku, with oya kept if `renchan`.
+#[derive(Clone, Debug)]
+pub enum Event {
+    Operated {
+        operation: Operation,
+        last_state: State,
+        state: State,
+    },
+    Undone {
+        operation: Operation,
+        state: State,
+    },
+    Reinitialized,
+    NextHand {
+        renchan: bool,
+    },
+}
+
+impl Naku {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Naku::Chii { juntsu, nakihai } => json!({
+                "type": "chii",
+                "juntsu": juntsu.to_json(),
+                "nakihai": nakihai.to_string(),
+            }),
+            Naku::Pon(koutsu) => json!({
+                "type": "pon",
+                "koutsu": koutsu.to_json(),
+            }),
+            Naku::Kan(kan) => json!({
+                "type": "kan",
+                "kan": kan.to_json(),
+            }),
+        }
+    }
+}
+
+impl TehaiOperation {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            TehaiOperation::Initialize(tehai) => json!({
+                "operation": "initialze",
+                "tehai": tehai.to_json(),
+            }),
+            TehaiOperation::Add {
+                hai,
+                haiyama_sensitive,
+            } => json!({
+                "operation": "add",
+                "hai": hai.to_string(),
+                "haiyama_sensitive": haiyama_sensitive,
+            }),
+            TehaiOperation::Discard(hai) => json!({
+                "operation": "discard",
+                "hai": hai.to_string(),
+            }),
+            TehaiOperation::Riichi(hai) => json!({
+                "operation": "riichi",
+                "hai": hai.to_string(),
+            }),
+            TehaiOperation::Naku {
+                kind,
+                haiyama_sensitive,
+            } => json!({
+                "operation": "naku",
+                "naku": kind.to_json(),
+                "haiyama_sensitive": haiyama_sensitive,
+            }),
+        }
+    }
+}
+
+impl HaiyamaOperation {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            HaiyamaOperation::Add(hai_vec) => {
+                let mut hai_string_vec = vec![];
+                for i in hai_vec {
+                    hai_string_vec.push(i.to_string());
+                }
+                json!({
+                    "operation": "add",
+                    "hai": hai_string_vec,
+                })
+            }
+            HaiyamaOperation::Discard(hai_vec) => {
+                let mut hai_string_vec = vec![];
+                for i in hai_vec {
+                    hai_string_vec.push(i.to_string());
+                }
+                json!({
+                    "operation": "discard",
+                    "hai": hai_string_vec,
+                })
+            }
+            HaiyamaOperation::Dora(indicator) => json!({
+                "operation": "dora",
+                "hai": [indicator.to_string()],
+            }),
+        }
+    }
+}
+
+impl State {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!(match self {
+            State::WaitToInit => "wait_to_init",
+            State::FullHai => "full_hai",
+            State::LackOneHai => "lack_one_hai",
+            State::WaitForRinshanhai => "wait_for_rinshanhai",
+            State::Ryuukyoku { .. } => "ryuukyoku",
+        })
+    }
+}
+
+impl Event {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Event::Operated {
+                operation,
+                last_state,
+                state,
+            } => json!({
+                "event": "operated",
+                "operation": operation.to_json(),
+                "last_state": last_state.to_json(),
+                "state": state.to_json(),
+            }),
+            Event::Undone { operation, state } => json!({
+                "event": "undone",
+                "operation": operation.to_json(),
+                "state": state.to_json(),
+            }),
+            Event::Reinitialized => json!({
+                "event": "reinitialized",
+            }),
+            Event::NextHand { renchan } => json!({
+                "event": "next_hand",
+                "renchan": renchan,
+            }),
+        }
+    }
+}
+
+impl Operation {
+    /// Parse an operation in the notation of interactive commands, such as `+4m`, `-1s`,
+    /// `*-1s777z`, `*d3p`, `>465s` and `>4444p5s`. `!` after the operator, or after `*+` and `*-`,
+    /// means not haiyama sensitive. Text without operator is regarded as initializing tehai.
+    ///
+    /// Kan is always parsed as `Kan::Unknown`, which is resolved when operating.
+    pub fn parse(string: &str, player_number: PlayerNumber) -> Result<Operation, String> {
+        let chars: Vec<char> = string.chars().collect();
+        if chars.len() < 3 {
+            return Err(i18n::format(Message::UnresolvedCommand, &[&string]));
+        }
+
+        let operator = chars[0];
+        let (haiyama_sensitive, rest) = match (operator, chars[1], chars[2]) {
+            ('*', '!', _) => (false, 3),
+            ('*', _, '!') => (false, 3),
+            ('*', _, _) => (true, 2),
+            (_, '!', _) => (false, 2),
+            _ => (true, 1),
+        };
+        let hai_string: String = chars[rest.min(chars.len())..].iter().collect();
+        let parse_hai = || Hai::from_string_unordered(&hai_string, player_number);
+
+        match operator {
+            '+' => {
+                let hai_vec = parse_hai()?;
+                if hai_vec.len() == 1 {
+                    Ok(Operation::Tehai(TehaiOperation::Add {
+                        hai: hai_vec[0],
+                        haiyama_sensitive,
+                    }))
+                } else {
+                    Err(i18n::text(Message::OneHaiToAdd).to_string())
+                }
+            }
+            '-' => {
+                let hai_vec = parse_hai()?;
+                if hai_vec.len() == 1 {
+                    Ok(Operation::Tehai(TehaiOperation::Discard(hai_vec[0])))
+                } else {
+                    Err(i18n::format(Message::OneHaiToDiscard, &[&'-']))
+                }
+            }
+            'r' => {
+                let hai_vec = parse_hai()?;
+                if hai_vec.len() == 1 {
+                    Ok(Operation::Tehai(TehaiOperation::Riichi(hai_vec[0])))
+                } else {
+                    Err(i18n::format(Message::OneHaiToDiscard, &[&'r']))
+                }
+            }
+            '*' => {
+                let kind = match chars[if chars[1] == '!' { 2 } else { 1 }] {
+                    '+' => HaiyamaOperation::Add(parse_hai()?),
+                    '-' => HaiyamaOperation::Discard(parse_hai()?),
+                    'd' => match &parse_hai()?[..] {
+                        [indicator] => HaiyamaOperation::Dora(*indicator),
+                        _ => return Err(i18n::text(Message::OneDoraIndicator).to_string()),
+                    },
+                    _ => return Err(i18n::format(Message::UnresolvedCommand, &[&string])),
+                };
+                Ok(Operation::Haiyama {
+                    kind,
+                    haiyama_sensitive,
+                })
+            }
+            '>' => {
+                let mut hai_vec = parse_hai()?;
+                let kind = match hai_vec.len() {
+                    3 => match Mentsu::new(&hai_vec, player_number) {
+                        Some(juntsu @ Mentsu::Juntsu(..)) => Naku::Chii {
+                            juntsu,
+                            nakihai: hai_vec[2],
+                        },
+                        Some(koutsu @ Mentsu::Koutsu(..)) => Naku::Pon(koutsu),
+                        Some(_) => return Err("Logic error: Code never reach here.".to_string()),
+                        None => return Err(i18n::format(Message::InvalidMentsu, &[&hai_string])),
+                    },
+                    4 => {
+                        if hai_vec[0] == hai_vec[1]
+                            && hai_vec[0] == hai_vec[2]
+                            && hai_vec[0] == hai_vec[3]
+                        {
+                            Naku::Kan(Kan::Unknown {
+                                kantsu: Mentsu::Kantsu(hai_vec[0]),
+                                rinshanhai: None,
+                            })
+                        } else {
+                            return Err(i18n::format(Message::InvalidMentsu, &[&hai_string]));
+                        }
+                    }
+                    5 => {
+                        hai_vec.sort();
+                        let (kantsuhai, rinshanhai) = if hai_vec[0] == hai_vec[1]
+                            && hai_vec[0] == hai_vec[2]
+                            && hai_vec[0] == hai_vec[3]
+                            && hai_vec[0] != hai_vec[4]
+                        {
+                            (hai_vec[0], hai_vec[4])
+                        } else if hai_vec[4] == hai_vec[1]
+                            && hai_vec[4] == hai_vec[2]
+                            && hai_vec[4] == hai_vec[3]
+                            && hai_vec[4] != hai_vec[0]
+                        {
+                            (hai_vec[4], hai_vec[0])
+                        } else {
+                            return Err(i18n::format(Message::InvalidMentsu, &[&hai_string]));
+                        };
+                        Naku::Kan(Kan::Unknown {
+                            kantsu: Mentsu::Kantsu(kantsuhai),
+                            rinshanhai: Some(rinshanhai),
+                        })
+                    }
+                    _ => return Err(i18n::format(Message::UnresolvedCommand, &[&string])),
+                };
+                Ok(Operation::Tehai(TehaiOperation::Naku {
+                    kind,
+                    haiyama_sensitive,
+                }))
+            }
+            _ => Ok(Operation::Tehai(TehaiOperation::Initialize(Tehai::new(
+                string.to_string(),
+                player_number,
+            )?))),
+        }
+    }
+
+    /// Return a copy of self which can be operated again on a fresh game manager.
+    ///
+    /// Kan in history has already been resolved to daiminkan, kakan or ankan, but
+    /// game manager only accepts `Kan::Unknown` when operating.
+    pub fn unresolved(&self) -> Operation {
+        match self {
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Kan(kan),
+                haiyama_sensitive,
+            }) => {
+                let (kantsu, rinshanhai) = match kan {
+                    Kan::Daiminkan { kantsu, rinshanhai }
+                    | Kan::Kakan { kantsu, rinshanhai }
+                    | Kan::Ankan { kantsu, rinshanhai }
+                    | Kan::Unknown { kantsu, rinshanhai } => (*kantsu, *rinshanhai),
+                };
+                Operation::Tehai(TehaiOperation::Naku {
+                    kind: Naku::Kan(Kan::Unknown { kantsu, rinshanhai }),
+                    haiyama_sensitive: *haiyama_sensitive,
+                })
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Clear `haiyama_sensitive` of self, as operations in theory mode never fail by haiyama.
+    fn ignore_haiyama(&mut self) {
+        match self {
+            Operation::Tehai(TehaiOperation::Add {
+                haiyama_sensitive, ..
+            })
+            | Operation::Tehai(TehaiOperation::Naku {
+                haiyama_sensitive, ..
+            })
+            | Operation::Haiyama {
+                haiyama_sensitive, ..
+            } => *haiyama_sensitive = false,
+            _ => {}
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Operation::Tehai(tehai_operation) => json!({
+                "object": "tehai",
+                "operation": tehai_operation.to_json(),
+            }),
+            Operation::Haiyama {
+                kind,
+                haiyama_sensitive,
+            } => json!({
+                "object": "tehai",
+                "operation": kind.to_json(),
+                "haiyama_sensitive": haiyama_sensitive,
+            }),
+        }
+    }
+}
+
+impl GameManager {
+    /// Create a instance of GameManager.
+    pub fn new(player_number: PlayerNumber) -> Self {
+        Self {
+            haiyama: Haiyama::new(player_number),
+            tehai: None,
+            kawa: Kawa::new(),
+            state: State::WaitToInit,
+            jikaze: Kaze::Ton,
+            bakaze: Kaze::Ton,
+            dora_indicators: vec![],
+            rules: Rules::default(),
+            deal_in_table: DealInTable::default(),
+            ledger: Ledger::new(player_number, 25000),
+            initial_haiyama: Haiyama::new(player_number),
+            player_number,
+            history: vec![],
+            annotations: BTreeMap::new(),
+            opponent_riichi: vec![],
+            sutehai_seats: BTreeMap::new(),
+            opponent_fuuro: BTreeMap::new(),
+            riichi_payouts: BTreeMap::new(),
+            events: None,
+            turn: None,
+            tehai_zobrist: 0,
+            theory: false,
+        }
+    }
+
+    /// Create a instance of GameManager in theory mode, for questions on tehai alone without
+    /// a game around it. Haiyama only counts hai out of own tehai, so sutehai, dora indicators
+    /// and hai of others never run out of haiyama and are not subtracted from acceptance.
+    /// Tehai still cannot hold a fifth hai of a type.
+    pub fn theory(player_number: PlayerNumber) -> Self {
+        Self {
+            theory: true,
+            ..Self::new(player_number)
+        }
+    }
+
+    /// Create a instance of GameManager with a custom haiyama, such as a stacked wall.
+    pub fn with_haiyama(player_number: PlayerNumber, haiyama: Haiyama) -> Self {
+        Self {
+            haiyama: haiyama.clone(),
+            initial_haiyama: haiyama,
+            ..Self::new(player_number)
+        }
+    }
+
+    pub fn reinitialize(&mut self, player_number: PlayerNumber) -> &mut Self {
+        let events = self.events.take();
+        let theory = self.theory;
+        *self = Self::new(player_number);
+        self.events = events;
+        self.theory = theory;
+        self.emit(Event::Reinitialized);
+        self
+    }
+
+    /// Start the next hand after ryuukyoku, which has carried riichi sticks over and increased
+    /// honba in the ledger. Without renchan, oya moves to the next seat, so jikaze of self and
+    /// seats of the ledger rotate. Bakaze is kept, set it by `set_kaze()` when the round
+    /// changes. Haiyama is filled again as the game started, and tehai, kawa, dora indicators,
+    /// history and marks on others are cleared, while rules and events are kept.
+    pub fn next_hand(&mut self) -> Result<&mut Self, String> {
+        let renchan = match self.state {
+            State::Ryuukyoku { renchan } => renchan,
+            _ => return Err(i18n::text(Message::NextHandBeforeRyuukyoku).to_string()),
+        };
+        let mut ledger = self.ledger.clone();
+        let mut jikaze = self.jikaze;
+        if !renchan {
+            let count = ledger.seats().len();
+            jikaze = Kaze::from_index((jikaze.to_index() + count - 1) % count);
+            ledger.pass_oya();
+        }
+        let next = Self {
+            haiyama: self.initial_haiyama.clone(),
+            jikaze,
+            bakaze: self.bakaze,
+            rules: self.rules,
+            deal_in_table: self.deal_in_table.clone(),
+            ledger,
+            initial_haiyama: self.initial_haiyama.clone(),
+            events: self.events.take(),
+            theory: self.theory,
+            ..Self::new(self.player_number)
+        };
+        *self = next;
+        self.emit(Event::NextHand { renchan });
+        Ok(self)
+    }
+
+    /// Create an independent copy of current state, including tehai, haiyama, sutehai and
+    /// history, to explore another line of play. Events not drained yet stay with self.
+    pub fn fork(&self) -> Self {
+        let mut forked = self.clone();
+        if let Some(events) = &mut forked.events {
+            events.clear();
+        }
+        forked
+    }
+
+    /// Start recording events, which can be taken by `drain_events()`. Events are not
+    /// recorded by default so that nothing piles up if no one drains them.
+    pub fn enable_events(&mut self) -> &mut Self {
+        if self.events.is_none() {
+            self.events = Some(vec![]);
+        }
+        self
+    }
+
+    /// Take all events recorded since last draining, in order of occurrence.
+    pub fn drain_events(&mut self) -> Vec<Event> {
+        match &mut self.events {
+            Some(events) => std::mem::take(events),
+            None => vec![],
+        }
+    }
+
+    /// Whether self is in theory mode, see `theory()`.
+    pub fn is_theory(&self) -> bool {
+        self.theory
+    }
+
+    /// Return the number of players.
+    pub fn player_number(&self) -> PlayerNumber {
+        self.player_number
+    }
+
+    /// Set wind of seat and round, which decide yakuhai.
+    pub fn set_kaze(&mut self, jikaze: Kaze, bakaze: Kaze) -> &mut Self {
+        self.jikaze = jikaze;
+        self.bakaze = bakaze;
+        self
+    }
+
+    /// Return wind of seat.
+    pub fn jikaze(&self) -> Kaze {
+        self.jikaze
+    }
+
+    /// Return wind of round.
+    pub fn bakaze(&self) -> Kaze {
+        self.bakaze
+    }
+
+    /// Return types of yakuhai under current winds.
+    pub fn yakuhai_type(&self) -> BTreeSet<Hai> {
+        Kaze::yakuhai_type(self.jikaze, self.bakaze)
+    }
+
+    /// Set dora indicators. Hai of indicators are not discarded from haiyama, which is done
+    /// by operations on haiyama as usual.
+    ///
+    /// # Japanese
+    /// * dora indicator: ドラ表示牌
+    pub fn set_dora_indicators(&mut self, dora_indicators: Vec<Hai>) -> &mut Self {
+        self.dora_indicators = dora_indicators;
+        self
+    }
+
+    /// Return dora indicators.
+    pub fn dora_indicators(&self) -> &Vec<Hai> {
+        &self.dora_indicators
+    }
+
+    /// Whether a kan has been declared since the last dora indicator revealed by
+    /// `HaiyamaOperation::Dora`, so that its kan-dora indicator is still to be given.
+    ///
+    /// # Japanese
+    /// * kan-dora: カンドラ
+    pub fn kan_dora_pending(&self) -> bool {
+        for (op, ..) in self.history.iter().rev() {
+            match op {
+                Operation::Haiyama {
+                    kind: HaiyamaOperation::Dora(_),
+                    ..
+                } => return false,
+                Operation::Tehai(TehaiOperation::Naku {
+                    kind: Naku::Kan(_), ..
+                }) => return true,
+                _ => (),
+            }
+        }
+        false
+    }
+
+    /// Return dora of each indicator, so the same type appears as many times as its han.
+    pub fn dora(&self) -> Vec<Hai> {
+        self.dora_indicators
+            .iter()
+            .filter_map(|indicator| indicator.next(self.player_number, true))
+            .collect()
+    }
+
+    /// Set optional rules.
+    pub fn set_rules(&mut self, rules: Rules) -> &mut Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Set the table of deal-in rates, from which defense estimates the risk of sutehai.
+    pub fn set_deal_in_table(&mut self, table: DealInTable) -> &mut Self {
+        self.deal_in_table = table;
+        self
+    }
+
+    /// Set the current turn, counted from 1 by draws of self, which is otherwise unknown if
+    /// not all sutehai of others are input. The turn advances with sutehai of self.
+    ///
+    /// # Japanese
+    /// * turn: 巡目
+    pub fn set_turn(&mut self, turn: usize) -> &mut Self {
+        self.turn = Some((turn, self.kawa.sutehai_vec().len()));
+        self
+    }
+
+    /// Return the current turn if set.
+    pub fn turn(&self) -> Option<usize> {
+        self.turn
+            .map(|(turn, kawa_len)| turn + self.kawa.sutehai_vec().len().saturating_sub(kawa_len))
+    }
+
+    /// Estimate number of draws left for self, from the number of hai left to draw for all
+    /// players, see `riichi::tsumo_number()`.
+    pub fn draws_remaining(&self) -> usize {
+        let player_count = match self.player_number {
+            PlayerNumber::Four => 4,
+            PlayerNumber::Three => 3,
+        };
+        riichi::tsumo_number(self).div_ceil(player_count)
+    }
+
+    /// Replace kawa of self, for describing a game already in progress. Sutehai in it are
+    /// removed from haiyama as drawn by self, and those of the replaced kawa are put back.
+    pub fn set_kawa(&mut self, kawa: Kawa) -> Result<&mut Self, String> {
+        if self.theory {
+            self.kawa = kawa;
+            return Ok(self);
+        }
+        let mut transaction = self.haiyama.begin();
+        for hai in self.kawa.sutehai_vec() {
+            transaction.add(hai)?;
+        }
+        for hai in kawa.sutehai_vec() {
+            if transaction.discard(hai).is_err() {
+                return Err(format!(
+                    "Not enough '{}' in haiyama to discard.",
+                    hai.to_string()
+                ));
+            }
+        }
+        transaction.commit();
+        self.kawa = kawa;
+        Ok(self)
+    }
+
+    /// Return optional rules.
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Return the table of deal-in rates.
+    pub fn deal_in_table(&self) -> &DealInTable {
+        &self.deal_in_table
+    }
+
+    /// Return points of self, 25000 by default.
+    pub fn points(&self) -> i32 {
+        // Seat of jikaze is always in game except pei in 3-player mode.
+        self.ledger.points(self.jikaze).unwrap_or(0)
+    }
+
+    /// Return the ledger of all seats.
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Return the mutable ledger of all seats, for settlements and corrections.
+    pub fn ledger_mut(&mut self) -> &mut Ledger {
+        &mut self.ledger
+    }
+
+    /// Return a reference of haiyama
+    pub fn haiyama(&self) -> &Haiyama {
+        &self.haiyama
+    }
+
+    /// Return a reference of the set within sutehai.
+    pub fn sutehai_type(&self) -> &BTreeSet<Hai> {
+        self.kawa.sutehai_type()
+    }
+
+    /// Return a reference of kawa.
+    pub fn kawa(&self) -> &Kawa {
+        &self.kawa
+    }
+
+    /// Return operation history, with kawa before each operation.
+    pub fn history(&self) -> &Vec<(Operation, State, Kawa)> {
+        &self.history
+    }
+
+    /// Return the reference of tehai.
+    pub fn tehai(&self) -> Option<&Tehai> {
+        return self.tehai.as_ref();
+    }
+
+    /// Zobrist hash of haiyama and tehai, which is maintained as hai move by operations, to key
+    /// caches and transposition tables of positions. See `zobrist`.
+    pub fn zobrist(&self) -> u64 {
+        self.haiyama.zobrist() ^ self.tehai_zobrist
+    }
+
+    /// Return the analysis of tehai.
+    pub fn tehai_analyze(&self) -> Result<(i32, Vec<MachiCondition>), String> {
+        let tehai = self
+            .tehai
+            .as_ref()
+            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+        tehai.analyze(self.player_number, Some(self))
+    }
+
+    /// Main function to control the game.
+    pub fn operate(&mut self, mut op: Operation) -> Result<(), String> {
+        if self.theory {
+            op.ignore_haiyama();
+        }
+        let last_state = self.state;
+        let last_kawa = self.kawa.clone();
+        let last_tehai = self.tehai.clone();
+        match last_state {
+            State::WaitToInit => self.operate_wait_to_init(&op)?,
+            State::FullHai => self.operate_full_hai(&mut op)?,
+            State::LackOneHai => self.operate_lack_one_hai(&mut op)?,
+            State::WaitForRinshanhai => self.operate_wait_for_rinshanhai(&op)?,
+            State::Ryuukyoku { .. } => return Err(i18n::text(Message::HandOver).to_string()),
+        }
+        if self.theory {
+            // Only operations bringing hai into tehai can fail, which change nothing else.
+            if let Err(error) = self.sync_theory_haiyama() {
+                self.state = last_state;
+                self.tehai = last_tehai;
+                self.sync_theory_haiyama()?;
+                return Err(error);
+            }
+        }
+        self.update_kawa(&op, last_tehai.as_ref());
+        self.update_tehai_zobrist(last_tehai.as_ref());
+        if self.events.is_some() {
+            self.emit(Event::Operated {
+                operation: op.clone(),
+                last_state,
+                state: self.state,
+            });
+        }
+        self.history.push((op, last_state, last_kawa));
+        Ok(())
+    }
+
+    /// Attach an annotation to the last operation in history.
+    pub fn annotate(&mut self, annotation: Annotation) -> Result<&mut Self, String> {
+        if self.history.is_empty() {
+            return Err(i18n::text(Message::NothingToAnnotate).to_string());
+        }
+        self.annotations
+            .entry(self.history.len() - 1)
+            .or_default()
+            .push(annotation);
+        Ok(self)
+    }
+
+    /// Return annotations by the index of operations in history. Undoing an operation drops
+    /// its annotations.
+    pub fn annotations(&self) -> &BTreeMap<usize, Vec<Annotation>> {
+        &self.annotations
+    }
+
+    /// Mark another player as riichi with the sutehai declaring it, which is not discarded
+    /// from haiyama. Undoing the operation before marking also removes the mark.
+    pub fn mark_opponent_riichi(&mut self, seat: Seat, sutehai: Hai) -> Result<&mut Self, String> {
+        if self
+            .opponent_riichi
+            .iter()
+            .any(|riichi| riichi.seat == seat)
+        {
+            return Err(i18n::format(
+                Message::OpponentAlreadyRiichi,
+                &[&seat.name()],
+            ));
+        }
+        self.opponent_riichi.push(OpponentRiichi {
+            seat,
+            sutehai,
+            since: self.history.len(),
+        });
+        Ok(self)
+    }
+
+    /// Return riichi of other players in the order marked.
+    pub fn opponent_riichi(&self) -> &Vec<OpponentRiichi> {
+        &self.opponent_riichi
+    }
+
+    /// Discard sutehai of another player from haiyama, recording who discarded it. Same as
+    /// `*-` otherwise, so it can be undone by `back()`.
+    pub fn discard_by(&mut self, seat: Seat, hai_vec: Vec<Hai>) -> Result<&mut Self, String> {
+        self.operate(Operation::Haiyama {
+            kind: HaiyamaOperation::Discard(hai_vec),
+            haiyama_sensitive: true,
+        })?;
+        self.sutehai_seats.insert(self.history.len() - 1, seat);
+        Ok(self)
+    }
+
+    /// Discard hai of fuuro of another player from haiyama, including the nakihai, recording
+    /// the mentsu for the player. Same as `*-` otherwise, so it can be undone by `back()`.
+    pub fn call_by(&mut self, seat: Seat, mentsu: Mentsu) -> Result<&mut Self, String> {
+        self.operate(Operation::Haiyama {
+            kind: HaiyamaOperation::Discard(mentsu.to_vec()),
+            haiyama_sensitive: true,
+        })?;
+        self.opponent_fuuro
+            .insert(self.history.len() - 1, (seat, mentsu));
+        Ok(self)
+    }
+
+    /// Sutehai of another player recorded by `discard_by()`, in the order discarded.
+    pub fn sutehai_of(&self, seat: Seat) -> Vec<Hai> {
+        let mut sutehai_vec = vec![];
+        for (index, (op, ..)) in self.history.iter().enumerate() {
+            if self.sutehai_seats.get(&index) != Some(&seat) {
+                continue;
+            }
+            if let Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                ..
+            } = op
+            {
+                sutehai_vec.extend(hai_vec.iter().copied());
+            }
+        }
+        sutehai_vec
+    }
+
+    /// Fuuro of another player recorded by `call_by()`, in the order called.
+    pub fn fuuro_of(&self, seat: Seat) -> Vec<Mentsu> {
+        self.opponent_fuuro
+            .values()
+            .filter(|(fuuro_seat, _)| *fuuro_seat == seat)
+            .map(|(_, mentsu)| *mentsu)
+            .collect()
+    }
+
+    /// Hai which can never be ron by another player, that is genbutsu: sutehai of the player,
+    /// which are furiten, and if the player has declared riichi, the sutehai declaring it and
+    /// hai discarded by anyone after it.
+    ///
+    /// # Japanese
+    /// * genbutsu: 現物
+    pub fn safe_against(&self, seat: Seat) -> HashSet<Hai> {
+        let mut genbutsu: HashSet<Hai> = self.sutehai_of(seat).into_iter().collect();
+        if let Some(riichi) = self
+            .opponent_riichi
+            .iter()
+            .find(|riichi| riichi.seat == seat)
+        {
+            genbutsu.insert(riichi.sutehai);
+            for (op, ..) in self.history.iter().skip(riichi.since) {
+                match op {
+                    Operation::Haiyama {
+                        kind: HaiyamaOperation::Discard(hai_vec),
+                        ..
+                    } => genbutsu.extend(hai_vec.iter().copied()),
+                    Operation::Tehai(TehaiOperation::Discard(hai))
+                    | Operation::Tehai(TehaiOperation::Riichi(hai)) => {
+                        genbutsu.insert(*hai);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        genbutsu
+    }
+
+    /// Undo last operation.
+    pub fn back(&mut self, haiyama_sensitive: bool) -> Result<(Operation, State), String> {
+        let haiyama_sensitive = haiyama_sensitive && !self.theory;
+        if let State::Ryuukyoku { .. } = self.state {
+            return Err(i18n::text(Message::UndoAfterRyuukyoku).to_string());
+        }
+        let (op, last_state, last_kawa) = self
+            .history
+            .pop()
+            .ok_or(i18n::text(Message::NoHistory).to_string())?;
+        match match last_state {
+            State::WaitToInit => self.back_wait_to_init(&op, haiyama_sensitive),
+            State::FullHai => self.back_full_hai(&op, haiyama_sensitive),
+            State::LackOneHai => self.back_lack_one_hai(&op, haiyama_sensitive),
+            State::WaitForRinshanhai => self.back_wait_for_rinshanhai(&op, haiyama_sensitive),
+            State::Ryuukyoku { .. } => Err("Logic error: Code cannot reach here.".to_string()),
+        } {
+            Ok(_) => {
+                if self.theory {
+                    self.sync_theory_haiyama()?;
+                }
+                self.state = last_state;
+                self.kawa = last_kawa;
+                self.tehai_zobrist = self.tehai.as_ref().map_or(0, Tehai::zobrist);
+                self.annotations.remove(&self.history.len());
+                self.sutehai_seats.remove(&self.history.len());
+                self.opponent_fuuro.remove(&self.history.len());
+                self.riichi_payouts.remove(&self.history.len());
+                let operation_number = self.history.len();
+                self.opponent_riichi
+                    .retain(|riichi| riichi.since <= operation_number);
+                if self.events.is_some() {
+                    self.emit(Event::Undone {
+                        operation: op.clone(),
+                        state: last_state,
+                    });
+                }
+                Ok((op, last_state))
+            }
+            Err(error) => {
+                self.history.push((op, last_state, last_kawa));
+                Err(error)
+            }
+        }
+    }
+
+    /// End the hand by ryuukyoku and settle noten bappu. Tenpai of self is judged by tehai,
+    /// and tenpai of others must be given by their seats.
+    ///
+    /// Tehai whose machihai are all in itself, which is 純カラ, is not tenpai, since it can
+    /// never win.
+    pub fn ryuukyoku(&mut self, others_tenpai: &[Kaze]) -> Result<Ryuukyoku, String> {
+        if self.state != State::LackOneHai {
+            return Err(i18n::text(Message::RyuukyokuNotLackOneHai).to_string());
+        }
+        if others_tenpai.contains(&self.jikaze) {
+            return Err(i18n::format(Message::SelfTenpai, &[&self.jikaze]));
+        }
+        let tehai = self
+            .tehai
+            .as_ref()
+            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+        let mut tenpai: BTreeSet<Kaze> = others_tenpai.iter().copied().collect();
+        let counts = tehai.to_counts();
+        if shanten::calculate_counts(&counts, tehai.fuuro.len()) == 0 {
+            let machi = shanten::machi_counts(&counts, tehai.fuuro.len());
+            let karaten = machi.iter().enumerate().all(|(index, machi)| {
+                // Index is always less than 34.
+                !machi || tehai.count(&Hai::from_index(index).unwrap()) == 4
+            });
+            if !karaten {
+                tenpai.insert(self.jikaze);
+            }
+        }
+        let ryuukyoku = self.ledger.ryuukyoku(&tenpai)?;
+        self.state = State::Ryuukyoku {
+            renchan: ryuukyoku.renchan,
+        };
+        Ok(ryuukyoku)
+    }
+
+    /// Check that tehai, fuuro, kawa and hai seen by operations on haiyama, such as sutehai of
+    /// others and dora indicators, are consistent with the number of hai in haiyama.
+    ///
+    /// Operations ignoring errors of haiyama (with `!`) may make them drift apart, so every
+    /// type of hai whose total is not its capacity is reported. In theory mode, only tehai is
+    /// taken out of haiyama.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut kawa_counts = Hai::to_counts(self.kawa.sutehai_vec().iter());
+        let mut seen_counts = [0i32; 34];
+        if self.theory {
+            kawa_counts = [0; 34];
+        }
+        for (op, ..) in self.history.iter().filter(|_| !self.theory) {
+            match op {
+                Operation::Haiyama {
+                    kind: HaiyamaOperation::Discard(hai_vec),
+                    ..
+                } => {
+                    for hai in hai_vec {
+                        seen_counts[hai.to_index()] += 1;
+                    }
+                }
+                Operation::Haiyama {
+                    kind: HaiyamaOperation::Dora(indicator),
+                    ..
+                } => seen_counts[indicator.to_index()] += 1,
+                Operation::Haiyama {
+                    kind: HaiyamaOperation::Add(hai_vec),
+                    ..
+                } => {
+                    for hai in hai_vec {
+                        seen_counts[hai.to_index()] -= 1;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let haiyama_counts = self.haiyama.to_counts();
+        let mut error_vec = vec![];
+        for index in 0..34 {
+            // Index is always less than 34.
+            let hai = Hai::from_index(index).unwrap();
+            let capacity = self.haiyama.capacity(&hai) as i32;
+            // Count of tehai includes fuuro.
+            let tehai_count = self.tehai.as_ref().map_or(0, |tehai| tehai.count(&hai));
+            let total = haiyama_counts[index] as i32
+                + tehai_count as i32
+                + kawa_counts[index] as i32
+                + seen_counts[index];
+            if total != capacity {
+                error_vec.push(format!(
+                    "'{}' is {}: {} in haiyama, {} in tehai, {} in kawa and {} seen, \
+                    but {} in total.",
+                    hai.to_string(),
+                    if total > capacity {
+                        "over-represented"
+                    } else {
+                        "under-represented"
+                    },
+                    haiyama_counts[index],
+                    tehai_count,
+                    kawa_counts[index],
+                    seen_counts[index],
+                    capacity
+                ));
+            }
+        }
+        if error_vec.is_empty() {
+            Ok(())
+        } else {
+            Err(error_vec.join("\n"))
+        }
+    }
+
+    /// Print self to json.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut sutehai_type_string_vec = vec![];
+        for hai in self.kawa.sutehai_type().iter() {
+            sutehai_type_string_vec.push(hai.to_string());
+        }
+
+        let mut exhausted_string_vec = vec![];
+        for hai in self.haiyama.exhausted() {
+            exhausted_string_vec.push(hai.to_string());
+        }
+
+        let tehai_json = match &self.tehai {
+            Some(tehai) => tehai.to_json(),
+            None => json!(i18n::text(Message::NotInitialized)),
+        };
+
+        let mut dora_indicator_string_vec = vec![];
+        for hai in &self.dora_indicators {
+            dora_indicator_string_vec.push(hai.to_string());
+        }
+
+        json!({
+            "haiyama": self.haiyama.to_json(),
+            "sutehai_type": json!(sutehai_type_string_vec),
+            "kawa": self.kawa.to_json(),
+            "jikaze": self.jikaze.to_hai().to_string(),
+            "bakaze": self.bakaze.to_hai().to_string(),
+            "dora_indicators": json!(dora_indicator_string_vec),
+            "exhausted": json!(exhausted_string_vec),
+            "tehai": tehai_json,
+            "theory": self.theory,
+        })
+    }
+
+    /// Record sutehai of an operation succeeded into kawa, and check whether a machihai in
+    /// sutehai of others is missed after riichi. Discarding machihai by self is always
+    /// furiten since it stays in kawa.
+    /// Update the zobrist hash of tehai by hai moved from the last tehai.
+    fn update_tehai_zobrist(&mut self, last_tehai: Option<&Tehai>) {
+        self.tehai_zobrist = match (last_tehai, &self.tehai) {
+            (Some(last_tehai), Some(tehai)) => {
+                let mut hash = self.tehai_zobrist
+                    ^ zobrist::delta_counts(
+                        &last_tehai.to_counts(),
+                        &tehai.to_counts(),
+                        &zobrist::TEHAI_KEYS,
+                    );
+                if last_tehai.fuuro != tehai.fuuro {
+                    hash ^=
+                        zobrist::hash_fuuro(&last_tehai.fuuro) ^ zobrist::hash_fuuro(&tehai.fuuro);
+                }
+                hash
+            }
+            (_, tehai) => tehai.as_ref().map_or(0, Tehai::zobrist),
+        };
+    }
+
+    fn update_kawa(&mut self, op: &Operation, last_tehai: Option<&Tehai>) {
+        let is_machihai = |tehai: Option<&Tehai>, hai_vec: &[Hai]| match tehai {
+            Some(tehai) if tehai.juntehai.len() % 3 == 1 => {
+                let counts = tehai.to_counts();
+                let fuuro_count = tehai.fuuro.len();
+                shanten::calculate_counts(&counts, fuuro_count) == 0
+                    && hai_vec
+                        .iter()
+                        .any(|hai| shanten::machi_counts(&counts, fuuro_count)[hai.to_index()])
+            }
+            _ => false,
+        };
+        match op {
+            Operation::Tehai(TehaiOperation::Discard(hai)) => self.kawa.push(*hai),
+            Operation::Tehai(TehaiOperation::Riichi(hai)) => {
+                // Checked before operating.
+                let _ = self.kawa.riichi(*hai);
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                ..
+            } => {
+                if self.kawa.is_riichi() && is_machihai(last_tehai, hai_vec) {
+                    self.kawa.miss_after_riichi();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Reject kuikae unless allowed by rules, which is discarding nakihai, or the hai on the
+    /// other side of juntsu when nakihai is at the end, right after chii or pon.
+    fn check_kuikae(&self, sutehai: &Hai) -> Result<(), String> {
+        if self.rules.kuikae {
+            return Ok(());
+        }
+        // The last operation on tehai, operations on haiyama such as dora may be between.
+        let last_naku = self.history.iter().rev().find_map(|(op, _, _)| match op {
+            Operation::Tehai(TehaiOperation::Naku { kind, .. }) => Some(Some(kind)),
+            Operation::Tehai(_) => Some(None),
+            Operation::Haiyama { .. } => None,
+        });
+        let (naku_name, kuikae_hai) = match last_naku {
+            Some(Some(Naku::Pon(Mentsu::Koutsu(hai)))) => ("pon", vec![*hai]),
+            Some(Some(Naku::Chii {
+                juntsu: Mentsu::Juntsu(first, _, last),
+                nakihai,
+            })) => {
+                let mut kuikae_hai = vec![*nakihai];
+                if nakihai == first {
+                    kuikae_hai.extend(last.next(self.player_number, false));
+                } else if nakihai == last {
+                    kuikae_hai.extend(first.previous(self.player_number, false));
+                }
+                ("chii", kuikae_hai)
+            }
+            _ => return Ok(()),
+        };
+        if kuikae_hai.contains(sutehai) {
+            Err(format!(
+                "Cannot discard '{}' right after {}, which is kuikae.",
+                sutehai, naku_name
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reveal a dora indicator, at most 5 of which including the first one.
+    fn reveal_dora(&mut self, indicator: &Hai, haiyama_sensitive: bool) -> Result<(), String> {
+        if self.dora_indicators.len() >= 5 {
+            return Err(i18n::text(Message::TooManyDoraIndicators).to_string());
+        }
+        if let Err(error) = self.haiyama.discard(indicator) {
+            if haiyama_sensitive {
+                return Err(error);
+            }
+        }
+        self.dora_indicators.push(*indicator);
+        Ok(())
+    }
+
+    /// Undo `reveal_dora()`. The indicator may have been cleared by `set_dora_indicators()`.
+    fn hide_dora(&mut self, indicator: &Hai, haiyama_sensitive: bool) -> Result<(), String> {
+        if let Err(error) = self.haiyama.add(indicator) {
+            if haiyama_sensitive {
+                return Err(error);
+            }
+        }
+        if let Some(index) = self
+            .dora_indicators
+            .iter()
+            .rposition(|hai| hai == indicator)
+        {
+            self.dora_indicators.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Reset haiyama to all hai out of own tehai, as haiyama is kept in theory mode.
+    fn sync_theory_haiyama(&mut self) -> Result<(), String> {
+        let mut haiyama = Haiyama::new(self.player_number);
+        if let Some(tehai) = &self.tehai {
+            let mut hai_vec = tehai.juntehai.clone();
+            for mentsu in &tehai.fuuro {
+                hai_vec.extend(mentsu.to_vec());
+            }
+            for hai in &hai_vec {
+                if haiyama.discard(hai).is_err() {
+                    return Err(i18n::format(Message::FifthHai, &[&hai]));
+                }
+            }
+        }
+        self.haiyama = haiyama;
+        Ok(())
+    }
+
+    fn emit(&mut self, event: Event) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
+    fn operate_wait_to_init(&mut self, op: &Operation) -> Result<(), String> {
+        fn operate_tehai_init(self_: &mut GameManager, tehai: &Tehai) -> Result<(), String> {
+            // Each fuuro takes the place of 3 juntehai, even kantsu.
+            match tehai.juntehai.len() + tehai.fuuro.len() * 3 {
+                13 => self_.state = State::LackOneHai,
+                14 => self_.state = State::FullHai,
+                num @ _ => {
+                    return Err(format!(
+                        "Cannot initialize tehai with {} juntehai, only 13 and 14 are supported.",
+                        num
+                    ))
+                }
+            }
+            let mut hai_vec = tehai.juntehai.clone();
+            for mentsu in &tehai.fuuro {
+                hai_vec.extend(mentsu.to_vec());
+            }
+            if let Err(error) = self_.haiyama.discard_with_vec(&hai_vec, !self_.theory) {
+                if !self_.theory {
+                    self_.state = State::WaitToInit;
+                    return Err(error);
+                }
+            }
+            self_.tehai = Some(tehai.clone());
+
+            Ok(())
+        }
+
+        match op {
+            Operation::Tehai(TehaiOperation::Initialize(tehai)) => {
+                operate_tehai_init(self, tehai)?;
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                haiyama_sensitive,
+            } => self.reveal_dora(indicator, *haiyama_sensitive)?,
+            _ => {
+                return Err(format!(
+                    "Unsupported opretion '{:?}' at state '{:?}'.",
+                    op, self.state
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn operate_full_hai(&mut self, op: &mut Operation) -> Result<(), String> {
+        match &*op {
+            Operation::Tehai(TehaiOperation::Discard(hai)) => {
+                self.check_kuikae(hai)?;
+                self.tehai.as_mut().unwrap().discard(hai)?;
+                self.state = State::LackOneHai;
+            }
+            Operation::Tehai(TehaiOperation::Riichi(hai)) => {
+                if self.kawa.is_riichi() {
+                    return Err(i18n::text(Message::AlreadyRiichi).to_string());
+                }
+                self.check_kuikae(hai)?;
+                self.ledger.riichi(self.jikaze)?;
+                if let Err(error) = self.tehai.as_mut().unwrap().discard(hai) {
+                    self.ledger.cancel_riichi(self.jikaze)?;
+                    return Err(error);
+                }
+                // Operation is pushed into history after this.
+                self.riichi_payouts
+                    .insert(self.history.len(), self.ledger.payouts());
+                self.state = State::LackOneHai;
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Kan(Kan::Unknown { kantsu, rinshanhai }),
+                haiyama_sensitive,
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                let state_backup = self.state;
+                let tehai_backup = self.tehai.clone();
+                if let Some(rinshanhai) = rinshanhai {
+                    if let Err(error) = haiyama.discard(rinshanhai) {
+                        if *haiyama_sensitive {
+                            return Err(error);
+                        }
+                    }
+                    self.state = State::FullHai;
+                } else {
+                    self.state = State::WaitForRinshanhai;
+                }
+
+                match self.tehai.as_mut().unwrap().kan(kantsu, rinshanhai) {
+                    Ok(kan) => {
+                        if let Kan::Ankan { .. } | Kan::Kakan { .. } = &kan {
+                            *op = Operation::Tehai(TehaiOperation::Naku {
+                                kind: Naku::Kan(kan),
+                                haiyama_sensitive: *haiyama_sensitive,
+                            })
+                        } else {
+                            self.state = state_backup;
+                            self.tehai = tehai_backup;
+                            return Err(
+                                "Logic error: Tehai currently is not able to kan.".to_string()
+                            );
+                        }
+                    }
+                    Err(error) => {
+                        self.state = state_backup;
+                        return Err(error);
+                    }
+                }
+                haiyama.commit();
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                haiyama_sensitive,
+            } => self.reveal_dora(indicator, *haiyama_sensitive)?,
+            _ => {
+                return Err(format!(
+                    "Unsupported opretion '{:?}' at state '{:?}'.",
+                    op, self.state
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn operate_lack_one_hai(&mut self, op: &mut Operation) -> Result<(), String> {
+        match &*op {
+            Operation::Tehai(TehaiOperation::Add {
+                hai,
+                haiyama_sensitive,
+            }) => {
+                if let Err(error) = self.haiyama.discard(hai) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai.as_mut().unwrap().juntehai.push(*hai);
+                self.tehai.as_mut().unwrap().juntehai.sort();
+                self.state = State::FullHai;
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Chii { juntsu, nakihai },
+                haiyama_sensitive,
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                if let Err(error) = haiyama.discard(nakihai) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai.as_mut().unwrap().chii(juntsu, nakihai)?;
+                haiyama.commit();
+                self.state = State::FullHai;
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Pon(koutsu @ Mentsu::Koutsu(hai)),
+                haiyama_sensitive,
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                if let Err(error) = haiyama.discard(hai) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai.as_mut().unwrap().pon(koutsu)?;
+                haiyama.commit();
+                self.state = State::FullHai;
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind:
+                    Naku::Kan(Kan::Unknown {
+                        kantsu: kantsu @ Mentsu::Kantsu(hai),
+                        rinshanhai,
+                    }),
+                haiyama_sensitive,
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                let state_backup = self.state;
+                let tehai_backup = self.tehai.clone();
+                if let Err(error) = haiyama.discard(hai) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                if let Some(rinshanhai) = rinshanhai {
+                    if let Err(error) = haiyama.discard(rinshanhai) {
+                        if *haiyama_sensitive {
+                            return Err(error);
+                        }
+                    }
+                    self.state = State::FullHai;
+                } else {
+                    self.state = State::WaitForRinshanhai;
+                }
+
+                match self.tehai.as_mut().unwrap().kan(kantsu, rinshanhai) {
+                    Ok(kan) => {
+                        if let Kan::Daiminkan { .. } = &kan {
+                            *op = Operation::Tehai(TehaiOperation::Naku {
+                                kind: Naku::Kan(kan),
+                                haiyama_sensitive: *haiyama_sensitive,
+                            })
+                        } else {
+                            self.state = state_backup;
+                            self.tehai = tehai_backup;
+                            return Err(
+                                "Logic error: Tehai currently is not able to kan.".to_string()
+                            );
+                        }
+                    }
+                    Err(error) => {
+                        self.state = state_backup;
+                        return Err(error);
+                    }
+                }
+                haiyama.commit();
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                haiyama_sensitive,
+            } => self.reveal_dora(indicator, *haiyama_sensitive)?,
+            _ => {
+                return Err(format!(
+                    "Unsupported opretion '{:?}' at state '{:?}'.",
+                    op, self.state
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn operate_wait_for_rinshanhai(&mut self, op: &Operation) -> Result<(), String> {
+        match op {
+            Operation::Tehai(TehaiOperation::Add {
+                hai,
+                haiyama_sensitive,
+            }) => {
+                if let Err(error) = self.haiyama.discard(hai) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai.as_mut().unwrap().juntehai.push(*hai);
+                self.tehai.as_mut().unwrap().juntehai.sort();
+                self.state = State::FullHai;
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                haiyama_sensitive,
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, *haiyama_sensitive) {
+                    if *haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                haiyama_sensitive,
+            } => self.reveal_dora(indicator, *haiyama_sensitive)?,
+            _ => {
+                return Err(format!(
+                    "Unsupported opretion '{:?}' at state '{:?}'.",
+                    op, self.state
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn back_wait_to_init(&mut self, op: &Operation, haiyama_sensitive: bool) -> Result<(), String> {
+        match op {
+            Operation::Tehai(TehaiOperation::Initialize(tehai)) => {
+                let mut hai_vec = tehai.juntehai.clone();
+                for mentsu in &tehai.fuuro {
+                    hai_vec.extend(mentsu.to_vec());
+                }
+                if let Err(error) = self.haiyama.add_with_vec(&hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai = None;
+                self.state = State::WaitToInit;
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                ..
+            } => self.hide_dora(indicator, haiyama_sensitive)?,
+            _ => return Err(format!("Logic error: confused with impossible state.")),
+        }
+        Ok(())
+    }
+
+    fn back_full_hai(&mut self, op: &Operation, haiyama_sensitive: bool) -> Result<(), String> {
+        match op {
+            Operation::Tehai(TehaiOperation::Discard(hai)) => {
+                self.tehai.as_mut().unwrap().juntehai.push(*hai);
+                self.tehai.as_mut().unwrap().juntehai.sort();
+            }
+            Operation::Tehai(TehaiOperation::Riichi(hai)) => {
+                // Riichi stick is taken by any winning settled after riichi.
+                let payouts = self.riichi_payouts.get(&self.history.len()).copied();
+                if payouts == Some(self.ledger.payouts()) && self.ledger.kyoutaku() > 0 {
+                    self.ledger.cancel_riichi(self.jikaze)?;
+                }
+                self.tehai.as_mut().unwrap().juntehai.push(*hai);
+                self.tehai.as_mut().unwrap().juntehai.sort();
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Kan(kan),
+                ..
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                if let Kan::Daiminkan {
+                    kantsu: Mentsu::Kantsu(hai),
+                    ..
+                } = kan
+                {
+                    if let Err(error) = haiyama.add(hai) {
+                        if haiyama_sensitive {
+                            return Err(error);
+                        }
+                    }
+                }
+                if let Some(rinshanhai) = match kan {
+                    Kan::Daiminkan { rinshanhai, .. } => rinshanhai,
+                    Kan::Kakan { rinshanhai, .. } => rinshanhai,
+                    Kan::Ankan { rinshanhai, .. } => rinshanhai,
+                    _ => {
+                        return Err(
+                            "Logic error: Tehai::de_kan() can not accept Kan::Unknown.".to_string()
+                        );
+                    }
+                } {
+                    if let Err(error) = haiyama.add(rinshanhai) {
+                        if haiyama_sensitive {
+                            return Err(error);
+                        }
+                    }
+                }
+                self.tehai.as_mut().unwrap().de_kan(kan)?;
+                haiyama.commit();
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                ..
+            } => self.hide_dora(indicator, haiyama_sensitive)?,
+            _ => return Err(format!("Logic error: confused with impossible state.")),
+        }
+        Ok(())
+    }
+
+    fn back_lack_one_hai(&mut self, op: &Operation, haiyama_sensitive: bool) -> Result<(), String> {
+        match op {
+            Operation::Tehai(TehaiOperation::Add { hai, .. }) => {
+                self.tehai.as_mut().unwrap().discard(hai)?;
+                if let Err(error) = self.haiyama.add(hai) {
+                    if haiyama_sensitive {
+                        self.tehai.as_mut().unwrap().juntehai.push(*hai);
+                        self.tehai.as_mut().unwrap().juntehai.sort();
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind:
+                    Naku::Chii {
+                        juntsu: juntsu @ Mentsu::Juntsu(..),
+                        nakihai,
+                    },
+                ..
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                if let Err(error) = haiyama.add(nakihai) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai.as_mut().unwrap().de_chii(juntsu, nakihai)?;
+                haiyama.commit();
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Pon(koutsu @ Mentsu::Koutsu(hai)),
+                ..
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                if let Err(error) = haiyama.add(hai) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+                self.tehai.as_mut().unwrap().de_pon(koutsu)?;
+                haiyama.commit();
+            }
+            Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Kan(kan),
+                ..
+            }) => {
+                let mut haiyama = self.haiyama.begin();
+                if let Kan::Daiminkan {
+                    kantsu: Mentsu::Kantsu(hai),
+                    ..
+                } = kan
+                {
+                    if let Err(error) = haiyama.add(hai) {
+                        if haiyama_sensitive {
+                            return Err(error);
+                        }
+                    }
+                }
+                if let Some(rinshanhai) = match kan {
+                    Kan::Daiminkan { rinshanhai, .. } => rinshanhai,
+                    Kan::Kakan { rinshanhai, .. } => rinshanhai,
+                    Kan::Ankan { rinshanhai, .. } => rinshanhai,
+                    _ => {
+                        return Err(
+                            "Logic error: Tehai::de_kan() can not accept Kan::Unknown.".to_string()
+                        );
+                    }
+                } {
+                    if let Err(error) = haiyama.add(rinshanhai) {
+                        if haiyama_sensitive {
+                            return Err(error);
+                        }
+                    }
+                }
+                self.tehai.as_mut().unwrap().de_kan(kan)?;
+                haiyama.commit();
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                ..
+            } => self.hide_dora(indicator, haiyama_sensitive)?,
+            _ => return Err(format!("Logic error: confused with impossible state.")),
+        }
+        Ok(())
+    }
+
+    fn back_wait_for_rinshanhai(
+        &mut self,
+        op: &Operation,
+        haiyama_sensitive: bool,
+    ) -> Result<(), String> {
+        match op {
+            Operation::Tehai(TehaiOperation::Add { hai, .. }) => {
+                self.tehai.as_mut().unwrap().discard(hai)?;
+                if let Err(error) = self.haiyama.add(hai) {
+                    if haiyama_sensitive {
+                        self.tehai.as_mut().unwrap().juntehai.push(*hai);
+                        self.tehai.as_mut().unwrap().juntehai.sort();
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Add(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.discard_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(hai_vec),
+                ..
+            } => {
+                if let Err(error) = self.haiyama.add_with_vec(hai_vec, haiyama_sensitive) {
+                    if haiyama_sensitive {
+                        return Err(error);
+                    }
+                }
+            }
+            Operation::Haiyama {
+                kind: HaiyamaOperation::Dora(indicator),
+                ..
+            } => self.hide_dora(indicator, haiyama_sensitive)?,
+            _ => return Err(format!("Logic error: confused with impossible state.")),
+        }
+        Ok(())
+    }
+}
+
+// Fails to compile if a field stops being `Send + Sync`, such as `Rc` or `RefCell`.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<GameManager>();
+    assert_send_sync::<Box<dyn Situation>>();
+};
+
+impl Situation for GameManager {
+    fn haiyama(&self) -> &Haiyama {
+        self.haiyama()
+    }
+
+    fn yakuhai_type(&self) -> BTreeSet<Hai> {
+        self.yakuhai_type()
+    }
+
+    fn kawa(&self) -> &Kawa {
+        self.kawa()
+    }
+
+    fn sutehai_type(&self) -> &BTreeSet<Hai> {
+        self.sutehai_type()
+    }
+
+    fn after_draw(&self, hai: Hai) -> Result<Box<dyn Situation>, String> {
+        let mut game_manager = self.clone();
+        if self.theory {
+            // Operations resync haiyama to tehai in theory mode, which would put it back.
+            let _ = game_manager.haiyama.discard(&hai);
+            return Ok(Box::new(game_manager));
+        }
+        game_manager.operate(Operation::Haiyama {
+            kind: HaiyamaOperation::Discard(vec![hai]),
+            haiyama_sensitive: false,
+        })?;
+        Ok(Box::new(game_manager))
+    }
+}
+
+impl std::fmt::Display for GameManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut sutehai_type_string = "".to_string();
+        if self.kawa.sutehai_type().len() == 0 {
+            sutehai_type_string += "無し";
+        } else {
+            for hai in self.kawa.sutehai_type().iter() {
+                sutehai_type_string += &hai.to_string();
+                sutehai_type_string += " ";
+            }
+        }
+        let mut exhausted_string = "".to_string();
+        let exhausted = self.haiyama.exhausted();
+        if exhausted.len() == 0 {
+            exhausted_string += "無し";
+        } else {
+            for hai in exhausted.iter() {
+                exhausted_string += &hai.to_string();
+                exhausted_string += " ";
+            }
+        }
+
+        write!(
+            f,
+            "牌山:\n  {}\n枯れ牌:\n  {}\n河:\n  {}\n捨て牌の種類:\n  {}\n手牌:\n  {}\n状態:\n  {:?} {}場 {}家",
+            self.haiyama.to_string(),
+            exhausted_string,
+            self.kawa,
+            sutehai_type_string,
+            match &self.tehai {
+                Some(tehai) => tehai.to_string(),
+                None => i18n::text(Message::NotInitialized).to_string(),
+            },
+            self.state,
+            self.bakaze,
+            self.jikaze
+        )
+    }
+}
@@ -0,0 +1,494 @@
+use super::{
+    shanten, simulate, simulate::Rng, Hai, Kaze, Ledger, PlayerNumber, Rules, Ryuukyoku, Score,
+};
+use serde_json::json;
+use std::collections::BTreeSet;
+
+/// Rules of a match.
+///
+/// # Japanese
+/// * hanchan: 半荘, a match of east and south rounds.
+/// * tonpuusen: 東風戦, a match of east round only.
+/// * uma: ウマ, bonus by placement.
+/// * oka: オカ, difference between return points and starting points of all players,
+/// which goes to the top.
+/// * tobi: 飛び, the match ends when points of any player are below 0.
+///
+/// # Member
+/// * starting_points: Points of each player at start.
+/// * return_points: Points subtracted from each player at the end.
+/// * uma: Bonus of each placement from the top, in thousands of points.
+/// * rounds: Number of rounds, 1 for tonpuusen and 2 for hanchan.
+/// * tobi: Whether the match ends when points of any player are below 0.
+#[derive(Clone, Debug)]
+pub struct MatchRules {
+    pub starting_points: i32,
+    pub return_points: i32,
+    pub uma: Vec<i32>,
+    pub rounds: usize,
+    pub tobi: bool,
+}
+
+/// Result of a hand, players are identified by index from chiicha.
+///
+/// # Japanese
+/// * chiicha: 起家, oya of the first hand.
+#[derive(Clone, Debug)]
+pub enum HandResult {
+    Ron {
+        winner: usize,
+        loser: usize,
+        score: Score,
+    },
+    Tsumo {
+        winner: usize,
+        score: Score,
+    },
+    Ryuukyoku {
+        tenpai: BTreeSet<usize>,
+    },
+}
+
+/// Record of a settled hand.
+///
+/// # Japanese
+/// * kyoku: 局
+///
+/// # Member
+/// * bakaze: Wind of round.
+/// * kyoku: Number of hand in the round from 1, which is also oya from 1.
+/// * honba: Number of honba before settlement.
+/// * result: Result of the hand.
+#[derive(Clone, Debug)]
+pub struct HandRecord {
+    pub bakaze: Kaze,
+    pub kyoku: usize,
+    pub honba: u32,
+    pub result: HandResult,
+}
+
+/// Final placement of a player.
+///
+/// # Member
+/// * player: Index from chiicha.
+/// * rank: Placement from 1.
+/// * points: Points at the end.
+/// * score: Final score after return points, uma and oka, in thousands of points.
+#[derive(Copy, Clone, Debug)]
+pub struct Placement {
+    pub player: usize,
+    pub rank: usize,
+    pub points: i32,
+    pub score: f64,
+}
+
+/// A match stringing hands together, which rotates oya, counts honba and settles
+/// placements at the end.
+#[derive(Clone, Debug)]
+pub struct Hanchan {
+    player_number: PlayerNumber,
+    rules: MatchRules,
+    points: Vec<i32>,
+    bakaze: Kaze,
+    kyoku: usize,
+    honba: u32,
+    kyoutaku: u32,
+    finished: bool,
+    records: Vec<HandRecord>,
+}
+
+impl MatchRules {
+    /// Common rules of hanchan, 25000 starting and 30000 return with uma 20-10 for
+    /// 4-player mode, or 35000 starting and 40000 return with uma 20 for 3-player mode.
+    pub fn new(player_number: PlayerNumber) -> Self {
+        match player_number {
+            PlayerNumber::Four => Self {
+                starting_points: 25000,
+                return_points: 30000,
+                uma: vec![20, 10, -10, -20],
+                rounds: 2,
+                tobi: true,
+            },
+            PlayerNumber::Three => Self {
+                starting_points: 35000,
+                return_points: 40000,
+                uma: vec![20, 0, -20],
+                rounds: 2,
+                tobi: true,
+            },
+        }
+    }
+}
+
+impl Hanchan {
+    pub fn new(player_number: PlayerNumber, rules: MatchRules) -> Self {
+        let player_count = match player_number {
+            PlayerNumber::Four => 4,
+            PlayerNumber::Three => 3,
+        };
+        Self {
+            player_number,
+            points: vec![rules.starting_points; player_count],
+            rules,
+            bakaze: Kaze::Ton,
+            kyoku: 1,
+            honba: 0,
+            kyoutaku: 0,
+            finished: false,
+            records: vec![],
+        }
+    }
+
+    /// Number of players.
+    pub fn player_count(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Index of oya in current hand.
+    pub fn oya(&self) -> usize {
+        self.kyoku - 1
+    }
+
+    /// Wind of seat of a player in current hand.
+    pub fn jikaze(&self, player: usize) -> Kaze {
+        Kaze::from_index((player + self.player_count() - self.oya()) % self.player_count())
+    }
+
+    pub fn bakaze(&self) -> Kaze {
+        self.bakaze
+    }
+
+    pub fn kyoku(&self) -> usize {
+        self.kyoku
+    }
+
+    pub fn honba(&self) -> u32 {
+        self.honba
+    }
+
+    pub fn points(&self) -> &Vec<i32> {
+        &self.points
+    }
+
+    pub fn records(&self) -> &Vec<HandRecord> {
+        &self.records
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Deposit a riichi stick of a player.
+    pub fn riichi(&mut self, player: usize) -> Result<(), String> {
+        let mut ledger = self.ledger();
+        ledger.riichi(self.jikaze(player))?;
+        self.take_ledger(&ledger);
+        Ok(())
+    }
+
+    /// Settle a hand, then move to the next hand or finish the match.
+    pub fn settle(&mut self, result: HandResult) -> Result<(), String> {
+        if self.finished {
+            return Err("The match has finished.".to_string());
+        }
+        let oya = self.oya();
+        let mut ledger = self.ledger();
+        let renchan = match &result {
+            HandResult::Ron {
+                winner,
+                loser,
+                score,
+            } => {
+                ledger.ron(self.jikaze(*winner), self.jikaze(*loser), score)?;
+                *winner == oya
+            }
+            HandResult::Tsumo { winner, score } => {
+                ledger.tsumo(self.jikaze(*winner), score)?;
+                *winner == oya
+            }
+            HandResult::Ryuukyoku { tenpai } => {
+                let mut tenpai_kaze = BTreeSet::new();
+                for player in tenpai {
+                    tenpai_kaze.insert(self.jikaze(*player));
+                }
+                let Ryuukyoku { renchan, .. } = ledger.ryuukyoku(&tenpai_kaze)?;
+                renchan
+            }
+        };
+        self.records.push(HandRecord {
+            bakaze: self.bakaze,
+            kyoku: self.kyoku,
+            honba: self.honba,
+            result: result.clone(),
+        });
+        self.take_ledger(&ledger);
+
+        // Honba increases on renchan and ryuukyoku, and is reset when ko wins.
+        match &result {
+            HandResult::Ryuukyoku { .. } => self.honba += 1,
+            _ if renchan => self.honba += 1,
+            _ => self.honba = 0,
+        }
+
+        let all_last =
+            self.bakaze.to_index() + 1 == self.rules.rounds && self.kyoku == self.player_count();
+        let top = self.points.iter().max().copied().unwrap_or(0);
+        let tobi = self.rules.tobi && self.points.iter().any(|points| *points < 0);
+        // Oya at the top may stop instead of renchan at the last hand.
+        let oya_top = self.points[oya] == top && top >= self.rules.return_points;
+        if tobi || (all_last && (!renchan || oya_top)) {
+            self.finished = true;
+        } else if !renchan {
+            self.kyoku += 1;
+            if self.kyoku > self.player_count() {
+                self.kyoku = 1;
+                self.bakaze = Kaze::from_index(self.bakaze.to_index() + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Final placements, ties are broken by seat from chiicha. Riichi sticks left go to
+    /// the top.
+    pub fn placements(&self) -> Vec<Placement> {
+        let mut players: Vec<usize> = (0..self.player_count()).collect();
+        players.sort_by(|lhs, rhs| self.points[*rhs].cmp(&self.points[*lhs]).then(lhs.cmp(rhs)));
+
+        let oka = (self.rules.return_points - self.rules.starting_points) as f64
+            * self.player_count() as f64
+            / 1000.0;
+        let mut placements = vec![];
+        for (rank, player) in players.into_iter().enumerate() {
+            let mut points = self.points[player];
+            if rank == 0 {
+                points += 1000 * self.kyoutaku as i32;
+            }
+            let mut score = (points - self.rules.return_points) as f64 / 1000.0
+                + *self.rules.uma.get(rank).unwrap_or(&0) as f64;
+            if rank == 0 {
+                score += oka;
+            }
+            placements.push(Placement {
+                player,
+                rank: rank + 1,
+                points,
+                score,
+            });
+        }
+        placements
+    }
+
+    /// Play hands by simulation until the match finishes, and return final placements.
+    ///
+    /// Each player draws and discards greedily to reduce shanten without riichi or naku,
+    /// and every winning scores `han` and `fu` since yaku are not judged.
+    pub fn simulate(&mut self, han: u32, fu: u32, seed: u64) -> Result<Vec<Placement>, String> {
+        let mut rng = Rng::new(seed);
+        while !self.finished {
+            let result = self.simulate_hand(han, fu, &mut rng)?;
+            self.settle(result)?;
+        }
+        Ok(self.placements())
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut record_json_vec = vec![];
+        for record in &self.records {
+            record_json_vec.push(record.to_json());
+        }
+        let mut placement_json_vec = vec![];
+        for placement in self.placements() {
+            placement_json_vec.push(placement.to_json());
+        }
+        json!({
+            "points": self.points,
+            "bakaze": self.bakaze.to_hai().to_string(),
+            "kyoku": self.kyoku,
+            "honba": self.honba,
+            "kyoutaku": self.kyoutaku,
+            "finished": self.finished,
+            "records": record_json_vec,
+            "placements": placement_json_vec,
+        })
+    }
+
+    /// Ledger of current hand, seats are winds of players.
+    fn ledger(&self) -> Ledger {
+        let mut ledger = Ledger::new(self.player_number, 0);
+        for (player, points) in self.points.iter().enumerate() {
+            // Seat of every player is always in game.
+            ledger.set_points(self.jikaze(player), *points).unwrap();
+        }
+        ledger.set_honba(self.honba);
+        ledger.set_kyoutaku(self.kyoutaku);
+        ledger
+    }
+
+    /// Take points and riichi sticks back from ledger.
+    fn take_ledger(&mut self, ledger: &Ledger) {
+        for player in 0..self.player_count() {
+            // Seat of every player is always in game.
+            self.points[player] = ledger.points(self.jikaze(player)).unwrap();
+        }
+        self.kyoutaku = ledger.kyoutaku();
+    }
+
+    /// Play a hand from shuffled haiyama, players draw in turn from oya.
+    fn simulate_hand(&self, han: u32, fu: u32, rng: &mut Rng) -> Result<HandResult, String> {
+        let player_count = self.player_count();
+        let mut yama = vec![];
+        for hai in Hai::all_type(self.player_number) {
+            for _ in 0..4 {
+                yama.push(hai.to_index());
+            }
+        }
+        rng.shuffle(&mut yama);
+        // The last 14 hai are wanpai.
+        let live_number = yama.len() - 14;
+
+        let mut counts_vec = vec![[0u8; 34]; player_count];
+        let mut drawn = 0;
+        for _ in 0..13 {
+            for counts in counts_vec.iter_mut() {
+                counts[yama[drawn]] += 1;
+                drawn += 1;
+            }
+        }
+
+        let rules = Rules::default();
+        let score = |winner: usize| Score::new(han, fu, winner == self.oya(), &rules);
+        let mut player = self.oya();
+        while drawn < live_number {
+            let counts = &mut counts_vec[player];
+            counts[yama[drawn]] += 1;
+            drawn += 1;
+            let shanten = shanten::calculate_counts(counts, 0);
+            if shanten == -1 {
+                return Ok(HandResult::Tsumo {
+                    winner: player,
+                    score: score(player)?,
+                });
+            }
+            let sutehai = simulate::greedy_sutehai(counts, 0, shanten)
+                .ok_or("Logic error: No hai to discard.".to_string())?;
+            counts[sutehai] -= 1;
+
+            // Ron is checked from shimocha, and furiten is ignored.
+            for offset in 1..player_count {
+                let other = (player + offset) % player_count;
+                let mut counts = counts_vec[other];
+                counts[sutehai] += 1;
+                if shanten::calculate_counts(&counts, 0) == -1 {
+                    return Ok(HandResult::Ron {
+                        winner: other,
+                        loser: player,
+                        score: score(other)?,
+                    });
+                }
+            }
+            player = (player + 1) % player_count;
+        }
+
+        let mut tenpai = BTreeSet::new();
+        for (player, counts) in counts_vec.iter().enumerate() {
+            if shanten::calculate_counts(counts, 0) == 0 {
+                tenpai.insert(player);
+            }
+        }
+        Ok(HandResult::Ryuukyoku { tenpai })
+    }
+}
+
+impl HandRecord {
+    pub fn to_json(&self) -> serde_json::Value {
+        let result = match &self.result {
+            HandResult::Ron {
+                winner,
+                loser,
+                score,
+            } => json!({
+                "type": "ron",
+                "winner": winner,
+                "loser": loser,
+                "score": score.to_json(),
+            }),
+            HandResult::Tsumo { winner, score } => json!({
+                "type": "tsumo",
+                "winner": winner,
+                "score": score.to_json(),
+            }),
+            HandResult::Ryuukyoku { tenpai } => json!({
+                "type": "ryuukyoku",
+                "tenpai": tenpai,
+            }),
+        };
+        json!({
+            "bakaze": self.bakaze.to_hai().to_string(),
+            "kyoku": self.kyoku,
+            "honba": self.honba,
+            "result": result,
+        })
+    }
+}
+
+impl Placement {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "player": self.player,
+            "rank": self.rank,
+            "points": self.points,
+            "score": self.score,
+        })
+    }
+}
+
+impl std::fmt::Display for HandRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result_string = match &self.result {
+            HandResult::Ron {
+                winner,
+                loser,
+                score,
+            } => format!(
+                "P{} 栄和 P{}放銃 {}飜{}符 {}点",
+                winner + 1,
+                loser + 1,
+                score.han,
+                score.fu,
+                score.ron
+            ),
+            HandResult::Tsumo { winner, score } => {
+                format!("P{} 自摸 {}飜{}符", winner + 1, score.han, score.fu)
+            }
+            HandResult::Ryuukyoku { tenpai } => {
+                let mut tenpai_string_vec = vec![];
+                for player in tenpai {
+                    tenpai_string_vec.push(format!("P{}", player + 1));
+                }
+                if tenpai_string_vec.is_empty() {
+                    "流局 全員不聴".to_string()
+                } else {
+                    format!("流局 聴牌 {}", tenpai_string_vec.join(" "))
+                }
+            }
+        };
+        write!(
+            f,
+            "{}{}局{}本場 {}",
+            self.bakaze, self.kyoku, self.honba, result_string
+        )
+    }
+}
+
+impl std::fmt::Display for Placement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}位 P{} {}点 {:+.1}",
+            self.rank,
+            self.player + 1,
+            self.points,
+            self.score
+        )
+    }
+}
@@ -0,0 +1,260 @@
+use super::{
+    Annotation, GameManager, Hai, HaiyamaOperation, Kan, Naku, Operation, PlayerNumber,
+    TehaiOperation,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Record of all operations in a game, which can be archived and shared.
+///
+/// Text form is human-readable, one operation each line in the same notation of
+/// interactive commands, such as `+4m`, `-1s` and `>465s`. The first line is the number
+/// of players, `4pl` or `3pl`, and lines starting with `#` are comments. A line starting
+/// with `@` annotates the operation before it, like `@ #misclick` or `@ considering pon here`.
+/// Before any operation, `player <name>` optionally names the player, for statistics over
+/// many kifu.
+///
+/// # Japanese
+/// * kifu: 棋譜
+#[derive(Clone, Debug)]
+pub struct Kifu {
+    pub player_number: PlayerNumber,
+    pub player: Option<String>,
+    pub operations: Vec<Operation>,
+    pub annotations: BTreeMap<usize, Vec<Annotation>>,
+}
+
+impl Kifu {
+    /// Record operation history of a game manager.
+    pub fn from_game_manager(game_manager: &GameManager) -> Self {
+        let mut operations = vec![];
+        for (op, _, _) in game_manager.history() {
+            operations.push(op.clone());
+        }
+        Self {
+            player_number: game_manager.player_number(),
+            player: None,
+            operations,
+            annotations: game_manager.annotations().clone(),
+        }
+    }
+
+    /// Parse kifu in text form.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut player_number = None;
+        let mut player = None;
+        let mut operations = vec![];
+        let mut annotations: BTreeMap<usize, Vec<Annotation>> = BTreeMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match player_number {
+                None => {
+                    player_number = Some(match line {
+                        "3pl" => PlayerNumber::Three,
+                        "4pl" => PlayerNumber::Four,
+                        _ => {
+                            return Err(format!(
+                                "Kifu should start with '3pl' or '4pl', but '{}' found at line {}.",
+                                line,
+                                index + 1
+                            ))
+                        }
+                    })
+                }
+                Some(_) if operations.is_empty() && line.starts_with("player ") => {
+                    player = Some(line[7..].trim().to_string());
+                }
+                Some(_) if line.starts_with('@') => {
+                    if operations.is_empty() {
+                        return Err(format!(
+                            "Line {}: No operation before annotation.",
+                            index + 1
+                        ));
+                    }
+                    let annotation = Annotation::new(&line[1..])
+                        .map_err(|error| format!("Line {}: {}", index + 1, error))?;
+                    annotations
+                        .entry(operations.len() - 1)
+                        .or_default()
+                        .push(annotation);
+                }
+                Some(player_number) => match Operation::parse(line, player_number) {
+                    Ok(op) => operations.push(op),
+                    Err(error) => return Err(format!("Line {}: {}", index + 1, error)),
+                },
+            }
+        }
+
+        Ok(Self {
+            player_number: player_number.ok_or("Empty kifu.".to_string())?,
+            player,
+            operations,
+            annotations,
+        })
+    }
+
+    /// Replay all operations on a fresh game manager.
+    pub fn replay(&self) -> Result<GameManager, String> {
+        let mut game_manager = GameManager::new(self.player_number);
+        for (index, op) in self.operations.iter().enumerate() {
+            if let Err(error) = game_manager.operate(op.unresolved()) {
+                return Err(format!("Failed to replay operation [{}]: {}", index, error));
+            }
+            for annotation in self.annotations.get(&index).into_iter().flatten() {
+                game_manager.annotate(annotation.clone())?;
+            }
+        }
+        Ok(game_manager)
+    }
+
+    /// Replay and check that the end state is equal to the game manager's, which means
+    /// kifu records the game completely.
+    pub fn verify(&self, game_manager: &GameManager) -> Result<(), String> {
+        let replayed = self.replay()?;
+        if replayed.state != game_manager.state {
+            return Err(format!(
+                "State differs: {:?} replayed, {:?} expected.",
+                replayed.state, game_manager.state
+            ));
+        }
+        if replayed.tehai() != game_manager.tehai() {
+            return Err("Tehai differs after replaying.".to_string());
+        }
+        if replayed.haiyama().to_counts() != game_manager.haiyama().to_counts() {
+            return Err("Haiyama differs after replaying.".to_string());
+        }
+        if replayed.kawa() != game_manager.kawa() {
+            return Err("Kawa differs after replaying.".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut operation_json_vec = vec![];
+        for op in &self.operations {
+            operation_json_vec.push(op.to_json());
+        }
+        let mut annotation_json_vec = vec![];
+        for (index, annotations) in &self.annotations {
+            for annotation in annotations {
+                let mut annotation_json = annotation.to_json();
+                annotation_json["operation"] = json!(index);
+                annotation_json_vec.push(annotation_json);
+            }
+        }
+        json!({
+            "player_number": self.player_number.to_string(),
+            "player": self.player,
+            "operations": operation_json_vec,
+            "annotations": annotation_json_vec,
+        })
+    }
+}
+
+impl std::fmt::Display for Kifu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut kifu_string = format!("# Japanese Mahjong Theory kifu\n{}pl", self.player_number);
+        if let Some(player) = &self.player {
+            kifu_string += &format!("\nplayer {}", player);
+        }
+        for (index, op) in self.operations.iter().enumerate() {
+            kifu_string += "\n";
+            kifu_string += &operation_to_line(op);
+            for annotation in self.annotations.get(&index).into_iter().flatten() {
+                kifu_string += &format!("\n@ {}", annotation);
+            }
+        }
+        write!(f, "{}", kifu_string)
+    }
+}
+
+/// Operation in the notation of interactive commands, the same as a line of kifu.
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", operation_to_line(self))
+    }
+}
+
+/// Write an operation in the notation of interactive commands.
+fn operation_to_line(op: &Operation) -> String {
+    fn sensitive_mark(haiyama_sensitive: bool) -> &'static str {
+        if haiyama_sensitive {
+            ""
+        } else {
+            "!"
+        }
+    }
+
+    fn hai_vec_string(hai_vec: &[Hai]) -> String {
+        let mut string = String::new();
+        for hai in hai_vec {
+            string += &hai.to_string();
+        }
+        string
+    }
+
+    match op {
+        Operation::Haiyama {
+            kind,
+            haiyama_sensitive,
+        } => match kind {
+            HaiyamaOperation::Add(hai_vec) => format!(
+                "*{}+{}",
+                sensitive_mark(*haiyama_sensitive),
+                hai_vec_string(hai_vec)
+            ),
+            HaiyamaOperation::Discard(hai_vec) => format!(
+                "*{}-{}",
+                sensitive_mark(*haiyama_sensitive),
+                hai_vec_string(hai_vec)
+            ),
+            HaiyamaOperation::Dora(indicator) => {
+                format!("*{}d{}", sensitive_mark(*haiyama_sensitive), indicator)
+            }
+        },
+        Operation::Tehai(TehaiOperation::Initialize(tehai)) => tehai.to_string(),
+        Operation::Tehai(TehaiOperation::Add {
+            hai,
+            haiyama_sensitive,
+        }) => format!("+{}{}", sensitive_mark(*haiyama_sensitive), hai),
+        Operation::Tehai(TehaiOperation::Discard(hai)) => format!("-{}", hai),
+        Operation::Tehai(TehaiOperation::Riichi(hai)) => format!("r{}", hai),
+        Operation::Tehai(TehaiOperation::Naku {
+            kind,
+            haiyama_sensitive,
+        }) => {
+            let hai_vec = match kind {
+                // Nakihai is the last one of chii.
+                Naku::Chii { juntsu, nakihai } => {
+                    let mut hai_vec = juntsu.to_vec();
+                    if let Some(index) = hai_vec.iter().position(|hai| hai == nakihai) {
+                        hai_vec.remove(index);
+                    }
+                    hai_vec.push(*nakihai);
+                    hai_vec
+                }
+                Naku::Pon(koutsu) => koutsu.to_vec(),
+                Naku::Kan(kan) => match kan {
+                    Kan::Daiminkan { kantsu, rinshanhai }
+                    | Kan::Kakan { kantsu, rinshanhai }
+                    | Kan::Ankan { kantsu, rinshanhai }
+                    | Kan::Unknown { kantsu, rinshanhai } => {
+                        let mut hai_vec = kantsu.to_vec();
+                        if let Some(rinshanhai) = rinshanhai {
+                            hai_vec.push(*rinshanhai);
+                        }
+                        hai_vec
+                    }
+                },
+            };
+            format!(
+                ">{}{}",
+                sensitive_mark(*haiyama_sensitive),
+                hai_vec_string(&hai_vec)
+            )
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use super::{Kaze, PlayerNumber, Score};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Points of each seat, with riichi sticks and honba on the table.
+///
+/// Seats are identified by jikaze of the current hand, and the seat of `Kaze::Ton` is oya.
+///
+/// # Japanese
+/// * kyoutaku: 供託, riichi sticks deposited on the table, taken by the next winner.
+/// * honba: 本場, counter sticks, which add 300 points to each winning.
+///
+/// # Member
+/// * points: Points of each seat in game.
+/// * kyoutaku: Number of riichi sticks.
+/// * honba: Number of honba.
+/// * payouts: Number of winnings which took riichi sticks on the table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ledger {
+    points: BTreeMap<Kaze, i32>,
+    kyoutaku: u32,
+    honba: u32,
+    payouts: u32,
+}
+
+/// Result of ryuukyoku.
+///
+/// # Japanese
+/// * ryuukyoku: 流局, exhaustive draw.
+/// * noten bappu: 不聴罰符, penalty paid by noten seats to tenpai seats.
+/// * renchan: 連荘, oya keeps the seat for the next hand.
+///
+/// # Member
+/// * tenpai: Seats of tenpai.
+/// * payments: Points each seat gained or lost by noten bappu.
+/// * renchan: Whether oya is tenpai and keeps the seat.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ryuukyoku {
+    pub tenpai: BTreeSet<Kaze>,
+    pub payments: BTreeMap<Kaze, i32>,
+    pub renchan: bool,
+}
+
+/// Points of a riichi stick.
+pub const RIICHI_STICK: i32 = 1000;
+
+/// Points paid for each honba, which are split among payers on tsumo.
+pub const HONBA_POINTS: i32 = 300;
+
+impl Ledger {
+    /// Create a ledger with same starting points for each seat.
+    pub fn new(player_number: PlayerNumber, starting_points: i32) -> Self {
+        let seats = match player_number {
+            PlayerNumber::Four => vec![Kaze::Ton, Kaze::Nan, Kaze::Sha, Kaze::Pei],
+            PlayerNumber::Three => vec![Kaze::Ton, Kaze::Nan, Kaze::Sha],
+        };
+        let mut points = BTreeMap::new();
+        for seat in seats {
+            points.insert(seat, starting_points);
+        }
+        Self {
+            points,
+            kyoutaku: 0,
+            honba: 0,
+            payouts: 0,
+        }
+    }
+
+    /// Seats in game, from oya.
+    pub fn seats(&self) -> Vec<Kaze> {
+        self.points.keys().copied().collect()
+    }
+
+    /// Points of a seat.
+    pub fn points(&self, seat: Kaze) -> Result<i32, String> {
+        self.points
+            .get(&seat)
+            .copied()
+            .ok_or_else(|| i18n::format(Message::NoSeat, &[&seat]))
+    }
+
+    pub fn set_points(&mut self, seat: Kaze, points: i32) -> Result<(), String> {
+        *self
+            .points
+            .get_mut(&seat)
+            .ok_or_else(|| i18n::format(Message::NoSeat, &[&seat]))? = points;
+        Ok(())
+    }
+
+    pub fn kyoutaku(&self) -> u32 {
+        self.kyoutaku
+    }
+
+    pub fn honba(&self) -> u32 {
+        self.honba
+    }
+
+    pub fn set_honba(&mut self, honba: u32) {
+        self.honba = honba;
+    }
+
+    /// Number of winnings settled, each taking all riichi sticks on the table, so a riichi
+    /// stick deposited before the last one is no longer on the table.
+    pub fn payouts(&self) -> u32 {
+        self.payouts
+    }
+
+    pub fn set_kyoutaku(&mut self, kyoutaku: u32) {
+        self.kyoutaku = kyoutaku;
+    }
+
+    /// Deposit a riichi stick of a seat.
+    pub fn riichi(&mut self, seat: Kaze) -> Result<(), String> {
+        let points = self.points(seat)?;
+        if points < RIICHI_STICK {
+            return Err(i18n::format(
+                Message::RiichiPoints,
+                &[&RIICHI_STICK, &seat, &points],
+            ));
+        }
+        self.set_points(seat, points - RIICHI_STICK)?;
+        self.kyoutaku += 1;
+        Ok(())
+    }
+
+    /// Move oya to the next seat for a hand without renchan, so points of each player move to
+    /// the seat of the new jikaze.
+    pub fn pass_oya(&mut self) {
+        let seats = self.seats();
+        let count = seats.len();
+        self.points = self
+            .points
+            .iter()
+            .map(|(seat, points)| (seats[(seat.to_index() + count - 1) % count], *points))
+            .collect();
+    }
+
+    /// Take back a riichi stick of a seat, used when riichi is undone.
+    pub fn cancel_riichi(&mut self, seat: Kaze) -> Result<(), String> {
+        if self.kyoutaku == 0 {
+            return Err(i18n::text(Message::NoRiichiStick).to_string());
+        }
+        let points = self.points(seat)?;
+        self.set_points(seat, points + RIICHI_STICK)?;
+        self.kyoutaku -= 1;
+        Ok(())
+    }
+
+    /// Settle ron, loser pays the winner with honba, and the winner takes all riichi sticks.
+    pub fn ron(&mut self, winner: Kaze, loser: Kaze, score: &Score) -> Result<(), String> {
+        self.check_oya(winner, score)?;
+        if winner == loser {
+            return Err(i18n::text(Message::SameWinnerLoser).to_string());
+        }
+        let payment = score.ron as i32 + HONBA_POINTS * self.honba as i32;
+        let loser_points = self.points(loser)?;
+        let winner_points = self.points(winner)?;
+        self.set_points(loser, loser_points - payment)?;
+        self.set_points(
+            winner,
+            winner_points + payment + RIICHI_STICK * self.kyoutaku as i32,
+        )?;
+        self.kyoutaku = 0;
+        self.payouts += 1;
+        Ok(())
+    }
+
+    /// Settle tsumo, every other seat pays the winner with honba, and the winner takes all
+    /// riichi sticks.
+    pub fn tsumo(&mut self, winner: Kaze, score: &Score) -> Result<(), String> {
+        self.check_oya(winner, score)?;
+        let mut total = 0;
+        for seat in self.seats() {
+            if seat == winner {
+                continue;
+            }
+            let payment = if seat == Kaze::Ton {
+                score.tsumo_oya as i32
+            } else {
+                score.tsumo_ko as i32
+            } + HONBA_POINTS / 3 * self.honba as i32;
+            self.set_points(seat, self.points(seat)? - payment)?;
+            total += payment;
+        }
+        let winner_points = self.points(winner)?;
+        self.set_points(
+            winner,
+            winner_points + total + RIICHI_STICK * self.kyoutaku as i32,
+        )?;
+        self.kyoutaku = 0;
+        self.payouts += 1;
+        Ok(())
+    }
+
+    /// Settle ryuukyoku, noten seats pay noten bappu to tenpai seats, which is 3000 points
+    /// in all, or 2000 in 3-player mode. Riichi sticks are carried over to the next hand,
+    /// and honba increases.
+    pub fn ryuukyoku(&mut self, tenpai: &BTreeSet<Kaze>) -> Result<Ryuukyoku, String> {
+        for seat in tenpai {
+            self.points(*seat)?;
+        }
+        let seats = self.seats();
+        let noten_bappu = if seats.len() == 4 { 3000 } else { 2000 };
+        let tenpai_number = tenpai.len() as i32;
+        let noten_number = seats.len() as i32 - tenpai_number;
+
+        let mut payments = BTreeMap::new();
+        for seat in seats {
+            let payment = if tenpai_number == 0 || noten_number == 0 {
+                0
+            } else if tenpai.contains(&seat) {
+                noten_bappu / tenpai_number
+            } else {
+                -noten_bappu / noten_number
+            };
+            self.set_points(seat, self.points(seat)? + payment)?;
+            payments.insert(seat, payment);
+        }
+        self.honba += 1;
+
+        Ok(Ryuukyoku {
+            tenpai: tenpai.clone(),
+            payments,
+            renchan: tenpai.contains(&Kaze::Ton),
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut points_json_vec = vec![];
+        for (seat, points) in &self.points {
+            points_json_vec.push(json!({
+                "seat": seat.to_hai().to_string(),
+                "points": points,
+            }));
+        }
+        json!({
+            "points": points_json_vec,
+            "kyoutaku": self.kyoutaku,
+            "honba": self.honba,
+        })
+    }
+
+    fn check_oya(&self, winner: Kaze, score: &Score) -> Result<(), String> {
+        self.points(winner)?;
+        if score.oya != (winner == Kaze::Ton) {
+            return Err(format!(
+                "Score is for {}, but winner {} is {}.",
+                if score.oya { "oya" } else { "ko" },
+                winner,
+                if winner == Kaze::Ton { "oya" } else { "ko" }
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Ledger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ledger_string = String::new();
+        for (seat, points) in &self.points {
+            ledger_string += &format!("{}家 {}点\n", seat, points);
+        }
+        write!(
+            f,
+            "{}供託 {}本 {}本場",
+            ledger_string, self.kyoutaku, self.honba
+        )
+    }
+}
+
+impl Ryuukyoku {
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut tenpai_json_vec = vec![];
+        for seat in &self.tenpai {
+            tenpai_json_vec.push(seat.to_hai().to_string());
+        }
+        let mut payments_json_vec = vec![];
+        for (seat, payment) in &self.payments {
+            payments_json_vec.push(json!({
+                "seat": seat.to_hai().to_string(),
+                "payment": payment,
+            }));
+        }
+        json!({
+            "tenpai": tenpai_json_vec,
+            "payments": payments_json_vec,
+            "renchan": self.renchan,
+        })
+    }
+}
+
+impl std::fmt::Display for Ryuukyoku {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ryuukyoku_string = "流局".to_string();
+        for (seat, payment) in &self.payments {
+            ryuukyoku_string += &format!(
+                "\n{}家 {} {:+}",
+                seat,
+                if self.tenpai.contains(seat) {
+                    "聴牌"
+                } else {
+                    "不聴"
+                },
+                payment
+            );
+        }
+        if self.renchan {
+            ryuukyoku_string += "\n親の連荘";
+        } else {
+            ryuukyoku_string += "\n親流れ";
+        }
+        write!(f, "{}", ryuukyoku_string)
+    }
+}
@@ -0,0 +1,169 @@
+use super::{Hai, Haiyama, Taatsu, TaatsuKind};
+use serde_json::json;
+
+/// Kind of a shape waiting for a hai.
+///
+/// # Japanese
+/// * Tanki: 単騎
+/// * Shanpon: 双碰
+/// * Ryanmen: 両面
+/// * Kanchan: 嵌張
+/// * Penchan: 辺張
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum MachiKind {
+    Ryanmen,
+    Kanchan,
+    Penchan,
+    Shanpon,
+    Tanki,
+}
+
+/// A shape of tehai waiting for a given hai, which may be hidden in tehai of others.
+///
+/// Compound shapes like nobetan are a mentsu with one of these shapes, so they are not
+/// listed.
+///
+/// # Japanese
+/// * nobetan: 延べ単
+/// * combinations: 組み合わせ
+///
+/// # Member
+/// * kind: Kind of the shape.
+/// * hai_vec: Hai of the shape held in tehai, before the machihai comes.
+/// * combinations: Number of ways to make the shape from unseen hai, more for more likely.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MachiShape {
+    pub kind: MachiKind,
+    pub hai_vec: Vec<Hai>,
+    pub combinations: u32,
+}
+
+impl MachiShape {
+    /// Enumerate all shapes waiting for `machihai`, which can be made from unseen hai in
+    /// haiyama, sorted from the most combinations.
+    pub fn search(machihai: Hai, haiyama: &Haiyama) -> Vec<MachiShape> {
+        let index = machihai.to_index();
+        let mut candidates = vec![
+            (MachiKind::Tanki, vec![index]),
+            (MachiKind::Shanpon, vec![index, index]),
+        ];
+        if index < 27 {
+            // Taatsu of two hai below, two hai beside, or two hai above the machihai.
+            let number = (index % 9) as isize;
+            for (lhs, rhs) in [(-2, -1), (-1, 1), (1, 2)].iter() {
+                if number + lhs < 0 || number + rhs > 8 {
+                    continue;
+                }
+                let lhs = (index as isize + lhs) as usize;
+                let rhs = (index as isize + rhs) as usize;
+                // Both are suuhai of the same type in range.
+                let taatsu =
+                    Taatsu::new(Hai::from_index(lhs).unwrap(), Hai::from_index(rhs).unwrap());
+                if let Some(taatsu) = taatsu {
+                    if taatsu.machihai().contains(&machihai) {
+                        let kind = match taatsu.kind() {
+                            TaatsuKind::Ryanmen => MachiKind::Ryanmen,
+                            TaatsuKind::Kanchan => MachiKind::Kanchan,
+                            TaatsuKind::Penchan => MachiKind::Penchan,
+                        };
+                        candidates.push((kind, vec![lhs, rhs]));
+                    }
+                }
+            }
+        }
+
+        let counts = haiyama.to_counts();
+        let mut shape_vec = vec![];
+        for (kind, index_vec) in candidates {
+            let mut combinations = 1;
+            let mut used = [0u8; 34];
+            for index in &index_vec {
+                used[*index] += 1;
+            }
+            for (index, number) in used.iter().enumerate() {
+                combinations *= choose(counts[index], *number);
+            }
+            if combinations == 0 {
+                continue;
+            }
+            shape_vec.push(MachiShape {
+                kind,
+                // Index is always less than 34 here.
+                hai_vec: index_vec
+                    .iter()
+                    .map(|index| Hai::from_index(*index).unwrap())
+                    .collect(),
+                combinations,
+            });
+        }
+        shape_vec.sort_by(|lhs, rhs| {
+            rhs.combinations
+                .cmp(&lhs.combinations)
+                .then(lhs.kind.cmp(&rhs.kind))
+                .then(lhs.hai_vec.cmp(&rhs.hai_vec))
+        });
+        shape_vec
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut hai_string_vec = vec![];
+        for hai in &self.hai_vec {
+            hai_string_vec.push(hai.to_string());
+        }
+        json!({
+            "kind": match self.kind {
+                MachiKind::Ryanmen => "ryanmen",
+                MachiKind::Kanchan => "kanchan",
+                MachiKind::Penchan => "penchan",
+                MachiKind::Shanpon => "shanpon",
+                MachiKind::Tanki => "tanki",
+            },
+            "tiles": hai_string_vec,
+            "combinations": self.combinations,
+        })
+    }
+}
+
+/// Number of ways to choose `k` from `n`.
+fn choose(n: u8, k: u8) -> u32 {
+    if k > n {
+        return 0;
+    }
+    let mut result = 1;
+    for i in 0..k as u32 {
+        result = result * (n as u32 - i) / (i + 1);
+    }
+    result
+}
+
+impl std::fmt::Display for MachiKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MachiKind::Ryanmen => "両面",
+                MachiKind::Kanchan => "嵌張",
+                MachiKind::Penchan => "辺張",
+                MachiKind::Shanpon => "双碰",
+                MachiKind::Tanki => "単騎",
+            }
+        )
+    }
+}
+
+impl std::fmt::Display for MachiShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut shape_string = String::new();
+        for hai in &self.hai_vec {
+            shape_string += &hai.to_string();
+        }
+        write!(
+            f,
+            "{} {} {}通り",
+            self.kind, shape_string, self.combinations
+        )
+    }
+}
@@ -0,0 +1,303 @@
+use super::{Hai, PlayerNumber};
+use crate::i18n::{self, Message};
+use serde_json::json;
+
+/// Type of mentsu(meld).
+///
+/// # Japanese
+/// Mentsu: 面子
+/// Juntsu: 順子
+/// Koutsu: 刻子
+/// Kantsu: 槓子
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Mentsu {
+    Juntsu(Hai, Hai, Hai),
+    Koutsu(Hai),
+    Kantsu(Hai),
+}
+
+/// Options to print mentsu by `Mentsu::display()`, the default is the same as `Display`.
+///
+/// # Member
+/// * compact: Print hai with the type character once like `456p`, as players write melds,
+/// otherwise each hai in brackets like `[4p5p6p]`.
+/// * ankan: Mark kantsu as concealed like `5555s(暗)`, which mentsu alone does not know.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MentsuFormat {
+    pub compact: bool,
+    pub ankan: bool,
+}
+
+/// Mentsu printed with given format, created by `Mentsu::display()`.
+pub struct MentsuDisplay<'a> {
+    mentsu: &'a Mentsu,
+    format: MentsuFormat,
+}
+
+/// Two different hai wait for one hai.
+///
+/// # Japanese
+/// * Taatsu: 搭子
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Taatsu(pub Hai, pub Hai);
+
+/// Shape of taatsu, which decides its machihai.
+///
+/// # Japanese
+/// * Ryanmen: 両面, like `34m` waiting for 2m and 5m.
+/// * Kanchan: 嵌張, like `35m` waiting for 4m.
+/// * Penchan: 辺張, like `12m` waiting for 3m.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TaatsuKind {
+    Ryanmen,
+    Kanchan,
+    Penchan,
+}
+
+/// Two same hai.
+///
+/// # Japanese
+/// * Toitsu: 対子
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Toitsu(pub Hai);
+
+/// An isolated hai.
+///
+/// # Japanese
+/// * Ukihai: 浮き牌
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Ukihai(pub Hai);
+
+impl Taatsu {
+    /// Create a taatsu from two hai in any order, if they are suuhai of the same type and
+    /// the difference of numbers is 1 or 2.
+    pub fn new(lhs: Hai, rhs: Hai) -> Option<Self> {
+        let (lhs, rhs) = if lhs < rhs { (lhs, rhs) } else { (rhs, lhs) };
+        match (lhs, rhs) {
+            (Hai::Manzu(a), Hai::Manzu(b))
+            | (Hai::Pinzu(a), Hai::Pinzu(b))
+            | (Hai::Souzu(a), Hai::Souzu(b))
+                if b - a == 1 || b - a == 2 =>
+            {
+                Some(Taatsu(lhs, rhs))
+            }
+            _ => None,
+        }
+    }
+
+    /// Classify the shape.
+    ///
+    /// **Note**: Result is meaningless for invalid taatsu, use `Taatsu::new()` to check.
+    pub fn kind(&self) -> TaatsuKind {
+        let (lhs, rhs) = (self.0.to_index() % 9 + 1, self.1.to_index() % 9 + 1);
+        if rhs - lhs == 2 {
+            TaatsuKind::Kanchan
+        } else if lhs == 1 || rhs == 9 {
+            TaatsuKind::Penchan
+        } else {
+            TaatsuKind::Ryanmen
+        }
+    }
+
+    /// Hai which make the taatsu a juntsu.
+    pub fn machihai(&self) -> Vec<Hai> {
+        // Both hai are suuhai of the same type, whose index differs by the number.
+        let offset =
+            |hai: &Hai, number: isize| Hai::from_index((hai.to_index() as isize + number) as usize);
+        let machihai = match self.kind() {
+            TaatsuKind::Ryanmen => vec![offset(&self.0, -1), offset(&self.1, 1)],
+            TaatsuKind::Kanchan => vec![offset(&self.0, 1)],
+            TaatsuKind::Penchan => {
+                if self.0.to_index() % 9 == 0 {
+                    vec![offset(&self.1, 1)]
+                } else {
+                    vec![offset(&self.0, -1)]
+                }
+            }
+        };
+        machihai.into_iter().flatten().collect()
+    }
+}
+
+impl Mentsu {
+    /// Create a mentsu from input vec of hai if they can make up a valid mentsu.
+    pub fn new(hai_vec: &Vec<Hai>, player_number: PlayerNumber) -> Option<Self> {
+        fn check_juntsu(mut a: u8, mut b: u8, mut c: u8) -> Option<(u8, u8, u8)> {
+            if a > b {
+                std::mem::swap(&mut a, &mut b)
+            }
+            if a > c {
+                std::mem::swap(&mut a, &mut c)
+            }
+            if b > c {
+                std::mem::swap(&mut b, &mut c)
+            }
+            if a + 1 == b && b + 1 == c {
+                Some((a, b, c))
+            } else {
+                None
+            }
+        }
+
+        if !Hai::check_iter_valid(hai_vec.iter(), player_number) {
+            None
+        } else if hai_vec.len() == 4 {
+            if hai_vec[0] == hai_vec[1] && hai_vec[0] == hai_vec[2] && hai_vec[0] == hai_vec[3] {
+                Some(Mentsu::Kantsu(hai_vec[0]))
+            } else {
+                None
+            }
+        } else if hai_vec.len() == 3 {
+            if hai_vec[0] == hai_vec[1] && hai_vec[0] == hai_vec[2] {
+                Some(Mentsu::Koutsu(hai_vec[0]))
+            } else {
+                match (hai_vec[0], hai_vec[1], hai_vec[2]) {
+                    (Hai::Manzu(a), Hai::Manzu(b), Hai::Manzu(c)) => match player_number {
+                        PlayerNumber::Four => {
+                            let (a, b, c) = check_juntsu(a, b, c)?;
+                            Some(Mentsu::Juntsu(Hai::Manzu(a), Hai::Manzu(b), Hai::Manzu(c)))
+                        }
+                        PlayerNumber::Three => None,
+                    },
+                    (Hai::Pinzu(a), Hai::Pinzu(b), Hai::Pinzu(c)) => {
+                        let (a, b, c) = check_juntsu(a, b, c)?;
+                        Some(Mentsu::Juntsu(Hai::Pinzu(a), Hai::Pinzu(b), Hai::Pinzu(c)))
+                    }
+                    (Hai::Souzu(a), Hai::Souzu(b), Hai::Souzu(c)) => {
+                        let (a, b, c) = check_juntsu(a, b, c)?;
+                        Some(Mentsu::Juntsu(Hai::Souzu(a), Hai::Souzu(b), Hai::Souzu(c)))
+                    }
+                    _ => None,
+                }
+            }
+        } else {
+            None
+        }
+    }
+
+    /// All hai in mentsu.
+    pub fn to_vec(&self) -> Vec<Hai> {
+        match self {
+            Mentsu::Juntsu(a, b, c) => vec![*a, *b, *c],
+            Mentsu::Koutsu(a) => vec![*a; 3],
+            Mentsu::Kantsu(a) => vec![*a; 4],
+        }
+    }
+
+    /// Print self with given format, like `println!("{}", mentsu.display(format))`.
+    pub fn display(&self, format: MentsuFormat) -> MentsuDisplay<'_> {
+        MentsuDisplay {
+            mentsu: self,
+            format,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut hai_string_vec = vec![];
+        match self {
+            Mentsu::Juntsu(a, b, c) => {
+                hai_string_vec.push(a.to_string());
+                hai_string_vec.push(b.to_string());
+                hai_string_vec.push(c.to_string());
+                json!({
+                    "type":"juntsu",
+                    "hai":hai_string_vec
+                })
+            }
+            Mentsu::Koutsu(a) => {
+                for _ in 0..3 {
+                    hai_string_vec.push(a.to_string());
+                }
+                json!({
+                    "type":"koutsu",
+                    "hai":hai_string_vec
+                })
+            }
+            Mentsu::Kantsu(a) => {
+                for _ in 0..4 {
+                    hai_string_vec.push(a.to_string());
+                }
+                json!({
+                    "type":"kantsu",
+                    "hai":hai_string_vec
+                })
+            }
+        }
+    }
+}
+
+/// Parse a mentsu in 4-player mode like `[456s]`, brackets are optional.
+impl std::str::FromStr for Mentsu {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let trimmed = string.trim();
+        let inner = trimmed
+            .strip_prefix('[')
+            .and_then(|inner| inner.strip_suffix(']'))
+            .unwrap_or(trimmed);
+        let hai_vec = Hai::from_string_unordered(inner, PlayerNumber::Four)?;
+        Mentsu::new(&hai_vec, PlayerNumber::Four)
+            .ok_or_else(|| i18n::format(Message::InvalidMentsu, &[&string]))
+    }
+}
+
+impl std::fmt::Display for Mentsu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Alternate flag `{:#}` prints compact form like `456p`.
+        let format = MentsuFormat {
+            compact: f.alternate(),
+            ..MentsuFormat::default()
+        };
+        self.display(format).fmt(f)
+    }
+}
+
+impl std::fmt::Display for MentsuDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hai_vec = self.mentsu.to_vec();
+        let mut format_string = String::new();
+        if self.format.compact {
+            for hai in &hai_vec {
+                format_string += &hai.to_string()[..1];
+            }
+            format_string += &hai_vec[0].to_string()[1..];
+        } else {
+            format_string.push('[');
+            for hai in &hai_vec {
+                format_string += &hai.to_string();
+            }
+            format_string.push(']');
+        }
+        if self.format.ankan {
+            if let Mentsu::Kantsu(_) = self.mentsu {
+                format_string += "(暗)";
+            }
+        }
+        write!(f, "{}", format_string)
+    }
+}
+
+impl std::fmt::Display for Taatsu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.0.to_string(), self.1.to_string())
+    }
+}
+
+impl std::fmt::Display for Toitsu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tile = self.0.to_string();
+        write!(f, "{}{}", tile, tile)
+    }
+}
+
+impl std::fmt::Display for Ukihai {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
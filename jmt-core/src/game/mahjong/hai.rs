@@ -1,4 +1,5 @@
 use super::PlayerNumber;
+use crate::i18n::{self, Message};
 use std::collections::BTreeSet;
 
 /// Type of hai(tile).
@@ -23,9 +24,28 @@ pub enum Hai {
     Jihai(u8),
 }
 
+/// Suit of hai, where jihai is one more suit as in the notation `z`.
+///
+/// # Japanese
+/// * Manzu: 萬子
+/// * Pinzu: 筒子
+/// * Souzu: 索子
+/// * Jihai: 字牌
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Suit {
+    Manzu,
+    Pinzu,
+    Souzu,
+    Jihai,
+}
+
 impl Hai {
     /// Parse string to a vec of hai. Order of hai is equal with input string.
-    pub fn from_string_unordered(string: &String, player_number: PlayerNumber) -> Result<Vec<Hai>, String> {
+    pub fn from_string_unordered(
+        string: &str,
+        player_number: PlayerNumber,
+    ) -> Result<Vec<Hai>, String> {
         fn handle_char_stash(
             hai_type: char,
             hai_type_char_index: usize,
@@ -34,9 +54,9 @@ impl Hai {
             output: &mut Vec<Hai>,
         ) -> Result<(), String> {
             if char_stash.len() == 0 {
-                Err(format!(
-                    "Unused type character '{}' at index {}.",
-                    hai_type, hai_type_char_index
+                Err(i18n::format(
+                    Message::UnusedTypeCharacter,
+                    &[&hai_type, &hai_type_char_index],
                 ))
             } else {
                 for hai in char_stash.iter() {
@@ -51,7 +71,7 @@ impl Hai {
                         output.push(hai);
                     } else {
                         char_stash.clear();
-                        return Err(format!("'{}' is invalid hai.", hai.to_string()));
+                        return Err(i18n::format(Message::InvalidHai, &[&hai]));
                     }
                 }
                 char_stash.clear();
@@ -61,7 +81,7 @@ impl Hai {
 
         let mut char_stash: Vec<char> = vec![];
         let mut hai_vec = vec![];
-        
+
         for (index, chr) in string.chars().enumerate() {
             match chr {
                 'm' | 'p' | 's' | 'z' => {
@@ -71,7 +91,7 @@ impl Hai {
                 // Ignore all spaces.
                 ' ' => (),
                 _ => {
-                    return Err(format!("Unknown character '{}' at index {}.", chr, index));
+                    return Err(i18n::format(Message::UnknownCharacter, &[&chr, &index]));
                 }
             }
         }
@@ -93,6 +113,57 @@ impl Hai {
         }
     }
 
+    /// Suit of hai.
+    pub fn suit(&self) -> Suit {
+        match self {
+            Hai::Manzu(_) => Suit::Manzu,
+            Hai::Pinzu(_) => Suit::Pinzu,
+            Hai::Souzu(_) => Suit::Souzu,
+            Hai::Jihai(_) => Suit::Jihai,
+        }
+    }
+
+    /// Number of hai in its suit, 1\~9 for suuhai and 1\~7 for jihai in the order of
+    /// 東南西北白發中.
+    pub fn number(&self) -> u8 {
+        match self {
+            Hai::Manzu(num) | Hai::Pinzu(num) | Hai::Souzu(num) | Hai::Jihai(num) => *num,
+        }
+    }
+
+    /// Return if jihai -- 1\~7z.
+    pub fn is_jihai(&self) -> bool {
+        self.suit() == Suit::Jihai
+    }
+
+    /// Return if routouhai -- 1m, 9m, 1p, 9p, 1s, 9s.
+    ///
+    /// # Japanese
+    /// * routouhai: 老頭牌
+    pub fn is_routou(&self) -> bool {
+        !self.is_jihai() && (self.number() == 1 || self.number() == 9)
+    }
+
+    /// Return if yaochuupai -- routouhai or jihai.
+    ///
+    /// # Japanese
+    /// * yaochuupai: 幺九牌
+    pub fn is_yaochuu(&self) -> bool {
+        self.is_jihai() || self.is_routou()
+    }
+
+    /// Iterate all valid hai of a suit from the smallest number.
+    pub fn iter_suit(suit: Suit, player_number: PlayerNumber) -> impl Iterator<Item = Hai> {
+        Self::iter_all(player_number).filter(move |hai| hai.suit() == suit)
+    }
+
+    /// Iterate all valid types of hai in the order of 34-index representation.
+    pub fn iter_all(player_number: PlayerNumber) -> impl Iterator<Item = Hai> {
+        (0..34)
+            .filter_map(Hai::from_index)
+            .filter(move |hai| hai.is_valid(player_number))
+    }
+
     /// Return ture when **all** hai in iterator is valid. Otherwise return false.
     pub fn check_iter_valid<'a, T>(iter: T, player_number: PlayerNumber) -> bool
     where
@@ -107,49 +178,117 @@ impl Hai {
         true
     }
 
-    /// Return a BTreeSet including all yaochuupai -- 1m, 9m, 1p, 9p, 1s, 9s, 1\~7z.
-    pub fn yaochuupai_type() -> BTreeSet<Hai> {
-        let mut yaochuupai_vec = BTreeSet::new();
-
-        yaochuupai_vec.insert(Hai::Manzu(1));
-        yaochuupai_vec.insert(Hai::Manzu(9));
-        yaochuupai_vec.insert(Hai::Pinzu(1));
-        yaochuupai_vec.insert(Hai::Pinzu(9));
-        yaochuupai_vec.insert(Hai::Souzu(1));
-        yaochuupai_vec.insert(Hai::Souzu(9));
-        for i in 1..=7 {
-            yaochuupai_vec.insert(Hai::Jihai(i));
+    /// Return index in 34-index representation -- 0\~8 for 1\~9m, 9\~17 for 1\~9p,
+    /// 18\~26 for 1\~9s and 27\~33 for 1\~7z.
+    ///
+    /// **Note**: Invalid hai, such as `Manzu(0)`, has no index and panics in debug builds, use
+    /// `Hai::is_valid()` to check hai not from the library.
+    pub fn to_index(&self) -> usize {
+        debug_assert!(self.is_valid(PlayerNumber::Four), "Invalid hai {:?}.", self);
+        match self {
+            Hai::Manzu(num) => *num as usize - 1,
+            Hai::Pinzu(num) => *num as usize + 8,
+            Hai::Souzu(num) => *num as usize + 17,
+            Hai::Jihai(num) => *num as usize + 26,
         }
+    }
 
-        yaochuupai_vec
+    /// Create hai from index in 34-index representation. Return `None` if index is
+    /// not less than 34.
+    pub fn from_index(index: usize) -> Option<Hai> {
+        match index {
+            0..=8 => Some(Hai::Manzu(index as u8 + 1)),
+            9..=17 => Some(Hai::Pinzu(index as u8 - 8)),
+            18..=26 => Some(Hai::Souzu(index as u8 - 17)),
+            27..=33 => Some(Hai::Jihai(index as u8 - 26)),
+            _ => None,
+        }
     }
 
-    /// Return a BTreeSet including all valid types of hai.
-    pub fn all_type(player_number: PlayerNumber) -> BTreeSet<Hai> {
-        let mut all_hai_type = BTreeSet::new();
+    /// Count hai in iterator to 34-index representation.
+    pub fn to_counts<'a, T>(iter: T) -> [u8; 34]
+    where
+        T: Iterator<Item = &'a Self>,
+    {
+        let mut counts = [0; 34];
+        for hai in iter {
+            counts[hai.to_index()] += 1;
+        }
+        counts
+    }
 
-        match player_number {
-            PlayerNumber::Four => {
-                for index in 1u8..=9u8 {
-                    all_hai_type.insert(Hai::Manzu(index));
-                }
-            }
-            PlayerNumber::Three => {
-                all_hai_type.insert(Hai::Manzu(1));
-                all_hai_type.insert(Hai::Manzu(9));
+    /// Create a sorted vec of hai from 34-index representation.
+    pub fn from_counts(counts: &[u8; 34]) -> Vec<Hai> {
+        let mut hai_vec = vec![];
+        for (index, count) in counts.iter().enumerate() {
+            for _ in 0..*count {
+                // Index is always less than 34.
+                hai_vec.push(Hai::from_index(index).unwrap());
             }
-        };
+        }
+        hai_vec
+    }
 
-        for index in 1u8..=9u8 {
-            all_hai_type.insert(Hai::Pinzu(index));
-            all_hai_type.insert(Hai::Souzu(index));
+    /// Create hai from tile ID in 136-tile representation used by tenhou logs, in which
+    /// `id / 4` is the index in 34-index representation. Return `None` if ID is not
+    /// less than 136.
+    pub fn from_id(id: u8) -> Option<Hai> {
+        if id < 136 {
+            Hai::from_index(id as usize / 4)
+        } else {
+            None
         }
+    }
+
+    /// Return if tile ID in 136-tile representation is a red 5 (aka dora), which
+    /// are 16 for 5m, 52 for 5p and 88 for 5s.
+    ///
+    /// # Japanese
+    /// * aka dora: 赤ドラ
+    pub fn is_red_id(id: u8) -> bool {
+        id == 16 || id == 52 || id == 88
+    }
 
-        for index in 1u8..=7u8 {
-            all_hai_type.insert(Hai::Jihai(index));
+    /// Return tile ID in 136-tile representation of the `copy`th (0\~3) copy of self.
+    pub fn to_id(&self, copy: u8) -> u8 {
+        self.to_index() as u8 * 4 + copy
+    }
+
+    /// Assign different tile IDs in 136-tile representation to each hai in iterator.
+    ///
+    /// Copies of each type of hai are assigned from the first one, except that red 5 is
+    /// assigned only when all other copies have been used.
+    pub fn to_ids<'a, T>(iter: T) -> Result<Vec<u8>, String>
+    where
+        T: Iterator<Item = &'a Self>,
+    {
+        let mut used = [0u8; 34];
+        let mut id_vec = vec![];
+        for hai in iter {
+            let index = hai.to_index();
+            if used[index] >= 4 {
+                return Err(i18n::format(Message::FifthHai, &[&hai]));
+            }
+            let copy = match hai {
+                Hai::Manzu(5) | Hai::Pinzu(5) | Hai::Souzu(5) => (used[index] + 1) % 4,
+                _ => used[index],
+            };
+            used[index] += 1;
+            id_vec.push(hai.to_id(copy));
         }
+        Ok(id_vec)
+    }
+
+    /// Return a BTreeSet including all yaochuupai -- 1m, 9m, 1p, 9p, 1s, 9s, 1\~7z.
+    pub fn yaochuupai_type() -> BTreeSet<Hai> {
+        Self::iter_all(PlayerNumber::Three)
+            .filter(Hai::is_yaochuu)
+            .collect()
+    }
 
-        all_hai_type
+    /// Return a BTreeSet including all valid types of hai.
+    pub fn all_type(player_number: PlayerNumber) -> BTreeSet<Hai> {
+        Self::iter_all(player_number).collect()
     }
 
     /// Return previous hai. It means, like 1m for 2m.
@@ -303,6 +442,40 @@ impl Hai {
     }
 }
 
+/// Parse a single hai in 4-player mode, like `5p`, or name of jihai, like `東` and `中`.
+impl std::str::FromStr for Hai {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let hai = match string.trim() {
+            "東" => Hai::Jihai(1),
+            "南" => Hai::Jihai(2),
+            "西" => Hai::Jihai(3),
+            "北" => Hai::Jihai(4),
+            "白" => Hai::Jihai(5),
+            "發" | "発" => Hai::Jihai(6),
+            "中" => Hai::Jihai(7),
+            trimmed => {
+                let hai_vec = Hai::from_string_unordered(trimmed, PlayerNumber::Four)?;
+                if hai_vec.len() != 1 {
+                    return Err(i18n::format(Message::NotSingleHai, &[&string]));
+                }
+                hai_vec[0]
+            }
+        };
+        Ok(hai)
+    }
+}
+
+/// Serialize as the string of hai like `4m`, so that maps keyed by hai stay maps in formats
+/// like JSON, the same as `to_json()` of other types.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Hai {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl std::fmt::Display for Hai {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
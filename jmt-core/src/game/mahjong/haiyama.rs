@@ -0,0 +1,299 @@
+use super::{zobrist, Hai, PlayerNumber};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut, Index},
+};
+
+/// The haiyama struct.
+///
+/// # Japanese
+/// * Haiyama: 牌山
+///
+/// # Member
+/// * map: Number of each type of hai remaining in haiyama.
+/// * capacity: Number of each type of hai when haiyama is created, which limits adding.
+/// * zobrist: Zobrist hash of remaining hai, updated as each hai is added or discarded.
+#[derive(Clone, Debug)]
+pub struct Haiyama {
+    map: BTreeMap<Hai, u8>,
+    capacity: BTreeMap<Hai, u8>,
+    zobrist: u64,
+}
+
+/// Guard of a transaction on haiyama, created by `Haiyama::begin()`.
+///
+/// Operations through the guard either fully apply by `commit()`, or leave haiyama
+/// untouched by `rollback()`. Dropping the guard without commit rolls back, so returning
+/// early on error never leaves haiyama half operated.
+pub struct HaiyamaTransaction<'a> {
+    haiyama: &'a mut Haiyama,
+    backup: BTreeMap<Hai, u8>,
+    backup_zobrist: u64,
+    finished: bool,
+}
+
+impl Haiyama {
+    /// Create a new haiyama with 4 of each type of hai.
+    pub fn new(player_number: PlayerNumber) -> Self {
+        let mut map = BTreeMap::new();
+        for hai in Hai::all_type(player_number) {
+            map.insert(hai, 4);
+        }
+        Self::from_map(map)
+    }
+
+    /// Create a haiyama from an arbitrary multiset of hai, such as a custom variant or a
+    /// stacked wall for training. Number of each type is not limited to 4, and types not
+    /// in the multiset are counted as 0.
+    pub fn from_hai_vec(hai_vec: &[Hai]) -> Self {
+        Self::from_counts(&Hai::to_counts(hai_vec.iter()))
+    }
+
+    /// Create a haiyama from an arbitrary multiset of hai in 34-index representation.
+    pub fn from_counts(counts: &[u8; 34]) -> Self {
+        let mut map = BTreeMap::new();
+        for (index, count) in counts.iter().enumerate() {
+            // Index is always less than 34.
+            map.insert(Hai::from_index(index).unwrap(), *count);
+        }
+        Self::from_map(map)
+    }
+
+    fn from_map(map: BTreeMap<Hai, u8>) -> Self {
+        let mut haiyama = Self {
+            capacity: map.clone(),
+            map,
+            zobrist: 0,
+        };
+        haiyama.zobrist = zobrist::hash_counts(&haiyama.to_counts(), &zobrist::HAIYAMA_KEYS);
+        haiyama
+    }
+
+    /// Create a haiyama with all visible hai removed, such as kawa, fuuro of others, dora
+    /// indicators and own tehai, so the tracker can be adopted in the middle of a game.
+    ///
+    /// # Parameters
+    /// * string: Visible hai in the same notation as tehai, like `1155m27p7z`.
+    pub fn from_seen_string(string: &str, player_number: PlayerNumber) -> Result<Self, String> {
+        let hai_vec = Hai::from_string_unordered(string, player_number)?;
+        let mut haiyama = Self::new(player_number);
+        haiyama.discard_with_vec(&hai_vec, true)?;
+        Ok(haiyama)
+    }
+
+    /// Number of each type of hai remaining, in 34-index representation.
+    pub fn to_counts(&self) -> [u8; 34] {
+        let mut counts = [0; 34];
+        for (hai, number) in &self.map {
+            counts[hai.to_index()] = *number;
+        }
+        counts
+    }
+
+    /// Zobrist hash of remaining hai, see `zobrist`.
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    /// Number of given type of hai when haiyama is created.
+    pub fn capacity(&self, hai: &Hai) -> u8 {
+        *self.capacity.get(hai).unwrap_or(&0)
+    }
+
+    /// Types of hai all visible, which can never be drawn. Types not in the game, whose
+    /// capacity is 0, are not included.
+    ///
+    /// # Japanese
+    /// * exhausted: 枯れ
+    pub fn exhausted(&self) -> Vec<Hai> {
+        let mut exhausted = vec![];
+        for (hai, number) in &self.map {
+            if *number == 0 && self.capacity(hai) > 0 {
+                exhausted.push(*hai);
+            }
+        }
+        exhausted
+    }
+
+    /// Add one hai to haiyama, limited to its capacity.
+    pub fn add(&mut self, hai: &Hai) -> Result<(), String> {
+        let number = self.map[hai];
+        let capacity = self.capacity(hai);
+        if number < capacity {
+            self.map.insert(*hai, number + 1);
+            self.zobrist ^= zobrist::key(&zobrist::HAIYAMA_KEYS, hai.to_index(), number);
+            Ok(())
+        } else {
+            Err(i18n::format(Message::HaiyamaFull, &[&capacity, &hai]))
+        }
+    }
+
+    /// Begin a transaction, operate haiyama through the returned guard.
+    pub fn begin(&mut self) -> HaiyamaTransaction<'_> {
+        HaiyamaTransaction {
+            backup: self.map.clone(),
+            backup_zobrist: self.zobrist,
+            haiyama: self,
+            finished: false,
+        }
+    }
+
+    /// Add a vec of hai to haiyama, limited to capacity.
+    ///
+    /// # Parameters
+    /// * auto_restore: If ture, haiyama will restore to original state
+    /// when error occured.
+    pub fn add_with_vec(&mut self, hai_vec: &Vec<Hai>, auto_restore: bool) -> Result<(), String> {
+        let mut transaction = self.begin();
+        for hai in hai_vec {
+            if let Err(error) = transaction.add(hai) {
+                if !auto_restore {
+                    transaction.commit();
+                }
+                return Err(error);
+            }
+        }
+        transaction.commit();
+        Ok(())
+    }
+
+    /// Discard one hai from haiyama.
+    pub fn discard(&mut self, hai: &Hai) -> Result<(), String> {
+        let number = self.map[hai];
+        if number > 0 {
+            self.map.insert(*hai, number - 1);
+            self.zobrist ^= zobrist::key(&zobrist::HAIYAMA_KEYS, hai.to_index(), number - 1);
+            Ok(())
+        } else {
+            Err(i18n::format(Message::HaiyamaEmpty, &[&hai]))
+        }
+    }
+
+    /// Discard a vec of hai from haiyama.
+    ///
+    /// # Parameters
+    /// * auto_restore: If ture, haiyama will restore to original state
+    /// when error occured.
+    pub fn discard_with_vec(
+        &mut self,
+        hai_vec: &Vec<Hai>,
+        auto_restore: bool,
+    ) -> Result<(), String> {
+        let mut transaction = self.begin();
+        for hai in hai_vec {
+            if let Err(_) = transaction.discard(hai) {
+                if !auto_restore {
+                    transaction.commit();
+                }
+                return Err(i18n::format(Message::NotEnoughInHaiyama, &[&hai]));
+            }
+        }
+        transaction.commit();
+
+        Ok(())
+    }
+
+    /// Remaining number of each type of hai in rows of manzu, pinzu, souzu and jihai, each
+    /// column is the number of hai from 1. Hai not in the game, such as 2m to 8m in
+    /// 3-player mode and columns after 7z, are `None`.
+    pub fn to_grid(&self) -> [[Option<u8>; 9]; 4] {
+        let mut grid = [[None; 9]; 4];
+        for (hai, number) in &self.map {
+            if self.capacity[hai] == 0 {
+                continue;
+            }
+            let index = hai.to_index();
+            grid[index / 9][index % 9] = Some(*number);
+        }
+        grid
+    }
+
+    /// Print self to json.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json_vec = vec![];
+        for (hai, number) in &self.map {
+            json_vec.push(json!({
+                hai.to_string(): number,
+            }));
+        }
+        json!(json_vec)
+    }
+}
+
+impl HaiyamaTransaction<'_> {
+    /// Keep all operations in the transaction.
+    pub fn commit(mut self) {
+        self.finished = true;
+    }
+
+    /// Discard all operations in the transaction.
+    pub fn rollback(mut self) {
+        self.haiyama.map = std::mem::take(&mut self.backup);
+        self.haiyama.zobrist = self.backup_zobrist;
+        self.finished = true;
+    }
+}
+
+impl Deref for HaiyamaTransaction<'_> {
+    type Target = Haiyama;
+
+    fn deref(&self) -> &Haiyama {
+        self.haiyama
+    }
+}
+
+impl DerefMut for HaiyamaTransaction<'_> {
+    fn deref_mut(&mut self) -> &mut Haiyama {
+        self.haiyama
+    }
+}
+
+impl Drop for HaiyamaTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.haiyama.map = std::mem::take(&mut self.backup);
+            self.haiyama.zobrist = self.backup_zobrist;
+        }
+    }
+}
+
+impl<'a> Index<&'a Hai> for Haiyama {
+    type Output = <BTreeMap<Hai, u8> as Index<&'a Hai>>::Output;
+
+    fn index(&self, hai: &'a Hai) -> &Self::Output {
+        self.map.index(hai)
+    }
+}
+
+impl std::fmt::Display for Haiyama {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Alternate flag `{:#}` prints a grid with a row for each suit.
+        if f.alternate() {
+            let mut grid_string = "  1 2 3 4 5 6 7 8 9".to_string();
+            for (row, suit) in self.to_grid().iter().zip(["m", "p", "s", "z"].iter()) {
+                grid_string += &format!("\n{}", suit);
+                let columns = if *suit == "z" { 7 } else { 9 };
+                for number in row.iter().take(columns) {
+                    match number {
+                        Some(number) => grid_string += &format!(" {}", number),
+                        None => grid_string += " -",
+                    }
+                }
+            }
+            return write!(f, "{}", grid_string);
+        }
+        let mut haiyama_string = String::new();
+        for (hai, number) in &self.map {
+            haiyama_string += &hai.to_string();
+            haiyama_string += ":";
+            haiyama_string += &number.to_string();
+            match hai {
+                Hai::Manzu(9) | Hai::Pinzu(9) | Hai::Souzu(9) => haiyama_string += "\n  ",
+                _ => haiyama_string += " ",
+            }
+        }
+        write!(f, "{}", haiyama_string)
+    }
+}
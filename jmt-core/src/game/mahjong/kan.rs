@@ -0,0 +1,54 @@
+use super::{Hai, Mentsu};
+use serde_json::json;
+
+/// Type of kan.
+///
+/// # Japanese
+/// * Daiminkan: 大明槓
+/// * Kakan: 加槓
+/// * Ankan: 暗槓
+/// * kantsu: 槓子
+/// * rinshanhai: 嶺上牌
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Kan {
+    Daiminkan {
+        kantsu: Mentsu,
+        rinshanhai: Option<Hai>,
+    },
+    Kakan {
+        kantsu: Mentsu,
+        rinshanhai: Option<Hai>,
+    },
+    Ankan {
+        kantsu: Mentsu,
+        rinshanhai: Option<Hai>,
+    },
+    Unknown {
+        kantsu: Mentsu,
+        rinshanhai: Option<Hai>,
+    },
+}
+
+impl Kan {
+    pub fn to_json(&self) -> serde_json::Value {
+        let (tp, kantsu, rinshanhai) = match self {
+            Kan::Daiminkan { kantsu, rinshanhai } => ("daiminkan", kantsu, rinshanhai),
+            Kan::Kakan { kantsu, rinshanhai } => ("kakan", kantsu, rinshanhai),
+            Kan::Ankan { kantsu, rinshanhai } => ("ankan", kantsu, rinshanhai),
+            Kan::Unknown { kantsu, rinshanhai } => ("unknown", kantsu, rinshanhai),
+        };
+        match rinshanhai {
+            Some(hai) => json!({
+                "type": tp,
+                "kantsu": kantsu.to_json(),
+                "rinshanhai": hai.to_string()
+            }),
+            None => json!({
+                "type": tp,
+                "kantsu": kantsu.to_json(),
+                "rinshanhai": null
+            }),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use super::Hai;
+use serde_json::json;
+use std::collections::BTreeSet;
+
+/// The kawa struct, sutehai of self in order.
+///
+/// # Japanese
+/// * Kawa: 河
+/// * riichi: 立直
+///
+/// # Member
+/// * sutehai_vec: All sutehai in order.
+/// * sutehai_type: Types of all sutehai, any of which in machihai makes tehai furiten.
+/// * riichi: Index of sutehai declaring riichi.
+/// * riichi_furiten: Whether a machihai was missed after riichi, which makes tehai furiten
+/// until the end of the hand.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Kawa {
+    sutehai_vec: Vec<Hai>,
+    sutehai_type: BTreeSet<Hai>,
+    riichi: Option<usize>,
+    riichi_furiten: bool,
+}
+
+impl Kawa {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sutehai.
+    pub fn push(&mut self, sutehai: Hai) {
+        self.sutehai_vec.push(sutehai);
+        self.sutehai_type.insert(sutehai);
+    }
+
+    /// Add a sutehai declaring riichi.
+    pub fn riichi(&mut self, sutehai: Hai) -> Result<(), String> {
+        if self.riichi.is_some() {
+            return Err("Already declared riichi.".to_string());
+        }
+        self.riichi = Some(self.sutehai_vec.len());
+        self.push(sutehai);
+        Ok(())
+    }
+
+    /// Mark that a machihai was missed after riichi.
+    pub fn miss_after_riichi(&mut self) {
+        if self.riichi.is_some() {
+            self.riichi_furiten = true;
+        }
+    }
+
+    pub fn sutehai_vec(&self) -> &Vec<Hai> {
+        &self.sutehai_vec
+    }
+
+    pub fn sutehai_type(&self) -> &BTreeSet<Hai> {
+        &self.sutehai_type
+    }
+
+    pub fn is_riichi(&self) -> bool {
+        self.riichi.is_some()
+    }
+
+    /// Index of sutehai declaring riichi.
+    pub fn riichi_index(&self) -> Option<usize> {
+        self.riichi
+    }
+
+    pub fn is_riichi_furiten(&self) -> bool {
+        self.riichi_furiten
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut sutehai_json_vec = vec![];
+        for hai in &self.sutehai_vec {
+            sutehai_json_vec.push(hai.to_string());
+        }
+        json!({
+            "sutehai": sutehai_json_vec,
+            "riichi": self.riichi,
+            "riichi_furiten": self.riichi_furiten,
+        })
+    }
+}
+
+impl std::fmt::Display for Kawa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.sutehai_vec.len() == 0 {
+            return write!(f, "無し");
+        }
+        let mut kawa_string = String::new();
+        for (index, hai) in self.sutehai_vec.iter().enumerate() {
+            // Sutehai declaring riichi is laid sideways, marked as `r`.
+            if Some(index) == self.riichi {
+                kawa_string += "r";
+            }
+            kawa_string += &hai.to_string();
+            kawa_string += " ";
+        }
+        if self.riichi_furiten {
+            kawa_string += "!振り聴!";
+        }
+        write!(f, "{}", kawa_string.trim_end())
+    }
+}
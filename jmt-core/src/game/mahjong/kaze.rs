@@ -0,0 +1,95 @@
+use super::Hai;
+use std::collections::BTreeSet;
+
+/// Wind of seat or round.
+///
+/// # Japanese
+/// * Kaze: 風
+/// * Ton: 東
+/// * Nan: 南
+/// * Sha: 西
+/// * Pei: 北
+/// * jikaze: 自風, wind of seat.
+/// * bakaze: 場風, wind of round.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Kaze {
+    #[default]
+    Ton,
+    Nan,
+    Sha,
+    Pei,
+}
+
+impl Kaze {
+    /// Parse from hai like `1z`, or name like `ton` and `東`.
+    pub fn from_string(string: &str) -> Result<Kaze, String> {
+        match string {
+            "1z" | "ton" | "東" | "e" | "east" => Ok(Kaze::Ton),
+            "2z" | "nan" | "南" | "s" | "south" => Ok(Kaze::Nan),
+            "3z" | "sha" | "西" | "w" | "west" => Ok(Kaze::Sha),
+            "4z" | "pei" | "北" | "n" | "north" => Ok(Kaze::Pei),
+            _ => Err(format!("Unknown kaze: {}.", string)),
+        }
+    }
+
+    /// Wind by index from 0 of ton, looping every 4.
+    pub fn from_index(index: usize) -> Kaze {
+        match index % 4 {
+            0 => Kaze::Ton,
+            1 => Kaze::Nan,
+            2 => Kaze::Sha,
+            _ => Kaze::Pei,
+        }
+    }
+
+    /// Index from 0 of ton.
+    pub fn to_index(&self) -> usize {
+        match self {
+            Kaze::Ton => 0,
+            Kaze::Nan => 1,
+            Kaze::Sha => 2,
+            Kaze::Pei => 3,
+        }
+    }
+
+    /// Hai of the wind, from 1z to 4z.
+    pub fn to_hai(&self) -> Hai {
+        match self {
+            Kaze::Ton => Hai::Jihai(1),
+            Kaze::Nan => Hai::Jihai(2),
+            Kaze::Sha => Hai::Jihai(3),
+            Kaze::Pei => Hai::Jihai(4),
+        }
+    }
+
+    /// Types of yakuhai under given winds, which are sangenpai, jikaze and bakaze.
+    ///
+    /// # Japanese
+    /// * yakuhai: 役牌
+    /// * sangenpai: 三元牌
+    pub fn yakuhai_type(jikaze: Kaze, bakaze: Kaze) -> BTreeSet<Hai> {
+        let mut yakuhai_type = BTreeSet::new();
+        for num in 5..=7 {
+            yakuhai_type.insert(Hai::Jihai(num));
+        }
+        yakuhai_type.insert(jikaze.to_hai());
+        yakuhai_type.insert(bakaze.to_hai());
+        yakuhai_type
+    }
+}
+
+impl std::fmt::Display for Kaze {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Kaze::Ton => "東",
+                Kaze::Nan => "南",
+                Kaze::Sha => "西",
+                Kaze::Pei => "北",
+            }
+        )
+    }
+}
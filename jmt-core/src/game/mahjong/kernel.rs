@@ -0,0 +1,70 @@
+//! Kernels on 34-index counts used in hot loops of shanten and simulation, such as the sum
+//! of counts and acceptance tallied over all 34 draws.
+//!
+//! With the `simd` feature, counts are widened to 64 lanes and computed by `std::simd`,
+//! which needs a nightly compiler. Otherwise they are plain loops, which give the same
+//! results.
+
+#[cfg(feature = "simd")]
+use std::simd::{cmp::SimdPartialOrd, num::SimdUint, Simd};
+
+/// Sum of counts.
+pub fn total(counts: &[u8; 34]) -> usize {
+    #[cfg(feature = "simd")]
+    {
+        widen(counts).cast::<u16>().reduce_sum() as usize
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        counts.iter().map(|count| *count as usize).sum()
+    }
+}
+
+/// Number of indices whose count is at least `minimum`, which is regarded as 1 if 0.
+pub fn kinds_at_least(counts: &[u8; 34], minimum: u8) -> usize {
+    #[cfg(feature = "simd")]
+    {
+        widen(counts)
+            .simd_ge(Simd::splat(minimum.max(1)))
+            .to_bitmask()
+            .count_ones() as usize
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        counts
+            .iter()
+            .filter(|count| **count >= minimum.max(1))
+            .count()
+    }
+}
+
+/// Acceptance, that is the sum of unseen counts of hai which are machihai.
+pub fn acceptance(machi: &[bool; 34], unseen: &[u8; 34]) -> usize {
+    #[cfg(feature = "simd")]
+    {
+        let mut lanes = [0; 64];
+        for (lane, machi) in lanes.iter_mut().zip(machi.iter()) {
+            *lane = *machi as u8;
+        }
+        (widen(unseen) * Simd::from_array(lanes))
+            .cast::<u16>()
+            .reduce_sum() as usize
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        machi
+            .iter()
+            .zip(unseen.iter())
+            .filter(|(machi, _)| **machi)
+            .map(|(_, number)| *number as usize)
+            .sum()
+    }
+}
+
+/// Counts in 64 lanes, the rest are 0 so that they change no result.
+#[cfg(feature = "simd")]
+fn widen(counts: &[u8; 34]) -> Simd<u8, 64> {
+    let mut lanes = [0; 64];
+    lanes[..34].copy_from_slice(counts);
+    Simd::from_array(lanes)
+}
@@ -0,0 +1,31 @@
+mod combination;
+mod hai;
+mod haiyama;
+mod kan;
+mod kawa;
+mod kaze;
+pub mod kernel;
+mod player_number;
+mod seat;
+pub mod shanten;
+mod situation;
+mod tehai;
+pub mod zobrist;
+
+use super::Cancellation;
+#[cfg(feature = "profiling")]
+use super::Timing;
+
+pub use combination::{Mentsu, MentsuDisplay, MentsuFormat, Taatsu, TaatsuKind, Toitsu, Ukihai};
+pub use hai::{Hai, Suit};
+pub use haiyama::Haiyama;
+pub use kan::Kan;
+pub use kawa::Kawa;
+pub use kaze::Kaze;
+pub use player_number::PlayerNumber;
+pub use seat::Seat;
+pub use situation::Situation;
+pub use tehai::{
+    AnalysisBuffers, Decomposer, DecomposerBuilder, Decompositions, Hourakei, MachiCondition,
+    Tehai, TehaiDisplay, TehaiFormat, Ukeire,
+};
@@ -1,6 +1,7 @@
 /// Number of players. Support 4-players mode and
 /// 3-players mode yet.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PlayerNumber {
     Three,
     Four,
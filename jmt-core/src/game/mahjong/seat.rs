@@ -0,0 +1,72 @@
+use super::{Kaze, PlayerNumber};
+
+/// Seat of another player relative to self.
+///
+/// # Japanese
+/// * Kamicha: 上家, the player before self, whose sutehai can be chii.
+/// * Toimen: 対面
+/// * Shimocha: 下家
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Seat {
+    Kamicha,
+    Toimen,
+    Shimocha,
+}
+
+impl Seat {
+    /// Parse seat from `kami`, `toimen` or `shimo`, full names like `kamicha` are also
+    /// accepted.
+    pub fn from_string(string: &str) -> Result<Self, String> {
+        match string {
+            "kami" | "kamicha" => Ok(Seat::Kamicha),
+            "toimen" => Ok(Seat::Toimen),
+            "shimo" | "shimocha" => Ok(Seat::Shimocha),
+            _ => Err(format!("Unknown seat: {}.", string)),
+        }
+    }
+
+    /// Jikaze of the player at the seat, `None` for toimen of 3 players.
+    pub fn kaze(&self, jikaze: Kaze, player_number: PlayerNumber) -> Option<Kaze> {
+        let player_count = match player_number {
+            PlayerNumber::Four => 4,
+            PlayerNumber::Three => 3,
+        };
+        let offset = match (self, player_number) {
+            (Seat::Shimocha, _) => 1,
+            (Seat::Toimen, PlayerNumber::Four) => 2,
+            (Seat::Toimen, PlayerNumber::Three) => return None,
+            (Seat::Kamicha, _) => player_count - 1,
+        };
+        Some(Kaze::from_index(
+            (jikaze.to_index() + offset) % player_count,
+        ))
+    }
+
+    /// Seats of other players in the game, in the order of turns from shimocha.
+    pub fn all(player_number: PlayerNumber) -> Vec<Seat> {
+        match player_number {
+            PlayerNumber::Four => vec![Seat::Shimocha, Seat::Toimen, Seat::Kamicha],
+            PlayerNumber::Three => vec![Seat::Shimocha, Seat::Kamicha],
+        }
+    }
+
+    /// Key of the seat in JSON, CSV and snapshots, also accepted by `from_string()`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Seat::Kamicha => "kamicha",
+            Seat::Toimen => "toimen",
+            Seat::Shimocha => "shimocha",
+        }
+    }
+}
+
+impl std::fmt::Display for Seat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Seat::Kamicha => write!(f, "上家"),
+            Seat::Toimen => write!(f, "対面"),
+            Seat::Shimocha => write!(f, "下家"),
+        }
+    }
+}
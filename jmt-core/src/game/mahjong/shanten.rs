@@ -0,0 +1,629 @@
+//! Shanten calculation on 34-index counts, without constructing `Tehai`.
+//!
+//! Functions here neither allocate nor validate, so they can be used in hot loops of
+//! simulators. Counts should be juntehai only, and the sum should be `13 - 3 * fuuro_count`
+//! or `14 - 3 * fuuro_count`.
+
+use super::{kernel, Decompositions, Hai, Haiyama, PlayerNumber, Tehai};
+use std::collections::{BTreeMap, HashMap};
+
+/// Yaochuuhai in 34-index representation.
+const YAOCHUUHAI_INDICES: [usize; 13] = [0, 8, 9, 17, 18, 26, 27, 28, 29, 30, 31, 32, 33];
+
+/// Calculate the number of shanten from 34-index counts, -1 if already won.
+///
+/// Chiitoitsu and kokushimusou are considered only if `fuuro_count` is 0.
+pub fn calculate_counts(counts: &[u8; 34], fuuro_count: usize) -> i32 {
+    let mut shanten = calculate_normal(counts, fuuro_count);
+    if fuuro_count == 0 {
+        shanten = shanten
+            .min(calculate_chiitoitsu(counts))
+            .min(calculate_kokushimusou(counts));
+    }
+    shanten
+}
+
+/// Iterate all decompositions of juntehai lazily, see `Tehai::decompositions()`.
+///
+/// Unlike other functions here, decomposers are allocated for each decomposition, so it is
+/// for inspecting shapes rather than hot loops.
+pub fn decompositions(tehai: &Tehai, player_number: PlayerNumber) -> Decompositions {
+    tehai.decompositions(player_number)
+}
+
+/// Find machihai of 34-index counts whose sum is `13 - 3 * fuuro_count`.
+///
+/// # Return
+/// * [bool; 34]: whether drawing the hai of each index reduces shanten. All `false` if the
+///   sum of counts is not `3k + 1`.
+pub fn machi_counts(counts: &[u8; 34], fuuro_count: usize) -> [bool; 34] {
+    let mut machi = [false; 34];
+    if kernel::total(counts) % 3 != 1 {
+        return machi;
+    }
+
+    let shanten = calculate_counts(counts, fuuro_count);
+    let mut counts = *counts;
+    for index in 0..34 {
+        if counts[index] >= 4 {
+            continue;
+        }
+        counts[index] += 1;
+        machi[index] = calculate_counts(&counts, fuuro_count) < shanten;
+        counts[index] -= 1;
+    }
+    machi
+}
+
+/// Acceptance of each sutehai keeping the least shanten, for callers which only need the
+/// numbers, such as bots. It stands for `nokori()` and kinds of `machihai` of
+/// `MachiCondition` without decomposing tehai or building conditions, but judged by shanten
+/// of counts, so it may have more sutehai than conditions, which only discard ukihai of
+/// decompositions.
+///
+/// # Return
+/// * BTreeMap<Hai, (u32, u32)>: the number of kinds of machihai not all visible, and the
+///   number of them unseen in haiyama, for each sutehai. Empty if tehai is not full with hai
+///   or already won.
+pub fn ukeire(tehai: &Tehai, haiyama: &Haiyama) -> BTreeMap<Hai, (u32, u32)> {
+    let mut ukeire = BTreeMap::new();
+    let mut counts = Hai::to_counts(tehai.juntehai.iter());
+    let total = kernel::total(&counts);
+    if total % 3 != 2 || total > 14 {
+        return ukeire;
+    }
+    // Missing juntehai are regarded as fuuro, as tehai are analyzed.
+    let fuuro_count = (14 - total) / 3;
+    let shanten = calculate_counts(&counts, fuuro_count);
+    if shanten == -1 {
+        return ukeire;
+    }
+
+    let unseen = haiyama.to_counts();
+    for index in 0..34 {
+        if counts[index] == 0 {
+            continue;
+        }
+        counts[index] -= 1;
+        if calculate_counts(&counts, fuuro_count) == shanten {
+            let machi = machi_counts(&counts, fuuro_count);
+            let kinds = machi
+                .iter()
+                .zip(unseen.iter())
+                .filter(|(machi, unseen)| **machi && **unseen > 0)
+                .count();
+            let sutehai = Hai::from_index(index).unwrap();
+            ukeire.insert(
+                sutehai,
+                (kinds as u32, kernel::acceptance(&machi, &unseen) as u32),
+            );
+        }
+        counts[index] += 1;
+    }
+    ukeire
+}
+
+/// Max shanten to calculate expected draws until tenpai, since the cost grows
+/// exponentially with shanten.
+pub const MAX_TENPAI_DRAWS_SHANTEN: i32 = 2;
+
+/// Expected number of draws until tenpai from 34-index counts whose sum is
+/// `13 - 3 * fuuro_count`, drawing from `unseen` counts.
+///
+/// Drawing other hai is assumed to be discarded at once, so the first machihai is expected
+/// at `(unseen + 1) / (machihai + 1)` drawing without replacement. After that, the best
+/// sutehai is chosen by the expectation of the rest, and the expectations after each
+/// machihai are averaged by their numbers.
+///
+/// # Return
+/// * Option<f64>: `None` if shanten is more than `MAX_TENPAI_DRAWS_SHANTEN`, or no machihai
+///   is unseen.
+pub fn expected_tenpai_draws(
+    counts: &[u8; 34],
+    fuuro_count: usize,
+    unseen: &[u8; 34],
+) -> Option<f64> {
+    let shanten = calculate_counts(counts, fuuro_count);
+    if shanten > MAX_TENPAI_DRAWS_SHANTEN {
+        return None;
+    }
+    expected_tenpai_draws_core(counts, fuuro_count, unseen, shanten)
+}
+
+fn expected_tenpai_draws_core(
+    counts: &[u8; 34],
+    fuuro_count: usize,
+    unseen: &[u8; 34],
+    shanten: i32,
+) -> Option<f64> {
+    if shanten <= 0 {
+        return Some(0.0);
+    }
+    let machi = machi_counts(counts, fuuro_count);
+    let total = kernel::total(unseen);
+    let machi_total = kernel::acceptance(&machi, unseen);
+    let mut rest_sum = 0.0;
+    let mut counts = *counts;
+    for index in 0..34 {
+        if !machi[index] || unseen[index] == 0 {
+            continue;
+        }
+        // Any sutehai keeping shanten leads to tenpai at once from 1 shanten.
+        if shanten == 1 {
+            continue;
+        }
+        counts[index] += 1;
+        let mut best: Option<f64> = None;
+        for sutehai in 0..34 {
+            if counts[sutehai] == 0 || sutehai == index {
+                continue;
+            }
+            counts[sutehai] -= 1;
+            if calculate_counts(&counts, fuuro_count) == shanten - 1 {
+                if let Some(draws) =
+                    expected_tenpai_draws_core(&counts, fuuro_count, unseen, shanten - 1)
+                {
+                    best = Some(best.map_or(draws, |best| best.min(draws)));
+                }
+            }
+            counts[sutehai] += 1;
+        }
+        counts[index] -= 1;
+        rest_sum += unseen[index] as f64 * best?;
+    }
+    if machi_total == 0 {
+        return None;
+    }
+    Some((total + 1) as f64 / (machi_total + 1) as f64 + rest_sum / machi_total as f64)
+}
+
+fn calculate_normal(counts: &[u8; 34], fuuro_count: usize) -> i32 {
+    let required = 4 - fuuro_count.min(4) as i32;
+    let mut counts = *counts;
+    let mut shanten = search(&mut counts, 0, 0, 0, required);
+
+    // Try each toitsu as jantou.
+    for index in 0..34 {
+        if counts[index] >= 2 {
+            counts[index] -= 2;
+            shanten = shanten.min(search(&mut counts, 0, 0, 0, required) - 1);
+            counts[index] += 2;
+        }
+    }
+    shanten
+}
+
+/// Search mentsu and taatsu from `index` recursively, return the least shanten without
+/// considering jantou.
+fn search(counts: &mut [u8; 34], mut index: usize, mentsu: i32, taatsu: i32, required: i32) -> i32 {
+    while index < 34 && counts[index] == 0 {
+        index += 1;
+    }
+    if index >= 34 {
+        return 2 * (required - mentsu) - taatsu.min(required - mentsu);
+    }
+
+    // Treat the rest of this hai as ukihai.
+    let mut shanten = search(counts, index + 1, mentsu, taatsu, required);
+
+    let is_suuhai = index < 27;
+    let number = index % 9;
+
+    // Mentsu.
+    if mentsu < required {
+        if counts[index] >= 3 {
+            counts[index] -= 3;
+            shanten = shanten.min(search(counts, index, mentsu + 1, taatsu, required));
+            counts[index] += 3;
+        }
+        if is_suuhai && number <= 6 && counts[index + 1] > 0 && counts[index + 2] > 0 {
+            counts[index] -= 1;
+            counts[index + 1] -= 1;
+            counts[index + 2] -= 1;
+            shanten = shanten.min(search(counts, index, mentsu + 1, taatsu, required));
+            counts[index] += 1;
+            counts[index + 1] += 1;
+            counts[index + 2] += 1;
+        }
+    }
+
+    // Taatsu, including toitsu which is not jantou.
+    if mentsu + taatsu < required {
+        if counts[index] >= 2 {
+            counts[index] -= 2;
+            shanten = shanten.min(search(counts, index, mentsu, taatsu + 1, required));
+            counts[index] += 2;
+        }
+        for offset in 1..=2 {
+            if is_suuhai && number + offset <= 8 && counts[index + offset] > 0 {
+                counts[index] -= 1;
+                counts[index + offset] -= 1;
+                shanten = shanten.min(search(counts, index, mentsu, taatsu + 1, required));
+                counts[index] += 1;
+                counts[index + offset] += 1;
+            }
+        }
+    }
+
+    shanten
+}
+
+fn calculate_chiitoitsu(counts: &[u8; 34]) -> i32 {
+    let toitsu = kernel::kinds_at_least(counts, 2) as i32;
+    let kinds = kernel::kinds_at_least(counts, 1) as i32;
+    6 - toitsu + (7 - kinds).max(0)
+}
+
+/// Calculate the number of shanten toward kokushimusou, whose sum of counts should be 13 or 14.
+pub fn calculate_kokushimusou(counts: &[u8; 34]) -> i32 {
+    let mut kinds = 0;
+    let mut has_toitsu = false;
+    for index in YAOCHUUHAI_INDICES.iter() {
+        if counts[*index] >= 1 {
+            kinds += 1;
+        }
+        if counts[*index] >= 2 {
+            has_toitsu = true;
+        }
+    }
+    13 - kinds - if has_toitsu { 1 } else { 0 }
+}
+
+/// Shanten of 34-index counts kept up to date by draws and discards one by one, for callers
+/// analyzing tehai after every change, such as GUIs and simulators.
+///
+/// Mentsu, taatsu and jantou never cross suits, so normal shanten is combined from the shapes
+/// of each suit, and only the suit of the changed hai is searched again. Shapes are also
+/// cached by counts of the suit, which recur a lot, so machihai and ukeire mostly look up
+/// the cache. Results are the same as `calculate_counts()`, `machi_counts()` and `ukeire()`.
+#[derive(Clone, Debug)]
+pub struct Incremental {
+    counts: [u8; 34],
+    fuuro_count: usize,
+    shapes: [Shapes; 4],
+    shanten: i32,
+    cache: HashMap<u32, Shapes>,
+}
+
+/// Shapes of a suit, the max number of taatsu for each number of mentsu, with and without
+/// jantou, -1 if unreachable. Both numbers are at most 4, which is enough for any tehai.
+type Shapes = [[i8; 5]; 2];
+
+impl Incremental {
+    /// Start from 34-index counts of juntehai, with `fuuro_count` as `calculate_counts()`.
+    pub fn new(counts: &[u8; 34], fuuro_count: usize) -> Self {
+        let mut incremental = Self {
+            counts: *counts,
+            fuuro_count,
+            shapes: [[[-1; 5]; 2]; 4],
+            shanten: 0,
+            cache: HashMap::new(),
+        };
+        for suit in 0..4 {
+            incremental.shapes[suit] = incremental.suit_shapes(counts, suit);
+        }
+        incremental.shanten = incremental.combine(counts, &incremental.shapes);
+        incremental
+    }
+
+    /// Start from juntehai of `tehai`, see `new()`.
+    pub fn from_tehai(tehai: &Tehai) -> Self {
+        Self::new(&tehai.to_counts(), tehai.fuuro.len())
+    }
+
+    /// 34-index counts of juntehai.
+    pub fn counts(&self) -> &[u8; 34] {
+        &self.counts
+    }
+
+    pub fn fuuro_count(&self) -> usize {
+        self.fuuro_count
+    }
+
+    /// Shanten of current counts, -1 if already won.
+    pub fn shanten(&self) -> i32 {
+        self.shanten
+    }
+
+    /// Add a hai to juntehai.
+    pub fn draw(&mut self, hai: &Hai) -> Result<(), String> {
+        let index = hai.to_index();
+        if self.counts[index] >= 4 {
+            return Err(format!("Fifth {} found.", hai));
+        }
+        self.counts[index] += 1;
+        self.update(index);
+        Ok(())
+    }
+
+    /// Remove a hai from juntehai.
+    pub fn discard(&mut self, hai: &Hai) -> Result<(), String> {
+        let index = hai.to_index();
+        if self.counts[index] == 0 {
+            return Err(format!("{} is not in tehai.", hai));
+        }
+        self.counts[index] -= 1;
+        self.update(index);
+        Ok(())
+    }
+
+    /// Machihai of current counts, see `machi_counts()`.
+    pub fn machi(&mut self) -> [bool; 34] {
+        let mut machi = [false; 34];
+        if kernel::total(&self.counts) % 3 != 1 {
+            return machi;
+        }
+        for (index, machi) in machi.iter_mut().enumerate() {
+            if self.counts[index] < 4 {
+                *machi = self.shanten_after(&[(index, 1)]) < self.shanten;
+            }
+        }
+        machi
+    }
+
+    /// Acceptance of each sutehai keeping the least shanten, see `ukeire()`.
+    ///
+    /// # Parameters
+    /// * unseen: 34-index counts of hai unseen, such as `Haiyama::to_counts()`.
+    pub fn ukeire(&mut self, unseen: &[u8; 34]) -> BTreeMap<Hai, (u32, u32)> {
+        let mut ukeire = BTreeMap::new();
+        let total = kernel::total(&self.counts);
+        if total % 3 != 2 || self.shanten == -1 {
+            return ukeire;
+        }
+        for sutehai in 0..34 {
+            if self.counts[sutehai] == 0 || self.shanten_after(&[(sutehai, -1)]) != self.shanten {
+                continue;
+            }
+            let mut machi = [false; 34];
+            for (index, machi) in machi.iter_mut().enumerate() {
+                let count = self.counts[index] - if index == sutehai { 1 } else { 0 };
+                if count < 4 {
+                    *machi = self.shanten_after(&[(sutehai, -1), (index, 1)]) < self.shanten;
+                }
+            }
+            let kinds = machi
+                .iter()
+                .zip(unseen.iter())
+                .filter(|(machi, unseen)| **machi && **unseen > 0)
+                .count();
+            // Index is always less than 34.
+            ukeire.insert(
+                Hai::from_index(sutehai).unwrap(),
+                (kinds as u32, kernel::acceptance(&machi, unseen) as u32),
+            );
+        }
+        ukeire
+    }
+
+    /// Search the suit of the changed index again.
+    fn update(&mut self, index: usize) {
+        let counts = self.counts;
+        self.shapes[index / 9] = self.suit_shapes(&counts, index / 9);
+        self.shanten = self.combine(&counts, &self.shapes);
+    }
+
+    /// Shanten of current counts with `changes` of some indices, without changing them.
+    fn shanten_after(&mut self, changes: &[(usize, i8)]) -> i32 {
+        let mut counts = self.counts;
+        let mut shapes = self.shapes;
+        for (index, change) in changes {
+            counts[*index] = (counts[*index] as i8 + change) as u8;
+        }
+        for (index, _) in changes {
+            shapes[index / 9] = self.suit_shapes(&counts, index / 9);
+        }
+        self.combine(&counts, &shapes)
+    }
+
+    /// Shapes of a suit, 0 to 2 for suuhai and 3 for jihai, looked up in the cache first.
+    fn suit_shapes(&mut self, counts: &[u8; 34], suit: usize) -> Shapes {
+        let range = suit * 9..(suit * 9 + 9).min(34);
+        // Counts of a suit in base 5, with jihai told apart by the highest bit.
+        let key = counts[range.clone()]
+            .iter()
+            .rev()
+            .fold(0, |key, count| key * 5 + *count as u32)
+            | if suit == 3 { 1 << 31 } else { 0 };
+        if let Some(shapes) = self.cache.get(&key) {
+            return *shapes;
+        }
+        let mut suit_counts = [0; 9];
+        suit_counts[..range.len()].copy_from_slice(&counts[range]);
+        let mut shapes = [[-1; 5]; 2];
+        search_suit(&mut suit_counts, 0, suit < 3, 0, 0, 0, &mut shapes);
+        self.cache.insert(key, shapes);
+        shapes
+    }
+
+    /// Shanten from shapes of all suits, with chiitoitsu and kokushimusou of counts.
+    fn combine(&self, counts: &[u8; 34], shapes: &[Shapes; 4]) -> i32 {
+        let mut merged = shapes[0];
+        for suit_shapes in &shapes[1..] {
+            let mut next = [[-1; 5]; 2];
+            for jantou in 0..2 {
+                for other_jantou in 0..2 - jantou {
+                    for mentsu in 0..5 {
+                        let taatsu = merged[jantou][mentsu];
+                        if taatsu < 0 {
+                            continue;
+                        }
+                        for other_mentsu in 0..5 {
+                            let other_taatsu = suit_shapes[other_jantou][other_mentsu];
+                            if other_taatsu < 0 {
+                                continue;
+                            }
+                            let best =
+                                &mut next[jantou + other_jantou][(mentsu + other_mentsu).min(4)];
+                            *best = (*best).max((taatsu + other_taatsu).min(4));
+                        }
+                    }
+                }
+            }
+            merged = next;
+        }
+
+        // Same as the end of `search()`, extra mentsu and taatsu are regarded as ukihai.
+        let required = 4 - self.fuuro_count.min(4) as i32;
+        let mut shanten = i32::MAX;
+        for (jantou, merged) in merged.iter().enumerate() {
+            for (mentsu, taatsu) in merged.iter().enumerate() {
+                if *taatsu < 0 {
+                    continue;
+                }
+                let mentsu = (mentsu as i32).min(required);
+                let taatsu = (*taatsu as i32).min(required - mentsu);
+                shanten = shanten.min(2 * (required - mentsu) - taatsu - jantou as i32);
+            }
+        }
+        if self.fuuro_count == 0 {
+            shanten = shanten
+                .min(calculate_chiitoitsu(counts))
+                .min(calculate_kokushimusou(counts));
+        }
+        shanten
+    }
+}
+
+/// Search all mentsu, taatsu and jantou of a suit from `index` recursively, recording the
+/// shapes reached.
+fn search_suit(
+    counts: &mut [u8; 9],
+    mut index: usize,
+    is_suuhai: bool,
+    mentsu: usize,
+    taatsu: i8,
+    jantou: usize,
+    shapes: &mut Shapes,
+) {
+    while index < 9 && counts[index] == 0 {
+        index += 1;
+    }
+    if index >= 9 {
+        let best = &mut shapes[jantou][mentsu.min(4)];
+        *best = (*best).max(taatsu.min(4));
+        return;
+    }
+
+    // Treat the rest of this hai as ukihai.
+    search_suit(counts, index + 1, is_suuhai, mentsu, taatsu, jantou, shapes);
+
+    if counts[index] >= 3 {
+        counts[index] -= 3;
+        search_suit(counts, index, is_suuhai, mentsu + 1, taatsu, jantou, shapes);
+        counts[index] += 3;
+    }
+    if is_suuhai && index <= 6 && counts[index + 1] > 0 && counts[index + 2] > 0 {
+        counts[index] -= 1;
+        counts[index + 1] -= 1;
+        counts[index + 2] -= 1;
+        search_suit(counts, index, is_suuhai, mentsu + 1, taatsu, jantou, shapes);
+        counts[index] += 1;
+        counts[index + 1] += 1;
+        counts[index + 2] += 1;
+    }
+    if counts[index] >= 2 {
+        counts[index] -= 2;
+        if jantou == 0 {
+            search_suit(counts, index, is_suuhai, mentsu, taatsu, 1, shapes);
+        }
+        search_suit(counts, index, is_suuhai, mentsu, taatsu + 1, jantou, shapes);
+        counts[index] += 2;
+    }
+    for offset in 1..=2 {
+        if is_suuhai && index + offset <= 8 && counts[index + offset] > 0 {
+            counts[index] -= 1;
+            counts[index + offset] -= 1;
+            search_suit(counts, index, is_suuhai, mentsu, taatsu + 1, jantou, shapes);
+            counts[index] += 1;
+            counts[index + offset] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Linear congruential generator, enough to shuffle walls in tests.
+    fn next(seed: &mut u64) -> usize {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (*seed >> 33) as usize
+    }
+
+    /// All 136 hai as indices in an order decided by `seed`.
+    fn wall(mut seed: u64) -> Vec<usize> {
+        let mut wall: Vec<usize> = (0..136).map(|index| index / 4).collect();
+        for index in (1..wall.len()).rev() {
+            wall.swap(index, next(&mut seed) % (index + 1));
+        }
+        wall
+    }
+
+    fn check(incremental: &mut Incremental) {
+        let counts = *incremental.counts();
+        let fuuro_count = incremental.fuuro_count();
+        assert_eq!(
+            incremental.shanten(),
+            calculate_counts(&counts, fuuro_count),
+            "{:?}",
+            counts
+        );
+        assert_eq!(
+            incremental.machi(),
+            machi_counts(&counts, fuuro_count),
+            "{:?}",
+            counts
+        );
+        if fuuro_count == 0 {
+            let tehai = Tehai::from_counts(&counts, PlayerNumber::Four).unwrap();
+            let mut unseen = [4; 34];
+            for (unseen, count) in unseen.iter_mut().zip(counts.iter()) {
+                *unseen -= count;
+            }
+            let haiyama = Haiyama::from_counts(&unseen);
+            assert_eq!(
+                incremental.ukeire(&unseen),
+                ukeire(&tehai, &haiyama),
+                "{:?}",
+                counts
+            );
+        }
+    }
+
+    #[test]
+    fn incremental_same_as_counts() {
+        for seed in 0..24 {
+            let fuuro_count = (seed % 3) as usize;
+            let wall = wall(seed);
+            let juntehai = 13 - 3 * fuuro_count;
+            let mut counts = [0; 34];
+            for index in &wall[..juntehai] {
+                counts[*index] += 1;
+            }
+            let mut incremental = Incremental::new(&counts, fuuro_count);
+            let mut order = seed;
+            check(&mut incremental);
+            for index in &wall[juntehai..juntehai + 24] {
+                incremental.draw(&Hai::from_index(*index).unwrap()).unwrap();
+                check(&mut incremental);
+                let juntehai = Hai::from_counts(incremental.counts());
+                let sutehai = juntehai[next(&mut order) % juntehai.len()];
+                incremental.discard(&sutehai).unwrap();
+                check(&mut incremental);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_rejects_invalid_changes() {
+        let mut counts = [0; 34];
+        counts[0] = 4;
+        let mut incremental = Incremental::new(&counts, 3);
+        assert!(incremental.draw(&Hai::Manzu(1)).is_err());
+        assert!(incremental.discard(&Hai::Manzu(2)).is_err());
+        assert_eq!(incremental.counts(), &counts);
+    }
+}
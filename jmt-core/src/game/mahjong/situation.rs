@@ -0,0 +1,24 @@
+use super::{Hai, Haiyama, Kawa};
+use std::collections::BTreeSet;
+
+/// What is known about the game besides tehai, which makes analysis of tehai exact at
+/// interactive mode. Analysis without it assumes every hai not on hand is unseen.
+///
+/// Implemented by `GameManager`, so analyzing tehai needs no game features. It is
+/// `Send + Sync` for analysis on other threads.
+pub trait Situation: Send + Sync {
+    /// Hai not seen yet.
+    fn haiyama(&self) -> &Haiyama;
+
+    /// Types of yakuhai by jikaze and bakaze.
+    fn yakuhai_type(&self) -> BTreeSet<Hai>;
+
+    /// Own kawa, deciding furiten.
+    fn kawa(&self) -> &Kawa;
+
+    /// Types of hai ever discarded by self.
+    fn sutehai_type(&self) -> &BTreeSet<Hai>;
+
+    /// The same situation after drawing `hai` from haiyama.
+    fn after_draw(&self, hai: Hai) -> Result<Box<dyn Situation>, String>;
+}
@@ -0,0 +1,2927 @@
+#[cfg(feature = "profiling")]
+use super::Timing;
+use super::{
+    shanten, zobrist, Cancellation, Hai, Kan, Mentsu, MentsuFormat, PlayerNumber, Seat, Situation,
+    Taatsu, Toitsu, Ukihai,
+};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+/// hai on hand.
+///
+/// # Japanese
+/// * Tehai: 手牌
+/// * juntehai: 純手牌
+/// * fuuro: 副露
+///
+/// # Member
+/// * juntehai: Vec of hai which not formed mentsu.
+/// * fuuro: Mentsu which already formed.
+/// * fuuro_detail: How each fuuro is formed, at the same index of `fuuro`.
+///
+/// Tehai are equal if they have the same hai and fuuro in any order, see `normalize()`.
+/// Details of fuuro are not compared.
+///
+/// # Examples
+/// ```no_run
+/// use jmt_core::game::{PlayerNumber, Tehai};
+/// use std::io;
+///
+/// let mut input = String::new();
+/// io::stdin().read_line(&mut input).expect("error: unable to read user input");
+/// let tehai = Tehai::new(input.trim().to_string(), PlayerNumber::Four).unwrap();
+/// println!("{}", tehai);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tehai {
+    pub juntehai: Vec<Hai>,
+    pub fuuro: Vec<Mentsu>,
+    pub fuuro_detail: Vec<FuuroDetail>,
+}
+
+/// How a fuuro is formed, which `Mentsu` alone can not tell.
+///
+/// # Japanese
+/// * nakihai: 鳴き牌
+/// * ankan: 暗槓
+///
+/// # Member
+/// * nakihai: The hai called, `None` if unknown or ankan.
+/// * from: Seat which nakihai is called from, `None` if unknown or ankan.
+/// * ankan: Whether the kantsu is concealed, which keeps tehai menzen.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FuuroDetail {
+    pub nakihai: Option<Hai>,
+    pub from: Option<Seat>,
+    pub ankan: bool,
+}
+
+/// Form of tehai when winning.
+///
+/// **Note**: Only 14 juntehai without fuuro can be Kokushimusou and Chiitoitsu.
+///
+/// # Japanese
+/// * Hourakei: 和了形
+/// * Mentsute: 面子手
+/// * Chiitoitsu: 七対子
+/// * Kokushimusou: 国士無双
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Hourakei {
+    Mentsute,
+    Chiitoitsu,
+    Kokushimusou,
+}
+
+/// Decompose a tehai to mentsu, taatsu, toitsu and ukihai for analyzing hai waiting for.
+///
+/// # Member
+/// * valid_ukihai_vec: Ukihai that can provide shanten. such as `3p`, `5p`, `6p` and `8p` in
+/// `11224477m356778p`, or any yaochuupai in kokushimusou type.
+/// * invalid_ukihai_vec: Ukihai that cannot provide shanten, absolutely useless. such as `1m`
+/// in `111224477m34577p`, or any non-yaochuupai in kokushimusou type.
+///
+/// Decomposers are produced by analyzing tehai, or built directly by `DecomposerBuilder`
+/// for shapes already known, such as winning shapes from replays.
+///
+/// Decomposers are equal if they have the same parts in any order, see `normalize()`, so
+/// that the same split found by different paths is kept once in a set.
+#[derive(Clone, Debug)]
+pub struct Decomposer {
+    mentsu_vec: Vec<Mentsu>,
+    toitsu_vec: Vec<Toitsu>,
+    taatsu_vec: Vec<Taatsu>,
+    valid_ukihai_vec: Vec<Ukihai>,
+    invalid_ukihai_vec: Vec<Ukihai>,
+    hourakei: Hourakei,
+}
+
+/// Builder of decomposer, checking the parts can be in one tehai when building.
+///
+/// # Examples
+/// ```rust
+/// use jmt_core::game::{DecomposerBuilder, Hai, Hourakei, Mentsu, Taatsu, Toitsu, Ukihai};
+///
+/// # fn main() -> Result<(), String> {
+/// let decomposer = DecomposerBuilder::new(Hourakei::Mentsute)
+///     .mentsu(Mentsu::Koutsu(Hai::Jihai(1)))
+///     .toitsu(Toitsu(Hai::Souzu(5)))
+///     .taatsu(Taatsu(Hai::Manzu(3), Hai::Manzu(4)))
+///     .ukihai(Ukihai(Hai::Pinzu(9)), false)
+///     .build()?;
+/// assert_eq!(decomposer.shanten_number(), 0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DecomposerBuilder {
+    decomposer: Decomposer,
+}
+
+/// Lazy iterator of all decompositions of tehai, created by `Tehai::decompositions()`.
+///
+/// Branches of the search copy no vec: the rest juntehai is counted in a fixed array, and
+/// parts found so far are a path of `arena`, where parts of branches done are dropped. A
+/// decomposer is built only when a branch reaches the end, so memory is bounded by the depth
+/// of the search however many decompositions there are.
+///
+/// # Member
+/// * stack: Branches to be split in depth-first order.
+/// * arena: Parts found in branches on the stack, each with the index of its parent part.
+/// * rest: Decomposers of chiitoitsu and kokushimusou, yielded after mentsute.
+/// * juntehai_number: Number of juntehai, which decides shanten of decomposers.
+/// * least_shanten: Least shanten found so far if pruning, see `Decompositions::pruned()`.
+pub struct Decompositions {
+    stack: Vec<Branch>,
+    arena: Vec<(Part, Option<usize>)>,
+    rest: Vec<Decomposer>,
+    juntehai_number: usize,
+    player_number: PlayerNumber,
+    least_shanten: Option<i32>,
+}
+
+/// Scratch space and output of `Tehai::analyze_into()`, kept by the caller across calls, so
+/// that simulators analyzing tehai in a loop reuse the same vecs instead of allocating them
+/// each time. Conditions themselves still hold maps of machihai and ukeire.
+///
+/// # Member
+/// * stack, arena: Buffers of `Decompositions`.
+/// * decomposers: Decomposers of the least shanten, whose parts are overwritten in place.
+/// * decomposer_number: Number of decomposers of the last analysis in `decomposers`.
+/// * conditions: Conditions of the last analysis, see `conditions()`.
+#[derive(Default)]
+pub struct AnalysisBuffers {
+    stack: Vec<Branch>,
+    arena: Vec<(Part, Option<usize>)>,
+    decomposers: Vec<Decomposer>,
+    decomposer_number: usize,
+    conditions: Vec<MachiCondition>,
+}
+
+/// Condition of different sutehai.
+///
+/// # Japanese
+/// * sutehai: 捨て牌
+/// * machihai: 待ち牌
+/// * furiten: 振り聴
+///
+/// # Member
+/// * sutehai: which ukihai will be discarded.
+/// * machihai: hai waiting for.
+/// * furiten: if machihai included prevenient sutehai.
+/// * ukeire: shapes which each machihai completes, explaining why it is waited for.
+/// * kairyouhai: hai not reducing shanten but increasing machihai, only available after
+/// `Tehai::analyze_kairyou()`.
+/// * dead_machihai: hai waited for but all visible, which are not in machihai.
+/// * unseen: number of all hai not visible, from which the next hai is drawn.
+/// * tenpai_draws: expected number of draws until tenpai, only calculated from 1 or 2
+/// shanten after discarding, see `shanten::expected_tenpai_draws()`.
+/// * no_yaku: tenpai but winning with any machihai gives no yaku, see `yaku::has_yaku()`.
+/// Only judged by `analyzer`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MachiCondition {
+    pub sutehai: Hai,
+    pub machihai: BTreeMap<Hai, u8>,
+    pub furiten: bool,
+    pub ukeire: BTreeMap<Hai, BTreeSet<Ukeire>>,
+    pub kairyouhai: BTreeMap<Hai, u8>,
+    pub dead_machihai: BTreeSet<Hai>,
+    pub unseen: usize,
+    pub tenpai_draws: Option<f64>,
+    pub no_yaku: bool,
+}
+
+/// Options to print tehai by `Tehai::display()`, the default is the same as `Display`.
+///
+/// # Member
+/// * compact: Print hai grouped by type like `123m456p`, otherwise each hai like `1m2m3m`.
+/// * call_marker: Print the kind of call before each fuuro like `ポン[5z5z5z]`.
+/// * separate_tsumo: Print the last juntehai apart when full with hai, which is the hai just
+/// drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TehaiFormat {
+    pub compact: bool,
+    pub call_marker: bool,
+    pub separate_tsumo: bool,
+}
+
+/// Tehai printed with given format, created by `Tehai::display()`.
+pub struct TehaiDisplay<'a> {
+    tehai: &'a Tehai,
+    format: TehaiFormat,
+}
+
+/// Shape in tehai which accepts a machihai.
+///
+/// # Japanese
+/// * Ukeire: 受け入れ
+///
+/// # Member
+/// * Taatsu: Taatsu becomes mentsu.
+/// * Toitsu: Toitsu becomes koutsu.
+/// * Ukihai: Ukihai becomes toitsu or taatsu.
+/// * Chiitoitsu: Any new type of hai for chiitoitsu, or a single hai becoming toitsu. Never
+/// a type already toitsu or more, since the third one makes no new toitsu.
+/// * Kokushimusou: Missing yaochuupai for kokushimusou.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Ukeire {
+    Taatsu(Taatsu),
+    Toitsu(Toitsu),
+    Ukihai(Ukihai),
+    Chiitoitsu,
+    Kokushimusou,
+}
+
+/// Part of a decomposer found in a branch of `Decompositions`.
+#[derive(Copy, Clone, Debug)]
+enum Part {
+    Mentsu(Mentsu),
+    Toitsu(Toitsu),
+    Taatsu(Taatsu),
+    Ukihai(Ukihai),
+}
+
+/// Rest of juntehai in a branch of `Decompositions`, counted in 34-index representation.
+#[derive(Copy, Clone, Debug)]
+struct RestHai {
+    counts: [u8; 34],
+    len: usize,
+}
+
+/// Branch of `Decompositions`.
+///
+/// # Member
+/// * rest: Rest of juntehai to be split.
+/// * last: Index of the last part in the arena, `None` if no part is found.
+/// * mentsu_number: Number of mentsu in parts.
+/// * block_number: Number of toitsu and taatsu in parts.
+#[derive(Copy, Clone, Debug)]
+struct Branch {
+    rest: RestHai,
+    last: Option<usize>,
+    mentsu_number: usize,
+    block_number: usize,
+}
+
+impl RestHai {
+    fn new(juntehai: &[Hai]) -> Self {
+        Self {
+            counts: Hai::to_counts(juntehai.iter()),
+            len: juntehai.len(),
+        }
+    }
+
+    /// The least hai.
+    fn first(&self) -> Option<Hai> {
+        let index = self.counts.iter().position(|count| *count > 0)?;
+        Hai::from_index(index)
+    }
+
+    fn count(&self, hai: Hai) -> u8 {
+        self.counts[hai.to_index()]
+    }
+
+    fn contains(&self, hai: Hai) -> bool {
+        self.count(hai) > 0
+    }
+
+    fn without(mut self, used: &[Hai]) -> Self {
+        for hai in used {
+            self.counts[hai.to_index()] -= 1;
+        }
+        self.len -= used.len();
+        self
+    }
+}
+
+/// Decompose sorted juntehai of 14 hai as chiitoitsu.
+fn chiitoitsu_decomposer(juntehai: &[Hai]) -> Decomposer {
+    let mut decomposer = Decomposer::new();
+    decomposer.hourakei = Hourakei::Chiitoitsu;
+
+    let mut juntehai_iter = juntehai.iter();
+    let mut last_hai_used = false;
+
+    if let Some(mut last_hai) = juntehai_iter.next() {
+        loop {
+            if let Some(cur) = juntehai_iter.next() {
+                if cur == last_hai {
+                    if !last_hai_used {
+                        last_hai_used = true;
+                        decomposer.toitsu_vec.push(Toitsu(*cur));
+                    } else {
+                        decomposer.invalid_ukihai_vec.push(Ukihai(*cur));
+                    }
+                } else {
+                    if !last_hai_used {
+                        decomposer.valid_ukihai_vec.push(Ukihai(*last_hai));
+                    }
+                    last_hai = cur;
+                    last_hai_used = false;
+                }
+            } else {
+                if !last_hai_used {
+                    decomposer.valid_ukihai_vec.push(Ukihai(*last_hai));
+                }
+                break;
+            }
+        }
+    }
+
+    decomposer
+}
+
+/// Decompose sorted juntehai of 14 hai as kokushimusou.
+fn kokushimusou_decomposer(juntehai: &[Hai]) -> Decomposer {
+    let mut decomposer = Decomposer::new();
+    let mut toitsu_included = false;
+    let mut yaochuupai_iter_changed = true;
+    decomposer.hourakei = Hourakei::Kokushimusou;
+
+    let yaochuupai_type = Hai::yaochuupai_type();
+    let mut yaochuupai_iter = yaochuupai_type.iter();
+    let mut juntehai_iter = juntehai.iter();
+    let mut yaochuupai_value = yaochuupai_iter.next();
+    let mut juntehai_value = juntehai_iter.next();
+
+    while yaochuupai_value.is_some() && juntehai_value.is_some() {
+        if let (Some(lhs), Some(rhs)) = (yaochuupai_value, juntehai_value) {
+            if lhs < rhs {
+                yaochuupai_value = yaochuupai_iter.next();
+                yaochuupai_iter_changed = true;
+            } else if lhs > rhs {
+                decomposer.invalid_ukihai_vec.push(Ukihai(*rhs));
+                juntehai_value = juntehai_iter.next();
+            } else if lhs == rhs {
+                if yaochuupai_iter_changed {
+                    decomposer.valid_ukihai_vec.push(Ukihai(*rhs));
+                } else if !toitsu_included {
+                    toitsu_included = true;
+                    decomposer.valid_ukihai_vec.push(Ukihai(*rhs));
+                } else {
+                    decomposer.invalid_ukihai_vec.push(Ukihai(*rhs));
+                }
+                yaochuupai_iter_changed = false;
+                juntehai_value = juntehai_iter.next();
+            }
+        }
+    }
+
+    decomposer
+}
+
+/// Split the least hai of the rest juntehai into each possible part, such as toitsu, koutsu,
+/// taatsu, juntsu or ukihai, and return each part with the rest juntehai.
+///
+/// # Reference
+/// * http://choco.properties/2019/06/22/%E6%97%A5%E9%BA%BB%E6%8A%98%E8%85%BE%E7%AC%94%E8%AE%B0-02-%E5%90%91%E5%90%AC%E6%95%B0%E7%9A%84%E5%88%A4%E6%96%AD/
+/// * Original author: 天羽ちよこ
+fn split(rest: &RestHai, player_number: PlayerNumber, mut push: impl FnMut(Part, RestHai)) {
+    let current = match rest.first() {
+        Some(current) => current,
+        None => return,
+    };
+
+    if rest.count(current) >= 2 {
+        push(
+            Part::Toitsu(Toitsu(current)),
+            rest.without(&[current, current]),
+        );
+    }
+
+    if rest.count(current) >= 3 {
+        push(
+            Part::Mentsu(Mentsu::Koutsu(current)),
+            rest.without(&[current, current, current]),
+        );
+    }
+
+    if !current.is_jihai() {
+        if let Some(current_plus_one) = current.next(player_number, false) {
+            let current_plus_two = current_plus_one.next(player_number, false);
+            let current_plus_two =
+                current_plus_two.filter(|current_plus_two| rest.contains(*current_plus_two));
+            if rest.contains(current_plus_one) {
+                push(
+                    Part::Taatsu(Taatsu(current, current_plus_one)),
+                    rest.without(&[current, current_plus_one]),
+                );
+
+                if let Some(current_plus_two) = current_plus_two {
+                    let juntsu = [current, current_plus_one, current_plus_two];
+                    push(
+                        Part::Mentsu(Mentsu::Juntsu(juntsu[0], juntsu[1], juntsu[2])),
+                        rest.without(&juntsu),
+                    );
+                }
+            } else if let Some(current_plus_two) = current_plus_two {
+                push(
+                    Part::Taatsu(Taatsu(current, current_plus_two)),
+                    rest.without(&[current, current_plus_two]),
+                );
+            }
+        }
+    }
+
+    push(Part::Ukihai(Ukihai(current)), rest.without(&[current]));
+}
+
+/// Whether two lists have the same parts in any order, counting each part in both, which is
+/// cheaper than sorting copies for the few parts of a decomposer.
+fn same_parts<T: PartialEq>(lhs: &[T], rhs: &[T]) -> bool {
+    let count = |parts: &[T], part: &T| parts.iter().filter(|other| *other == part).count();
+    lhs.len() == rhs.len() && lhs.iter().all(|part| count(lhs, part) == count(rhs, part))
+}
+
+/// Hash parts in any order the same, by the wrapping sum of the hash of each part.
+fn hash_parts<T: Hash, H: Hasher>(parts: &[T], state: &mut H) {
+    let sum = parts.iter().fold(0u64, |sum, part| {
+        let mut hasher = DefaultHasher::new();
+        part.hash(&mut hasher);
+        sum.wrapping_add(hasher.finish())
+    });
+    parts.len().hash(state);
+    sum.hash(state);
+}
+
+/// Max number of mentsu, toitsu and taatsu which juntehai of `juntehai_number` hai can be
+/// decomposed into, that is 4 mentsu and a jantou less one mentsu for each fuuro.
+///
+/// Counted by juntehai alone, since each fuuro, kantsu included, is a mentsu taking 3 hai
+/// out of 14, never the 4 hai of a kantsu as loose ones.
+fn max_mentsu_toitsu_taatsu(juntehai_number: usize) -> usize {
+    (juntehai_number + 1) / 3
+}
+
+/// Print sorted hai like `123m456p789s11z`, the type character is written once for each group.
+fn compact_hai_string(hai_vec: &[Hai]) -> String {
+    let mut hai_vec = hai_vec.to_vec();
+    hai_vec.sort();
+
+    let mut compact_string = String::new();
+    let mut last_type = None;
+    for hai in hai_vec {
+        let (num, hai_type) = match hai {
+            Hai::Manzu(num) => (num, 'm'),
+            Hai::Pinzu(num) => (num, 'p'),
+            Hai::Souzu(num) => (num, 's'),
+            Hai::Jihai(num) => (num, 'z'),
+        };
+        if let Some(last_type) = last_type {
+            if last_type != hai_type {
+                compact_string.push(last_type);
+            }
+        }
+        compact_string += &num.to_string();
+        last_type = Some(hai_type);
+    }
+    if let Some(last_type) = last_type {
+        compact_string.push(last_type);
+    }
+
+    compact_string
+}
+
+/// Hai of a fuuro in the syntax of `Tehai::new()`, like `4'56m` and `(7777s)`.
+fn fuuro_string(mentsu: &Mentsu, detail: FuuroDetail, compact: bool) -> String {
+    let mut hai_vec = match mentsu {
+        Mentsu::Juntsu(a, b, c) => vec![*a, *b, *c],
+        Mentsu::Koutsu(hai) => vec![*hai; 3],
+        Mentsu::Kantsu(hai) => vec![*hai; 4],
+    };
+    // Nakihai of chii is written first, followed by the others in order.
+    if let (Some(nakihai), Mentsu::Juntsu(..)) = (detail.nakihai, mentsu) {
+        if let Some(index) = hai_vec.iter().position(|hai| *hai == nakihai) {
+            hai_vec.remove(index);
+            hai_vec.insert(0, nakihai);
+        }
+    }
+    let claim = match detail.from {
+        Some(Seat::Kamicha) => Some(0),
+        Some(Seat::Toimen) => Some(1),
+        Some(Seat::Shimocha) => Some(hai_vec.len() - 1),
+        None => None,
+    };
+
+    let mut string = String::new();
+    for (index, hai) in hai_vec.iter().enumerate() {
+        let hai_string = hai.to_string();
+        if compact {
+            string += &hai_string[..1];
+        } else {
+            string += &hai_string;
+        }
+        if claim == Some(index) {
+            string.push('\'');
+        }
+    }
+    if compact {
+        string += &hai_vec[0].to_string()[1..];
+    }
+    if detail.ankan {
+        format!("({})", string)
+    } else {
+        string
+    }
+}
+
+impl Tehai {
+    /// Create tehai from string.
+    ///
+    /// # Input
+    /// You can input hai out of order, and use [] represent formed melds. All spaces will be ignored.
+    /// (they will not be considered for shanten number).
+    /// * stanard: `1m2m3m4m4m5m4p4p4p5p8s[1z1z1z]`
+    /// * shorter: `123445m4445p8s[111z]`
+    /// * with spaces: `123445m 4445p 8s [111z]`
+    /// * chaos: `45p 8s14 4m[11 1z]2 5m44p 3m`
+    ///
+    /// In `[]`, `'` after a hai marks it as nakihai, whose position tells where it is called
+    /// from: the first from kamicha, the last from shimocha, and others from toimen. Kantsu in
+    /// `()` is ankan.
+    /// * chii: `[4'56m]`
+    /// * pon from toimen: `[55'5z]`
+    /// * daiminkan from shimocha: `[555'5p]`
+    /// * ankan: `[(7777s)]`
+    ///
+    /// # Examples
+    /// ```rust
+    /// use jmt_core::game::{PlayerNumber, Tehai};
+    ///
+    /// let tehai = Tehai::new("45p8s144m[111z]25m44p3m".to_string(), PlayerNumber::Four);
+    /// assert!(tehai.is_ok());
+    /// ```
+    pub fn new(string: String, player_number: PlayerNumber) -> Result<Self, String> {
+        fn handle_char_stash(
+            hai_type: char,
+            hai_type_char_index: usize,
+            player_number: PlayerNumber,
+            char_stash: &mut Vec<char>,
+            output: &mut Vec<Hai>,
+        ) -> Result<(), String> {
+            if char_stash.len() == 0 {
+                Err(i18n::format(
+                    Message::UnusedTypeCharacter,
+                    &[&hai_type, &hai_type_char_index],
+                ))
+            } else {
+                for hai in char_stash.iter() {
+                    let hai = match hai_type {
+                        'm' => Hai::Manzu(*hai as u8 - 48),
+                        'p' => Hai::Pinzu(*hai as u8 - 48),
+                        's' => Hai::Souzu(*hai as u8 - 48),
+                        'z' => Hai::Jihai(*hai as u8 - 48),
+                        _ => Hai::Manzu(0), // Never reach here.
+                    };
+                    if hai.is_valid(player_number) {
+                        output.push(hai);
+                    } else {
+                        char_stash.clear();
+                        return Err(i18n::format(Message::InvalidHai, &[&hai]));
+                    }
+                }
+                char_stash.clear();
+                Ok(())
+            }
+        }
+
+        fn handle_hai_in_mentsu_stash(
+            char_index: usize,
+            player_number: PlayerNumber,
+            hai_in_mentsu_stash: &mut Vec<Hai>,
+            output: &mut Vec<Mentsu>,
+        ) -> Result<(), String> {
+            let mentsu = Mentsu::new(hai_in_mentsu_stash, player_number)
+                .ok_or(i18n::format(Message::InvalidMeld, &[&char_index]))?;
+
+            output.push(mentsu);
+            hai_in_mentsu_stash.clear();
+            Ok(())
+        }
+
+        /// Detail of fuuro from the position of nakihai in `[]` and whether in `()`.
+        fn parse_fuuro_detail(
+            char_index: usize,
+            hai_in_mentsu: &[Hai],
+            claim: Option<usize>,
+            ankan: bool,
+        ) -> Result<FuuroDetail, String> {
+            let last = hai_in_mentsu.len() - 1;
+            let is_juntsu = hai_in_mentsu.iter().any(|hai| *hai != hai_in_mentsu[0]);
+            if ankan {
+                if hai_in_mentsu.len() != 4 {
+                    return Err(i18n::format(Message::InvalidAnkan, &[&char_index]));
+                }
+                return Ok(FuuroDetail {
+                    ankan: true,
+                    ..FuuroDetail::default()
+                });
+            }
+            match claim {
+                None => Ok(FuuroDetail::default()),
+                Some(position) if is_juntsu && position != 0 => {
+                    Err(i18n::format(Message::InvalidClaim, &[&char_index]))
+                }
+                Some(position) => Ok(FuuroDetail {
+                    nakihai: Some(hai_in_mentsu[position]),
+                    from: Some(match position {
+                        0 => Seat::Kamicha,
+                        _ if position == last => Seat::Shimocha,
+                        _ => Seat::Toimen,
+                    }),
+                    ankan: false,
+                }),
+            }
+        }
+
+        let chars: Vec<char> = string.chars().collect();
+        let mut juntehai = vec![];
+        let mut fuuro = vec![];
+        let mut fuuro_detail = vec![];
+        let mut char_stash: Vec<char> = vec![];
+        let mut hai_in_mentsu_stash: Vec<Hai> = vec![];
+        let mut in_mentsu = false;
+        let mut claim: Option<usize> = None;
+        let mut ankan = false;
+
+        for (index, chr) in chars.iter().copied().enumerate() {
+            if index > 0 && chars[index - 1] == ')' && chr != ']' {
+                return Err(i18n::format(Message::InvalidAnkan, &[&(index - 1)]));
+            }
+            match chr {
+                'm' | 'p' | 's' | 'z' => {
+                    if in_mentsu {
+                        handle_char_stash(
+                            chr,
+                            index,
+                            player_number,
+                            &mut char_stash,
+                            &mut hai_in_mentsu_stash,
+                        )?;
+                    } else {
+                        handle_char_stash(
+                            chr,
+                            index,
+                            player_number,
+                            &mut char_stash,
+                            &mut juntehai,
+                        )?;
+                    }
+                }
+                '1'..='9' => char_stash.push(chr),
+                '[' => {
+                    if in_mentsu {
+                        return Err(i18n::format(Message::SecondBracket, &[&index]));
+                    }
+                    if char_stash.len() > 0 {
+                        return Err(i18n::format(Message::MissingType, &[&'[', &index]));
+                    };
+                    in_mentsu = true;
+                }
+                ']' => {
+                    if !in_mentsu {
+                        return Err(i18n::format(Message::UnmatchedBracket, &[&index]));
+                    }
+                    if char_stash.len() > 0 {
+                        return Err(i18n::format(Message::MissingType, &[&']', &index]));
+                    };
+                    if ankan && chars[index - 1] != ')' {
+                        return Err(i18n::format(Message::InvalidAnkan, &[&index]));
+                    }
+                    if hai_in_mentsu_stash.len() > 0 {
+                        fuuro_detail.push(parse_fuuro_detail(
+                            index,
+                            &hai_in_mentsu_stash,
+                            claim,
+                            ankan,
+                        )?);
+                    }
+                    handle_hai_in_mentsu_stash(
+                        index,
+                        player_number,
+                        &mut hai_in_mentsu_stash,
+                        &mut fuuro,
+                    )?;
+                    in_mentsu = false;
+                    claim = None;
+                    ankan = false;
+                }
+                '\'' => {
+                    let position = hai_in_mentsu_stash.len() + char_stash.len();
+                    if !in_mentsu || ankan || claim.is_some() || position == 0 {
+                        return Err(i18n::format(Message::InvalidClaim, &[&index]));
+                    }
+                    match chars[index - 1] {
+                        '1'..='9' | 'm' | 'p' | 's' | 'z' => claim = Some(position - 1),
+                        _ => return Err(i18n::format(Message::InvalidClaim, &[&index])),
+                    }
+                }
+                '(' => {
+                    if !in_mentsu || chars[index - 1] != '[' {
+                        return Err(i18n::format(Message::InvalidAnkan, &[&index]));
+                    }
+                    ankan = true;
+                }
+                ')' => {
+                    if !ankan || char_stash.len() > 0 || claim.is_some() {
+                        return Err(i18n::format(Message::InvalidAnkan, &[&index]));
+                    }
+                }
+                // Ignore all spaces.
+                ' ' => (),
+                _ => {
+                    return Err(i18n::format(Message::UnknownCharacter, &[&chr, &index]));
+                }
+            }
+        }
+
+        if char_stash.len() > 0 {
+            return Err(i18n::format(
+                Message::NoTypeAtEnd,
+                &[&format!("{:?}", char_stash)],
+            ));
+        }
+
+        juntehai.sort();
+        let tehai = Self {
+            juntehai,
+            fuuro,
+            fuuro_detail,
+        };
+
+        // Each fuuro is one mentsu even if kantsu, taking 3 of 14 hai of a full tehai.
+        if !tehai.fuuro.is_empty() && tehai.juntehai.len() + 3 * tehai.fuuro.len() > 14 {
+            let most = 14usize.saturating_sub(3 * tehai.fuuro.len());
+            return Err(i18n::format(
+                Message::TooManyJuntehai,
+                &[&tehai.fuuro.len(), &most],
+            ));
+        }
+
+        match tehai.check_hai_number() {
+            Ok(_) => Ok(tehai),
+            Err(hai) => Err(i18n::format(Message::FifthHai, &[&hai])),
+        }
+    }
+
+    /// Analyze conditions of sutehai and machihai.
+    ///
+    /// # Return
+    /// * i32: the number of shanten.
+    /// * Vec<Condition>: all conditions of different sutehai.
+    pub fn analyze(
+        &self,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> Result<(i32, Vec<MachiCondition>), String> {
+        let (shanten, decomposers) = self.decompose(player_number)?;
+        let conditions_vec = self.conditions(shanten, &decomposers, player_number, situation)?;
+        Ok((shanten, conditions_vec))
+    }
+
+    /// Same as `analyze()`, but the conditions are left in `buffers`, whose vecs are reused
+    /// from the last call.
+    ///
+    /// # Return
+    /// * i32: the number of shanten, conditions are `buffers.conditions()`.
+    pub fn analyze_into(
+        &self,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+        buffers: &mut AnalysisBuffers,
+    ) -> Result<i32, String> {
+        if self.juntehai.len() % 3 != 2 {
+            return Err(format!(
+                "The number of hai on hand must be 3*k+2, \
+                such as 8, 11, 14, even 17, but {} provided.",
+                self.juntehai.len()
+            ));
+        }
+
+        let juntehai_number = self.juntehai.len();
+        let mut decompositions = self
+            .decompositions_with(
+                player_number,
+                std::mem::take(&mut buffers.stack),
+                std::mem::take(&mut buffers.arena),
+            )
+            .pruned();
+        let mut shanten = ((juntehai_number / 3) * 2) as i32;
+        buffers.decomposer_number = 0;
+        loop {
+            if buffers.decomposer_number == buffers.decomposers.len() {
+                buffers.decomposers.push(Decomposer::new());
+            }
+            let decomposer = &mut buffers.decomposers[buffers.decomposer_number];
+            if !decompositions.next_into(decomposer) {
+                break;
+            }
+            // Duplicates are kept, which change no condition.
+            let decomposer_shanten = decomposer.shanten(juntehai_number);
+            if decomposer_shanten < shanten {
+                shanten = decomposer_shanten;
+                buffers.decomposers.swap(0, buffers.decomposer_number);
+                buffers.decomposer_number = 1;
+            } else if decomposer_shanten == shanten {
+                buffers.decomposer_number += 1;
+            }
+        }
+        buffers.stack = std::mem::take(&mut decompositions.stack);
+        buffers.arena = std::mem::take(&mut decompositions.arena);
+
+        self.conditions_into(
+            shanten,
+            &buffers.decomposers[..buffers.decomposer_number],
+            player_number,
+            situation,
+            &mut buffers.conditions,
+        )?;
+        Ok(shanten)
+    }
+
+    /// Same as `analyze()`, also returning time of decomposition and evaluating machihai.
+    #[cfg(feature = "profiling")]
+    pub fn analyze_timed(
+        &self,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> Result<(i32, Vec<MachiCondition>, Timing), String> {
+        let start = std::time::Instant::now();
+        let (shanten, decomposers) = self.decompose(player_number)?;
+        let decomposition = start.elapsed();
+        let start = std::time::Instant::now();
+        let conditions_vec = self.conditions(shanten, &decomposers, player_number, situation)?;
+        let timing = Timing {
+            decomposition,
+            machi: start.elapsed(),
+            ..Timing::default()
+        };
+        Ok((shanten, conditions_vec, timing))
+    }
+
+    /// Same as `analyze()`, but decomposers stream through conditions instead of being
+    /// collected, so that memory is bounded however many decompositions tehai has, at the
+    /// cost of searching decompositions twice: once for the least shanten, and once for
+    /// decomposers of it.
+    pub fn analyze_bounded(
+        &self,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> Result<(i32, Vec<MachiCondition>), String> {
+        if self.juntehai.len() % 3 != 2 {
+            return Err(format!(
+                "The number of hai on hand must be 3*k+2, \
+                such as 8, 11, 14, even 17, but {} provided.",
+                self.juntehai.len()
+            ));
+        }
+
+        let juntehai_number = self.juntehai.len();
+        let shanten = self
+            .decompositions(player_number)
+            .pruned()
+            .map(|decomposer| decomposer.shanten(juntehai_number))
+            .min()
+            .unwrap_or(((juntehai_number / 3) * 2) as i32);
+        let mut decompositions = self.decompositions(player_number);
+        decompositions.least_shanten = Some(shanten);
+        let decomposers =
+            decompositions.filter(|decomposer| decomposer.shanten(juntehai_number) == shanten);
+        let conditions_vec = self.conditions(shanten, decomposers, player_number, situation)?;
+        Ok((shanten, conditions_vec))
+    }
+
+    /// Conditions of each sutehai from decomposers of the least shanten, sorted from the best.
+    ///
+    /// Decomposers are handled one by one, so they can be streamed. The same decomposer may
+    /// come more than once, which changes nothing.
+    fn conditions<I, D>(
+        &self,
+        shanten: i32,
+        decomposers: I,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> Result<Vec<MachiCondition>, String>
+    where
+        I: IntoIterator<Item = D>,
+        D: std::borrow::Borrow<Decomposer>,
+    {
+        let mut conditions_vec = vec![];
+        self.conditions_into(
+            shanten,
+            decomposers,
+            player_number,
+            situation,
+            &mut conditions_vec,
+        )?;
+        Ok(conditions_vec)
+    }
+
+    /// Same as `conditions()`, but into `conditions_vec` cleared first.
+    fn conditions_into<I, D>(
+        &self,
+        shanten: i32,
+        decomposers: I,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+        conditions_vec: &mut Vec<MachiCondition>,
+    ) -> Result<(), String>
+    where
+        I: IntoIterator<Item = D>,
+        D: std::borrow::Borrow<Decomposer>,
+    {
+        conditions_vec.clear();
+
+        if let i32::MIN..=-2 = shanten {
+            return Err("Logic Error: Shanten is less than -1.".to_string());
+        }
+
+        // Tenpai
+        if shanten == -1 {
+            return Ok(());
+        }
+
+        // A decomposer is handled by conditions of all hai it can discard, but only gives
+        // conditions of its ukihai.
+        let mut sutehai_set = HashSet::new();
+        let mut condition_map: BTreeMap<Hai, MachiCondition> = BTreeMap::new();
+        for decomposer in decomposers {
+            let decomposer = decomposer.borrow();
+            let mut handled_vec: Vec<Hai> = decomposer
+                .invalid_ukihai_vec
+                .iter()
+                .map(|ukihai| ukihai.0)
+                .collect();
+            sutehai_set.extend(handled_vec.iter().copied());
+            // Only chiitoitsu type can discard valid tiles but not ukihai.
+            if decomposer.hourakei == Hourakei::Chiitoitsu {
+                if decomposer.invalid_ukihai_vec.len() == 0 {
+                    for Ukihai(sutehai) in &decomposer.valid_ukihai_vec {
+                        sutehai_set.insert(*sutehai);
+                    }
+                }
+                handled_vec.extend(decomposer.valid_ukihai_vec.iter().map(|ukihai| ukihai.0));
+            }
+            handled_vec.sort();
+            handled_vec.dedup();
+            for sutehai in handled_vec {
+                condition_map
+                    .entry(sutehai)
+                    .or_insert_with(|| MachiCondition::new(sutehai))
+                    .handle(decomposer, self.juntehai.len(), player_number)?;
+            }
+        }
+        let unseen_counts = self.unseen_counts(player_number, situation);
+        for (sutehai, mut condition) in condition_map {
+            if !sutehai_set.contains(&sutehai) {
+                continue;
+            }
+            condition.finally(self, player_number, situation);
+            if shanten > 0 {
+                let mut counts = self.to_counts();
+                counts[sutehai.to_index()] -= 1;
+                condition.tenpai_draws =
+                    shanten::expected_tenpai_draws(&counts, self.fuuro.len(), &unseen_counts);
+            }
+            conditions_vec.push(condition);
+        }
+
+        // Keep conditions only waiting for dead hai, to tell the wait is dead.
+        conditions_vec.retain(|conditon| conditon.nokori() > 0 || conditon.dead_machihai.len() > 0);
+        // Breaking toitsu or koutsu of yakuhai loses yaku, which is worse when equal.
+        let yakuhai_type = match situation {
+            Some(situation) => situation.yakuhai_type(),
+            None => BTreeSet::new(),
+        };
+        let breaks_yakuhai = |sutehai: &Hai| {
+            yakuhai_type.contains(sutehai)
+                && self.juntehai.iter().filter(|hai| *hai == sutehai).count() >= 2
+        };
+        // Less expected draws until tenpai is faster, which is more realistic than nokori
+        // from 2 shanten.
+        let tenpai_draws = |condition: &MachiCondition| condition.tenpai_draws.unwrap_or(f64::MAX);
+        conditions_vec.sort_by(|lhs, rhs| {
+            tenpai_draws(lhs)
+                .partial_cmp(&tenpai_draws(rhs))
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(lhs.nokori().cmp(&rhs.nokori()).reverse())
+                .then(breaks_yakuhai(&lhs.sutehai).cmp(&breaks_yakuhai(&rhs.sutehai)))
+                .then(lhs.sutehai.cmp(&rhs.sutehai))
+        });
+
+        Ok(())
+    }
+
+    /// Analyze every type of hai in juntehai as sutehai, including those increasing shanten,
+    /// for ranking all choices rather than only the best ones.
+    ///
+    /// # Return
+    /// * Vec<(i32, MachiCondition)>: the number of shanten after each sutehai and its
+    /// condition, sorted from the best.
+    pub fn analyze_all_sutehai(
+        &self,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> Result<Vec<(i32, MachiCondition)>, String> {
+        if self.juntehai.len() % 3 != 2 {
+            return Err(format!(
+                "The number of hai on hand must be 3*k+2, \
+                such as 8, 11, 14, even 17, but {} provided.",
+                self.juntehai.len()
+            ));
+        }
+
+        // All decompositions are needed, since pruned ones may be the best for a sutehai.
+        let decomposers: Vec<Decomposer> = self.decompositions(player_number).collect();
+        let unseen_counts = self.unseen_counts(player_number, situation);
+        let sutehai_set: BTreeSet<Hai> = self.juntehai.iter().copied().collect();
+        let mut result_vec = vec![];
+        for sutehai in sutehai_set {
+            // Decomposers discarding the sutehai, same as those handled by the condition.
+            let discarding: Vec<&Decomposer> = decomposers
+                .iter()
+                .filter(|decomposer| {
+                    decomposer.invalid_ukihai_vec.contains(&Ukihai(sutehai))
+                        || decomposer.hourakei == Hourakei::Chiitoitsu
+                            && decomposer.valid_ukihai_vec.contains(&Ukihai(sutehai))
+                })
+                .collect();
+            let shanten = match discarding
+                .iter()
+                .map(|decomposer| decomposer.shanten(self.juntehai.len()))
+                .min()
+            {
+                Some(shanten) => shanten,
+                None => continue,
+            };
+
+            let mut condition = MachiCondition::new(sutehai);
+            for decomposer in discarding {
+                if decomposer.shanten(self.juntehai.len()) == shanten {
+                    condition.handle(decomposer, self.juntehai.len(), player_number)?;
+                }
+            }
+            condition.finally(self, player_number, situation);
+            if shanten > 0 {
+                let mut counts = self.to_counts();
+                counts[sutehai.to_index()] -= 1;
+                condition.tenpai_draws =
+                    shanten::expected_tenpai_draws(&counts, self.fuuro.len(), &unseen_counts);
+            }
+            result_vec.push((shanten, condition));
+        }
+
+        let tenpai_draws = |condition: &MachiCondition| condition.tenpai_draws.unwrap_or(f64::MAX);
+        result_vec.sort_by(|(lhs_shanten, lhs), (rhs_shanten, rhs)| {
+            lhs_shanten
+                .cmp(rhs_shanten)
+                .then(
+                    tenpai_draws(lhs)
+                        .partial_cmp(&tenpai_draws(rhs))
+                        .unwrap_or(std::cmp::Ordering::Equal),
+                )
+                .then(lhs.nokori().cmp(&rhs.nokori()).reverse())
+                .then(lhs.sutehai.cmp(&rhs.sutehai))
+        });
+        Ok(result_vec)
+    }
+
+    /// Number of each type of hai unseen, which are in haiyama, or all hai except tehai at
+    /// non-interactive mode.
+    fn unseen_counts(
+        &self,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) -> [u8; 34] {
+        match situation {
+            Some(situation) => situation.haiyama().to_counts(),
+            None => {
+                let mut unseen_counts = [0u8; 34];
+                for hai in Hai::all_type(player_number) {
+                    unseen_counts[hai.to_index()] = 4;
+                }
+                for hai in &self.juntehai {
+                    unseen_counts[hai.to_index()] -= 1;
+                }
+                for mentsu in &self.fuuro {
+                    for hai in mentsu.to_vec() {
+                        unseen_counts[hai.to_index()] =
+                            unseen_counts[hai.to_index()].saturating_sub(1);
+                    }
+                }
+                unseen_counts
+            }
+        }
+    }
+
+    /// Analyze kairyouhai of conditions returned by `analyze()`.
+    ///
+    /// Kairyouhai does not reduce shanten, but after drawing it and discarding properly,
+    /// tehai will wait for more hai than the condition does. Such as drawing 4m for
+    /// `13m` which will become `34m`.
+    ///
+    /// # Japanese
+    /// * kairyouhai: 改良牌
+    ///
+    /// # Return
+    /// * bool: `false` if cancelled, and only kairyouhai found before that are filled.
+    pub fn analyze_kairyou(
+        &self,
+        conditions: &mut Vec<MachiCondition>,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+        cancellation: Option<&Cancellation>,
+    ) -> Result<bool, String> {
+        let all_hai_type = Hai::all_type(player_number);
+        for condition in conditions.iter_mut() {
+            let mut tehai = self.clone();
+            tehai.discard(&condition.sutehai)?;
+
+            for hai in all_hai_type.iter() {
+                if let Some(cancellation) = cancellation {
+                    if cancellation.is_cancelled() {
+                        return Ok(false);
+                    }
+                }
+                if condition.machihai.contains_key(hai) {
+                    continue;
+                }
+                let nokori = match situation {
+                    Some(situation) => situation.haiyama()[hai],
+                    None => 4 - self.count(hai),
+                };
+                if nokori == 0 {
+                    continue;
+                }
+
+                let mut kairyou_tehai = tehai.clone();
+                kairyou_tehai.juntehai.push(*hai);
+                kairyou_tehai.juntehai.sort();
+                let (_, kairyou_conditions) = match situation {
+                    // Drawn hai is no longer in haiyama.
+                    Some(situation) => {
+                        let situation = situation.after_draw(*hai)?;
+                        kairyou_tehai.analyze(player_number, Some(&*situation))?
+                    }
+                    None => kairyou_tehai.analyze(player_number, None)?,
+                };
+
+                if let Some(best) = kairyou_conditions.first() {
+                    if best.nokori() > condition.nokori() {
+                        condition.kairyouhai.insert(*hai, nokori);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Count a type of hai in both juntehai and fuuro.
+    pub fn count(&self, hai: &Hai) -> u8 {
+        let mut count = 0;
+        for item in &self.juntehai {
+            if item == hai {
+                count += 1;
+            }
+        }
+        for mentsu in &self.fuuro {
+            match mentsu {
+                Mentsu::Juntsu(a, b, c) => {
+                    for item in vec![a, b, c] {
+                        if item == hai {
+                            count += 1;
+                        }
+                    }
+                }
+                Mentsu::Koutsu(item) => {
+                    if item == hai {
+                        count += 3;
+                    }
+                }
+                Mentsu::Kantsu(item) => {
+                    if item == hai {
+                        count += 4;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Discard a hai from juntehai.
+    pub fn discard(&mut self, hai: &Hai) -> Result<(), String> {
+        let mut index = None;
+        for (i, item) in self.juntehai.iter().enumerate() {
+            if item == hai {
+                index = Some(i);
+                break;
+            }
+        }
+        match index {
+            Some(index) => {
+                self.juntehai.remove(index);
+                Ok(())
+            }
+            None => return Err(i18n::format(Message::NotEnoughToDiscard, &[&hai])),
+        }
+    }
+
+    /// Chii, for an example, 23m catch 4m.
+    pub fn chii(&mut self, juntsu: &Mentsu, nakihai: &Hai) -> Result<(), String> {
+        if let Mentsu::Juntsu(a, b, c) = juntsu {
+            let backup = self.clone();
+            for hai in vec![a, b, c] {
+                if hai == nakihai {
+                    continue;
+                }
+                if let Err(error) = self.discard(hai) {
+                    *self = backup;
+                    return Err(error);
+                }
+            }
+            self.fuuro.push(*juntsu);
+            self.fuuro_detail.push(FuuroDetail {
+                nakihai: Some(*nakihai),
+                from: Some(Seat::Kamicha),
+                ankan: false,
+            });
+            Ok(())
+        } else {
+            Err("Logic error: Tehai::chii() can only accept Mentsu::Juntsu.".to_string())
+        }
+    }
+
+    /// Pon, for an example, 22m catch 2m.
+    pub fn pon(&mut self, koutsu: &Mentsu) -> Result<(), String> {
+        if let Mentsu::Koutsu(hai) = koutsu {
+            let backup = self.clone();
+            for _ in 0..2 {
+                if let Err(error) = self.discard(hai) {
+                    *self = backup;
+                    return Err(error);
+                }
+            }
+            self.fuuro.push(*koutsu);
+            self.fuuro_detail.push(FuuroDetail {
+                nakihai: Some(*hai),
+                ..FuuroDetail::default()
+            });
+            Ok(())
+        } else {
+            Err("Logic error: Tehai::pon() can only accept Mentsu::Koutsu.".to_string())
+        }
+    }
+
+    /// Kan, for an example, 222m catch 2m.
+    pub fn kan(&mut self, kantsu: &Mentsu, rinshanhai: &Option<Hai>) -> Result<Kan, String> {
+        if let Mentsu::Kantsu(hai) = kantsu {
+            let mut hai_num = 0;
+            let mut exist_koutsu = false;
+            let mut exist_koutsu_index = 0;
+            for i in &self.juntehai {
+                if i == hai {
+                    hai_num += 1;
+                }
+            }
+            for (index, mentsu) in self.fuuro.iter().enumerate() {
+                if let Mentsu::Koutsu(i) = mentsu {
+                    if i == hai {
+                        exist_koutsu = true;
+                        exist_koutsu_index = index;
+                        break;
+                    }
+                }
+            }
+            let kan;
+            // Kakan or Ankan
+            if self.juntehai.len() % 3 == 2 {
+                if hai_num == 1 && exist_koutsu {
+                    // Undoubtedly exist. Ignore the error.
+                    self.discard(hai)?;
+                    self.fuuro[exist_koutsu_index] = *kantsu;
+                    kan = Kan::Kakan {
+                        kantsu: *kantsu,
+                        rinshanhai: *rinshanhai,
+                    }
+                } else if hai_num == 4 && !exist_koutsu {
+                    for _ in 0..4 {
+                        // Undoubtedly exist. Ignore the error.
+                        self.discard(hai)?;
+                    }
+                    self.fuuro.push(kantsu.clone());
+                    self.fuuro_detail.push(FuuroDetail {
+                        ankan: true,
+                        ..FuuroDetail::default()
+                    });
+                    kan = Kan::Ankan {
+                        kantsu: *kantsu,
+                        rinshanhai: rinshanhai.clone(),
+                    }
+                } else {
+                    return Err("Logic error: Tehai currently is not able to kan.".to_string());
+                }
+            }
+            // Daiminkan
+            else if self.juntehai.len() % 3 == 1 {
+                if hai_num == 3 && !exist_koutsu {
+                    for _ in 0..3 {
+                        // Undoubtedly exist. Ignore the error.
+                        self.discard(hai)?;
+                    }
+                    self.fuuro.push(kantsu.clone());
+                    self.fuuro_detail.push(FuuroDetail {
+                        nakihai: Some(*hai),
+                        ..FuuroDetail::default()
+                    });
+                    kan = Kan::Daiminkan {
+                        kantsu: *kantsu,
+                        rinshanhai: rinshanhai.clone(),
+                    }
+                } else {
+                    return Err("Logic error: Tehai currently is not able to kan.".to_string());
+                }
+            } else {
+                return Err("Logic error: Tehai currently is not able to kan.".to_string());
+            }
+
+            // Deal with rinshanhai
+            if let Some(rinshanhai) = rinshanhai {
+                self.juntehai.push(*rinshanhai);
+                self.juntehai.sort();
+            }
+            Ok(kan)
+        } else {
+            Err("Logic error: Tehai::kan() can only accept Mentsu::Kantsu.".to_string())
+        }
+    }
+
+    /// Undo a operation chii.
+    pub fn de_chii(&mut self, juntsu: &Mentsu, nakihai: &Hai) -> Result<(), String> {
+        if let Mentsu::Juntsu(a, b, c) = juntsu {
+            let mut index = None;
+            for (i, mentsu) in self.fuuro.iter().enumerate() {
+                if mentsu == juntsu {
+                    index = Some(i);
+                    break;
+                }
+            }
+
+            let index = index.ok_or("Logic error: can not find juntsu in fuuro.".to_string())?;
+            self.remove_fuuro(index);
+            for hai in vec![a, b, c] {
+                if hai != nakihai {
+                    self.juntehai.push(*hai);
+                }
+            }
+            self.juntehai.sort();
+            Ok(())
+        } else {
+            Err("Logic error: Tehai::de_chii() can only accept Mentsu::Juntsu.".to_string())
+        }
+    }
+
+    /// Undo a operation pon.
+    pub fn de_pon(&mut self, koutsu: &Mentsu) -> Result<(), String> {
+        if let Mentsu::Koutsu(hai) = koutsu {
+            let mut index = None;
+            for (i, mentsu) in self.fuuro.iter().enumerate() {
+                if mentsu == koutsu {
+                    index = Some(i);
+                    break;
+                }
+            }
+
+            let index = index.ok_or("Logic error: can not find koutsu in fuuro.".to_string())?;
+            self.remove_fuuro(index);
+            for _ in 0..2 {
+                self.juntehai.push(*hai);
+            }
+            self.juntehai.sort();
+            Ok(())
+        } else {
+            Err("Logic error: Tehai::de_pon() can only accept Mentsu::Koutsu.".to_string())
+        }
+    }
+
+    /// Undo a operation kan.
+    pub fn de_kan(&mut self, kan: &Kan) -> Result<(), String> {
+        fn discard_kantsu(tehai: &mut Tehai, kantsu: &Mentsu) -> Result<FuuroDetail, String> {
+            let mut index = None;
+            for (i, mentsu) in tehai.fuuro.iter().enumerate() {
+                if mentsu == kantsu {
+                    index = Some(i);
+                    break;
+                }
+            }
+
+            let index = index.ok_or("Logic error: can not find kantsu in fuuro.".to_string())?;
+            Ok(tehai.remove_fuuro(index))
+        }
+
+        let backup = self.clone();
+        let rinshanhai = match kan {
+            Kan::Daiminkan { kantsu, rinshanhai } => {
+                if let Mentsu::Kantsu(hai) = kantsu {
+                    discard_kantsu(self, kantsu)?;
+                    for _ in 0..3 {
+                        self.juntehai.push(*hai);
+                    }
+                } else {
+                    return Err(
+                        "Logic error: interaction::Kan can only include Kantsu.".to_string()
+                    );
+                }
+                rinshanhai
+            }
+            Kan::Ankan { kantsu, rinshanhai } => {
+                if let Mentsu::Kantsu(hai) = kantsu {
+                    discard_kantsu(self, kantsu)?;
+                    for _ in 0..4 {
+                        self.juntehai.push(*hai);
+                    }
+                } else {
+                    return Err(
+                        "Logic error: interaction::Kan can only include Kantsu.".to_string()
+                    );
+                }
+                rinshanhai
+            }
+            Kan::Kakan { kantsu, rinshanhai } => {
+                if let Mentsu::Kantsu(hai) = kantsu {
+                    let detail = discard_kantsu(self, kantsu)?;
+                    self.fuuro.push(Mentsu::Koutsu(*hai));
+                    self.fuuro_detail.push(detail);
+                    self.juntehai.push(*hai);
+                } else {
+                    return Err(
+                        "Logic error: interaction::Kan can only include Kantsu.".to_string()
+                    );
+                }
+                rinshanhai
+            }
+            _ => {
+                return Err("Logic error: Tehai::de_kan() can not accept Kan::Unknown.".to_string())
+            }
+        };
+        if let Some(rinshanhai) = rinshanhai {
+            if let Err(error) = self.discard(rinshanhai) {
+                *self = backup;
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a fuuro with its detail, returning the detail.
+    fn remove_fuuro(&mut self, index: usize) -> FuuroDetail {
+        self.fuuro.remove(index);
+        if index < self.fuuro_detail.len() {
+            self.fuuro_detail.remove(index)
+        } else {
+            FuuroDetail::default()
+        }
+    }
+
+    /// Detail of the fuuro at `index`, default if not recorded.
+    pub fn fuuro_detail(&self, index: usize) -> FuuroDetail {
+        self.fuuro_detail.get(index).copied().unwrap_or_default()
+    }
+
+    /// Whether tehai is menzen, i.e. without fuuro other than ankan.
+    ///
+    /// # Japanese
+    /// * menzen: 門前
+    pub fn is_menzen(&self) -> bool {
+        (0..self.fuuro.len()).all(|index| self.fuuro_detail(index).ankan)
+    }
+
+    /// Create tehai without fuuro from 34-index representation of juntehai.
+    pub fn from_counts(counts: &[u8; 34], player_number: PlayerNumber) -> Result<Self, String> {
+        let juntehai = Hai::from_counts(counts);
+        for hai in &juntehai {
+            if !hai.is_valid(player_number) {
+                return Err(i18n::format(Message::InvalidHai, &[&hai]));
+            }
+            if counts[hai.to_index()] > 4 {
+                return Err(format!("Fifth {} found.", hai.to_string()));
+            }
+        }
+
+        Ok(Self {
+            juntehai,
+            fuuro: vec![],
+            fuuro_detail: vec![],
+        })
+    }
+
+    /// Print juntehai to 34-index representation. Fuuro is not included.
+    pub fn to_counts(&self) -> [u8; 34] {
+        Hai::to_counts(self.juntehai.iter())
+    }
+
+    /// Create tehai without fuuro from tile IDs of juntehai in 136-tile representation.
+    /// Red 5 is regarded as normal 5.
+    pub fn from_ids(id_vec: &[u8], player_number: PlayerNumber) -> Result<Self, String> {
+        let mut counts = [0; 34];
+        let mut id_set = HashSet::new();
+        for id in id_vec {
+            let hai = Hai::from_id(*id).ok_or(format!("'{}' is invalid tile ID.", id))?;
+            if !id_set.insert(*id) {
+                return Err(format!("Tile ID '{}' is used more than once.", id));
+            }
+            counts[hai.to_index()] += 1;
+        }
+
+        Self::from_counts(&counts, player_number)
+    }
+
+    /// Print juntehai to tile IDs in 136-tile representation. Fuuro is not included.
+    pub fn to_ids(&self) -> Result<Vec<u8>, String> {
+        Hai::to_ids(self.juntehai.iter())
+    }
+
+    /// Create tehai from a hand analysis URL of tenhou, like `https://tenhou.net/2/?q=123m456p789s11z`.
+    ///
+    /// Scheme and host can be omitted, and `0` (red 5) is regarded as normal 5.
+    pub fn from_tenhou_url(url: &str, player_number: PlayerNumber) -> Result<Self, String> {
+        let query_start = url
+            .find("?q=")
+            .or(url.find("&q="))
+            .ok_or(format!("No hand found in tenhou URL '{}'.", url))?;
+        let query = &url[query_start + 3..];
+        let query_end = query
+            .find(|chr| chr == '&' || chr == '#')
+            .unwrap_or(query.len());
+
+        Self::new(query[..query_end].replace('0', "5"), player_number)
+    }
+
+    /// Print self to a hand analysis URL of tenhou.
+    ///
+    /// **Note**: Tenhou cannot analyze fuuro, so only tehai without fuuro is supported.
+    pub fn to_tenhou_url(&self) -> Result<String, String> {
+        if self.fuuro.len() != 0 {
+            return Err("Tenhou hand analysis does not support fuuro.".to_string());
+        }
+        Ok(format!(
+            "https://tenhou.net/2/?q={}",
+            compact_hai_string(&self.juntehai)
+        ))
+    }
+
+    /// Canonical representation with juntehai sorted and fuuro sorted, ignoring the order of
+    /// drawing and calling, so that the same hand always has the same representation.
+    pub fn normalize(&self) -> Self {
+        let mut tehai = self.clone();
+        tehai.juntehai.sort();
+        // Sort details together with fuuro, keeping them at the same index.
+        let mut fuuro_vec: Vec<(Mentsu, FuuroDetail)> = (0..self.fuuro.len())
+            .map(|index| (self.fuuro[index], self.fuuro_detail(index)))
+            .collect();
+        fuuro_vec.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+        tehai.fuuro = fuuro_vec.iter().map(|(mentsu, _)| *mentsu).collect();
+        tehai.fuuro_detail = fuuro_vec.iter().map(|(_, detail)| *detail).collect();
+        tehai
+    }
+
+    /// Zobrist hash of juntehai and fuuro in any order, see `zobrist`. Callers moving hai
+    /// one by one should update the hash by keys instead.
+    pub fn zobrist(&self) -> u64 {
+        zobrist::hash_counts(&self.to_counts(), &zobrist::TEHAI_KEYS)
+            ^ zobrist::hash_fuuro(&self.fuuro)
+    }
+
+    /// Print self with given format, like `println!("{}", tehai.display(format))`.
+    pub fn display(&self, format: TehaiFormat) -> TehaiDisplay<'_> {
+        TehaiDisplay {
+            tehai: self,
+            format,
+        }
+    }
+
+    /// Print fuuro with given format separated by spaces, like `456p 777z 5555s(暗)` if
+    /// compact. Ankan is marked by `fuuro_detail` instead of `format.ankan`.
+    pub fn fuuro_display(&self, format: MentsuFormat) -> String {
+        self.fuuro
+            .iter()
+            .enumerate()
+            .map(|(index, mentsu)| {
+                let format = MentsuFormat {
+                    ankan: self.fuuro_detail(index).ankan,
+                    ..format
+                };
+                mentsu.display(format).to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Print self to json.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut juntehai_string_vec = vec![];
+        for hai in &self.juntehai {
+            juntehai_string_vec.push(hai.to_string());
+        }
+        let mut fuuro_json_vec = vec![];
+        let mut fuuro_detail_json_vec = vec![];
+        for (index, mentsu) in self.fuuro.iter().enumerate() {
+            let detail = self.fuuro_detail(index);
+            fuuro_json_vec.push(mentsu.to_json());
+            fuuro_detail_json_vec.push(json!({
+                "nakihai": detail.nakihai.map(|hai| hai.to_string()),
+                "from": detail.from.map(|seat| seat.name()),
+                "ankan": detail.ankan,
+            }));
+        }
+        json!({
+           "juntehai": juntehai_string_vec,
+           "fuuro": fuuro_json_vec,
+           "fuuro_detail": fuuro_detail_json_vec
+        })
+    }
+
+    /// Decompose self to a vec of Decomposer.
+    ///
+    /// # Return
+    /// * The `i32` data is the minimum shanten.
+    /// * The `HashSet<Decomposer>` data is all decomposers that thier shanten are minimum one.
+    fn decompose(&self, player_number: PlayerNumber) -> Result<(i32, HashSet<Decomposer>), String> {
+        // Only work for 3*k+2 juntehai.
+        if self.juntehai.len() % 3 != 2 {
+            return Err(format!(
+                "The number of hai on hand must be 3*k+2, \
+                such as 8, 11, 14, even 17, but {} provided.",
+                self.juntehai.len()
+            ));
+        }
+
+        let mut min_shanten = ((self.juntehai.len() / 3) * 2) as i32;
+        let mut min_shanten_decomposers = HashSet::new();
+
+        let mut push_into_decomposers = |decomposer: Decomposer| {
+            if decomposer.shanten(self.juntehai.len()) == min_shanten {
+                min_shanten_decomposers.insert(decomposer);
+            } else if decomposer.shanten(self.juntehai.len()) < min_shanten {
+                min_shanten = decomposer.shanten(self.juntehai.len());
+                min_shanten_decomposers.clear();
+                min_shanten_decomposers.insert(decomposer);
+            }
+        };
+
+        for decomposer in self.decompositions(player_number).pruned() {
+            push_into_decomposers(decomposer);
+        }
+
+        Ok((min_shanten, min_shanten_decomposers))
+    }
+
+    /// Iterate all decompositions of self lazily, so that callers can stop early, such as
+    /// at the first winning shape. Decompositions of mentsute come first, followed by
+    /// chiitoitsu and kokushimusou if juntehai is 14 without fuuro.
+    pub fn decompositions(&self, player_number: PlayerNumber) -> Decompositions {
+        self.decompositions_with(player_number, vec![], vec![])
+    }
+
+    /// Same as `decompositions()`, searching in given buffers which are cleared first.
+    fn decompositions_with(
+        &self,
+        player_number: PlayerNumber,
+        mut stack: Vec<Branch>,
+        mut arena: Vec<(Part, Option<usize>)>,
+    ) -> Decompositions {
+        // Popped from the end.
+        let mut rest = vec![];
+        if self.juntehai.len() == 14 && self.fuuro.len() == 0 {
+            let mut juntehai = self.juntehai.clone();
+            juntehai.sort();
+            rest.push(kokushimusou_decomposer(&juntehai));
+            rest.push(chiitoitsu_decomposer(&juntehai));
+        }
+        stack.clear();
+        stack.push(Branch {
+            rest: RestHai::new(&self.juntehai),
+            last: None,
+            mentsu_number: 0,
+            block_number: 0,
+        });
+        arena.clear();
+        Decompositions {
+            juntehai_number: self.juntehai.len(),
+            stack,
+            arena,
+            rest,
+            player_number,
+            least_shanten: None,
+        }
+    }
+
+    fn check_hai_number(&self) -> Result<(), Hai> {
+        let mut tehai_map: HashMap<Hai, u8> = HashMap::new();
+
+        let mut check_count = |hai| -> bool {
+            if tehai_map.contains_key(hai) {
+                let count = tehai_map[hai] + 1;
+                if count > 4 {
+                    return false;
+                }
+                tehai_map.insert(*hai, count);
+            } else {
+                tehai_map.insert(*hai, 1);
+            }
+            return true;
+        };
+
+        for hai in self.juntehai.iter() {
+            if !check_count(hai) {
+                return Err(*hai);
+            }
+        }
+        for mentsu in self.fuuro.iter() {
+            match mentsu {
+                Mentsu::Juntsu(a, b, c) => {
+                    for hai in vec![a, b, c] {
+                        if !check_count(hai) {
+                            return Err(*hai);
+                        }
+                    }
+                }
+                Mentsu::Koutsu(hai) => {
+                    for _ in 0..3 {
+                        if !check_count(hai) {
+                            return Err(*hai);
+                        }
+                    }
+                }
+                Mentsu::Kantsu(hai) => {
+                    for _ in 0..4 {
+                        if !check_count(hai) {
+                            return Err(*hai);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PartialEq for Tehai {
+    fn eq(&self, other: &Self) -> bool {
+        let lhs = self.normalize();
+        let rhs = other.normalize();
+        lhs.juntehai == rhs.juntehai && lhs.fuuro == rhs.fuuro
+    }
+}
+
+impl Eq for Tehai {}
+
+impl Hash for Tehai {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let tehai = self.normalize();
+        tehai.juntehai.hash(state);
+        tehai.fuuro.hash(state);
+    }
+}
+
+// Parts are compared and hashed in any order without sorting copies, since decomposers are
+// deduplicated in hot loops of analysis.
+impl PartialEq for Decomposer {
+    fn eq(&self, other: &Self) -> bool {
+        self.hourakei == other.hourakei
+            && same_parts(&self.mentsu_vec, &other.mentsu_vec)
+            && same_parts(&self.toitsu_vec, &other.toitsu_vec)
+            && same_parts(&self.taatsu_vec, &other.taatsu_vec)
+            && same_parts(&self.valid_ukihai_vec, &other.valid_ukihai_vec)
+            && same_parts(&self.invalid_ukihai_vec, &other.invalid_ukihai_vec)
+    }
+}
+
+impl Eq for Decomposer {}
+
+impl Hash for Decomposer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_parts(&self.mentsu_vec, state);
+        hash_parts(&self.toitsu_vec, state);
+        hash_parts(&self.taatsu_vec, state);
+        hash_parts(&self.valid_ukihai_vec, state);
+        hash_parts(&self.invalid_ukihai_vec, state);
+        self.hourakei.hash(state);
+    }
+}
+
+impl std::fmt::Display for Tehai {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display(TehaiFormat::default()).fmt(f)
+    }
+}
+
+impl std::fmt::Display for TehaiDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hai_string = |hai_vec: &[Hai]| {
+            if self.format.compact {
+                compact_hai_string(hai_vec)
+            } else {
+                let mut hai_string = String::new();
+                for hai in hai_vec {
+                    hai_string += &hai.to_string();
+                }
+                hai_string
+            }
+        };
+
+        let mut juntehai = self.tehai.juntehai.as_slice();
+        let mut tsumohai = None;
+        if self.format.separate_tsumo && juntehai.len() % 3 == 2 {
+            tsumohai = juntehai.last();
+            juntehai = &juntehai[..juntehai.len() - 1];
+        }
+        let mut format_string = hai_string(juntehai);
+        if let Some(tsumohai) = tsumohai {
+            format_string += " ";
+            format_string += &tsumohai.to_string();
+        }
+
+        for (index, mentsu) in self.tehai.fuuro.iter().enumerate() {
+            let detail = self.tehai.fuuro_detail(index);
+            let call = match mentsu {
+                Mentsu::Juntsu(..) => "チー",
+                Mentsu::Koutsu(_) => "ポン",
+                Mentsu::Kantsu(_) if detail.ankan => "暗カン",
+                Mentsu::Kantsu(_) => "カン",
+            };
+            if self.format.call_marker {
+                format_string += call;
+            }
+            format_string += &format!("[{}]", fuuro_string(mentsu, detail, self.format.compact));
+        }
+
+        write!(f, "{}", format_string)
+    }
+}
+
+impl Decomposer {
+    fn new() -> Self {
+        Self {
+            mentsu_vec: vec![],
+            toitsu_vec: vec![],
+            taatsu_vec: vec![],
+            valid_ukihai_vec: vec![],
+            invalid_ukihai_vec: vec![],
+            hourakei: Hourakei::Mentsute,
+        }
+    }
+
+    /// Copy with parts of each kind sorted, which is the canonical form compared.
+    pub fn normalize(&self) -> Self {
+        let mut decomposer = self.clone();
+        decomposer.mentsu_vec.sort();
+        decomposer.toitsu_vec.sort();
+        decomposer.taatsu_vec.sort();
+        decomposer.valid_ukihai_vec.sort();
+        decomposer.invalid_ukihai_vec.sort();
+        decomposer
+    }
+
+    pub fn mentsu_vec(&self) -> &Vec<Mentsu> {
+        &self.mentsu_vec
+    }
+
+    pub fn toitsu_vec(&self) -> &Vec<Toitsu> {
+        &self.toitsu_vec
+    }
+
+    pub fn taatsu_vec(&self) -> &Vec<Taatsu> {
+        &self.taatsu_vec
+    }
+
+    pub fn valid_ukihai_vec(&self) -> &Vec<Ukihai> {
+        &self.valid_ukihai_vec
+    }
+
+    pub fn invalid_ukihai_vec(&self) -> &Vec<Ukihai> {
+        &self.invalid_ukihai_vec
+    }
+
+    pub fn hourakei(&self) -> Hourakei {
+        self.hourakei
+    }
+
+    /// All hai in the decomposer, which are juntehai of the tehai decomposed.
+    pub fn to_hai_vec(&self) -> Vec<Hai> {
+        let mut hai_vec = vec![];
+        for mentsu in &self.mentsu_vec {
+            hai_vec.extend(mentsu.to_vec());
+        }
+        for toitsu in &self.toitsu_vec {
+            hai_vec.extend(vec![toitsu.0; 2]);
+        }
+        for taatsu in &self.taatsu_vec {
+            hai_vec.extend(vec![taatsu.0, taatsu.1]);
+        }
+        for ukihai in self
+            .valid_ukihai_vec
+            .iter()
+            .chain(self.invalid_ukihai_vec.iter())
+        {
+            hai_vec.push(ukihai.0);
+        }
+        hai_vec
+    }
+
+    /// Shanten of the decomposer, -1 if it is a winning shape.
+    pub fn shanten_number(&self) -> i32 {
+        self.shanten(self.to_hai_vec().len())
+    }
+
+    /// Calculate shanten for current decompser.
+    ///
+    /// # Japanese
+    /// * shanten: 向聴
+    fn shanten(&self, juntehai_number: usize) -> i32 {
+        match self.hourakei {
+            Hourakei::Mentsute => {
+                let mut toitsu_set = HashSet::new();
+                for toitsu in self.toitsu_vec.iter() {
+                    toitsu_set.insert(toitsu);
+                }
+                if toitsu_set.len() != self.toitsu_vec.len() {
+                    return 13;
+                }
+
+                let max_mentsu_toitsu_taatsu = max_mentsu_toitsu_taatsu(juntehai_number);
+                let taatsu_num = std::cmp::min(
+                    max_mentsu_toitsu_taatsu - 1 - self.mentsu_vec.len(),
+                    self.taatsu_vec.len(),
+                );
+                let toitsu_num = std::cmp::min(
+                    max_mentsu_toitsu_taatsu - self.mentsu_vec.len() - taatsu_num,
+                    self.toitsu_vec.len(),
+                );
+
+                ((juntehai_number / 3) * 2) as i32
+                    - 2 * self.mentsu_vec.len() as i32
+                    - toitsu_num as i32
+                    - taatsu_num as i32
+            }
+            Hourakei::Chiitoitsu => {
+                13 - 2 * self.toitsu_vec.len() as i32
+                    - std::cmp::min(self.valid_ukihai_vec.len(), 7 - self.toitsu_vec.len()) as i32
+            }
+            Hourakei::Kokushimusou => 13 - self.valid_ukihai_vec.len() as i32,
+        }
+    }
+}
+
+impl Decompositions {
+    /// Skip decompositions of mentsute which cannot reach the least shanten found so far,
+    /// so that only decompositions of the least shanten and a few before them are yielded.
+    ///
+    /// The least shanten starts from chiitoitsu and kokushimusou, and a branch is pruned if
+    /// its bound is more, where the rest `n` juntehai reduce shanten by at most `2n/3` as
+    /// mentsu reduce 2 by 3 hai.
+    pub fn pruned(mut self) -> Self {
+        let juntehai_number = self.juntehai_number;
+        self.least_shanten = Some(
+            self.rest
+                .iter()
+                .map(|decomposer| decomposer.shanten(juntehai_number))
+                .min()
+                .unwrap_or(13),
+        );
+        self
+    }
+
+    /// Least shanten which the branch can reach with the rest juntehai.
+    fn bound(&self, branch: &Branch) -> i32 {
+        let max_mentsu_toitsu_taatsu = max_mentsu_toitsu_taatsu(self.juntehai_number);
+        let block_num = std::cmp::min(
+            max_mentsu_toitsu_taatsu.saturating_sub(branch.mentsu_number),
+            branch.block_number,
+        );
+        ((self.juntehai_number / 3) * 2) as i32
+            - 2 * branch.mentsu_number as i32
+            - block_num as i32
+            - (branch.rest.len * 2 / 3) as i32
+    }
+
+    /// Build the decomposer of a branch at the end into `decomposer`, from its path of parts
+    /// in the arena, overwriting parts of the decomposer.
+    fn decomposer_into(&self, branch: &Branch, decomposer: &mut Decomposer) {
+        decomposer.mentsu_vec.clear();
+        decomposer.toitsu_vec.clear();
+        decomposer.taatsu_vec.clear();
+        decomposer.valid_ukihai_vec.clear();
+        decomposer.invalid_ukihai_vec.clear();
+        let mut cursor = branch.last;
+        while let Some(index) = cursor {
+            let (part, parent) = self.arena[index];
+            match part {
+                Part::Mentsu(mentsu) => decomposer.mentsu_vec.push(mentsu),
+                Part::Toitsu(toitsu) => decomposer.toitsu_vec.push(toitsu),
+                Part::Taatsu(taatsu) => decomposer.taatsu_vec.push(taatsu),
+                Part::Ukihai(ukihai) => decomposer.invalid_ukihai_vec.push(ukihai),
+            }
+            cursor = parent;
+        }
+        // Parts are walked from the last.
+        decomposer.mentsu_vec.reverse();
+        decomposer.toitsu_vec.reverse();
+        decomposer.taatsu_vec.reverse();
+        decomposer.invalid_ukihai_vec.reverse();
+        if let Some(hai) = branch.rest.first() {
+            decomposer.invalid_ukihai_vec.push(Ukihai(hai));
+        }
+        decomposer.hourakei = Hourakei::Mentsute;
+    }
+
+    /// Same as `next()`, but into `decomposer`, whose parts are overwritten.
+    ///
+    /// # Return
+    /// * bool: `false` if no decomposition is left, when `decomposer` is unspecified.
+    fn next_into(&mut self, decomposer: &mut Decomposer) -> bool {
+        while let Some(branch) = self.stack.pop() {
+            if let Some(least_shanten) = self.least_shanten {
+                if self.bound(&branch) > least_shanten {
+                    continue;
+                }
+            }
+            if branch.rest.len <= 1 {
+                self.decomposer_into(&branch, decomposer);
+                if let Some(least_shanten) = &mut self.least_shanten {
+                    let shanten = decomposer.shanten(self.juntehai_number);
+                    if shanten > *least_shanten {
+                        continue;
+                    }
+                    *least_shanten = shanten;
+                }
+                return true;
+            }
+            // Parts after the last one any branch refers to are of branches done, so the arena
+            // stays as small as the stack.
+            let live = self
+                .stack
+                .iter()
+                .chain(std::iter::once(&branch))
+                .filter_map(|branch| branch.last)
+                .max();
+            self.arena.truncate(live.map_or(0, |last| last + 1));
+            let start = self.stack.len();
+            let (stack, arena) = (&mut self.stack, &mut self.arena);
+            split(&branch.rest, self.player_number, |part, rest| {
+                let (mentsu_number, block_number) = match part {
+                    Part::Mentsu(_) => (branch.mentsu_number + 1, branch.block_number),
+                    Part::Toitsu(_) | Part::Taatsu(_) => {
+                        (branch.mentsu_number, branch.block_number + 1)
+                    }
+                    Part::Ukihai(_) => (branch.mentsu_number, branch.block_number),
+                };
+                arena.push((part, branch.last));
+                stack.push(Branch {
+                    rest,
+                    last: Some(arena.len() - 1),
+                    mentsu_number,
+                    block_number,
+                });
+            });
+            // Reversed to split in the same order as children.
+            self.stack[start..].reverse();
+        }
+        match self.rest.pop() {
+            Some(rest) => {
+                *decomposer = rest;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Iterator for Decompositions {
+    type Item = Decomposer;
+
+    fn next(&mut self) -> Option<Decomposer> {
+        let mut decomposer = Decomposer::new();
+        if self.next_into(&mut decomposer) {
+            Some(decomposer)
+        } else {
+            None
+        }
+    }
+}
+
+impl AnalysisBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Conditions of the last `Tehai::analyze_into()`, the same as `Tehai::analyze()`.
+    pub fn conditions(&self) -> &[MachiCondition] {
+        &self.conditions
+    }
+}
+
+impl DecomposerBuilder {
+    pub fn new(hourakei: Hourakei) -> Self {
+        Self {
+            decomposer: Decomposer {
+                hourakei,
+                ..Decomposer::new()
+            },
+        }
+    }
+
+    pub fn mentsu(mut self, mentsu: Mentsu) -> Self {
+        self.decomposer.mentsu_vec.push(mentsu);
+        self
+    }
+
+    pub fn toitsu(mut self, toitsu: Toitsu) -> Self {
+        self.decomposer.toitsu_vec.push(toitsu);
+        self
+    }
+
+    pub fn taatsu(mut self, taatsu: Taatsu) -> Self {
+        self.decomposer.taatsu_vec.push(taatsu);
+        self
+    }
+
+    /// Add an ukihai, which is valid if it can reduce shanten, such as any yaochuupai in
+    /// kokushimusou.
+    pub fn ukihai(mut self, ukihai: Ukihai, valid: bool) -> Self {
+        if valid {
+            self.decomposer.valid_ukihai_vec.push(ukihai);
+        } else {
+            self.decomposer.invalid_ukihai_vec.push(ukihai);
+        }
+        self
+    }
+
+    /// Check the parts and build the decomposer.
+    pub fn build(self) -> Result<Decomposer, String> {
+        let decomposer = self.decomposer;
+        for taatsu in &decomposer.taatsu_vec {
+            if Taatsu::new(taatsu.0, taatsu.1).is_none() {
+                return Err(format!("'{}' is not a valid taatsu.", taatsu));
+            }
+        }
+        if decomposer.hourakei != Hourakei::Mentsute
+            && (decomposer.mentsu_vec.len() > 0 || decomposer.taatsu_vec.len() > 0)
+        {
+            return Err("Only mentsute can have mentsu and taatsu.".to_string());
+        }
+
+        let hai_vec = decomposer.to_hai_vec();
+        if !Hai::check_iter_valid(hai_vec.iter(), PlayerNumber::Four) {
+            return Err("Decomposer contains invalid hai.".to_string());
+        }
+        if hai_vec.len() > 14 || hai_vec.len() % 3 == 0 {
+            return Err(format!(
+                "The number of hai must be 3*k+1 or 3*k+2 and at most 14, but {} provided.",
+                hai_vec.len()
+            ));
+        }
+        for (index, number) in Hai::to_counts(hai_vec.iter()).iter().enumerate() {
+            if *number > 4 {
+                return Err(format!(
+                    "Too many '{}' in decomposer.",
+                    // Index is always less than 34.
+                    Hai::from_index(index).unwrap().to_string()
+                ));
+            }
+        }
+        Ok(decomposer)
+    }
+}
+
+impl MachiCondition {
+    /// Get how many hai can waiting for.
+    ///
+    /// # Japanese
+    /// * nokori: 残り
+    pub fn nokori(&self) -> usize {
+        let mut nokori = 0;
+        for (_, number) in &self.machihai {
+            nokori += *number as usize;
+        }
+        nokori
+    }
+
+    /// Forms of winning which machihai are waited for, judged by their shapes.
+    pub fn hourakei(&self) -> BTreeSet<Hourakei> {
+        self.ukeire
+            .values()
+            .flatten()
+            .map(|ukeire| ukeire.hourakei())
+            .collect()
+    }
+
+    /// Forms of winning which a machihai is waited for, empty if it is not machihai.
+    pub fn hourakei_of(&self, hai: &Hai) -> BTreeSet<Hourakei> {
+        self.ukeire
+            .get(hai)
+            .map(|ukeire_set| ukeire_set.iter().map(|ukeire| ukeire.hourakei()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Probability that any of the next `draws` draws is machihai, which reduces shanten.
+    /// Hai are drawn from unseen hai without replacement.
+    pub fn advance_rate(&self, draws: usize) -> f64 {
+        let nokori = self.nokori();
+        if nokori == 0 || self.unseen == 0 {
+            return 0.0;
+        }
+        // Probability that every draw misses machihai.
+        let mut miss = 1.0;
+        for draw in 0..draws.min(self.unseen) {
+            miss *=
+                (self.unseen - nokori).saturating_sub(draw) as f64 / (self.unseen - draw) as f64;
+        }
+        1.0 - miss
+    }
+
+    /// Expected number of draws until drawing machihai, `None` if no machihai left.
+    ///
+    /// Drawing all unseen hai in random order, `nokori` machihai split the others into
+    /// `nokori + 1` parts of equal expected size, so the first machihai is expected at
+    /// `(unseen + 1) / (nokori + 1)`.
+    pub fn expected_advance_draws(&self) -> Option<f64> {
+        let nokori = self.nokori();
+        if nokori == 0 || self.unseen == 0 {
+            None
+        } else {
+            Some((self.unseen + 1) as f64 / (nokori + 1) as f64)
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut machi_hai_json_vec = vec![];
+        for (hai, num) in &self.machihai {
+            let mut ukeire_json_vec = vec![];
+            if let Some(ukeire_set) = self.ukeire.get(hai) {
+                for ukeire in ukeire_set {
+                    ukeire_json_vec.push(ukeire.to_json());
+                }
+            }
+            machi_hai_json_vec.push(json!({
+                "tile": hai.to_string(),
+                "number": num,
+                "ukeire": ukeire_json_vec,
+                "hourakei": self
+                    .hourakei_of(hai)
+                    .iter()
+                    .map(|hourakei| hourakei.name())
+                    .collect::<Vec<_>>()
+            }));
+        }
+        let mut kairyouhai_json_vec = vec![];
+        for (hai, num) in &self.kairyouhai {
+            kairyouhai_json_vec.push(json!({
+                "tile": hai.to_string(),
+                "number": num
+            }));
+        }
+        let mut dead_machihai_json_vec = vec![];
+        for hai in &self.dead_machihai {
+            dead_machihai_json_vec.push(hai.to_string());
+        }
+        json!({
+            "sutehai": self.sutehai.to_string(),
+            "hourakei": self
+                .hourakei()
+                .iter()
+                .map(|hourakei| hourakei.name())
+                .collect::<Vec<_>>(),
+            "furiten": self.furiten,
+            "no_yaku": self.no_yaku,
+            "machihai_number": self.nokori(),
+            "machihai": machi_hai_json_vec,
+            "kairyouhai": kairyouhai_json_vec,
+            "dead_machihai": dead_machihai_json_vec,
+            "advance_rate": self.advance_rate(1),
+            "expected_advance_draws": self.expected_advance_draws(),
+            "expected_tenpai_draws": self.tenpai_draws
+        })
+    }
+
+    fn new(sutehai: Hai) -> Self {
+        Self {
+            sutehai,
+            machihai: BTreeMap::new(),
+            furiten: false,
+            ukeire: BTreeMap::new(),
+            kairyouhai: BTreeMap::new(),
+            dead_machihai: BTreeSet::new(),
+            unseen: 0,
+            tenpai_draws: None,
+            no_yaku: false,
+        }
+    }
+
+    /// Wait for a hai accepted by a shape.
+    fn insert_machihai(&mut self, hai: Hai, ukeire: Ukeire) {
+        self.machihai.insert(hai, 4);
+        self.ukeire
+            .entry(hai)
+            .or_insert(BTreeSet::new())
+            .insert(ukeire);
+    }
+
+    fn handle(
+        &mut self,
+        decomposer: &Decomposer,
+        juntehai_number: usize,
+        player_number: PlayerNumber,
+    ) -> Result<&mut Self, String> {
+        if let i32::MIN..=-1 = decomposer.shanten(juntehai_number) {
+            return Err("Logic Error: Code cannot reach here.".to_string());
+        }
+
+        // If invalid_ukihai_vec does not contain sutehai, no need to analyze.
+        if !decomposer
+            .invalid_ukihai_vec
+            .contains(&Ukihai { 0: self.sutehai })
+        {
+            // But Chiitoitsu is a little special.
+            if decomposer.hourakei == Hourakei::Chiitoitsu {
+                if !decomposer
+                    .valid_ukihai_vec
+                    .contains(&Ukihai { 0: self.sutehai })
+                {
+                    return Ok(self);
+                }
+            } else {
+                return Ok(self);
+            }
+        }
+
+        match decomposer.hourakei {
+            Hourakei::Mentsute => {
+                self.handle_mentsute(decomposer, player_number, juntehai_number)?;
+            }
+            Hourakei::Chiitoitsu => {
+                self.handle_chiitoitsu(decomposer, player_number)?;
+            }
+            Hourakei::Kokushimusou => {
+                self.handle_kokushimusou(decomposer)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn handle_taatsu(&mut self, decomposer: &Decomposer) -> Result<&mut Self, String> {
+        for taatsu in &decomposer.taatsu_vec {
+            if Taatsu::new(taatsu.0, taatsu.1).is_none() {
+                return Err("Logic error: Code cannot reach here.".to_string());
+            }
+            for machi in taatsu.machihai() {
+                self.insert_machihai(machi, Ukeire::Taatsu(*taatsu));
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn finally(
+        &mut self,
+        tehai: &Tehai,
+        player_number: PlayerNumber,
+        situation: Option<&dyn Situation>,
+    ) {
+        // If interactive mode.
+        if let Some(situation) = situation {
+            self.unseen = situation
+                .haiyama()
+                .to_counts()
+                .iter()
+                .map(|number| *number as usize)
+                .sum();
+            if situation.kawa().is_riichi_furiten() {
+                self.furiten = true;
+            }
+            let mut zero_nokori_hai = vec![];
+            for (key, value) in self.machihai.iter_mut() {
+                // Waiting for sutehai itself is furiten too, since it will be in kawa.
+                if !self.furiten {
+                    if situation.sutehai_type().contains(key) || *key == self.sutehai {
+                        self.furiten = true;
+                    }
+                }
+                *value = situation.haiyama()[key];
+                if *value == 0 {
+                    zero_nokori_hai.push(*key);
+                }
+            }
+            for hai in zero_nokori_hai {
+                self.machihai.remove(&hai);
+                self.dead_machihai.insert(hai);
+            }
+        }
+        // If non-interactive mode.
+        else {
+            // Remove hai whose number is 0.
+            let check_count = |machihai: &mut BTreeMap<_, _>, dead: &mut BTreeSet<_>, item| {
+                if machihai.contains_key(item) {
+                    if machihai[item] > 1 {
+                        machihai.insert(*item, machihai[item] - 1);
+                    } else if machihai[item] == 1 {
+                        machihai.remove(item);
+                        dead.insert(*item);
+                    }
+                }
+            };
+            for item in &tehai.juntehai {
+                check_count(&mut self.machihai, &mut self.dead_machihai, item);
+            }
+            // All hai except tehai are unseen.
+            let fuuro_number: usize = tehai.fuuro.iter().map(|mentsu| mentsu.to_vec().len()).sum();
+            self.unseen =
+                Hai::all_type(player_number).len() * 4 - tehai.juntehai.len() - fuuro_number;
+
+            for mentsu in &tehai.fuuro {
+                match mentsu {
+                    Mentsu::Juntsu(a, b, c) => {
+                        for item in vec![a, b, c] {
+                            check_count(&mut self.machihai, &mut self.dead_machihai, item);
+                        }
+                    }
+                    Mentsu::Koutsu(item) => {
+                        for _ in 0..3 {
+                            check_count(&mut self.machihai, &mut self.dead_machihai, item);
+                        }
+                    }
+                    Mentsu::Kantsu(item) => {
+                        for _ in 0..4 {
+                            check_count(&mut self.machihai, &mut self.dead_machihai, item);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Shapes of machihai removed are useless.
+        let machihai = &self.machihai;
+        self.ukeire.retain(|hai, _| machihai.contains_key(hai));
+    }
+
+    fn handle_mentsute(
+        &mut self,
+        decomposer: &Decomposer,
+        player_number: PlayerNumber,
+        juntehai_number: usize,
+    ) -> Result<&mut Self, String> {
+        let max_mentsu_toitsu_taatsu = max_mentsu_toitsu_taatsu(juntehai_number);
+
+        // If taatsu overload, no need to analyze.
+        if decomposer.mentsu_vec.len() + decomposer.taatsu_vec.len() > max_mentsu_toitsu_taatsu - 1
+        {
+            return Ok(self);
+        }
+
+        // If toitsu overload, no need to analyze.
+        if decomposer.mentsu_vec.len() + decomposer.taatsu_vec.len() + decomposer.toitsu_vec.len()
+            > max_mentsu_toitsu_taatsu
+        {
+            return Ok(self);
+        }
+
+        // Analyze taatsu.
+        self.handle_taatsu(decomposer)?;
+
+        // If more than 1 toitsu, analyze toitsu.
+        if decomposer.toitsu_vec.len() > 1 {
+            for toitsu in &decomposer.toitsu_vec {
+                self.insert_machihai(toitsu.0, Ukeire::Toitsu(*toitsu));
+            }
+        }
+
+        // If taatsu and toitsu not enough.
+        if decomposer.mentsu_vec.len() + decomposer.taatsu_vec.len() + decomposer.toitsu_vec.len()
+            < max_mentsu_toitsu_taatsu
+        {
+            // Toitsu to koutsu
+            for toitsu in decomposer.toitsu_vec.iter() {
+                self.insert_machihai(toitsu.0, Ukeire::Toitsu(*toitsu));
+            }
+
+            // Ukihai to taatsu or toitsu
+            for ukihai in &decomposer.invalid_ukihai_vec {
+                if ukihai.0 == self.sutehai {
+                    continue;
+                }
+
+                // Ukihai to toitsu
+                self.insert_machihai(ukihai.0, Ukeire::Ukihai(*ukihai));
+                // Ukihai to taatsu
+                if decomposer.mentsu_vec.len() + decomposer.taatsu_vec.len()
+                    < max_mentsu_toitsu_taatsu - 1
+                {
+                    // Jihai cannot become taatsu
+                    if ukihai.0.is_jihai() {
+                        continue;
+                    }
+
+                    if let Some(machi) = ukihai.0.previous(player_number, false) {
+                        self.insert_machihai(machi, Ukeire::Ukihai(*ukihai));
+                        if let Some(machi_2) = machi.previous(player_number, false) {
+                            self.insert_machihai(machi_2, Ukeire::Ukihai(*ukihai));
+                        }
+                    }
+                    if let Some(machi) = ukihai.0.next(player_number, false) {
+                        self.insert_machihai(machi, Ukeire::Ukihai(*ukihai));
+                        if let Some(machi_2) = machi.next(player_number, false) {
+                            self.insert_machihai(machi_2, Ukeire::Ukihai(*ukihai));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn handle_chiitoitsu(
+        &mut self,
+        decomposer: &Decomposer,
+        player_number: PlayerNumber,
+    ) -> Result<&mut Self, String> {
+        // Enough single hai, each waiting for its pair. Valid ukihai are single, the rest of
+        // two or more are toitsu and invalid ukihai.
+        if decomposer.toitsu_vec.len() + decomposer.valid_ukihai_vec.len() >= 7 {
+            for Ukihai(hai) in &decomposer.valid_ukihai_vec {
+                if hai != &self.sutehai {
+                    self.insert_machihai(*hai, Ukeire::Chiitoitsu);
+                }
+            }
+        }
+        // Need more single hai for shanten.
+        else {
+            let mut all_hai = Hai::all_type(player_number);
+
+            // Not wait hai that already been pairs.
+            for toitsu in decomposer.toitsu_vec.iter() {
+                all_hai.remove(&toitsu.0);
+            }
+
+            // The rest is wanted hai.
+            for hai in all_hai {
+                self.insert_machihai(hai, Ukeire::Chiitoitsu);
+            }
+        }
+
+        Ok(self)
+    }
+
+    fn handle_kokushimusou(&mut self, decomposer: &Decomposer) -> Result<&mut Self, String> {
+        let yaochuupai_type = Hai::yaochuupai_type();
+        let mut yaochuupai_iter = yaochuupai_type.iter();
+        let mut kokushimusou_valid_iter = decomposer.valid_ukihai_vec.iter();
+
+        let mut yaochuupai_pair = false;
+
+        // Check for yaochuupai pair.
+
+        let mut iter = decomposer.valid_ukihai_vec.iter();
+        let first = iter.next();
+        if let Some(mut last) = first {
+            for hai in iter {
+                if hai == last {
+                    yaochuupai_pair = true;
+                    break;
+                } else {
+                    last = hai;
+                }
+            }
+        }
+
+        // If no yaochuupai pair, waiting for all yaochuupais.
+        if !yaochuupai_pair {
+            for yaochuupai in yaochuupai_iter {
+                self.insert_machihai(*yaochuupai, Ukeire::Kokushimusou);
+            }
+            return Ok(self);
+        }
+
+        // If having yaochuupai pair, find missing yaochuupai.
+        let mut yaochuupai_value = yaochuupai_iter.next();
+        let mut kokushimusou_valid_value = kokushimusou_valid_iter.next();
+        let mut yaochuupai_used = false;
+
+        while yaochuupai_value != None && kokushimusou_valid_value != None {
+            if let (Some(lhs), Some(Ukihai(rhs))) = (yaochuupai_value, kokushimusou_valid_value) {
+                if lhs < rhs {
+                    if !yaochuupai_used {
+                        self.insert_machihai(*lhs, Ukeire::Kokushimusou);
+                    }
+                    yaochuupai_used = false;
+                    yaochuupai_value = yaochuupai_iter.next();
+                } else if lhs > rhs {
+                    kokushimusou_valid_value = kokushimusou_valid_iter.next();
+                } else if lhs == rhs {
+                    yaochuupai_used = true;
+                    kokushimusou_valid_value = kokushimusou_valid_iter.next();
+                }
+            }
+        }
+        if !yaochuupai_pair {
+            if let Some(yaochuupai) = yaochuupai_value {
+                self.insert_machihai(*yaochuupai, Ukeire::Kokushimusou);
+            }
+        }
+        for rest in yaochuupai_iter {
+            self.insert_machihai(*rest, Ukeire::Kokushimusou);
+        }
+
+        Ok(self)
+    }
+}
+
+impl std::fmt::Display for MachiCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut machihai_string = String::new();
+        let mut furiten_string = String::new();
+        let nokori = self.nokori();
+        // Machihai are grouped by forms of winning only if waited for by more than one, like
+        // `面子手 3p 七対子 3p 7z`, where machihai of both forms are in both groups.
+        let hourakei_set = self.hourakei();
+        let mut group_vec: Vec<Option<Hourakei>> = hourakei_set.iter().copied().map(Some).collect();
+        if group_vec.len() <= 1 {
+            group_vec = vec![None];
+        }
+        for group in group_vec {
+            if let Some(hourakei) = group {
+                machihai_string += &format!("{} ", hourakei);
+            }
+            for machihai in self.machihai.keys() {
+                if let Some(hourakei) = group {
+                    if !self.hourakei_of(machihai).contains(&hourakei) {
+                        continue;
+                    }
+                }
+                machihai_string += &machihai.to_string();
+                // Alternate flag `{:#}` prints shapes accepting each machihai.
+                if f.alternate() {
+                    if let Some(ukeire_set) = self.ukeire.get(machihai) {
+                        let mut ukeire_string_vec = vec![];
+                        for ukeire in ukeire_set {
+                            if group.map_or(true, |hourakei| ukeire.hourakei() == hourakei) {
+                                ukeire_string_vec.push(ukeire.to_string());
+                            }
+                        }
+                        machihai_string += &format!("({})", ukeire_string_vec.join(","));
+                    }
+                }
+                machihai_string += " ";
+            }
+        }
+        if self.furiten {
+            furiten_string = "!振り聴!".to_string();
+        }
+        if self.no_yaku {
+            furiten_string += "!役なし!";
+        }
+        // Dead machihai can never be drawn, which is called 純カラ.
+        let mut dead_machihai_string = String::new();
+        if self.dead_machihai.len() > 0 {
+            dead_machihai_string += " 純カラ ";
+            for hai in self.dead_machihai.iter() {
+                dead_machihai_string += &hai.to_string();
+                dead_machihai_string += " ";
+            }
+            dead_machihai_string.pop();
+        }
+        let mut kairyouhai_string = String::new();
+        if self.kairyouhai.len() > 0 {
+            let mut kairyou_nokori = 0;
+            kairyouhai_string += " 改良 ";
+            for (kairyouhai, number) in self.kairyouhai.iter() {
+                kairyouhai_string += &kairyouhai.to_string();
+                kairyouhai_string += " ";
+                kairyou_nokori += *number as usize;
+            }
+            kairyouhai_string += &format!(" 残り{}枚", kairyou_nokori);
+        }
+        let mut advance_string = String::new();
+        if let Some(draws) = self.expected_advance_draws() {
+            advance_string = format!(
+                " 次巡{:.1}% 平均{:.1}巡",
+                self.advance_rate(1) * 100.0,
+                draws
+            );
+        }
+        if let Some(draws) = self.tenpai_draws {
+            advance_string += &format!(" 聴牌まで{:.1}巡", draws);
+        }
+        write!(
+            f,
+            "打 {} 摸 {} 残り{}枚{}{}{}{}",
+            self.sutehai.to_string(),
+            machihai_string,
+            nokori,
+            advance_string,
+            furiten_string,
+            dead_machihai_string,
+            kairyouhai_string
+        )
+    }
+}
+
+impl Hourakei {
+    /// Key of the hourakei in JSON of machi conditions, under `"hourakei"` of each condition
+    /// and of each machihai.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hourakei::Mentsute => "mentsute",
+            Hourakei::Chiitoitsu => "chiitoitsu",
+            Hourakei::Kokushimusou => "kokushimusou",
+        }
+    }
+}
+
+impl std::fmt::Display for Hourakei {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hourakei::Mentsute => write!(f, "面子手"),
+            Hourakei::Chiitoitsu => write!(f, "七対子"),
+            Hourakei::Kokushimusou => write!(f, "国士無双"),
+        }
+    }
+}
+
+impl Ukeire {
+    /// Form of winning the shape is for.
+    pub fn hourakei(&self) -> Hourakei {
+        match self {
+            Ukeire::Taatsu(_) | Ukeire::Toitsu(_) | Ukeire::Ukihai(_) => Hourakei::Mentsute,
+            Ukeire::Chiitoitsu => Hourakei::Chiitoitsu,
+            Ukeire::Kokushimusou => Hourakei::Kokushimusou,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Ukeire::Taatsu(Taatsu(lhs, rhs)) => json!({
+                "type": "taatsu",
+                "hai": [lhs.to_string(), rhs.to_string()]
+            }),
+            Ukeire::Toitsu(Toitsu(hai)) => json!({
+                "type": "toitsu",
+                "hai": [hai.to_string(), hai.to_string()]
+            }),
+            Ukeire::Ukihai(Ukihai(hai)) => json!({
+                "type": "ukihai",
+                "hai": [hai.to_string()]
+            }),
+            Ukeire::Chiitoitsu => json!({ "type": "chiitoitsu" }),
+            Ukeire::Kokushimusou => json!({ "type": "kokushimusou" }),
+        }
+    }
+}
+
+impl std::fmt::Display for Ukeire {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ukeire::Taatsu(taatsu) => taatsu.fmt(f),
+            Ukeire::Toitsu(toitsu) => toitsu.fmt(f),
+            Ukeire::Ukihai(ukihai) => ukihai.fmt(f),
+            Ukeire::Chiitoitsu => write!(f, "七対子"),
+            Ukeire::Kokushimusou => write!(f, "国士無双"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::TaatsuKind;
+
+    /// Tenpai tehai with 1 to 4 kantsu in fuuro after discarding 9p, each waiting for
+    /// 2s and 5s where all 5s are in a kantsu, except the last one waiting for 3m alone.
+    const KAN_TEHAI: [&str; 4] = [
+        "123m456p34s11z9p[5555s]",
+        "456p34s11z9p[5555s][7777z]",
+        "34s11z9p[5555s][7777z][1111m]",
+        "3m9p[5555s][7777z][1111m][2222m]",
+    ];
+
+    fn tehai(string: &str) -> Tehai {
+        Tehai::new(string.to_string(), PlayerNumber::Four).unwrap()
+    }
+
+    fn condition_of(tehai: &Tehai, sutehai: Hai) -> MachiCondition {
+        let (shanten, conditions) = tehai.analyze(PlayerNumber::Four, None).unwrap();
+        assert_eq!(shanten, 0, "{}", tehai);
+        conditions
+            .into_iter()
+            .find(|condition| condition.sutehai == sutehai)
+            .unwrap()
+    }
+
+    #[test]
+    fn juntehai_number_with_kantsu() {
+        for (index, string) in KAN_TEHAI.iter().enumerate() {
+            let tehai = tehai(string);
+            assert_eq!(tehai.fuuro.len(), index + 1);
+            assert_eq!(tehai.juntehai.len(), 14 - 3 * (index + 1));
+        }
+        // One more juntehai than a full tehai with the kantsu.
+        for string in &[
+            "123m456p34s11z99p[5555s]",
+            "456p34s11z99p[5555s][7777z]",
+            "34s11z99p[5555s][7777z][1111m]",
+            "3m99p[5555s][7777z][1111m][2222m]",
+        ] {
+            assert!(Tehai::new(string.to_string(), PlayerNumber::Four).is_err());
+        }
+    }
+
+    #[test]
+    fn shanten_with_kantsu() {
+        for string in &KAN_TEHAI {
+            let tehai = tehai(string);
+            let (shanten, _) = tehai.analyze(PlayerNumber::Four, None).unwrap();
+            assert_eq!(shanten, 0, "{}", tehai);
+            assert_eq!(
+                shanten::calculate_counts(&tehai.to_counts(), tehai.fuuro.len()),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn machihai_with_kantsu() {
+        for string in &KAN_TEHAI[..3] {
+            let condition = condition_of(&tehai(string), Hai::Pinzu(9));
+            // All 5s are in the kantsu.
+            assert_eq!(
+                condition.machihai.keys().copied().collect::<Vec<_>>(),
+                vec![Hai::Souzu(2)]
+            );
+            assert!(condition.dead_machihai.contains(&Hai::Souzu(5)));
+        }
+        let condition = condition_of(&tehai(KAN_TEHAI[3]), Hai::Pinzu(9));
+        assert_eq!(
+            condition.machihai.keys().copied().collect::<Vec<_>>(),
+            vec![Hai::Manzu(3)]
+        );
+    }
+
+    #[test]
+    fn nokori_without_kantsu() {
+        for string in &KAN_TEHAI[..3] {
+            let condition = condition_of(&tehai(string), Hai::Pinzu(9));
+            assert_eq!(condition.nokori(), 4);
+        }
+        // 3m in juntehai is not counted, and 1m and 2m are all in the kantsu.
+        let tehai = tehai(KAN_TEHAI[3]);
+        assert_eq!(condition_of(&tehai, Hai::Pinzu(9)).nokori(), 3);
+        assert_eq!(tehai.unseen_counts(PlayerNumber::Four, None)[0], 0);
+        assert_eq!(tehai.unseen_counts(PlayerNumber::Four, None)[1], 0);
+    }
+
+    #[test]
+    fn kakan_on_pon() {
+        let mut pon = tehai("123m456p78s99s2z[222z]");
+        let kan = pon
+            .kan(&Mentsu::Kantsu(Hai::Jihai(2)), &Some(Hai::Souzu(3)))
+            .unwrap();
+        assert!(matches!(kan, Kan::Kakan { .. }));
+        assert_eq!(pon.fuuro, vec![Mentsu::Kantsu(Hai::Jihai(2))]);
+        assert_eq!(pon.juntehai.len(), 11);
+        assert!(!pon.juntehai.contains(&Hai::Jihai(2)));
+        // Only koutsu of the same hai in fuuro takes the fourth one.
+        let mut other = tehai("123m456p78s99s2z[111z]");
+        assert!(other
+            .kan(&Mentsu::Kantsu(Hai::Jihai(2)), &Some(Hai::Souzu(3)))
+            .is_err());
+    }
+
+    #[test]
+    fn ryanmen_machihai_of_each_type() {
+        for (lhs, rhs, machihai) in [
+            (Hai::Manzu(7), Hai::Manzu(8), [Hai::Manzu(6), Hai::Manzu(9)]),
+            (Hai::Pinzu(7), Hai::Pinzu(8), [Hai::Pinzu(6), Hai::Pinzu(9)]),
+            (Hai::Souzu(7), Hai::Souzu(8), [Hai::Souzu(6), Hai::Souzu(9)]),
+        ] {
+            let taatsu = Taatsu::new(lhs, rhs).unwrap();
+            assert_eq!(taatsu.kind(), TaatsuKind::Ryanmen);
+            assert_eq!(taatsu.machihai(), machihai.to_vec());
+        }
+        // Once waited for 7p and 9p, by the previous hai of 7p instead of 6p.
+        let condition = condition_of(&tehai("123m456m789m11z78p9s"), Hai::Souzu(9));
+        assert_eq!(
+            condition.machihai.keys().copied().collect::<Vec<_>>(),
+            vec![Hai::Pinzu(6), Hai::Pinzu(9)]
+        );
+    }
+}
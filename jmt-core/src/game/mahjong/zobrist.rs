@@ -0,0 +1,181 @@
+//! Zobrist hashing of hai, keying caches and transposition tables by one `u64` which is
+//! updated by one XOR as a hai moves, instead of hashing whole tehai at every lookup.
+//!
+//! Each copy of each type of hai has a random key, and a multiset of hai is hashed as XOR
+//! of keys of all copies in it, so adding or removing a copy toggles its key:
+//!
+//! ```ignore
+//! hash ^= zobrist::key(&zobrist::TEHAI_KEYS, index, counts[index]);
+//! counts[index] += 1;
+//! ```
+//!
+//! Keys differ between tehai, fuuro and haiyama, so that the same hai in different places
+//! hash differently. Multisets with more than `COPIES` of a type, such as custom haiyama,
+//! reuse keys cyclically, which is still a function of counts but collides more.
+
+use super::Mentsu;
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Number of copies with distinct keys of each type of hai.
+pub const COPIES: usize = 4;
+
+/// Keys of each copy of each type of hai in 34-index representation.
+pub type Keys = [[u64; COPIES]; 34];
+
+/// Keys of juntehai.
+pub const TEHAI_KEYS: Keys = keys(1);
+
+/// Keys of unseen hai in haiyama.
+pub const HAIYAMA_KEYS: Keys = keys(2);
+
+/// Keys of fuuro of juntsu, koutsu and kantsu, by the index of their first hai.
+pub const FUURO_KEYS: [Keys; 3] = [keys(3), keys(4), keys(5)];
+
+/// Build hasher passing zobrist hashes through, for maps keyed by them.
+pub type BuildZobristHasher = BuildHasherDefault<ZobristHasher>;
+
+/// Hasher taking a `u64` written as the hash, since zobrist hashes are already uniform.
+/// Other data are mixed roughly, which is only for completeness.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ZobristHasher(u64);
+
+/// Juntehai and unseen hai in 34-index representation with their hashes, which searches
+/// change in place by a hai at a time as their trees are walked.
+///
+/// # Member
+/// * counts: Counts of juntehai.
+/// * unseen: Counts of unseen hai in haiyama.
+/// * tehai_hash: Hash of counts by `TEHAI_KEYS`.
+/// * unseen_hash: Hash of unseen by `HAIYAMA_KEYS`.
+#[derive(Copy, Clone, Debug)]
+pub struct Position {
+    pub counts: [u8; 34],
+    pub unseen: [u8; 34],
+    pub tehai_hash: u64,
+    pub unseen_hash: u64,
+}
+
+/// Key of a copy of the type of hai at `index`, where `copy` is the number of copies before
+/// adding it, or after removing it.
+pub fn key(keys: &Keys, index: usize, copy: u8) -> u64 {
+    keys[index][copy as usize % COPIES]
+}
+
+/// Hash of counts in 34-index representation.
+pub fn hash_counts(counts: &[u8; 34], keys: &Keys) -> u64 {
+    let mut hash = 0;
+    for (index, count) in counts.iter().enumerate() {
+        for copy in 0..*count {
+            hash ^= key(keys, index, copy);
+        }
+    }
+    hash
+}
+
+/// Change of the hash from counts `before` to `after`, toggling keys of copies added or
+/// removed, which is XORed to the hash of `before` to have that of `after`.
+pub fn delta_counts(before: &[u8; 34], after: &[u8; 34], keys: &Keys) -> u64 {
+    let mut delta = 0;
+    for (index, (before, after)) in before.iter().zip(after.iter()).enumerate() {
+        for copy in *before.min(after)..*before.max(after) {
+            delta ^= key(keys, index, copy);
+        }
+    }
+    delta
+}
+
+/// Hash of fuuro in any order.
+pub fn hash_fuuro(fuuro: &[Mentsu]) -> u64 {
+    let mut counts = [[0u8; 34]; 3];
+    for mentsu in fuuro {
+        let (kind, hai) = match mentsu {
+            Mentsu::Juntsu(hai, _, _) => (0, hai),
+            Mentsu::Koutsu(hai) => (1, hai),
+            Mentsu::Kantsu(hai) => (2, hai),
+        };
+        counts[kind][hai.to_index()] += 1;
+    }
+    counts
+        .iter()
+        .zip(FUURO_KEYS.iter())
+        .map(|(counts, keys)| hash_counts(counts, keys))
+        .fold(0, |hash, fuuro_hash| hash ^ fuuro_hash)
+}
+
+/// Keys by splitmix64 from a seed, the same in every build. Seeds are spread far apart in
+/// the sequence, otherwise keys of one seed are those of another shifted, which collide.
+const fn keys(seed: u64) -> Keys {
+    let mut keys = [[0; COPIES]; 34];
+    let mut state = seed << 40;
+    let mut index = 0;
+    while index < 34 {
+        let mut copy = 0;
+        while copy < COPIES {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut key = state;
+            key = (key ^ (key >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            key = (key ^ (key >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            keys[index][copy] = key ^ (key >> 31);
+            copy += 1;
+        }
+        index += 1;
+    }
+    keys
+}
+
+impl Position {
+    pub fn new(counts: [u8; 34], unseen: [u8; 34]) -> Self {
+        Position {
+            counts,
+            unseen,
+            tehai_hash: hash_counts(&counts, &TEHAI_KEYS),
+            unseen_hash: hash_counts(&unseen, &HAIYAMA_KEYS),
+        }
+    }
+
+    /// Hash of both juntehai and unseen hai.
+    pub fn hash(&self) -> u64 {
+        self.tehai_hash ^ self.unseen_hash
+    }
+
+    /// Draw a hai from haiyama to juntehai, or return it if `draw` is false.
+    pub fn move_hai(&mut self, index: usize, draw: bool) {
+        if draw {
+            self.unseen[index] -= 1;
+            self.unseen_hash ^= key(&HAIYAMA_KEYS, index, self.unseen[index]);
+            self.add(index);
+        } else {
+            self.remove(index);
+            self.unseen_hash ^= key(&HAIYAMA_KEYS, index, self.unseen[index]);
+            self.unseen[index] += 1;
+        }
+    }
+
+    /// Add a hai to juntehai, such as undoing a discard.
+    pub fn add(&mut self, index: usize) {
+        self.tehai_hash ^= key(&TEHAI_KEYS, index, self.counts[index]);
+        self.counts[index] += 1;
+    }
+
+    /// Remove a hai from juntehai, such as discarding it.
+    pub fn remove(&mut self, index: usize) {
+        self.counts[index] -= 1;
+        self.tehai_hash ^= key(&TEHAI_KEYS, index, self.counts[index]);
+    }
+}
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ *byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 ^= hash;
+    }
+}
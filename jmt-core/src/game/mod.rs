@@ -0,0 +1,111 @@
+//! Analysis of tehai at `core`, and the game around it at `game`.
+
+#[cfg(feature = "game")]
+mod agari;
+#[cfg(feature = "game")]
+pub mod analyzer;
+#[cfg(feature = "game")]
+mod annotation;
+mod cancellation;
+#[cfg(feature = "game")]
+mod deal_in;
+#[cfg(feature = "game")]
+mod defense;
+pub mod endgame;
+pub mod enumerate;
+pub mod expectimax;
+#[cfg(feature = "game")]
+mod explanation;
+#[cfg(feature = "game")]
+mod game_manager;
+#[cfg(feature = "game")]
+mod hanchan;
+#[cfg(feature = "game")]
+mod kifu;
+#[cfg(feature = "game")]
+mod ledger;
+mod machi_search;
+mod mahjong;
+#[cfg(feature = "game")]
+pub mod opponent;
+#[cfg(feature = "game")]
+mod reaction;
+#[cfg(feature = "validate")]
+pub mod reference;
+#[cfg(feature = "game")]
+mod review;
+#[cfg(feature = "game")]
+pub mod riichi;
+mod rules;
+#[cfg(feature = "game")]
+mod scene;
+mod score;
+#[cfg(feature = "game")]
+mod session;
+pub mod simulate;
+#[cfg(feature = "game")]
+pub mod snapshot;
+#[cfg(feature = "game")]
+mod tehai_diff;
+#[cfg(feature = "profiling")]
+mod timing;
+#[cfg(feature = "game")]
+mod trade_off;
+#[cfg(feature = "game")]
+mod warning;
+pub mod yaku;
+mod yakuman;
+
+pub use cancellation::Cancellation;
+pub use machi_search::{MachiKind, MachiShape};
+pub use mahjong::{
+    kernel, shanten, zobrist, AnalysisBuffers, Decomposer, DecomposerBuilder, Decompositions, Hai,
+    Haiyama, Hourakei, Kan, Kawa, Kaze, MachiCondition, Mentsu, MentsuDisplay, MentsuFormat,
+    PlayerNumber, Seat, Situation, Suit, Taatsu, TaatsuKind, Tehai, TehaiDisplay, TehaiFormat,
+    Toitsu, Ukeire, Ukihai,
+};
+pub use rules::Rules;
+pub use score::Score;
+pub use yaku::Yaku;
+pub use yakuman::{Yakuman, YakumanChance};
+
+#[cfg(feature = "profiling")]
+pub use timing::Timing;
+
+#[cfg(feature = "game")]
+pub use agari::{Agari, AgariTable, MachiValue};
+#[cfg(feature = "game")]
+pub use analyzer::{AnalysisReport, AnalyzeOptions, ConditionOrder};
+#[cfg(feature = "game")]
+pub use annotation::Annotation;
+#[cfg(feature = "game")]
+pub use deal_in::{DealInCategory, DealInTable};
+#[cfg(feature = "game")]
+pub use defense::{Chance, OpponentRiichi, RiichiSafety, Safety};
+#[cfg(feature = "game")]
+pub use explanation::ConditionComparison;
+#[cfg(feature = "game")]
+pub use game_manager::{
+    Event, GameManager, HaiyamaOperation, Naku, Operation, State, TehaiOperation,
+};
+#[cfg(feature = "game")]
+pub use hanchan::{Hanchan, HandRecord, HandResult, MatchRules, Placement};
+#[cfg(feature = "game")]
+pub use kifu::Kifu;
+#[cfg(feature = "game")]
+pub use ledger::{Ledger, Ryuukyoku};
+#[cfg(feature = "game")]
+pub use opponent::OpponentValue;
+#[cfg(feature = "game")]
+pub use reaction::{Reaction, ReactionResult};
+#[cfg(feature = "game")]
+pub use review::{
+    Agreement, DiscardRating, EfficiencyReport, HandMistakes, HandRating, Review, ReviewStep,
+    Severity, BIGGEST_MISTAKES, MISTAKE_THRESHOLD, REVIEW_ITERATIONS, REVIEW_RANKING_SHOWN,
+};
+#[cfg(feature = "game")]
+pub use session::SessionManager;
+#[cfg(feature = "game")]
+pub use trade_off::{Line, TradeOff};
+#[cfg(feature = "game")]
+pub use warning::{Warning, FEW_DRAWS};
@@ -0,0 +1,296 @@
+//! Expected value of the hand of another player, estimated from what is visible of the
+//! player: riichi, fuuro, dora discarded, and the turn.
+//!
+//! The model is a rough guide in han. A riichi or menzen hand has a few han of common yaku,
+//! an open hand has its yakuhai or one yaku, and each hand holds hidden dora in proportion to
+//! unseen dora among unseen hai, fewer if the player has discarded dora. Points are those of
+//! ron at `FU` by oya or ko, interpolated between neighboring han.
+
+use super::{GameManager, Hai, Kaze, Mentsu, Rules, Score, Seat};
+use crate::i18n::{self, Message};
+use serde_json::json;
+
+/// Expected han of riichi besides dora, with ura-dora, ippatsu, tsumo and yaku such as
+/// pinfu and tanyao.
+pub const RIICHI_HAN: f64 = 2.6;
+
+/// Expected han of menzen tenpai without riichi besides dora.
+pub const DAMA_HAN: f64 = 1.8;
+
+/// Expected han of an open hand besides dora and yakuhai, from yaku such as tanyao and
+/// situational han.
+pub const OPEN_HAN: f64 = 1.3;
+
+/// Expected han of an open hand whose fuuro are all of one suit with jihai, likely honitsu
+/// or toitoi.
+pub const ONE_SUIT_HAN: f64 = 2.5;
+
+/// Han of hidden dora taken away by each dora the player has discarded.
+pub const DORA_DISCARD_HAN: f64 = 0.3;
+
+/// Fu of the estimated points.
+pub const FU: u32 = 30;
+
+/// Probability of tenpai from which a player without riichi is a threat, whose safety is
+/// shown in analysis.
+pub const THREAT_TENPAI: f64 = 0.3;
+
+/// Number of the latest sutehai judged for discard patterns.
+const LATEST_SUTEHAI: usize = 3;
+
+/// Estimated hand of another player.
+///
+/// # Japanese
+/// * oya: 親
+/// * tenpai: 聴牌
+///
+/// # Member
+/// * seat: Seat of the player.
+/// * oya: Whether the player is oya, which is judged from jikaze of self.
+/// * riichi: Whether the player has declared riichi.
+/// * fuuro: Fuuro of the player.
+/// * dora: Dora visible in fuuro.
+/// * dora_discarded: Dora in sutehai of the player.
+/// * hidden_dora: Expected number of dora in juntehai of the player.
+/// * han: Expected han including dora.
+/// * points: Expected points paid on dealing in.
+/// * tenpai: Probability that the player is tenpai, 1 after riichi, growing with fuuro and
+///   the turn otherwise.
+#[derive(Clone, Debug)]
+pub struct OpponentValue {
+    pub seat: Seat,
+    pub oya: bool,
+    pub riichi: bool,
+    pub fuuro: Vec<Mentsu>,
+    pub dora: u32,
+    pub dora_discarded: u32,
+    pub hidden_dora: f64,
+    pub han: f64,
+    pub points: f64,
+    pub tenpai: f64,
+}
+
+impl OpponentValue {
+    /// Estimate the hand of the player at a seat.
+    pub fn estimate(game_manager: &GameManager, seat: Seat) -> Result<Self, String> {
+        let player_number = game_manager.player_number();
+        let kaze = seat
+            .kaze(game_manager.jikaze(), player_number)
+            .ok_or(format!(
+                "There is no {} for {}.",
+                seat.name(),
+                player_number
+            ))?;
+        let riichi = game_manager
+            .opponent_riichi()
+            .iter()
+            .any(|riichi| riichi.seat == seat);
+        let fuuro = game_manager.fuuro_of(seat);
+        let dora_vec = game_manager.dora();
+        let count_dora = |hai_vec: &[Hai]| {
+            hai_vec
+                .iter()
+                .map(|hai| dora_vec.iter().filter(|dora| *dora == hai).count() as u32)
+                .sum::<u32>()
+        };
+
+        let dora = fuuro
+            .iter()
+            .map(|mentsu| count_dora(&mentsu.to_vec()))
+            .sum();
+        let dora_discarded = count_dora(&game_manager.sutehai_of(seat));
+        let counts = game_manager.haiyama().to_counts();
+        let unseen = counts.iter().map(|count| *count as u32).sum::<u32>();
+        let unseen_dora = dora_vec
+            .iter()
+            .map(|dora| counts[dora.to_index()] as u32)
+            .sum::<u32>();
+        let concealed = 13u32.saturating_sub(3 * fuuro.len() as u32);
+        let hidden_dora = if unseen == 0 {
+            0.0
+        } else {
+            (concealed as f64 * unseen_dora as f64 / unseen as f64
+                - DORA_DISCARD_HAN * dora_discarded as f64)
+                .max(0.0)
+        };
+
+        let yakuhai_type = Kaze::yakuhai_type(kaze, game_manager.bakaze());
+        let yakuhai = fuuro
+            .iter()
+            .filter(|mentsu| match mentsu {
+                Mentsu::Koutsu(hai) | Mentsu::Kantsu(hai) => yakuhai_type.contains(hai),
+                Mentsu::Juntsu(..) => false,
+            })
+            .count();
+        let yaku_han = if riichi {
+            RIICHI_HAN
+        } else if fuuro.is_empty() {
+            DAMA_HAN
+        } else if one_suit(&fuuro) {
+            ONE_SUIT_HAN + yakuhai as f64
+        } else {
+            OPEN_HAN.max(yakuhai as f64 + OPEN_HAN - 1.0)
+        };
+        let han = yaku_han + dora as f64 + hidden_dora;
+
+        let tenpai = tenpai_probability(game_manager, seat);
+        let oya = kaze == Kaze::Ton;
+        Ok(Self {
+            seat,
+            oya,
+            riichi,
+            fuuro,
+            dora,
+            dora_discarded,
+            hidden_dora,
+            han,
+            points: points(han, oya, &game_manager.rules())?,
+            tenpai,
+        })
+    }
+
+    /// Estimate hands of all other players in the game.
+    pub fn estimate_all(game_manager: &GameManager) -> Result<Vec<Self>, String> {
+        if game_manager.tehai().is_none() {
+            return Err(i18n::text(Message::NotInitialized).to_string());
+        }
+        Seat::all(game_manager.player_number())
+            .into_iter()
+            .map(|seat| Self::estimate(game_manager, seat))
+            .collect()
+    }
+
+    /// Points expected to be paid on dealing in, weighted by the probability of tenpai.
+    pub fn expected_payout(&self) -> f64 {
+        self.points * self.tenpai
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "seat": self.seat.name(),
+            "oya": self.oya,
+            "riichi": self.riichi,
+            "fuuro": self
+                .fuuro
+                .iter()
+                .map(|mentsu| mentsu.to_json())
+                .collect::<Vec<_>>(),
+            "dora": self.dora,
+            "dora_discarded": self.dora_discarded,
+            "hidden_dora": self.hidden_dora,
+            "han": self.han,
+            "points": self.points,
+            "tenpai": self.tenpai,
+            "expected_payout": self.expected_payout(),
+        })
+    }
+}
+
+/// Probability that the player at a seat is tenpai, 1 after riichi. Otherwise it grows with
+/// the turn and fuuro, and with discard patterns of a hand ready: the latest sutehai being
+/// chunchanhai or dora, which are kept until the hand needs no more.
+///
+/// # Japanese
+/// * chunchanhai: 中張牌, 2 to 8.
+pub fn tenpai_probability(game_manager: &GameManager, seat: Seat) -> f64 {
+    if game_manager
+        .opponent_riichi()
+        .iter()
+        .any(|riichi| riichi.seat == seat)
+    {
+        return 1.0;
+    }
+    let turn = game_manager
+        .turn()
+        .unwrap_or(game_manager.kawa().sutehai_vec().len() + 1);
+    let progress = (turn as f64 / 18.0).min(1.0);
+    let base = match game_manager.fuuro_of(seat).len() {
+        0 => 0.4 * progress,
+        1 => 0.2 + 0.3 * progress,
+        2 => 0.4 + 0.3 * progress,
+        _ => 0.7 + 0.25 * progress,
+    };
+    let sutehai_vec = game_manager.sutehai_of(seat);
+    let dora_vec = game_manager.dora();
+    let pattern: f64 = sutehai_vec
+        .iter()
+        .rev()
+        .take(LATEST_SUTEHAI)
+        .map(|hai| {
+            if dora_vec.contains(hai) {
+                0.1
+            } else if !hai.is_jihai() && (3..=7).contains(&hai.number()) {
+                0.05
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    // Patterns say little before the middle of the game.
+    (base + pattern * progress).min(0.95)
+}
+
+/// Other players regarded as tenpai with the probability: those declared riichi, and those
+/// whose probability is at least `THREAT_TENPAI`.
+pub fn threats(game_manager: &GameManager) -> Vec<(Seat, f64)> {
+    Seat::all(game_manager.player_number())
+        .into_iter()
+        .filter(|seat| {
+            seat.kaze(game_manager.jikaze(), game_manager.player_number())
+                .is_some()
+        })
+        .map(|seat| (seat, tenpai_probability(game_manager, seat)))
+        .filter(|(_, tenpai)| *tenpai >= THREAT_TENPAI)
+        .collect()
+}
+
+/// Whether all fuuro are of one suit, or jihai.
+fn one_suit(fuuro: &[Mentsu]) -> bool {
+    let mut suits = fuuro
+        .iter()
+        .map(|mentsu| mentsu.to_vec()[0].to_index() / 9)
+        .filter(|suit| *suit < 3);
+    match suits.next() {
+        Some(suit) => suits.all(|other| other == suit),
+        None => true,
+    }
+}
+
+/// Points of ron with fractional han, interpolated between neighboring han.
+fn points(han: f64, oya: bool, rules: &Rules) -> Result<f64, String> {
+    let han = han.clamp(1.0, 13.0);
+    let lower = han.floor() as u32;
+    let upper = han.ceil() as u32;
+    let lower_points = Score::new(lower, FU, oya, rules)?.ron as f64;
+    let upper_points = Score::new(upper, FU, oya, rules)?.ron as f64;
+    Ok(lower_points + (upper_points - lower_points) * (han - lower as f64))
+}
+
+impl std::fmt::Display for OpponentValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.seat)?;
+        if self.oya {
+            write!(f, "（親）")?;
+        }
+        if self.riichi {
+            write!(f, " 立直")?;
+        }
+        if !self.fuuro.is_empty() {
+            let fuuro_string_vec: Vec<String> = self
+                .fuuro
+                .iter()
+                .map(|mentsu| format!("{:#}", mentsu))
+                .collect();
+            write!(f, " 副露 {}", fuuro_string_vec.join(" "))?;
+        }
+        write!(
+            f,
+            " 推定{:.1}飜（ドラ{} 隠れドラ{:.1}） {:.0}点 聴牌率{:.0}%",
+            self.han,
+            self.dora,
+            self.hidden_dora,
+            self.points,
+            self.tenpai * 100.0
+        )
+    }
+}
@@ -0,0 +1,245 @@
+use super::{
+    shanten, GameManager, Hai, HaiyamaOperation, Kan, Mentsu, Naku, Operation, PlayerNumber, Seat,
+    State, TehaiOperation,
+};
+use crate::i18n::{self, Message};
+use serde_json::json;
+
+/// Possible reaction to sutehai of another player.
+///
+/// # Japanese
+/// * Pass: 見送り
+/// * Ron: 栄和
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reaction {
+    Pass,
+    Ron,
+    Chii(Mentsu),
+    Pon(Mentsu),
+    Kan(Mentsu),
+}
+
+/// Result of a reaction.
+///
+/// # Member
+/// * reaction: The reaction.
+/// * shanten: Shanten after reaction and the best discard, -1 for ron.
+/// * sutehai: The best discard after chii or pon.
+/// * nokori: Number of hai waiting for after reaction.
+#[derive(Clone, Debug)]
+pub struct ReactionResult {
+    pub reaction: Reaction,
+    pub shanten: i32,
+    pub sutehai: Option<Hai>,
+    pub nokori: usize,
+}
+
+impl Reaction {
+    /// Enumerate all possible reactions to sutehai of a player, with the result of each.
+    /// Game manager must lack one hai. Results are sorted from the best: ron first, then
+    /// less shanten, then more nokori, and passing is preferred when equal since it keeps
+    /// tehai closed.
+    ///
+    /// Yaku and furiten are not considered for ron yet.
+    pub fn analyze(
+        game_manager: &GameManager,
+        sutehai: Hai,
+        seat: Seat,
+    ) -> Result<Vec<ReactionResult>, String> {
+        if game_manager.state != State::LackOneHai {
+            return Err("Can only react to sutehai when lack one hai.".to_string());
+        }
+        let player_number = game_manager.player_number();
+        if !Seat::all(player_number).contains(&seat) {
+            return Err(format!("No {} in a game of 3 players.", seat.name()));
+        }
+        let tehai = game_manager
+            .tehai()
+            .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+        let fuuro_count = tehai.fuuro.len();
+        let mut results = vec![];
+
+        let mut counts = tehai.to_counts();
+        counts[sutehai.to_index()] += 1;
+        if shanten::calculate_counts(&counts, fuuro_count) == -1 {
+            results.push(ReactionResult {
+                reaction: Reaction::Ron,
+                shanten: -1,
+                sutehai: None,
+                nokori: 0,
+            });
+        }
+
+        let mut passed = game_manager.fork();
+        passed.operate(Operation::Haiyama {
+            kind: HaiyamaOperation::Discard(vec![sutehai]),
+            haiyama_sensitive: true,
+        })?;
+        let (shanten, nokori) = waiting(&passed)?;
+        results.push(ReactionResult {
+            reaction: Reaction::Pass,
+            shanten,
+            sutehai: None,
+            nokori,
+        });
+
+        let mut nakus = vec![];
+        // Chii is not allowed with 3 players.
+        if seat == Seat::Kamicha && player_number == PlayerNumber::Four {
+            for juntsu in juntsu_with(&sutehai, game_manager) {
+                nakus.push(Naku::Chii {
+                    juntsu,
+                    nakihai: sutehai,
+                });
+            }
+        }
+        let count = tehai.juntehai.iter().filter(|hai| **hai == sutehai).count();
+        if count >= 2 {
+            nakus.push(Naku::Pon(Mentsu::Koutsu(sutehai)));
+        }
+        if count >= 3 {
+            nakus.push(Naku::Kan(Kan::Unknown {
+                kantsu: Mentsu::Kantsu(sutehai),
+                rinshanhai: None,
+            }));
+        }
+
+        for naku in nakus {
+            let mut naku_game_manager = game_manager.fork();
+            naku_game_manager.operate(Operation::Tehai(TehaiOperation::Naku {
+                kind: naku.clone(),
+                haiyama_sensitive: true,
+            }))?;
+            match naku {
+                Naku::Kan(_) => {
+                    let (shanten, nokori) = waiting(&naku_game_manager)?;
+                    results.push(ReactionResult {
+                        reaction: Reaction::Kan(Mentsu::Kantsu(sutehai)),
+                        shanten,
+                        sutehai: None,
+                        nokori,
+                    });
+                }
+                Naku::Chii { juntsu: mentsu, .. } | Naku::Pon(mentsu) => {
+                    let (shanten, conditions) = naku_game_manager.tehai_analyze()?;
+                    let reaction = match naku {
+                        Naku::Pon(_) => Reaction::Pon(mentsu),
+                        _ => Reaction::Chii(mentsu),
+                    };
+                    let (sutehai, nokori) = match conditions.first() {
+                        Some(condition) => (Some(condition.sutehai), condition.nokori()),
+                        None => (None, 0),
+                    };
+                    results.push(ReactionResult {
+                        reaction,
+                        shanten,
+                        sutehai,
+                        nokori,
+                    });
+                }
+            }
+        }
+
+        // Stable sort keeps ron and passing before naku when equal.
+        results.sort_by(|lhs, rhs| {
+            lhs.shanten
+                .cmp(&rhs.shanten)
+                .then(lhs.nokori.cmp(&rhs.nokori).reverse())
+        });
+        Ok(results)
+    }
+}
+
+impl ReactionResult {
+    pub fn to_json(&self) -> serde_json::Value {
+        let (reaction, mentsu) = match &self.reaction {
+            Reaction::Pass => ("pass", None),
+            Reaction::Ron => ("ron", None),
+            Reaction::Chii(mentsu) => ("chii", Some(mentsu.to_json())),
+            Reaction::Pon(mentsu) => ("pon", Some(mentsu.to_json())),
+            Reaction::Kan(mentsu) => ("kan", Some(mentsu.to_json())),
+        };
+        json!({
+            "reaction": reaction,
+            "mentsu": mentsu,
+            "shanten_number": self.shanten,
+            "sutehai": self.sutehai.map(|hai| hai.to_string()),
+            "machihai_number": self.nokori,
+        })
+    }
+}
+
+impl std::fmt::Display for ReactionResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reaction_string = match &self.reaction {
+            Reaction::Pass => "見送り".to_string(),
+            Reaction::Ron => return write!(f, "栄和"),
+            Reaction::Chii(mentsu) => format!("吃 {}", mentsu),
+            Reaction::Pon(mentsu) => format!("碰 {}", mentsu),
+            Reaction::Kan(mentsu) => format!("槓 {}", mentsu),
+        };
+        let sutehai_string = match self.sutehai {
+            Some(sutehai) => format!(" 打 {}", sutehai),
+            None => String::new(),
+        };
+        write!(
+            f,
+            "{} 向聴 {}{} 残り{}枚",
+            reaction_string, self.shanten, sutehai_string, self.nokori
+        )
+    }
+}
+
+/// Shanten and number of hai reducing it of tehai lacking one hai, counted by haiyama.
+fn waiting(game_manager: &GameManager) -> Result<(i32, usize), String> {
+    let tehai = game_manager
+        .tehai()
+        .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+    let counts = tehai.to_counts();
+    let fuuro_count = tehai.fuuro.len();
+    let haiyama_counts = game_manager.haiyama().to_counts();
+    let mut nokori = 0;
+    for (index, machi) in shanten::machi_counts(&counts, fuuro_count)
+        .iter()
+        .enumerate()
+    {
+        if *machi {
+            nokori += haiyama_counts[index] as usize;
+        }
+    }
+    Ok((shanten::calculate_counts(&counts, fuuro_count), nokori))
+}
+
+/// All juntsu made of nakihai and two hai in juntehai.
+fn juntsu_with(nakihai: &Hai, game_manager: &GameManager) -> Vec<Mentsu> {
+    let mut juntsu_vec = vec![];
+    let tehai = match game_manager.tehai() {
+        Some(tehai) => tehai,
+        None => return juntsu_vec,
+    };
+    if nakihai.is_jihai() {
+        return juntsu_vec;
+    }
+    let index = nakihai.to_index() as i32;
+    for start in index - 2..=index {
+        // All three hai must be in the same type.
+        if start < 0 || start / 9 != index / 9 || (start + 2) / 9 != index / 9 {
+            continue;
+        }
+        let mut hai_vec = vec![];
+        for offset in 0..3 {
+            // Index is always less than 27 here.
+            hai_vec.push(Hai::from_index((start + offset) as usize).unwrap());
+        }
+        let in_juntehai = hai_vec
+            .iter()
+            .filter(|hai| *hai != nakihai)
+            .all(|hai| tehai.juntehai.contains(hai));
+        if in_juntehai {
+            if let Some(juntsu) = Mentsu::new(&hai_vec, game_manager.player_number()) {
+                juntsu_vec.push(juntsu);
+            }
+        }
+    }
+    juntsu_vec
+}
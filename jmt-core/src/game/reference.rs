@@ -0,0 +1,189 @@
+//! Slow but obviously correct shanten, only built with the `validate` feature.
+//!
+//! Shanten is defined here as the least number of hai missing from any winning shape, minus
+//! 1, by trying every combination of mentsu and jantou. Same as the fast backends, it only
+//! measures the shape, so the winning shape may need a fifth hai of a type, such as tenpai
+//! waiting for hai all in tehai (純カラ). It shares no code with `shanten` or
+//! `Tehai::analyze()`, so a disagreement found by cross checking means that one of them is
+//! wrong, which has to be told by hand.
+
+use super::{shanten, simulate::Rng, Hai, PlayerNumber, Tehai};
+
+/// Shanten from 34-index counts whose sum is `13 - 3 * fuuro_count` or
+/// `14 - 3 * fuuro_count`, -1 if already won.
+///
+/// Chiitoitsu and kokushimusou are considered only if `fuuro_count` is 0. Hai of a type in
+/// the winning shape are not limited to 4, see the module documentation.
+pub fn calculate_counts(counts: &[u8; 34], fuuro_count: usize, player_number: PlayerNumber) -> i32 {
+    let valid: Vec<usize> = (0..34)
+        .filter(|index| Hai::from_index(*index).unwrap().is_valid(player_number))
+        .collect();
+
+    // Every koutsu and juntsu in the game.
+    let mut mentsu_vec = vec![];
+    for index in &valid {
+        mentsu_vec.push(vec![*index, *index, *index]);
+        if *index < 27 && index % 9 <= 6 && valid.contains(&(index + 1)) {
+            mentsu_vec.push(vec![*index, index + 1, index + 2]);
+        }
+    }
+
+    let mut least_missing = usize::MAX;
+    let mut target = [0u8; 34];
+    search_mentsute(
+        counts,
+        &mentsu_vec,
+        &valid,
+        0,
+        4 - fuuro_count,
+        &mut target,
+        0,
+        &mut least_missing,
+    );
+
+    if fuuro_count == 0 {
+        // Chiitoitsu takes the 7 types which have the most hai up to 2.
+        let mut pair_vec: Vec<usize> = valid
+            .iter()
+            .map(|index| counts[*index].min(2) as usize)
+            .collect();
+        pair_vec.sort_by(|lhs, rhs| rhs.cmp(lhs));
+        let missing = 14 - pair_vec.iter().take(7).sum::<usize>();
+        least_missing = least_missing.min(missing);
+
+        // Kokushimusou takes each yaochuuhai once, and any of them twice.
+        let yaochuuhai: Vec<usize> = Hai::iter_all(player_number)
+            .filter(Hai::is_yaochuu)
+            .map(|hai| hai.to_index())
+            .collect();
+        for jantou in &yaochuuhai {
+            let missing = yaochuuhai
+                .iter()
+                .map(|index| {
+                    let needed = if index == jantou { 2 } else { 1 };
+                    needed - counts[*index].min(needed)
+                })
+                .sum::<u8>() as usize;
+            least_missing = least_missing.min(missing);
+        }
+    }
+
+    least_missing as i32 - 1
+}
+
+/// Choose `rest` more mentsu from `mentsu_vec[start..]` and a jantou, keeping the least
+/// number of hai missing from `counts`.
+fn search_mentsute(
+    counts: &[u8; 34],
+    mentsu_vec: &[Vec<usize>],
+    valid: &[usize],
+    start: usize,
+    rest: usize,
+    target: &mut [u8; 34],
+    missing: usize,
+    least_missing: &mut usize,
+) {
+    // Missing hai never decrease by adding more.
+    if missing >= *least_missing {
+        return;
+    }
+    if rest == 0 {
+        for index in valid {
+            let missing = missing
+                + (target[*index] as usize + 2)
+                    .saturating_sub(counts[*index].max(target[*index]) as usize);
+            *least_missing = (*least_missing).min(missing);
+        }
+        return;
+    }
+    for (offset, mentsu) in mentsu_vec[start..].iter().enumerate() {
+        // Koutsu on a type already taken twice needs 6 or more hai of it, which no tehai can
+        // make use of, and skipping it keeps the search small.
+        if mentsu[0] == mentsu[1] && target[mentsu[0]] > 1 {
+            continue;
+        }
+        let mut added_missing = 0;
+        for index in mentsu {
+            if target[*index] >= counts[*index] {
+                added_missing += 1;
+            }
+            target[*index] += 1;
+        }
+        search_mentsute(
+            counts,
+            mentsu_vec,
+            valid,
+            start + offset,
+            rest - 1,
+            target,
+            missing + added_missing,
+            least_missing,
+        );
+        for index in mentsu {
+            target[*index] -= 1;
+        }
+    }
+}
+
+/// Compare shanten of the reference with `shanten::calculate_counts()`, and with
+/// `Tehai::analyze()` if the number of hai is `3k+2`.
+pub fn cross_check(
+    counts: &[u8; 34],
+    fuuro_count: usize,
+    player_number: PlayerNumber,
+) -> Result<(), String> {
+    let expected = calculate_counts(counts, fuuro_count, player_number);
+    let tehai = Tehai::from_counts(counts, player_number)?;
+
+    let actual = shanten::calculate_counts(counts, fuuro_count);
+    if actual != expected {
+        return Err(format!(
+            "Shanten of {} with {} fuuro is {} by shanten::calculate_counts(), but {} by reference.",
+            tehai, fuuro_count, actual, expected
+        ));
+    }
+    if tehai.juntehai.len() % 3 == 2 {
+        let (actual, _) = tehai.analyze(player_number, None)?;
+        if actual != expected {
+            return Err(format!(
+                "Shanten of {} with {} fuuro is {} by Tehai::analyze(), but {} by reference.",
+                tehai, fuuro_count, actual, expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Cross check random hands of `size` hai dealt from a full haiyama, return the first
+/// disagreement as error.
+pub fn fuzz(
+    size: usize,
+    iterations: usize,
+    seed: u64,
+    player_number: PlayerNumber,
+) -> Result<(), String> {
+    if size == 0 || size > 14 || size % 3 == 0 {
+        return Err(format!(
+            "The number of hai must be 3*k+1 or 3*k+2 and at most 14, but {} provided.",
+            size
+        ));
+    }
+    let fuuro_count = (14 - size) / 3;
+    let mut yama = vec![];
+    for hai in Hai::all_type(player_number) {
+        for _ in 0..4 {
+            yama.push(hai.to_index());
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    for _ in 0..iterations {
+        rng.shuffle(&mut yama);
+        let mut counts = [0u8; 34];
+        for index in &yama[..size] {
+            counts[*index] += 1;
+        }
+        cross_check(&counts, fuuro_count, player_number)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,799 @@
+use super::{
+    simulate, Annotation, GameManager, Hai, Haiyama, MachiCondition, Operation, PlayerNumber,
+    State, Tehai, TehaiOperation,
+};
+use crate::i18n::{self, Message};
+use serde_json::json;
+use std::{cmp::Ordering, collections::BTreeMap};
+
+/// Rating of one discard compared with the analysis.
+///
+/// # Japanese
+/// * sutehai: 捨て牌
+/// * nokori: 残り
+///
+/// # Member
+/// * tehai: Tehai just before discarding.
+/// * shanten: Shanten number of tehai.
+/// * sutehai: Hai actually discarded.
+/// * nokori: Number of hai actual discard waiting for, 0 if it raises shanten.
+/// * best_sutehai: All discards which wait for the most hai.
+/// * best_nokori: Number of hai the best discards waiting for.
+/// * annotations: Annotations of the discard, and of operations since the last rated
+///   discard, such as "considering pon here" on sutehai of others.
+#[derive(Clone, Debug)]
+pub struct DiscardRating {
+    pub tehai: Tehai,
+    pub shanten: i32,
+    pub sutehai: Hai,
+    pub nokori: usize,
+    pub best_sutehai: Vec<Hai>,
+    pub best_nokori: usize,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Number of sutehai in the ranking printed at each step of a review, besides the actual one.
+pub const REVIEW_RANKING_SHOWN: usize = 5;
+
+/// A decision point in a review, which is a discard at full hai state.
+///
+/// # Member
+/// * index: Index of the discard in operations.
+/// * hand: Number of the hand counted from 1, increased by initializing tehai.
+/// * tehai: Tehai just before discarding.
+/// * shanten: Shanten number of tehai.
+/// * ranking: Conditions of sutehai keeping shanten, in the order recommended.
+/// * sutehai: Hai actually discarded.
+/// * rank: Position of the actual sutehai in ranking from 0, `None` if it raises shanten.
+/// * annotations: Annotations of the discard, and of operations since the last step.
+/// * haiyama: Hai which can be drawn before discarding.
+/// * hora_loss: Win probability lost compared with the best sutehai, estimated by
+///   `Review::evaluate()`.
+#[derive(Clone, Debug)]
+pub struct ReviewStep {
+    pub index: usize,
+    pub hand: usize,
+    pub tehai: Tehai,
+    pub shanten: i32,
+    pub ranking: Vec<MachiCondition>,
+    pub sutehai: Hai,
+    pub rank: Option<usize>,
+    pub annotations: Vec<Annotation>,
+    pub haiyama: Haiyama,
+    pub hora_loss: Option<f64>,
+}
+
+/// Default of `Review::threshold`.
+pub const MISTAKE_THRESHOLD: f64 = 0.2;
+
+/// Number of mistakes listed as the biggest of a review.
+pub const BIGGEST_MISTAKES: usize = 3;
+
+/// Iterations of simulation for each mistake in `Review::evaluate()`.
+pub const REVIEW_ITERATIONS: usize = 300;
+
+/// Severity of a mistake in review.
+///
+/// # Member
+/// * Minor: Lost acceptance not less than the threshold, but less than half.
+/// * Major: Lost at least half of acceptance.
+/// * Backward: Raised shanten, which loses all acceptance of the best.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Minor,
+    Major,
+    Backward,
+}
+
+/// Mistakes of one hand in review.
+///
+/// # Member
+/// * hand: Number of the hand counted from 1.
+/// * steps: Number of steps reviewed in the hand.
+/// * minor, major, backward: Number of mistakes of each severity.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HandMistakes {
+    pub hand: usize,
+    pub steps: usize,
+    pub minor: usize,
+    pub major: usize,
+    pub backward: usize,
+}
+
+/// Turn-by-turn review of a game, such as a loaded kifu, which re-analyzes tehai at every
+/// discard and puts the ranking of analysis next to the actual choice.
+///
+/// # Member
+/// * steps: All decision points in order.
+/// * threshold: Ratio of acceptance lost by a discard, compared with the best, from which it
+///   is marked as a mistake.
+#[derive(Clone, Debug)]
+pub struct Review {
+    pub steps: Vec<ReviewStep>,
+    pub threshold: f64,
+}
+
+/// Agreement of actual sutehai with the ranking of analysis, summed over steps of reviews.
+///
+/// # Member
+/// * games: Number of reviews summed.
+/// * steps: Number of steps.
+/// * first: Number of steps where the actual sutehai is ranked first.
+/// * top3: Number of steps where the actual sutehai is ranked in the first three.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Agreement {
+    pub games: usize,
+    pub steps: usize,
+    pub first: usize,
+    pub top3: usize,
+}
+
+/// Ratings of all discards in one hand, from initializing tehai to the end.
+#[derive(Clone, Debug, Default)]
+pub struct HandRating {
+    pub discards: Vec<DiscardRating>,
+}
+
+/// Discard efficiency of a whole game.
+#[derive(Clone, Debug, Default)]
+pub struct EfficiencyReport {
+    pub hands: Vec<HandRating>,
+}
+
+impl DiscardRating {
+    /// Efficiency of this discard, from 0.0 to 1.0.
+    pub fn efficiency(&self) -> f64 {
+        self.nokori as f64 / self.best_nokori as f64
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut best_sutehai_string_vec = vec![];
+        for hai in &self.best_sutehai {
+            best_sutehai_string_vec.push(hai.to_string());
+        }
+        json!({
+            "tehai": self.tehai.to_json(),
+            "shanten_number": self.shanten,
+            "sutehai": self.sutehai.to_string(),
+            "machihai_number": self.nokori,
+            "best_sutehai": best_sutehai_string_vec,
+            "best_machihai_number": self.best_nokori,
+            "efficiency": self.efficiency(),
+            "annotations": self
+                .annotations
+                .iter()
+                .map(Annotation::to_json)
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl HandRating {
+    /// Average efficiency of all discards in this hand. `None` if nothing rated.
+    pub fn efficiency(&self) -> Option<f64> {
+        average(self.discards.iter())
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut discard_json_vec = vec![];
+        for discard in &self.discards {
+            discard_json_vec.push(discard.to_json());
+        }
+        json!({
+            "discards": discard_json_vec,
+            "efficiency": self.efficiency(),
+        })
+    }
+}
+
+impl EfficiencyReport {
+    /// Rate every discard in a sequence of operations, like the history of a game manager.
+    ///
+    /// Operations are replayed on a fresh game manager. Each discard at full hai state is
+    /// compared with the analysis of tehai at that moment. Initializing tehai again starts
+    /// a new hand. Annotations are by the index of operations, like those of a game manager.
+    pub fn rate(
+        operations: &[Operation],
+        annotations: &BTreeMap<usize, Vec<Annotation>>,
+        player_number: PlayerNumber,
+    ) -> Result<Self, String> {
+        let mut report = Self::default();
+        let mut game_manager = GameManager::new(player_number);
+        let mut hand = HandRating::default();
+        let mut pending_annotations = vec![];
+
+        for (index, op) in operations.iter().enumerate() {
+            let op = op.unresolved();
+            match (&op, game_manager.state) {
+                (Operation::Tehai(TehaiOperation::Initialize(_)), State::WaitToInit) => (),
+                (Operation::Tehai(TehaiOperation::Initialize(_)), _) => {
+                    report.push_hand(std::mem::take(&mut hand));
+                    game_manager.reinitialize(player_number);
+                    pending_annotations.clear();
+                }
+                _ => (),
+            }
+            pending_annotations.extend(annotations.get(&index).into_iter().flatten().cloned());
+            match (&op, game_manager.state) {
+                (Operation::Tehai(TehaiOperation::Discard(sutehai)), State::FullHai)
+                | (Operation::Tehai(TehaiOperation::Riichi(sutehai)), State::FullHai) => {
+                    if let Some(mut rating) = rate_discard(&game_manager, *sutehai)? {
+                        rating.annotations = std::mem::take(&mut pending_annotations);
+                        hand.discards.push(rating);
+                    }
+                }
+                _ => (),
+            }
+            if let Err(error) = game_manager.operate(op) {
+                return Err(format!("Failed to replay operation [{}]: {}", index, error));
+            }
+        }
+        report.push_hand(hand);
+
+        Ok(report)
+    }
+
+    /// Average efficiency of all discards in the game. `None` if nothing rated.
+    pub fn efficiency(&self) -> Option<f64> {
+        average(self.hands.iter().flat_map(|hand| hand.discards.iter()))
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut hand_json_vec = vec![];
+        for hand in &self.hands {
+            hand_json_vec.push(hand.to_json());
+        }
+        json!({
+            "hands": hand_json_vec,
+            "efficiency": self.efficiency(),
+        })
+    }
+
+    fn push_hand(&mut self, hand: HandRating) {
+        if !hand.discards.is_empty() {
+            self.hands.push(hand);
+        }
+    }
+}
+
+impl Severity {
+    /// Name of the severity in snake case, which is stable for tools.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+            Severity::Backward => "backward",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Minor => write!(f, "小"),
+            Severity::Major => write!(f, "大"),
+            Severity::Backward => write!(f, "向聴戻し"),
+        }
+    }
+}
+
+impl HandMistakes {
+    /// Total number of mistakes.
+    pub fn mistakes(&self) -> usize {
+        self.minor + self.major + self.backward
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "hand": self.hand,
+            "steps": self.steps,
+            "mistakes": self.mistakes(),
+            "minor": self.minor,
+            "major": self.major,
+            "backward": self.backward,
+        })
+    }
+}
+
+impl std::fmt::Display for HandMistakes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "局 {}：{}打中 ミス {}回（大 {} 小 {} 向聴戻し {}）",
+            self.hand,
+            self.steps,
+            self.mistakes(),
+            self.major,
+            self.minor,
+            self.backward
+        )
+    }
+}
+
+impl Agreement {
+    /// Agreement of one review as a game.
+    pub fn from_review(review: &Review) -> Self {
+        let rank_within = |number: usize| {
+            review
+                .steps
+                .iter()
+                .filter(|step| step.rank.is_some_and(|rank| rank < number))
+                .count()
+        };
+        Self {
+            games: 1,
+            steps: review.steps.len(),
+            first: rank_within(1),
+            top3: rank_within(3),
+        }
+    }
+
+    /// Sum up with another agreement.
+    pub fn add(&mut self, other: &Agreement) -> &mut Self {
+        self.games += other.games;
+        self.steps += other.steps;
+        self.first += other.first;
+        self.top3 += other.top3;
+        self
+    }
+
+    /// Ratio of steps agreeing with the first choice. `None` if no step.
+    pub fn first_rate(&self) -> Option<f64> {
+        self.rate(self.first)
+    }
+
+    /// Ratio of steps agreeing with any of the first three choices. `None` if no step.
+    pub fn top3_rate(&self) -> Option<f64> {
+        self.rate(self.top3)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "games": self.games,
+            "steps": self.steps,
+            "first": self.first,
+            "top3": self.top3,
+            "first_rate": self.first_rate(),
+            "top3_rate": self.top3_rate(),
+        })
+    }
+
+    fn rate(&self, agreed: usize) -> Option<f64> {
+        if self.steps == 0 {
+            None
+        } else {
+            Some(agreed as f64 / self.steps as f64)
+        }
+    }
+}
+
+impl std::fmt::Display for Agreement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let percent = |rate: Option<f64>| {
+            rate.map_or("-".to_string(), |rate| format!("{:.1}%", rate * 100.0))
+        };
+        write!(
+            f,
+            "{}局 {}打 一致率 {} 上位3 {}",
+            self.games,
+            self.steps,
+            percent(self.first_rate()),
+            percent(self.top3_rate())
+        )
+    }
+}
+
+impl ReviewStep {
+    /// Ratio of acceptance lost by the actual sutehai compared with the best, 1.0 if it
+    /// raises shanten. `None` if tehai has already won or nothing is waiting for.
+    pub fn loss(&self) -> Option<f64> {
+        let best_nokori = match self.ranking.first() {
+            Some(condition) if self.shanten >= 0 && condition.nokori() > 0 => condition.nokori(),
+            _ => return None,
+        };
+        match self.rank {
+            Some(rank) => Some(1.0 - self.ranking[rank].nokori() as f64 / best_nokori as f64),
+            None => Some(1.0),
+        }
+    }
+
+    /// Severity of the actual sutehai as a mistake, `None` if its loss is below `threshold`.
+    pub fn mistake(&self, threshold: f64) -> Option<Severity> {
+        let loss = self.loss()?;
+        if self.rank.is_none() {
+            Some(Severity::Backward)
+        } else if loss >= 0.5 && loss >= threshold {
+            Some(Severity::Major)
+        } else if loss >= threshold && loss > 0.0 {
+            Some(Severity::Minor)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut ranking_json_vec = vec![];
+        for condition in &self.ranking {
+            ranking_json_vec.push(condition.to_json());
+        }
+        json!({
+            "index": self.index,
+            "hand": self.hand,
+            "tehai": self.tehai.to_json(),
+            "shanten_number": self.shanten,
+            "ranking": ranking_json_vec,
+            "sutehai": self.sutehai.to_string(),
+            "rank": self.rank,
+            "annotations": self
+                .annotations
+                .iter()
+                .map(Annotation::to_json)
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl Review {
+    /// Replay operations on a fresh game manager and analyze tehai before every discard at
+    /// full hai state. Annotations are by the index of operations, like those of a game
+    /// manager.
+    pub fn new(
+        operations: &[Operation],
+        annotations: &BTreeMap<usize, Vec<Annotation>>,
+        player_number: PlayerNumber,
+    ) -> Result<Self, String> {
+        let mut review = Self {
+            steps: vec![],
+            threshold: MISTAKE_THRESHOLD,
+        };
+        let mut game_manager = GameManager::new(player_number);
+        let mut hand = 0;
+        let mut pending_annotations = vec![];
+
+        for (index, op) in operations.iter().enumerate() {
+            let op = op.unresolved();
+            if let Operation::Tehai(TehaiOperation::Initialize(_)) = &op {
+                if game_manager.state != State::WaitToInit {
+                    game_manager.reinitialize(player_number);
+                }
+                hand += 1;
+                pending_annotations.clear();
+            }
+            pending_annotations.extend(annotations.get(&index).into_iter().flatten().cloned());
+            match (&op, game_manager.state) {
+                (Operation::Tehai(TehaiOperation::Discard(sutehai)), State::FullHai)
+                | (Operation::Tehai(TehaiOperation::Riichi(sutehai)), State::FullHai) => {
+                    let tehai = game_manager
+                        .tehai()
+                        .ok_or(i18n::text(Message::NotInitialized).to_string())?
+                        .clone();
+                    let (shanten, ranking) = game_manager.tehai_analyze()?;
+                    review.steps.push(ReviewStep {
+                        index,
+                        hand,
+                        tehai,
+                        shanten,
+                        rank: ranking
+                            .iter()
+                            .position(|condition| condition.sutehai == *sutehai),
+                        ranking,
+                        sutehai: *sutehai,
+                        annotations: std::mem::take(&mut pending_annotations),
+                        haiyama: game_manager.haiyama().clone(),
+                        hora_loss: None,
+                    });
+                }
+                _ => (),
+            }
+            if let Err(error) = game_manager.operate(op) {
+                return Err(format!("Failed to replay operation [{}]: {}", index, error));
+            }
+        }
+
+        Ok(review)
+    }
+
+    /// Ratio of steps where the actual sutehai is ranked first. `None` if no step.
+    pub fn agreement(&self) -> Option<f64> {
+        Agreement::from_review(self).first_rate()
+    }
+
+    /// Estimate win probability lost by each mistake under current threshold, comparing it
+    /// with the best sutehai on the same draw sequences, see `simulate::compare()`.
+    pub fn evaluate(&mut self, iterations: usize) -> Result<&mut Self, String> {
+        let threshold = self.threshold;
+        let cache = simulate::RolloutCache::new();
+        for step in &mut self.steps {
+            step.hora_loss = None;
+            if step.mistake(threshold).is_none() {
+                continue;
+            }
+            let best_sutehai = step.ranking[0].sutehai;
+            let comparison = simulate::compare_with_cache(
+                &step.tehai,
+                &best_sutehai,
+                &step.sutehai,
+                &step.haiyama,
+                iterations,
+                &cache,
+            )?;
+            step.hora_loss = Some(
+                (comparison.results[0].hora_rate() - comparison.results[1].hora_rate()).max(0.0),
+            );
+        }
+        Ok(self)
+    }
+
+    /// Mistakes losing the most win probability, or acceptance if not evaluated, at most
+    /// `number` of them.
+    pub fn biggest_mistakes(&self, number: usize) -> Vec<&ReviewStep> {
+        let mut mistakes: Vec<&ReviewStep> = self
+            .steps
+            .iter()
+            .filter(|step| step.mistake(self.threshold).is_some())
+            .collect();
+        let key = |step: &ReviewStep| (step.hora_loss.unwrap_or(0.0), step.loss().unwrap_or(0.0));
+        // Stable sort keeps earlier mistakes first among equals.
+        mistakes.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal));
+        mistakes.truncate(number);
+        mistakes
+    }
+
+    /// Mistakes of each hand with any step, in order.
+    pub fn hand_mistakes(&self) -> Vec<HandMistakes> {
+        let mut hand_mistakes_vec: Vec<HandMistakes> = vec![];
+        for step in &self.steps {
+            if hand_mistakes_vec.last().map(|last| last.hand) != Some(step.hand) {
+                hand_mistakes_vec.push(HandMistakes {
+                    hand: step.hand,
+                    ..HandMistakes::default()
+                });
+            }
+            let hand_mistakes = hand_mistakes_vec.last_mut().unwrap();
+            hand_mistakes.steps += 1;
+            match step.mistake(self.threshold) {
+                Some(Severity::Minor) => hand_mistakes.minor += 1,
+                Some(Severity::Major) => hand_mistakes.major += 1,
+                Some(Severity::Backward) => hand_mistakes.backward += 1,
+                None => (),
+            }
+        }
+        hand_mistakes_vec
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut step_json_vec = vec![];
+        for step in &self.steps {
+            let mut step_json = step.to_json();
+            step_json["loss"] = json!(step.loss());
+            step_json["mistake"] =
+                json!(step.mistake(self.threshold).map(|severity| severity.kind()));
+            step_json["hora_loss"] = json!(step.hora_loss);
+            step_json_vec.push(step_json);
+        }
+        let biggest_mistakes: Vec<usize> = self
+            .biggest_mistakes(BIGGEST_MISTAKES)
+            .iter()
+            .map(|step| step.index)
+            .collect();
+        let mut hand_json_vec = vec![];
+        for hand_mistakes in self.hand_mistakes() {
+            hand_json_vec.push(hand_mistakes.to_json());
+        }
+        json!({
+            "steps": step_json_vec,
+            "hands": hand_json_vec,
+            "biggest_mistakes": biggest_mistakes,
+            "threshold": self.threshold,
+            "agreement": self.agreement(),
+        })
+    }
+}
+
+impl std::fmt::Display for ReviewStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "局 {} 手牌 {} {}",
+            self.hand,
+            self.tehai,
+            if self.shanten == 0 {
+                "聴牌".to_string()
+            } else if self.shanten < 0 {
+                "和了".to_string()
+            } else {
+                format!("{}向聴", self.shanten)
+            }
+        )?;
+        for (rank, condition) in self.ranking.iter().enumerate() {
+            let actual = self.rank == Some(rank);
+            if rank >= REVIEW_RANKING_SHOWN && !actual {
+                continue;
+            }
+            let mut machihai_string = String::new();
+            for hai in condition.machihai.keys() {
+                machihai_string += &hai.to_string();
+            }
+            write!(
+                f,
+                "\n  {}. 打 {} 摸 {} 残り{}枚{}",
+                rank + 1,
+                condition.sutehai,
+                machihai_string,
+                condition.nokori(),
+                if actual { " ← 実戦" } else { "" }
+            )?;
+        }
+        if self.rank.is_none() {
+            write!(f, "\n  -. 打 {} 向聴戻し ← 実戦", self.sutehai)?;
+        }
+        for annotation in &self.annotations {
+            write!(f, "\n  @ {}", annotation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Review {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.steps.is_empty() {
+            return write!(f, "No discard to review.");
+        }
+        let hand_mistakes_vec = self.hand_mistakes();
+        for (step_index, step) in self.steps.iter().enumerate() {
+            write!(f, "[{}] {}", step_index + 1, step)?;
+            if let Some(severity) = step.mistake(self.threshold) {
+                write!(
+                    f,
+                    "\n  ミス（{}）受け入れ -{:.1}%",
+                    severity,
+                    step.loss().unwrap_or(0.0) * 100.0
+                )?;
+                if let Some(hora_loss) = step.hora_loss {
+                    write!(f, " 和了率 -{:.1}%", hora_loss * 100.0)?;
+                }
+            }
+            writeln!(f)?;
+            let hand_end = self
+                .steps
+                .get(step_index + 1)
+                .is_none_or(|next| next.hand != step.hand);
+            if hand_end {
+                if let Some(hand_mistakes) = hand_mistakes_vec
+                    .iter()
+                    .find(|hand_mistakes| hand_mistakes.hand == step.hand)
+                {
+                    writeln!(f, "{}", hand_mistakes)?;
+                }
+            }
+        }
+        let biggest_mistakes = self.biggest_mistakes(BIGGEST_MISTAKES);
+        if !biggest_mistakes.is_empty() {
+            writeln!(f, "大きなミス：")?;
+            for step in biggest_mistakes {
+                let number = self
+                    .steps
+                    .iter()
+                    .position(|other| other.index == step.index)
+                    .unwrap_or(0);
+                write!(
+                    f,
+                    "  [{}] 打 {}（最善 打 {}）受け入れ -{:.1}%",
+                    number + 1,
+                    step.sutehai,
+                    step.ranking[0].sutehai,
+                    step.loss().unwrap_or(0.0) * 100.0
+                )?;
+                if let Some(hora_loss) = step.hora_loss {
+                    write!(f, " 和了率 -{:.1}%", hora_loss * 100.0)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        if let Some(agreement) = self.agreement() {
+            write!(f, "一致率：{:.1}%", agreement * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DiscardRating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut best_sutehai_string = String::new();
+        for hai in &self.best_sutehai {
+            best_sutehai_string += &hai.to_string();
+            best_sutehai_string += " ";
+        }
+        write!(
+            f,
+            "手牌 {} 向聴 {}\n  打 {} 残り{}枚 / 最善 打 {}残り{}枚 {:.1}%",
+            self.tehai,
+            self.shanten,
+            self.sutehai,
+            self.nokori,
+            best_sutehai_string,
+            self.best_nokori,
+            self.efficiency() * 100.0
+        )?;
+        for annotation in &self.annotations {
+            write!(f, "\n  @ {}", annotation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for EfficiencyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.hands.is_empty() {
+            return write!(f, "No discard to rate.");
+        }
+
+        let mut report_string = String::new();
+        for (hand_index, hand) in self.hands.iter().enumerate() {
+            for (discard_index, discard) in hand.discards.iter().enumerate() {
+                report_string +=
+                    &format!("[{}-{}] {}\n", hand_index + 1, discard_index + 1, discard);
+            }
+            if let Some(efficiency) = hand.efficiency() {
+                report_string += &format!("局 {}：{:.1}%\n", hand_index + 1, efficiency * 100.0);
+            }
+        }
+        if let Some(efficiency) = self.efficiency() {
+            report_string += &format!("総合：{:.1}%", efficiency * 100.0);
+        }
+
+        write!(f, "{}", report_string)
+    }
+}
+
+/// Rate a discard on current state of game manager, which must be full with hai.
+///
+/// Return `None` if tehai has already won or nothing is waiting for.
+fn rate_discard(game_manager: &GameManager, sutehai: Hai) -> Result<Option<DiscardRating>, String> {
+    let tehai = game_manager
+        .tehai()
+        .ok_or(i18n::text(Message::NotInitialized).to_string())?;
+    let (shanten, conditions) = game_manager.tehai_analyze()?;
+    let best_nokori = match conditions.first() {
+        Some(condition) if shanten >= 0 && condition.nokori() > 0 => condition.nokori(),
+        _ => return Ok(None),
+    };
+
+    let mut best_sutehai = vec![];
+    let mut nokori = 0;
+    for condition in &conditions {
+        if condition.nokori() == best_nokori {
+            best_sutehai.push(condition.sutehai);
+        }
+        if condition.sutehai == sutehai {
+            nokori = condition.nokori();
+        }
+    }
+
+    Ok(Some(DiscardRating {
+        tehai: tehai.clone(),
+        shanten,
+        sutehai,
+        nokori,
+        best_sutehai,
+        best_nokori,
+        annotations: vec![],
+    }))
+}
+
+fn average<'a, T>(iter: T) -> Option<f64>
+where
+    T: Iterator<Item = &'a DiscardRating>,
+{
+    let mut sum = 0.0;
+    let mut count = 0;
+    for discard in iter {
+        sum += discard.efficiency();
+        count += 1;
+    }
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f64)
+    }
+}
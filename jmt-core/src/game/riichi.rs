@@ -0,0 +1,131 @@
+//! Legality of declaring riichi.
+
+use super::{shanten, GameManager, Hai, PlayerNumber, State};
+
+/// Points needed to declare riichi, paid as a riichi stick.
+pub const RIICHI_POINTS: i32 = 1000;
+
+/// Number of hai which must be left in haiyama to declare riichi, so that self can draw
+/// at least once more.
+pub const RIICHI_MIN_HAIYAMA: usize = 4;
+
+/// Number of hai in wanpai, which can never be drawn except rinshanhai.
+///
+/// # Japanese
+/// * wanpai: 王牌
+const WANPAI_NUMBER: usize = 14;
+
+/// Reason why riichi cannot be declared.
+///
+/// # Japanese
+/// * menzen: 門前, no naku except ankan.
+///
+/// # Member
+/// * NotFullHai: Riichi is declared with a discard, so tehai must be full with hai.
+/// * AlreadyRiichi: Riichi has been declared.
+/// * NotMenzen: Tehai has naku other than ankan.
+/// * NotEnoughPoints: Points of self, less than `RIICHI_POINTS`.
+/// * NotEnoughHaiyama: Estimated number of hai left to draw, less than `RIICHI_MIN_HAIYAMA`.
+/// * NotTenpai: No discard keeps tenpai, with shanten after the best discard.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    NotFullHai,
+    AlreadyRiichi,
+    NotMenzen,
+    NotEnoughPoints(i32),
+    NotEnoughHaiyama(usize),
+    NotTenpai(i32),
+}
+
+/// Return all types of sutehai keeping tenpai, if riichi can be declared.
+pub fn can_riichi(game_manager: &GameManager) -> Result<Vec<Hai>, Reason> {
+    if game_manager.state != State::FullHai {
+        return Err(Reason::NotFullHai);
+    }
+    let tehai = game_manager.tehai().ok_or(Reason::NotFullHai)?;
+    if game_manager.kawa().is_riichi() {
+        return Err(Reason::AlreadyRiichi);
+    }
+    if !tehai.is_menzen() {
+        return Err(Reason::NotMenzen);
+    }
+    if game_manager.points() < RIICHI_POINTS {
+        return Err(Reason::NotEnoughPoints(game_manager.points()));
+    }
+    let haiyama_number = tsumo_number(game_manager);
+    if haiyama_number < RIICHI_MIN_HAIYAMA {
+        return Err(Reason::NotEnoughHaiyama(haiyama_number));
+    }
+
+    let mut counts = tehai.to_counts();
+    let fuuro_count = tehai.fuuro.len();
+    let mut sutehai_vec = vec![];
+    let mut min_shanten = i32::MAX;
+    for index in 0..34 {
+        if counts[index] == 0 {
+            continue;
+        }
+        counts[index] -= 1;
+        let shanten = shanten::calculate_counts(&counts, fuuro_count);
+        counts[index] += 1;
+        min_shanten = min_shanten.min(shanten);
+        if shanten == 0 {
+            // Index is always less than 34.
+            sutehai_vec.push(Hai::from_index(index).unwrap());
+        }
+    }
+    if sutehai_vec.len() == 0 {
+        return Err(Reason::NotTenpai(min_shanten));
+    }
+    Ok(sutehai_vec)
+}
+
+/// Estimate number of hai left to draw, assuming hai unseen are in haiyama, wanpai and
+/// juntehai of others, which is 13 each. If the turn is known, every player is assumed to
+/// have drawn that many hai, which bounds the estimate when sutehai of others are not input.
+pub fn tsumo_number(game_manager: &GameManager) -> usize {
+    let unseen: usize = game_manager
+        .haiyama()
+        .to_counts()
+        .iter()
+        .map(|number| *number as usize)
+        .sum();
+    let others = match game_manager.player_number() {
+        PlayerNumber::Four => 3,
+        PlayerNumber::Three => 2,
+    };
+    let estimate = unseen.saturating_sub(13 * others + WANPAI_NUMBER);
+    match game_manager.turn() {
+        Some(turn) => {
+            let total = Hai::all_type(game_manager.player_number()).len() * 4;
+            let drawn = (13 + turn) * (others + 1) + WANPAI_NUMBER;
+            estimate.min(total.saturating_sub(drawn))
+        }
+        None => estimate,
+    }
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::NotFullHai => write!(f, "Can only declare riichi when full with hai."),
+            Reason::AlreadyRiichi => write!(f, "Already declared riichi."),
+            Reason::NotMenzen => write!(f, "Cannot declare riichi with naku except ankan."),
+            Reason::NotEnoughPoints(points) => write!(
+                f,
+                "Need {} points to declare riichi, but only {} left.",
+                RIICHI_POINTS, points
+            ),
+            Reason::NotEnoughHaiyama(number) => write!(
+                f,
+                "Need {} hai left in haiyama to declare riichi, but only about {} left.",
+                RIICHI_MIN_HAIYAMA, number
+            ),
+            Reason::NotTenpai(shanten) => write!(
+                f,
+                "Cannot declare riichi without tenpai, shanten is {} after any discard.",
+                shanten
+            ),
+        }
+    }
+}
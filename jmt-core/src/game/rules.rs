@@ -0,0 +1,18 @@
+/// Optional rules which differ between variants.
+///
+/// # Japanese
+/// * aotenjou: 青天井
+/// * kuikae: 喰い替え
+/// * kuitan nashi: 喰いタンなし
+///
+/// # Member
+/// * aotenjou: No limit of score, han keep doubling without mangan caps.
+/// * kuikae: Allow discarding nakihai or the hai swapping it in juntsu right after chii or
+/// pon, such as discarding 6m after chii 3m with 45m. Forbidden by default.
+/// * kuitan_nashi: Tanyao is not a yaku with fuuro other than ankan. Allowed by default.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub aotenjou: bool,
+    pub kuikae: bool,
+    pub kuitan_nashi: bool,
+}
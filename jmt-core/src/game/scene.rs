@@ -0,0 +1,90 @@
+//! One-line notation of a whole situation, for asking about a hand without setting up a game
+//! step by step.
+//!
+//! A scene is `key=value` pairs separated by `;`, of which only `hand` is required:
+//!
+//! ```text
+//! hand=123m456p78s1155z[4'56m]; dora=3p; seat=S; round=E; turn=6; river=19m4z; seen=666z
+//! ```
+//!
+//! * hand: Tehai of 13 or 14 hai in the syntax of `Tehai::new()`, fuuro included.
+//! * dora: Dora indicators.
+//! * seat, round: Jikaze and bakaze, like `S`, `nan` or `2z`. Ton by default.
+//! * turn: Current turn counted by draws of self, see `GameManager::set_turn()`.
+//! * river: Sutehai of self in order, which decide furiten.
+//! * seen: Other visible hai, such as sutehai and fuuro of others.
+//!
+//! Hai of dora indicators, river and seen are removed from haiyama together with tehai, so
+//! that the scene passes `validate`.
+
+use super::{
+    GameManager, Hai, HaiyamaOperation, Kawa, Kaze, Operation, PlayerNumber, Tehai, TehaiOperation,
+};
+
+impl GameManager {
+    /// Create a game manager ready to analyze from a scene, see the module document.
+    pub fn from_scene(scene: &str, player_number: PlayerNumber) -> Result<Self, String> {
+        let mut hand = None;
+        let mut dora_indicators = vec![];
+        let mut jikaze = Kaze::Ton;
+        let mut bakaze = Kaze::Ton;
+        let mut turn = None;
+        let mut river = vec![];
+        let mut seen = vec![];
+
+        let mut keys = vec![];
+        for pair in scene
+            .split(';')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            let (key, value) = match pair.find('=') {
+                Some(index) => (pair[..index].trim(), pair[index + 1..].trim()),
+                None => return Err(format!("Need 'key=value' in scene but find '{}'.", pair)),
+            };
+            if keys.contains(&key) {
+                return Err(format!("Duplicate key '{}' in scene.", key));
+            }
+            keys.push(key);
+
+            let hai_vec = || Hai::from_string_unordered(value, player_number);
+            match key {
+                "hand" => hand = Some(Tehai::new(value.to_string(), player_number)?),
+                "dora" => dora_indicators = hai_vec()?,
+                "seat" => jikaze = Kaze::from_string(&value.to_lowercase())?,
+                "round" => bakaze = Kaze::from_string(&value.to_lowercase())?,
+                "turn" => match value.parse::<usize>() {
+                    Ok(number) if number > 0 => turn = Some(number),
+                    _ => return Err(format!("Unresolved turn: {}.", value)),
+                },
+                "river" => river = hai_vec()?,
+                "seen" => seen = hai_vec()?,
+                _ => return Err(format!("Unknown key '{}' in scene.", key)),
+            }
+        }
+        let hand = hand.ok_or("Scene must have 'hand'.".to_string())?;
+
+        let mut game_manager = Self::new(player_number);
+        game_manager.set_kaze(jikaze, bakaze);
+        game_manager.set_dora_indicators(dora_indicators.clone());
+
+        let mut visible = dora_indicators;
+        visible.extend(seen);
+        if !visible.is_empty() {
+            game_manager.operate(Operation::Haiyama {
+                kind: HaiyamaOperation::Discard(visible),
+                haiyama_sensitive: true,
+            })?;
+        }
+        let mut kawa = Kawa::new();
+        for sutehai in river {
+            kawa.push(sutehai);
+        }
+        game_manager.set_kawa(kawa)?;
+        game_manager.operate(Operation::Tehai(TehaiOperation::Initialize(hand)))?;
+        if let Some(turn) = turn {
+            game_manager.set_turn(turn);
+        }
+        Ok(game_manager)
+    }
+}
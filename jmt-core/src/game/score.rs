@@ -0,0 +1,113 @@
+use super::Rules;
+use serde_json::json;
+
+/// Points of a winning hand with given han and fu.
+///
+/// # Japanese
+/// * han: 飜
+/// * fu: 符
+/// * oya: 親
+/// * ko: 子
+/// * ron: 栄和
+/// * tsumo: 自摸
+///
+/// # Member
+/// * han: Number of han.
+/// * fu: Number of fu, rounded up to tens except 25 of chiitoitsu.
+/// * oya: Whether the winner is oya.
+/// * base: Base points, `fu * 2^(han + 2)` before capping.
+/// * ron: Points paid by the player discarding the winning hai.
+/// * tsumo_ko: Points paid by each ko on tsumo.
+/// * tsumo_oya: Points paid by oya on tsumo, 0 if the winner is oya.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Score {
+    pub han: u32,
+    pub fu: u32,
+    pub oya: bool,
+    pub base: u64,
+    pub ron: u64,
+    pub tsumo_ko: u64,
+    pub tsumo_oya: u64,
+}
+
+impl Score {
+    /// Calculate points under given rules. Limits from mangan to kazoe yakuman are
+    /// applied unless `rules.aotenjou` is set.
+    pub fn new(han: u32, fu: u32, oya: bool, rules: &Rules) -> Result<Self, String> {
+        if han == 0 {
+            return Err("Han must be at least 1.".to_string());
+        }
+        if fu < 20 {
+            return Err(format!("Fu must be at least 20, but {} provided.", fu));
+        }
+        let fu = if fu == 25 { fu } else { fu.div_ceil(10) * 10 };
+
+        let base = 2u64
+            .checked_pow(han + 2)
+            .and_then(|power| power.checked_mul(fu as u64))
+            .ok_or(format!("Score of {} han {} fu is too large.", han, fu))?;
+        let base = if rules.aotenjou {
+            base
+        } else {
+            match han {
+                13..=u32::MAX => 8000,
+                11..=12 => 6000,
+                8..=10 => 4000,
+                6..=7 => 3000,
+                5 => 2000,
+                _ => base.min(2000),
+            }
+        };
+
+        let payment = |multiple: u64| -> Result<u64, String> {
+            base.checked_mul(multiple)
+                .and_then(|points| points.div_ceil(100).checked_mul(100))
+                .ok_or(format!("Score of {} han {} fu is too large.", han, fu))
+        };
+        let (ron, tsumo_ko, tsumo_oya) = if oya {
+            (payment(6)?, payment(2)?, 0)
+        } else {
+            (payment(4)?, payment(1)?, payment(2)?)
+        };
+
+        Ok(Self {
+            han,
+            fu,
+            oya,
+            base,
+            ron,
+            tsumo_ko,
+            tsumo_oya,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "han": self.han,
+            "fu": self.fu,
+            "oya": self.oya,
+            "base": self.base,
+            "ron": self.ron,
+            "tsumo_ko": self.tsumo_ko,
+            "tsumo_oya": self.tsumo_oya,
+        })
+    }
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.oya {
+            write!(
+                f,
+                "{}飜{}符 親 栄和 {} 自摸 {}オール",
+                self.han, self.fu, self.ron, self.tsumo_ko
+            )
+        } else {
+            write!(
+                f,
+                "{}飜{}符 子 栄和 {} 自摸 {}-{}",
+                self.han, self.fu, self.ron, self.tsumo_ko, self.tsumo_oya
+            )
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use super::{GameManager, PlayerNumber};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Game managers of many users keyed by session ID, for servers tracking hands of many users
+/// in one process.
+///
+/// Sessions expire after not being accessed for the lifetime. Expired sessions can not be
+/// accessed any more, and are dropped by `expire()`, which a server should call
+/// periodically. Share it between threads behind a lock such as `Mutex`.
+#[derive(Clone, Debug)]
+pub struct SessionManager {
+    sessions: HashMap<String, Session>,
+    lifetime: Duration,
+}
+
+#[derive(Clone, Debug)]
+struct Session {
+    game_manager: GameManager,
+    last_access: Instant,
+}
+
+impl Session {
+    fn is_expired(&self, lifetime: Duration, now: Instant) -> bool {
+        now.duration_since(self.last_access) >= lifetime
+    }
+}
+
+impl SessionManager {
+    /// Create a manager whose sessions expire after `lifetime` without access.
+    pub fn new(lifetime: Duration) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            lifetime,
+        }
+    }
+
+    /// Start a session with a new game. An expired session of the same ID is replaced.
+    pub fn create(
+        &mut self,
+        id: &str,
+        player_number: PlayerNumber,
+    ) -> Result<&mut GameManager, String> {
+        let now = Instant::now();
+        if let Some(session) = self.sessions.get(id) {
+            if !session.is_expired(self.lifetime, now) {
+                return Err(format!("Session '{}' already exists.", id));
+            }
+        }
+        self.sessions.insert(
+            id.to_string(),
+            Session {
+                game_manager: GameManager::new(player_number),
+                last_access: now,
+            },
+        );
+        Ok(&mut self.sessions.get_mut(id).unwrap().game_manager)
+    }
+
+    /// Access the game of a session, which also postpones its expiry.
+    pub fn get_mut(&mut self, id: &str) -> Result<&mut GameManager, String> {
+        let now = Instant::now();
+        let lifetime = self.lifetime;
+        match self.sessions.get_mut(id) {
+            Some(session) if !session.is_expired(lifetime, now) => {
+                session.last_access = now;
+                Ok(&mut session.game_manager)
+            }
+            Some(_) => Err(format!("Session '{}' has expired.", id)),
+            None => Err(format!("Session '{}' not found.", id)),
+        }
+    }
+
+    /// End a session, returning its game if not expired.
+    pub fn remove(&mut self, id: &str) -> Option<GameManager> {
+        let session = self.sessions.remove(id)?;
+        if session.is_expired(self.lifetime, Instant::now()) {
+            None
+        } else {
+            Some(session.game_manager)
+        }
+    }
+
+    /// Drop all expired sessions, returning their IDs.
+    pub fn expire(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        let lifetime = self.lifetime;
+        let expired_vec: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.is_expired(lifetime, now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired_vec.iter() {
+            self.sessions.remove(id);
+        }
+        expired_vec
+    }
+
+    /// Number of sessions, including expired ones not dropped yet.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
@@ -0,0 +1,527 @@
+use super::{shanten, Cancellation, Hai, Haiyama, Tehai};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Default max number of entries of `RolloutCache`, about 16 MB.
+pub const ROLLOUT_CACHE_CAPACITY: usize = 1 << 18;
+
+/// Monte Carlo simulation of drawing from haiyama until winning.
+///
+/// Each iteration shuffles haiyama, then draws and discards greedily, keeping the least
+/// shanten, for at most `draws` times.
+///
+/// # Member
+/// * iterations: Number of iterations.
+/// * draws: Max number of draws in each iteration, 18 is about a whole game.
+/// * seed: Seed of random number generator, same seed rolls same draw sequences.
+#[derive(Copy, Clone, Debug)]
+pub struct Simulation {
+    pub iterations: usize,
+    pub draws: usize,
+    pub seed: u64,
+}
+
+/// Progress of a running simulation.
+///
+/// # Member
+/// * completed: Number of iterations completed.
+/// * total: Number of iterations in all.
+/// * elapsed: Time since simulation started.
+/// * eta: Estimated time until simulation finishes, `None` before the first iteration.
+#[derive(Copy, Clone, Debug)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+    pub eta: Option<Duration>,
+}
+
+/// Result of simulation.
+///
+/// # Japanese
+/// * tenpai: 聴牌
+/// * hora: 和了
+///
+/// # Member
+/// * iterations: Number of iterations completed, less than required if cancelled.
+/// * tenpai: Number of iterations reaching tenpai.
+/// * hora: Number of iterations winning.
+/// * hora_draws: Sum of draws needed to win in winning iterations.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SimulationResult {
+    pub iterations: usize,
+    pub tenpai: usize,
+    pub hora: usize,
+    pub hora_draws: usize,
+}
+
+/// Result of comparing two sutehai on identical draw sequences.
+///
+/// # Member
+/// * sutehai: The two sutehai compared.
+/// * iterations: Number of iterations.
+/// * tenpai_first: Number of iterations where each sutehai reaches tenpai strictly earlier.
+/// * hora_first: Number of iterations where each sutehai wins strictly earlier.
+/// * results: Result of each sutehai alone.
+#[derive(Copy, Clone, Debug)]
+pub struct Comparison {
+    pub sutehai: [Hai; 2],
+    pub iterations: usize,
+    pub tenpai_first: [usize; 2],
+    pub hora_first: [usize; 2],
+    pub results: [SimulationResult; 2],
+}
+
+/// Shanten and the greedy sutehai by counts and the number of fuuro.
+type RolloutTable = HashMap<([u8; 34], usize), (i32, Option<usize>)>;
+
+/// Cache of each step of rollouts shared across them, since the same tehai recur constantly
+/// and judging them dominates runtime.
+///
+/// A step is keyed by counts of juntehai full with hai and the number of fuuro, which decide
+/// shanten and the greedy sutehai regardless of haiyama, so the cache stays valid across
+/// simulations of any tehai. Clones share the same entries, and it is safe to use from
+/// multiple threads. New steps are not cached once full.
+///
+/// # Member
+/// * entries: Shanten and the greedy sutehai of each step.
+/// * capacity: Max number of entries.
+/// * hits: Number of steps found in the cache.
+/// * misses: Number of steps judged.
+#[derive(Clone, Debug)]
+pub struct RolloutCache {
+    entries: Arc<RwLock<RolloutTable>>,
+    capacity: usize,
+    hits: Arc<AtomicUsize>,
+    misses: Arc<AtomicUsize>,
+}
+
+/// Random number generator of xorshift64*, enough for shuffling haiyama.
+#[derive(Copy, Clone, Debug)]
+pub struct Rng(u64);
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            draws: 18,
+            seed: 0,
+        }
+    }
+}
+
+impl Simulation {
+    /// Simulate tehai waiting to draw, which has `3k + 1` juntehai.
+    ///
+    /// # Parameters
+    /// * haiyama: Hai which can be drawn.
+    /// * progress: Called after each iteration.
+    /// * cancellation: Stop and return iterations completed if cancelled.
+    pub fn run(
+        &self,
+        tehai: &Tehai,
+        haiyama: &Haiyama,
+        progress: Option<&mut dyn FnMut(Progress)>,
+        cancellation: Option<&Cancellation>,
+    ) -> Result<SimulationResult, String> {
+        self.run_with_cache(tehai, haiyama, &RolloutCache::new(), progress, cancellation)
+    }
+
+    /// Same as `run()`, sharing steps of rollouts with other simulations by `cache`.
+    pub fn run_with_cache(
+        &self,
+        tehai: &Tehai,
+        haiyama: &Haiyama,
+        cache: &RolloutCache,
+        mut progress: Option<&mut dyn FnMut(Progress)>,
+        cancellation: Option<&Cancellation>,
+    ) -> Result<SimulationResult, String> {
+        if tehai.juntehai.len() % 3 != 1 {
+            return Err(format!(
+                "The number of hai on hand must be 3*k+1 to simulate, but {} provided.",
+                tehai.juntehai.len()
+            ));
+        }
+
+        let counts = tehai.to_counts();
+        let fuuro_count = tehai.fuuro.len();
+        let mut yama = vec![];
+        for (index, number) in haiyama.to_counts().iter().enumerate() {
+            for _ in 0..*number {
+                yama.push(index);
+            }
+        }
+
+        let start = Instant::now();
+        let mut rng = Rng::new(self.seed);
+        let mut result = SimulationResult::default();
+        for completed in 0..self.iterations {
+            if let Some(cancellation) = cancellation {
+                if cancellation.is_cancelled() {
+                    break;
+                }
+            }
+
+            rng.shuffle(&mut yama);
+            let (tenpai_draws, hora_draws) = play(&counts, fuuro_count, &yama, self.draws, cache);
+            result.iterations += 1;
+            if tenpai_draws.is_some() {
+                result.tenpai += 1;
+            }
+            if let Some(draws) = hora_draws {
+                result.hora += 1;
+                result.hora_draws += draws;
+            }
+
+            if let Some(progress) = &mut progress {
+                let elapsed = start.elapsed();
+                let completed = completed + 1;
+                progress(Progress {
+                    completed,
+                    total: self.iterations,
+                    elapsed,
+                    eta: Some(
+                        elapsed.mul_f64((self.iterations - completed) as f64 / completed as f64),
+                    ),
+                });
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Compare two sutehai of tehai full with hai by `iterations` identical draw sequences
+/// from haiyama, answering which one reaches tenpai and wins first more often.
+///
+/// Draws of each iteration and the seed follow `Simulation::default()`.
+pub fn compare(
+    tehai: &Tehai,
+    discard_a: &Hai,
+    discard_b: &Hai,
+    haiyama: &Haiyama,
+    iterations: usize,
+) -> Result<Comparison, String> {
+    compare_with_cache(
+        tehai,
+        discard_a,
+        discard_b,
+        haiyama,
+        iterations,
+        &RolloutCache::new(),
+    )
+}
+
+/// Same as `compare()`, sharing steps of rollouts with other simulations by `cache`.
+pub fn compare_with_cache(
+    tehai: &Tehai,
+    discard_a: &Hai,
+    discard_b: &Hai,
+    haiyama: &Haiyama,
+    iterations: usize,
+    cache: &RolloutCache,
+) -> Result<Comparison, String> {
+    if tehai.juntehai.len() % 3 != 2 {
+        return Err(format!(
+            "The number of hai on hand must be 3*k+2 to compare discards, but {} provided.",
+            tehai.juntehai.len()
+        ));
+    }
+    let mut counts_pair = [[0u8; 34]; 2];
+    for (counts, sutehai) in counts_pair.iter_mut().zip([discard_a, discard_b].iter()) {
+        let mut tehai = tehai.clone();
+        tehai.discard(sutehai)?;
+        *counts = tehai.to_counts();
+    }
+    let fuuro_count = tehai.fuuro.len();
+    let mut yama = vec![];
+    for (index, number) in haiyama.to_counts().iter().enumerate() {
+        for _ in 0..*number {
+            yama.push(index);
+        }
+    }
+
+    let simulation = Simulation::default();
+    let mut rng = Rng::new(simulation.seed);
+    let mut comparison = Comparison {
+        sutehai: [*discard_a, *discard_b],
+        iterations,
+        tenpai_first: [0; 2],
+        hora_first: [0; 2],
+        results: [SimulationResult::default(); 2],
+    };
+    for _ in 0..iterations {
+        rng.shuffle(&mut yama);
+        let mut plays = [(None, None); 2];
+        for (side, counts) in counts_pair.iter().enumerate() {
+            plays[side] = play(counts, fuuro_count, &yama, simulation.draws, cache);
+            let result = &mut comparison.results[side];
+            result.iterations += 1;
+            if plays[side].0.is_some() {
+                result.tenpai += 1;
+            }
+            if let Some(draws) = plays[side].1 {
+                result.hora += 1;
+                result.hora_draws += draws;
+            }
+        }
+        // Never reaching is later than any draws.
+        let later = |draws: Option<usize>| draws.unwrap_or(usize::MAX);
+        for side in 0..2 {
+            let other = 1 - side;
+            if later(plays[side].0) < later(plays[other].0) {
+                comparison.tenpai_first[side] += 1;
+            }
+            if later(plays[side].1) < later(plays[other].1) {
+                comparison.hora_first[side] += 1;
+            }
+        }
+    }
+    Ok(comparison)
+}
+
+impl SimulationResult {
+    /// Probability of reaching tenpai.
+    pub fn tenpai_rate(&self) -> f64 {
+        self.tenpai as f64 / self.iterations.max(1) as f64
+    }
+
+    /// Probability of winning.
+    pub fn hora_rate(&self) -> f64 {
+        self.hora as f64 / self.iterations.max(1) as f64
+    }
+
+    /// Average draws needed to win, `None` if never won.
+    pub fn average_hora_draws(&self) -> Option<f64> {
+        if self.hora == 0 {
+            None
+        } else {
+            Some(self.hora_draws as f64 / self.hora as f64)
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "iterations": self.iterations,
+            "tenpai": self.tenpai,
+            "hora": self.hora,
+            "tenpai_rate": self.tenpai_rate(),
+            "hora_rate": self.hora_rate(),
+            "average_hora_draws": self.average_hora_draws(),
+        })
+    }
+}
+
+impl std::fmt::Display for SimulationResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "試行 {}回 聴牌率 {:.1}% 和了率 {:.1}%",
+            self.iterations,
+            self.tenpai_rate() * 100.0,
+            self.hora_rate() * 100.0
+        )?;
+        if let Some(draws) = self.average_hora_draws() {
+            write!(f, " 平均 {:.1}巡", draws)?;
+        }
+        Ok(())
+    }
+}
+
+impl Comparison {
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut side_json_vec = vec![];
+        for side in 0..2 {
+            side_json_vec.push(json!({
+                "sutehai": self.sutehai[side].to_string(),
+                "tenpai_first": self.tenpai_first[side],
+                "hora_first": self.hora_first[side],
+                "result": self.results[side].to_json(),
+            }));
+        }
+        json!({
+            "iterations": self.iterations,
+            "sides": side_json_vec,
+        })
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut comparison_string_vec = vec![];
+        for side in 0..2 {
+            comparison_string_vec.push(format!(
+                "打 {} 先に聴牌 {}回 先に和了 {}回 {}",
+                self.sutehai[side],
+                self.tenpai_first[side],
+                self.hora_first[side],
+                self.results[side]
+            ));
+        }
+        write!(f, "{}", comparison_string_vec.join("\n"))
+    }
+}
+
+impl RolloutCache {
+    pub fn new() -> Self {
+        Self::with_capacity(ROLLOUT_CACHE_CAPACITY)
+    }
+
+    /// Create a cache of at most `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            capacity,
+            hits: Arc::new(AtomicUsize::new(0)),
+            misses: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.read().map_or(0, |entries| entries.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ratio of steps found in the cache, 0 if never used.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        hits as f64 / (hits + misses).max(1) as f64
+    }
+
+    /// Shanten and the greedy sutehai of juntehai full with hai, judged if not cached.
+    fn step(&self, counts: &[u8; 34], fuuro_count: usize) -> (i32, Option<usize>) {
+        let key = (*counts, fuuro_count);
+        // A poisoned lock only means another thread panicked, entries are still valid.
+        if let Some(step) = self
+            .entries
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .get(&key)
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return *step;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let shanten = shanten::calculate_counts(counts, fuuro_count);
+        let sutehai = if shanten == -1 {
+            None
+        } else {
+            greedy_sutehai(counts, fuuro_count, shanten)
+        };
+        let mut entries = self
+            .entries
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        if entries.len() < self.capacity {
+            entries.insert(key, (shanten, sutehai));
+        }
+        (shanten, sutehai)
+    }
+}
+
+impl Default for RolloutCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // State of xorshift must not be 0, which the seed of the constant itself gives.
+        Self((seed ^ 0x9E37_79B9_7F4A_7C15).max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Random number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Shuffle slice by Fisher-Yates.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// Play one iteration with shuffled haiyama.
+///
+/// # Return
+/// * Option<usize>: Number of draws to reach tenpai, 0 if already tenpai, `None` if not
+/// reached.
+/// * Option<usize>: Number of draws to win, `None` if not won.
+fn play(
+    counts: &[u8; 34],
+    fuuro_count: usize,
+    yama: &[usize],
+    draws: usize,
+    cache: &RolloutCache,
+) -> (Option<usize>, Option<usize>) {
+    let mut counts = *counts;
+    let mut tenpai_draws = if shanten::calculate_counts(&counts, fuuro_count) == 0 {
+        Some(0)
+    } else {
+        None
+    };
+
+    for (draw, index) in yama.iter().take(draws).enumerate() {
+        counts[*index] += 1;
+        let (shanten, sutehai) = cache.step(&counts, fuuro_count);
+        if shanten == -1 {
+            return (tenpai_draws.or(Some(draw + 1)), Some(draw + 1));
+        }
+
+        let sutehai = sutehai.unwrap_or(*index);
+        counts[sutehai] -= 1;
+        if shanten == 0 && tenpai_draws.is_none() {
+            tenpai_draws = Some(draw + 1);
+        }
+    }
+
+    (tenpai_draws, None)
+}
+
+/// Choose sutehai greedily for tehai full with hai, in 34-index representation.
+///
+/// The first hai keeping the least shanten is discarded. Jihai and yaochuuhai are tried
+/// first since they are usually less useful.
+pub fn greedy_sutehai(counts: &[u8; 34], fuuro_count: usize, shanten: i32) -> Option<usize> {
+    let mut counts = *counts;
+    for candidate in DISCARD_ORDER.iter() {
+        if counts[*candidate] == 0 {
+            continue;
+        }
+        counts[*candidate] -= 1;
+        let keep = shanten::calculate_counts(&counts, fuuro_count) == shanten;
+        counts[*candidate] += 1;
+        if keep {
+            return Some(*candidate);
+        }
+    }
+    None
+}
+
+/// Order of trying to discard, in 34-index representation.
+const DISCARD_ORDER: [usize; 34] = [
+    27, 28, 29, 30, 31, 32, 33, 0, 8, 9, 17, 18, 26, 1, 7, 10, 16, 19, 25, 2, 6, 11, 15, 20, 24, 3,
+    5, 12, 14, 21, 23, 4, 13, 22,
+];
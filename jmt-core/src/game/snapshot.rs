@@ -0,0 +1,174 @@
+//! Stable plain-text format of analysis results, for golden files and diff-based review.
+//!
+//! Unlike printing for users, the format is the same in every locale and never depends on
+//! iteration order of hash containers. Each line is a key followed by values separated by
+//! spaces, and lines of a condition are indented under it. Lists are sorted, `-` stands for
+//! an empty list or no value, and real numbers have 4 decimal places. The first line carries
+//! `SNAPSHOT_VERSION`, which is increased whenever the format changes, so that tools can
+//! tell outdated golden files from regressions.
+//!
+//! ```text
+//! jmt-snapshot 6
+//! tehai 1m2m3m4p5p6p7s8s1z1z3z5z5z7z
+//! shanten 1
+//! condition 3z
+//!   machihai 6s*4 9s*4 1z*2 5z*2
+//!   nokori 12
+//!   ...
+//! ```
+
+use super::{AnalysisReport, Chance, Hai, Line, MachiCondition, Warning, Yakuman};
+use std::collections::BTreeMap;
+
+/// Version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 6;
+
+impl AnalysisReport {
+    /// Print self in the stable snapshot format, see the module document.
+    pub fn to_snapshot(&self) -> String {
+        let mut lines = vec![
+            format!("jmt-snapshot {}", SNAPSHOT_VERSION),
+            format!("tehai {}", self.tehai),
+            format!("shanten {}", self.shanten),
+        ];
+        // Conditions are kept in the order of recommendation, which is total.
+        for condition in &self.conditions {
+            push_condition(&mut lines, condition);
+        }
+        for chance in &self.yakuman {
+            lines.push(format!(
+                "yakuman {} shanten={} probability={:.4}",
+                match chance.yakuman {
+                    Yakuman::Kokushimusou => "kokushimusou",
+                    Yakuman::Daisangen => "daisangen",
+                    Yakuman::Suuankou => "suuankou",
+                    Yakuman::Tsuuiisou => "tsuuiisou",
+                },
+                chance.shanten,
+                chance.probability
+            ));
+        }
+        if let Some(trade_off) = &self.trade_off {
+            lines.push(format!("trade_off speed {}", line(&trade_off.speed)));
+            lines.push(format!("trade_off value {}", line(&trade_off.value)));
+        }
+        lines.push(format!("dora {}", self.dora));
+        lines.push(format!("riichi_furiten {}", self.riichi_furiten));
+        lines.push(format!(
+            "yakuhai_type {}",
+            hai_list(self.yakuhai_type.iter())
+        ));
+        // Explanations are for users, so only the kind and its subject are stable.
+        for warning in &self.warnings {
+            lines.push(match warning {
+                Warning::Inconsistent(message) => format!("warning inconsistent {}", message),
+                Warning::FewDraws(draws) => format!("warning few_draws {}", draws),
+                _ => format!(
+                    "warning {} {}",
+                    warning.kind(),
+                    warning
+                        .sutehai()
+                        .map_or("-".to_string(), |hai| hai.to_string())
+                ),
+            });
+        }
+        for safety in &self.safety {
+            lines.push(format!(
+                "safety {} chance={} nokori={} kabe={}",
+                safety.hai,
+                match safety.chance {
+                    Some(Chance::NoChance) => "no_chance",
+                    Some(Chance::OneChance) => "one_chance",
+                    Some(Chance::Normal) => "normal",
+                    None => "-",
+                },
+                safety.nokori,
+                count_list(safety.kabe.iter().map(|(hai, number)| (hai, number))),
+            ));
+        }
+        for (seat, tenpai) in &self.threats {
+            lines.push(format!("threat {} tenpai={:.4}", seat.name(), tenpai));
+        }
+        for (hai, seat_safety_vec) in &self.riichi_safety {
+            let mut line = format!("riichi_safety {}", hai);
+            for (seat, safety) in seat_safety_vec {
+                line += &format!(" {}={}", seat.name(), safety.name());
+            }
+            if let Some(rate) = self.deal_in.get(hai) {
+                line += &format!(" deal_in={:.4}", rate);
+            }
+            lines.push(line);
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn push_condition(lines: &mut Vec<String>, condition: &MachiCondition) {
+    lines.push(format!("condition {}", condition.sutehai));
+    lines.push(format!("  machihai {}", count_map(&condition.machihai)));
+    lines.push(format!("  nokori {}", condition.nokori()));
+    lines.push(format!("  furiten {}", condition.furiten));
+    lines.push(format!("  no_yaku {}", condition.no_yaku));
+    lines.push(format!(
+        "  dead_machihai {}",
+        hai_list(condition.dead_machihai.iter())
+    ));
+    lines.push(format!("  kairyouhai {}", count_map(&condition.kairyouhai)));
+    lines.push(format!("  unseen {}", condition.unseen));
+    lines.push(format!("  advance_rate {:.4}", condition.advance_rate(1)));
+    lines.push(format!(
+        "  expected_advance_draws {}",
+        real(condition.expected_advance_draws())
+    ));
+    lines.push(format!(
+        "  expected_tenpai_draws {}",
+        real(condition.tenpai_draws)
+    ));
+}
+
+fn line(line: &Line) -> String {
+    format!(
+        "sutehai={} shanten={} nokori={} han={} score={}",
+        line.sutehai,
+        line.shanten,
+        line.nokori,
+        line.han,
+        line.score
+            .map_or("-".to_string(), |score| score.ron.to_string())
+    )
+}
+
+fn hai_list<'a>(hai_iter: impl Iterator<Item = &'a Hai>) -> String {
+    let mut hai_vec: Vec<&Hai> = hai_iter.collect();
+    hai_vec.sort();
+    if hai_vec.is_empty() {
+        return "-".to_string();
+    }
+    hai_vec
+        .iter()
+        .map(|hai| hai.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn count_map(hai_map: &BTreeMap<Hai, u8>) -> String {
+    count_list(hai_map.iter())
+}
+
+/// Hai with numbers like `6s*4`, sorted by hai.
+fn count_list<'a>(count_iter: impl Iterator<Item = (&'a Hai, &'a u8)>) -> String {
+    let mut count_vec: Vec<(&Hai, &u8)> = count_iter.collect();
+    count_vec.sort();
+    if count_vec.is_empty() {
+        return "-".to_string();
+    }
+    count_vec
+        .iter()
+        .map(|(hai, number)| format!("{}*{}", hai, number))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn real(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |value| format!("{:.4}", value))
+}
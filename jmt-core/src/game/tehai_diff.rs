@@ -0,0 +1,412 @@
+//! Operations between two snapshots of tehai, for clients which only report tehai instead of
+//! what happened to it.
+
+use super::{
+    GameManager, Hai, Kan, Mentsu, Naku, Operation, PlayerNumber, State, Tehai, TehaiOperation,
+};
+use crate::i18n::{self, Message};
+use std::collections::HashSet;
+
+impl Operation {
+    /// Operations of drawing, discarding and calling which turn tehai `before` into `after`,
+    /// in an order that game manager accepts.
+    ///
+    /// Fuuro only in `after` are called, a pon becoming kantsu is kakan, and as few hai as the
+    /// calls allow are drawn and discarded. Snapshots cannot tell riichi, order of sutehai or
+    /// hai drawn and discarded at once, so discards are plain and hai passing through tehai are
+    /// not included. Nakihai of chii unknown in `after` is the hai least in `before`.
+    ///
+    /// # Japanese
+    /// * before: 変化前の手牌
+    /// * after: 変化後の手牌
+    pub fn diff(
+        before: &Tehai,
+        after: &Tehai,
+        player_number: PlayerNumber,
+    ) -> Result<Vec<Operation>, String> {
+        let error = || i18n::format(Message::CannotDiff, &[&before, &after]);
+
+        let calls = new_calls(before, after)?;
+        let counts = before.to_counts();
+        let mut needed = after.to_counts();
+        for naku in &calls {
+            for hai in consumed(naku) {
+                needed[hai.to_index()] += 1;
+            }
+        }
+        let mut draws = vec![];
+        let mut discards = vec![];
+        for index in 0..34 {
+            // Index is always less than 34.
+            let hai = Hai::from_index(index).unwrap();
+            for _ in counts[index]..needed[index] {
+                draws.push(hai);
+            }
+            for _ in needed[index]..counts[index] {
+                discards.push(hai);
+            }
+        }
+
+        // Search steps on a game manager, which only cares about tehai in theory mode.
+        let mut game_manager = GameManager::theory(player_number);
+        game_manager.operate(Operation::Tehai(TehaiOperation::Initialize(before.clone())))?;
+        let pending = Pending {
+            game_manager,
+            calls,
+            draws,
+            discards,
+        };
+        match search(pending, &mut HashSet::new()) {
+            Some(operations) => Ok(operations),
+            None => Err(error()),
+        }
+    }
+}
+
+impl GameManager {
+    /// Bring tehai to the snapshot `tehai` by operations from `Operation::diff()`, which are
+    /// returned and recorded in history as if operated one by one. Tehai is initialized by the
+    /// snapshot if not yet. Nothing is operated if any of the operations fails.
+    pub fn sync_tehai(&mut self, tehai: &Tehai) -> Result<Vec<Operation>, String> {
+        let operations = match self.tehai() {
+            Some(current) => Operation::diff(current, tehai, self.player_number())?,
+            None => vec![Operation::Tehai(TehaiOperation::Initialize(tehai.clone()))],
+        };
+        let mut trial = self.fork();
+        for op in &operations {
+            trial.operate(op.clone())?;
+        }
+        for op in &operations {
+            self.operate(op.clone())?;
+        }
+        Ok(operations)
+    }
+}
+
+/// What remains to be operated, with the game manager operated so far.
+#[derive(Clone)]
+struct Pending {
+    game_manager: GameManager,
+    calls: Vec<Naku>,
+    draws: Vec<Hai>,
+    discards: Vec<Hai>,
+}
+
+/// A candidate operation, with what it takes from pending calls, draws and discards.
+///
+/// # Member
+/// * passing: Rinshanhai which is not drawn in total, so it must be discarded later.
+struct Step {
+    operation: Operation,
+    call: Option<usize>,
+    draw: Option<Hai>,
+    discard: Option<Hai>,
+    passing: Option<Hai>,
+}
+
+/// Depth-first search of steps which finish all pending ones, skipping positions already
+/// failed, which are told by tehai, remaining draws and discards and the last operation
+/// deciding kuikae.
+fn search(
+    pending: Pending,
+    visited: &mut HashSet<(u64, Vec<Hai>, Vec<Hai>, String)>,
+) -> Option<Vec<Operation>> {
+    if pending.calls.is_empty() && pending.draws.is_empty() && pending.discards.is_empty() {
+        return Some(vec![]);
+    }
+    let last = match pending.game_manager.history().last() {
+        Some((op, ..)) => op.to_string(),
+        None => String::new(),
+    };
+    let key = (
+        pending.game_manager.zobrist(),
+        pending.draws.clone(),
+        pending.discards.clone(),
+        last,
+    );
+    if !visited.insert(key) {
+        return None;
+    }
+
+    for step in steps(&pending) {
+        let mut next = pending.clone();
+        if next.game_manager.operate(step.operation.clone()).is_err() {
+            continue;
+        }
+        if let Some(index) = step.call {
+            next.calls.remove(index);
+        }
+        for (hai_vec, hai) in [
+            (&mut next.draws, step.draw),
+            (&mut next.discards, step.discard),
+        ] {
+            if let Some(index) = hai.and_then(|hai| hai_vec.iter().position(|h| *h == hai)) {
+                hai_vec.remove(index);
+            }
+        }
+        if let Some(hai) = step.passing {
+            next.discards.push(hai);
+            next.discards.sort();
+        }
+        if let Some(mut operations) = search(next, visited) {
+            operations.insert(0, step.operation);
+            return Some(operations);
+        }
+    }
+    None
+}
+
+/// Calls forming fuuro only in `after`, with kan resolved and without rinshanhai.
+fn new_calls(before: &Tehai, after: &Tehai) -> Result<Vec<Naku>, String> {
+    let mut remaining: Vec<_> = (0..after.fuuro.len())
+        .map(|index| (after.fuuro[index], after.fuuro_detail(index)))
+        .collect();
+    let mut calls = vec![];
+    for mentsu in &before.fuuro {
+        if let Some(index) = remaining.iter().position(|(fuuro, _)| fuuro == mentsu) {
+            remaining.remove(index);
+            continue;
+        }
+        if let Mentsu::Koutsu(hai) = mentsu {
+            let kantsu = Mentsu::Kantsu(*hai);
+            if let Some(index) = remaining.iter().position(|(fuuro, _)| *fuuro == kantsu) {
+                remaining.remove(index);
+                calls.push(Naku::Kan(Kan::Kakan {
+                    kantsu,
+                    rinshanhai: None,
+                }));
+                continue;
+            }
+        }
+        return Err(i18n::format(
+            Message::FuuroTakenBack,
+            &[&format!("{:#}", mentsu)],
+        ));
+    }
+
+    for (mentsu, detail) in remaining {
+        calls.push(match mentsu {
+            Mentsu::Juntsu(a, b, c) => Naku::Chii {
+                juntsu: mentsu,
+                nakihai: detail.nakihai.unwrap_or_else(|| {
+                    let counts = before.to_counts();
+                    // The first one wins ties.
+                    *[a, b, c]
+                        .iter()
+                        .min_by_key(|hai| counts[hai.to_index()])
+                        .unwrap()
+                }),
+            },
+            Mentsu::Koutsu(_) => Naku::Pon(mentsu),
+            Mentsu::Kantsu(_) if detail.ankan => Naku::Kan(Kan::Ankan {
+                kantsu: mentsu,
+                rinshanhai: None,
+            }),
+            Mentsu::Kantsu(_) => Naku::Kan(Kan::Daiminkan {
+                kantsu: mentsu,
+                rinshanhai: None,
+            }),
+        });
+    }
+    Ok(calls)
+}
+
+/// Hai taken from juntehai by a call.
+fn consumed(naku: &Naku) -> Vec<Hai> {
+    match naku {
+        Naku::Chii { juntsu, nakihai } => {
+            let mut hai_vec = juntsu.to_vec();
+            if let Some(index) = hai_vec.iter().position(|hai| hai == nakihai) {
+                hai_vec.remove(index);
+            }
+            hai_vec
+        }
+        Naku::Pon(koutsu) => koutsu.to_vec()[1..].to_vec(),
+        Naku::Kan(Kan::Daiminkan { kantsu, .. }) => kantsu.to_vec()[1..].to_vec(),
+        Naku::Kan(Kan::Kakan { kantsu, .. }) => kantsu.to_vec()[3..].to_vec(),
+        Naku::Kan(Kan::Ankan { kantsu, .. }) | Naku::Kan(Kan::Unknown { kantsu, .. }) => {
+            kantsu.to_vec()
+        }
+    }
+}
+
+/// Candidate steps in order of preference. Calls come first as they wait for nothing else,
+/// and hai needed by pending calls are drawn first. Rinshanhai is one of the draws, or the
+/// first type of hai tehai can hold if none is left.
+fn steps(pending: &Pending) -> Vec<Step> {
+    let mut distinct_draws = pending.draws.clone();
+    distinct_draws.dedup();
+    let kan = |index: usize, kantsu: &Mentsu| -> Vec<Step> {
+        let step = |rinshanhai: Hai, draw: Option<Hai>| Step {
+            operation: Operation::Tehai(TehaiOperation::Naku {
+                kind: Naku::Kan(Kan::Unknown {
+                    kantsu: *kantsu,
+                    rinshanhai: Some(rinshanhai),
+                }),
+                haiyama_sensitive: true,
+            }),
+            call: Some(index),
+            draw,
+            discard: None,
+            passing: if draw.is_none() {
+                Some(rinshanhai)
+            } else {
+                None
+            },
+        };
+        if !distinct_draws.is_empty() {
+            return distinct_draws
+                .iter()
+                .map(|rinshanhai| step(*rinshanhai, Some(*rinshanhai)))
+                .collect();
+        }
+        let game_manager = &pending.game_manager;
+        // Tehai is always initialized while searching.
+        let tehai = game_manager.tehai().unwrap();
+        Hai::iter_all(game_manager.player_number())
+            .find(|hai| tehai.count(hai) < 4 && kantsu.to_vec()[0] != *hai)
+            .map(|rinshanhai| step(rinshanhai, None))
+            .into_iter()
+            .collect()
+    };
+
+    let calls = &pending.calls;
+    let mut steps = vec![];
+    match pending.game_manager.state {
+        State::LackOneHai => {
+            for (index, naku) in calls.iter().enumerate() {
+                match naku {
+                    Naku::Chii { .. } | Naku::Pon(..) => steps.push(Step {
+                        operation: Operation::Tehai(TehaiOperation::Naku {
+                            kind: naku.clone(),
+                            haiyama_sensitive: true,
+                        }),
+                        call: Some(index),
+                        draw: None,
+                        discard: None,
+                        passing: None,
+                    }),
+                    Naku::Kan(Kan::Daiminkan { kantsu, .. }) => steps.extend(kan(index, kantsu)),
+                    _ => (),
+                }
+            }
+            let wanted: Vec<Hai> = calls.iter().flat_map(consumed).collect();
+            let (mut sorted_draws, others): (Vec<Hai>, Vec<Hai>) =
+                distinct_draws.iter().partition(|hai| wanted.contains(hai));
+            sorted_draws.extend(others);
+            for hai in sorted_draws {
+                steps.push(Step {
+                    operation: Operation::Tehai(TehaiOperation::Add {
+                        hai,
+                        haiyama_sensitive: true,
+                    }),
+                    call: None,
+                    draw: Some(hai),
+                    discard: None,
+                    passing: None,
+                });
+            }
+        }
+        State::FullHai => {
+            for (index, naku) in calls.iter().enumerate() {
+                if let Naku::Kan(Kan::Ankan { kantsu, .. }) | Naku::Kan(Kan::Kakan { kantsu, .. }) =
+                    naku
+                {
+                    steps.extend(kan(index, kantsu));
+                }
+            }
+            let mut distinct_discards = pending.discards.clone();
+            distinct_discards.dedup();
+            for hai in distinct_discards {
+                steps.push(Step {
+                    operation: Operation::Tehai(TehaiOperation::Discard(hai)),
+                    call: None,
+                    draw: None,
+                    discard: Some(hai),
+                    passing: None,
+                });
+            }
+        }
+        _ => (),
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tehai(string: &str) -> Tehai {
+        Tehai::new(string.to_string(), PlayerNumber::Four).unwrap()
+    }
+
+    /// Operations from `before` to `after` printed as in kifu, checking that syncing a game
+    /// manager at `before` by them reaches `after`.
+    fn diff(before: &str, after: &str) -> Vec<String> {
+        let (before, after) = (tehai(before), tehai(after));
+        let operations = Operation::diff(&before, &after, PlayerNumber::Four).unwrap();
+        let mut game_manager = GameManager::theory(PlayerNumber::Four);
+        game_manager
+            .operate(Operation::Tehai(TehaiOperation::Initialize(before)))
+            .unwrap();
+        let synced = game_manager.sync_tehai(&after).unwrap();
+        assert_eq!(synced.len(), operations.len());
+        // Nakihai unknown in `after` is decided by the operations.
+        let tehai = game_manager.tehai().unwrap();
+        assert_eq!(tehai.juntehai, after.juntehai);
+        assert_eq!(tehai.fuuro, after.fuuro);
+        operations.iter().map(|op| op.to_string()).collect()
+    }
+
+    #[test]
+    fn pon_to_kakan() {
+        assert_eq!(
+            diff("123m456p78s99s2z[222z]", "123m456p78s99s3s[2222z]"),
+            vec![">2z2z2z2z3s"]
+        );
+        // Rinshanhai discarded at once.
+        assert_eq!(
+            diff("123m456p78s99s2z[222z]", "123m456p78s99s[2222z]"),
+            vec![">2z2z2z2z1m", "-1m"]
+        );
+    }
+
+    #[test]
+    fn chii_with_unknown_nakihai() {
+        // 3m is the only one of 345m not in tehai before.
+        for after in &["125m456p78s99s[345m]", "125m456p78s99s[3'45m]"] {
+            assert_eq!(diff("12455m456p78s99s2z", after), vec![">4m5m3m", "-2z"]);
+        }
+    }
+
+    #[test]
+    fn ankan_with_rinshanhai() {
+        assert_eq!(
+            diff("123m456p78s9999s22z", "123m456p78s22z5s[(9999s)]"),
+            vec![">9s9s9s9s5s"]
+        );
+        assert_eq!(
+            diff("123m456p78s9999s22z", "123m456p78s2z5s[(9999s)]"),
+            vec![">9s9s9s9s5s", "-2z"]
+        );
+    }
+
+    #[test]
+    fn sync_tehai_keeps_state_on_failure() {
+        let mut game_manager = GameManager::theory(PlayerNumber::Four);
+        game_manager
+            .operate(Operation::Tehai(TehaiOperation::Initialize(tehai(
+                "123m456p78s99s2z[222z]",
+            ))))
+            .unwrap();
+        // Pon cannot be taken back into tehai.
+        assert!(game_manager
+            .sync_tehai(&tehai("123m456p78s99s222z"))
+            .is_err());
+        assert_eq!(game_manager.history().len(), 1);
+        assert_eq!(
+            game_manager.tehai().unwrap().to_string(),
+            tehai("123m456p78s99s2z[222z]").to_string()
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Time spent in each phase of an analysis, recorded with the `profiling` feature, so that
+//! pathological tehai such as 14 connected souzu show where the time goes.
+
+use serde_json::json;
+use std::time::Duration;
+
+/// Time of each phase of an analysis.
+///
+/// # Member
+/// * parsing: Parsing the input into tehai, only known to the caller parsing it.
+/// * decomposition: Searching decompositions of the least shanten.
+/// * machi: Evaluating machihai of each sutehai, including kairyouhai.
+/// * formatting: Formatting the result, only known to the caller printing it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub parsing: Duration,
+    pub decomposition: Duration,
+    pub machi: Duration,
+    pub formatting: Duration,
+}
+
+impl Timing {
+    /// Sum of all phases.
+    pub fn total(&self) -> Duration {
+        self.parsing + self.decomposition + self.machi + self.formatting
+    }
+
+    /// Time of each phase in microseconds.
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "parsing": self.parsing.as_micros() as u64,
+            "decomposition": self.decomposition.as_micros() as u64,
+            "machi": self.machi.as_micros() as u64,
+            "formatting": self.formatting.as_micros() as u64,
+        })
+    }
+}
+
+impl std::fmt::Display for Timing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "時間：読込{:.3}ms 分解{:.3}ms 待ち{:.3}ms 整形{:.3}ms 計{:.3}ms",
+            millis(self.parsing),
+            millis(self.decomposition),
+            millis(self.machi),
+            millis(self.formatting),
+            millis(self.total())
+        )
+    }
+}
@@ -0,0 +1,209 @@
+//! Trade-off between speed and value, so that a sutehai losing yaku or dora for a little more
+//! ukeire is reported explicitly rather than chosen silently.
+//!
+//! Value is estimated only from yakuhai, dora and riichi, since other yaku are not recognized.
+
+use super::{GameManager, Hai, Kaze, MachiCondition, Score, Tehai};
+use serde_json::json;
+
+/// Fu assumed for expected score, which is the most common for a hand with no other info.
+const ASSUMED_FU: u32 = 30;
+
+/// A line of play starting from a sutehai.
+///
+/// # Member
+/// * sutehai: The hai to discard.
+/// * shanten: Shanten after the sutehai.
+/// * nokori: Number of remaining hai improving tehai after the sutehai.
+/// * han: Han kept after the sutehai, see `TradeOff::han()`.
+/// * score: Points of ron with `han` and 30 fu, `None` if no yaku is kept.
+#[derive(Copy, Clone, Debug)]
+pub struct Line {
+    pub sutehai: Hai,
+    pub shanten: i32,
+    pub nokori: usize,
+    pub han: u32,
+    pub score: Option<Score>,
+}
+
+/// The fastest line compared with the most valuable line.
+///
+/// # Member
+/// * speed: The best sutehai by efficiency, the first of `Tehai::analyze()`.
+/// * value: The sutehai keeping the most han, at most 1 shanten slower than `speed`.
+#[derive(Copy, Clone, Debug)]
+pub struct TradeOff {
+    pub speed: Line,
+    pub value: Line,
+}
+
+impl TradeOff {
+    /// Compare the fastest sutehai with the most valuable one for tehai full with hai.
+    ///
+    /// # Return
+    /// * Option<TradeOff>: `None` if tehai has won, or the fastest sutehai keeps the most han.
+    pub fn analyze(
+        tehai: &Tehai,
+        shanten: i32,
+        conditions: &[MachiCondition],
+        game_manager: &GameManager,
+    ) -> Result<Option<TradeOff>, String> {
+        let fastest = match conditions.first() {
+            Some(condition) => condition,
+            None => return Ok(None),
+        };
+        // Ranking all sutehai is slow, so skip it unless some sutehai keeps more han.
+        let speed_han = Self::han(tehai, &fastest.sutehai, game_manager);
+        if tehai
+            .juntehai
+            .iter()
+            .all(|sutehai| Self::han(tehai, sutehai, game_manager) <= speed_han)
+        {
+            return Ok(None);
+        }
+
+        let speed = Self::line(
+            tehai,
+            fastest.sutehai,
+            shanten,
+            fastest.nokori(),
+            game_manager,
+        )?;
+        // Ranked from the best, so the first of the most han is the fastest of them.
+        let mut value: Option<Line> = None;
+        for (sutehai_shanten, condition) in
+            tehai.analyze_all_sutehai(game_manager.player_number(), Some(game_manager))?
+        {
+            if sutehai_shanten > shanten + 1 {
+                continue;
+            }
+            let han = Self::han(tehai, &condition.sutehai, game_manager);
+            if value.map_or(true, |value| han > value.han) {
+                value = Some(Self::line(
+                    tehai,
+                    condition.sutehai,
+                    sutehai_shanten,
+                    condition.nokori(),
+                    game_manager,
+                )?);
+            }
+        }
+        Ok(match value {
+            Some(value) if value.han > speed.han => Some(TradeOff { speed, value }),
+            _ => None,
+        })
+    }
+
+    /// Han kept after discarding `sutehai`, counting toitsu of yakuhai as koutsu, each dora,
+    /// and riichi if there is no fuuro other than ankan.
+    ///
+    /// # Japanese
+    /// * yakuhai: 役牌
+    /// * dora: ドラ
+    pub fn han(tehai: &Tehai, sutehai: &Hai, game_manager: &GameManager) -> u32 {
+        let mut counts = tehai.to_counts();
+        counts[sutehai.to_index()] -= 1;
+        for mentsu in &tehai.fuuro {
+            for hai in mentsu.to_vec() {
+                counts[hai.to_index()] += 1;
+            }
+        }
+
+        let mut han = 0;
+        for hai in game_manager.yakuhai_type() {
+            if counts[hai.to_index()] >= 2 {
+                // Double wind counts twice.
+                han += match hai {
+                    Hai::Jihai(5..=7) => 1,
+                    _ => {
+                        (hai == game_manager.jikaze().to_hai()) as u32
+                            + (hai == game_manager.bakaze().to_hai()) as u32
+                    }
+                };
+            }
+        }
+        for dora in game_manager.dora() {
+            han += counts[dora.to_index()] as u32;
+        }
+        if tehai.is_menzen() {
+            han += 1;
+        }
+        han
+    }
+
+    fn line(
+        tehai: &Tehai,
+        sutehai: Hai,
+        shanten: i32,
+        nokori: usize,
+        game_manager: &GameManager,
+    ) -> Result<Line, String> {
+        let han = Self::han(tehai, &sutehai, game_manager);
+        let score = if han > 0 {
+            Some(Score::new(
+                han,
+                ASSUMED_FU,
+                game_manager.jikaze() == Kaze::Ton,
+                &game_manager.rules(),
+            )?)
+        } else {
+            None
+        };
+        Ok(Line {
+            sutehai,
+            shanten,
+            nokori,
+            han,
+            score,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "speed": self.speed.to_json(),
+            "value": self.value.to_json(),
+        })
+    }
+}
+
+impl Line {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "sutehai": self.sutehai.to_string(),
+            "shanten_number": self.shanten,
+            "nokori": self.nokori,
+            "han": self.han,
+            "score": self.score.map(|score| score.ron),
+        })
+    }
+}
+
+impl std::fmt::Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "打 {} {} 残り{}枚 {}",
+            self.sutehai,
+            if self.shanten == 0 {
+                "聴牌".to_string()
+            } else {
+                format!("向聴：{}", self.shanten)
+            },
+            self.nokori,
+            match self.score {
+                Some(score) => format!("{}飜 {}点", self.han, score.ron),
+                None => "役無し".to_string(),
+            }
+        )
+    }
+}
+
+impl std::fmt::Display for TradeOff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "速度と打点の選択\n速度 {}\n打点 {}",
+            self.speed, self.value
+        )
+    }
+}
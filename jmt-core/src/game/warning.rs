@@ -0,0 +1,125 @@
+//! Warnings about a sutehai or the game, which are legal but likely a mistake, such as a
+//! tenpai which can never win.
+
+use super::{riichi, AnalysisReport, GameManager, Hai, MachiCondition};
+use serde_json::json;
+
+/// Number of draws of self below which declaring riichi is hardly worth the riichi stick.
+pub const FEW_DRAWS: usize = 4;
+
+/// A warning in analysis.
+///
+/// # Japanese
+/// * furiten riichi: 振り聴立直
+/// * dead machi: 純カラ待ち
+/// * yaku: 役
+///
+/// # Member
+/// * Inconsistent: Visible hai do not match haiyama, see `GameManager::validate()`.
+/// * FuritenRiichi: Riichi with the sutehai would be furiten, winning only by tsumo.
+/// * DeadMachi: Every machihai after the sutehai is visible, so tenpai can never win.
+/// * FewDraws: Riichi can be declared, but self has only this many draws left.
+/// * NoYaku: Open tenpai after the sutehai, but no machihai gives a yaku.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Warning {
+    Inconsistent(String),
+    FuritenRiichi(Hai),
+    DeadMachi(Hai),
+    FewDraws(usize),
+    NoYaku(Hai),
+}
+
+impl Warning {
+    /// Warnings of tenpai conditions in a report, which need nothing of the game.
+    pub fn judge_conditions(report: &AnalysisReport) -> Vec<Warning> {
+        let mut warnings = vec![];
+        if report.shanten != 0 {
+            return warnings;
+        }
+        for condition in &report.conditions {
+            if condition.machihai.is_empty() && !condition.dead_machihai.is_empty() {
+                warnings.push(Warning::DeadMachi(condition.sutehai));
+            }
+            if condition.no_yaku {
+                warnings.push(Warning::NoYaku(condition.sutehai));
+            }
+        }
+        warnings
+    }
+
+    /// Warnings of declaring riichi in the game, if riichi can be declared.
+    pub fn judge_riichi(conditions: &[MachiCondition], game_manager: &GameManager) -> Vec<Warning> {
+        let mut warnings = vec![];
+        let sutehai_vec = match riichi::can_riichi(game_manager) {
+            Ok(sutehai_vec) => sutehai_vec,
+            Err(_) => return warnings,
+        };
+        for condition in conditions {
+            if condition.furiten && sutehai_vec.contains(&condition.sutehai) {
+                warnings.push(Warning::FuritenRiichi(condition.sutehai));
+            }
+        }
+        let draws = game_manager.draws_remaining();
+        if draws < FEW_DRAWS {
+            warnings.push(Warning::FewDraws(draws));
+        }
+        warnings
+    }
+
+    /// Name of the kind in snake case, which is stable for tools.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Warning::Inconsistent(_) => "inconsistent",
+            Warning::FuritenRiichi(_) => "furiten_riichi",
+            Warning::DeadMachi(_) => "dead_machi",
+            Warning::FewDraws(_) => "few_draws",
+            Warning::NoYaku(_) => "no_yaku",
+        }
+    }
+
+    /// Sutehai the warning is about, `None` if about the game.
+    pub fn sutehai(&self) -> Option<Hai> {
+        match self {
+            Warning::FuritenRiichi(hai) | Warning::DeadMachi(hai) | Warning::NoYaku(hai) => {
+                Some(*hai)
+            }
+            Warning::Inconsistent(_) | Warning::FewDraws(_) => None,
+        }
+    }
+
+    /// Explanation for users.
+    pub fn explanation(&self) -> String {
+        match self {
+            Warning::Inconsistent(message) => message.clone(),
+            Warning::FuritenRiichi(hai) => format!(
+                "打{}で立直すると振り聴立直になり、ツモでしか和了れません。",
+                hai
+            ),
+            Warning::DeadMachi(hai) => {
+                format!("打{}の待ち牌は全て見えていて、和了れません。", hai)
+            }
+            Warning::FewDraws(draws) => format!(
+                "残りツモは{}回しかなく、立直しても和了れる見込みは低いです。",
+                draws
+            ),
+            Warning::NoYaku(hai) => format!(
+                "打{}の聴牌は副露していて役がなく、どの待ち牌でも和了れません。",
+                hai
+            ),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "kind": self.kind(),
+            "sutehai": self.sutehai().map(|hai| hai.to_string()),
+            "explanation": self.explanation(),
+        })
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.explanation())
+    }
+}
@@ -0,0 +1,605 @@
+//! Yaku of a winning hand, judged from its mentsu and a few facts of the game. `Agari` scores
+//! hands by them, and `has_yaku()` tells apart a tenpai which can never win.
+//!
+//! Yaku of situations other than riichi and menzen tsumo, such as ippatsu, haitei and rinshan
+//! kaihou, are ignored.
+
+use super::{Hai, Hourakei, Kaze, Mentsu, PlayerNumber, Rules, Tehai};
+use serde_json::json;
+
+/// Yaku of a winning hand.
+///
+/// # Japanese
+/// * Riichi: 立直
+/// * MenzenTsumo: 門前清自摸和
+/// * Pinfu: 平和
+/// * Tanyao: 断幺九
+/// * Iipeikou: 一盃口
+/// * Jikaze: 自風牌
+/// * Bakaze: 場風牌
+/// * Sangenpai: 三元牌
+/// * Sanshoku: 三色同順
+/// * Ittsu: 一気通貫
+/// * Chanta: 混全帯幺九
+/// * Chiitoitsu: 七対子
+/// * Toitoi: 対々和
+/// * Sanankou: 三暗刻
+/// * SanshokuDoukou: 三色同刻
+/// * Sankantsu: 三槓子
+/// * Shousangen: 小三元
+/// * Honroutou: 混老頭
+/// * Honitsu: 混一色
+/// * Junchan: 純全帯幺九
+/// * Ryanpeikou: 二盃口
+/// * Chinitsu: 清一色
+/// * Kokushimusou: 国士無双
+/// * Suuankou: 四暗刻
+/// * Daisangen: 大三元
+/// * Shousuushii: 小四喜
+/// * Daisuushii: 大四喜
+/// * Tsuuiisou: 字一色
+/// * Ryuuiisou: 緑一色
+/// * Chinroutou: 清老頭
+/// * Suukantsu: 四槓子
+/// * Chuurenpoutou: 九蓮宝燈
+///
+/// # Member
+/// * Sangenpai: Koutsu of the sangenpai.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Yaku {
+    Riichi,
+    MenzenTsumo,
+    Pinfu,
+    Tanyao,
+    Iipeikou,
+    Jikaze,
+    Bakaze,
+    Sangenpai(Hai),
+    Sanshoku,
+    Ittsu,
+    Chanta,
+    Chiitoitsu,
+    Toitoi,
+    Sanankou,
+    SanshokuDoukou,
+    Sankantsu,
+    Shousangen,
+    Honroutou,
+    Honitsu,
+    Junchan,
+    Ryanpeikou,
+    Chinitsu,
+    Kokushimusou,
+    Suuankou,
+    Daisangen,
+    Shousuushii,
+    Daisuushii,
+    Tsuuiisou,
+    Ryuuiisou,
+    Chinroutou,
+    Suukantsu,
+    Chuurenpoutou,
+}
+
+/// Facts of the game which yaku depend on besides the hand.
+///
+/// # Member
+/// * tsumo: Won by tsumo, otherwise by ron.
+/// * riichi: Whether riichi is declared.
+/// * jikaze: Jikaze, `None` if unknown, where only sangenpai are yakuhai.
+/// * bakaze: Bakaze, `None` if unknown.
+/// * kuitan_nashi: Tanyao with fuuro is not a yaku.
+#[derive(Copy, Clone, Debug)]
+pub struct YakuContext {
+    pub tsumo: bool,
+    pub riichi: bool,
+    pub jikaze: Option<Kaze>,
+    pub bakaze: Option<Kaze>,
+    pub kuitan_nashi: bool,
+}
+
+/// Mentsu of a winning hand by the index of its first hai, including fuuro.
+///
+/// # Member
+/// * index: Index of the first hai in 34-index representation.
+/// * juntsu: Whether it is juntsu, otherwise koutsu or kantsu.
+/// * kantsu: Whether it is kantsu.
+/// * concealed: Whether it is concealed, not fuuro except ankan nor koutsu completed by ron.
+#[derive(Copy, Clone, Debug)]
+pub struct Block {
+    pub index: usize,
+    pub juntsu: bool,
+    pub kantsu: bool,
+    pub concealed: bool,
+}
+
+/// Whether tehai waiting for `agarihai` has any yaku after winning with it, by tsumo so that
+/// koutsu completed by agarihai is concealed. A menzen hand always has yaku since it can
+/// declare riichi.
+///
+/// # Parameters
+/// * tehai: Tehai with `3k + 1` juntehai, which wins with `agarihai`.
+/// * player_number: Number of players, which decides hai in the game.
+/// * jikaze: Jikaze, `None` if unknown.
+/// * bakaze: Bakaze, `None` if unknown.
+/// * rules: Tanyao with fuuro is not a yaku if `kuitan_nashi`.
+///
+/// # Japanese
+/// * agarihai: 和了牌
+/// * yaku: 役
+pub fn has_yaku(
+    tehai: &Tehai,
+    agarihai: &Hai,
+    player_number: PlayerNumber,
+    jikaze: Option<Kaze>,
+    bakaze: Option<Kaze>,
+    rules: &Rules,
+) -> bool {
+    if tehai.is_menzen() {
+        return true;
+    }
+
+    let context = YakuContext {
+        tsumo: true,
+        riichi: false,
+        jikaze,
+        bakaze,
+        kuitan_nashi: rules.kuitan_nashi,
+    };
+    let mut agari_tehai = tehai.clone();
+    agari_tehai.juntehai.push(*agarihai);
+    let counts = all_counts(&agari_tehai);
+    let fuuro_blocks = Block::fuuro_vec(tehai);
+    agari_tehai
+        .decompositions(player_number)
+        .filter(|decomposer| decomposer.shanten_number() == -1)
+        .filter(|decomposer| decomposer.hourakei() == Hourakei::Mentsute)
+        .any(|decomposer| {
+            let mut blocks = fuuro_blocks.clone();
+            blocks.extend(decomposer.mentsu_vec().iter().map(Block::new));
+            match decomposer.toitsu_vec().first() {
+                Some(toitsu) => {
+                    let pair = toitsu.0.to_index();
+                    !mentsute_yaku(&counts, pair, &blocks, false, false, &context).is_empty()
+                }
+                None => false,
+            }
+        })
+}
+
+/// Yaku of a mentsute hand, only yakuman if it has any.
+///
+/// # Parameters
+/// * counts: Counts of hai in juntehai and fuuro, see `all_counts()`.
+/// * pair: Index of the jantou.
+/// * blocks: All mentsu of the hand.
+/// * menzen: Whether the hand has no fuuro except ankan.
+/// * ryanmen: Whether the agarihai completes a juntsu from both sides, which pinfu needs.
+/// * context: Facts of the game.
+pub fn mentsute_yaku(
+    counts: &[u8; 34],
+    pair: usize,
+    blocks: &[Block],
+    menzen: bool,
+    ryanmen: bool,
+    context: &YakuContext,
+) -> Vec<Yaku> {
+    let jikaze = context.jikaze.map(|kaze| kaze.to_hai().to_index());
+    let bakaze = context.bakaze.map(|kaze| kaze.to_hai().to_index());
+    let is_sangen = |index: usize| index >= 31;
+    let is_yakuhai =
+        |index: usize| is_sangen(index) || Some(index) == jikaze || Some(index) == bakaze;
+    let koutsu: Vec<usize> = blocks
+        .iter()
+        .filter(|block| !block.juntsu)
+        .map(|block| block.index)
+        .collect();
+    let has_juntsu = |index: usize| {
+        blocks
+            .iter()
+            .any(|block| block.juntsu && block.index == index)
+    };
+    let ankou = blocks
+        .iter()
+        .filter(|block| !block.juntsu && block.concealed)
+        .count();
+    let kantsu = blocks.iter().filter(|block| block.kantsu).count();
+    let sangen_koutsu = koutsu.iter().filter(|index| is_sangen(**index)).count();
+    let kaze_koutsu = koutsu
+        .iter()
+        .filter(|index| (27..31).contains(*index))
+        .count();
+
+    // Kantsu are always fuuro, and other fuuro break menzen.
+    let no_fuuro = menzen && blocks.iter().all(|block| !block.kantsu);
+    let mut yaku = shape_yakuman(counts, no_fuuro);
+    if ankou == 4 {
+        yaku.push(Yaku::Suuankou);
+    }
+    if sangen_koutsu == 3 {
+        yaku.push(Yaku::Daisangen);
+    }
+    if kaze_koutsu == 4 {
+        yaku.push(Yaku::Daisuushii);
+    } else if kaze_koutsu == 3 && (27..31).contains(&pair) {
+        yaku.push(Yaku::Shousuushii);
+    }
+    if kantsu == 4 {
+        yaku.push(Yaku::Suukantsu);
+    }
+    if !yaku.is_empty() {
+        yaku.sort();
+        return yaku;
+    }
+
+    let pinfu = menzen && koutsu.is_empty() && !is_yakuhai(pair) && ryanmen;
+    yaku = hai_yaku(counts, menzen, context);
+    if context.tsumo && menzen {
+        yaku.push(Yaku::MenzenTsumo);
+    }
+    if pinfu {
+        yaku.push(Yaku::Pinfu);
+    }
+    if menzen {
+        let mut juntsu: Vec<usize> = blocks
+            .iter()
+            .filter(|block| block.juntsu)
+            .map(|block| block.index)
+            .collect();
+        juntsu.sort();
+        let mut peikou = 0;
+        let mut index = 0;
+        while index + 1 < juntsu.len() {
+            if juntsu[index] == juntsu[index + 1] {
+                peikou += 1;
+                index += 2;
+            } else {
+                index += 1;
+            }
+        }
+        match peikou {
+            2 => yaku.push(Yaku::Ryanpeikou),
+            1 => yaku.push(Yaku::Iipeikou),
+            _ => (),
+        }
+    }
+    for index in &koutsu {
+        if is_sangen(*index) {
+            // Index is always less than 34.
+            yaku.push(Yaku::Sangenpai(Hai::from_index(*index).unwrap()));
+        }
+        if Some(*index) == jikaze {
+            yaku.push(Yaku::Jikaze);
+        }
+        if Some(*index) == bakaze {
+            yaku.push(Yaku::Bakaze);
+        }
+    }
+    if (0..7).any(|number| (0..3).all(|suit| has_juntsu(suit * 9 + number))) {
+        yaku.push(Yaku::Sanshoku);
+    }
+    if (0..3).any(|suit| (0..3).all(|part| has_juntsu(suit * 9 + part * 3))) {
+        yaku.push(Yaku::Ittsu);
+    }
+    if (0..9).any(|number| (0..3).all(|suit| koutsu.contains(&(suit * 9 + number)))) {
+        yaku.push(Yaku::SanshokuDoukou);
+    }
+    let all_yaochuu = is_yaochuu(pair) && blocks.iter().all(Block::has_yaochuu);
+    // Without juntsu it is honroutou.
+    if all_yaochuu && koutsu.len() < blocks.len() {
+        if counts[27..].iter().any(|count| *count > 0) {
+            yaku.push(Yaku::Chanta);
+        } else {
+            yaku.push(Yaku::Junchan);
+        }
+    }
+    if koutsu.len() == blocks.len() {
+        yaku.push(Yaku::Toitoi);
+    }
+    if ankou == 3 {
+        yaku.push(Yaku::Sanankou);
+    }
+    if kantsu == 3 {
+        yaku.push(Yaku::Sankantsu);
+    }
+    if sangen_koutsu == 2 && is_sangen(pair) {
+        yaku.push(Yaku::Shousangen);
+    }
+    yaku.sort();
+    yaku
+}
+
+/// Yaku of a hand of chiitoitsu or kokushimusou, only yakuman if it has any.
+pub fn special_yaku(counts: &[u8; 34], hourakei: Hourakei, context: &YakuContext) -> Vec<Yaku> {
+    let mut yaku = shape_yakuman(counts, true);
+    if hourakei == Hourakei::Kokushimusou {
+        yaku.push(Yaku::Kokushimusou);
+    }
+    if !yaku.is_empty() {
+        return yaku;
+    }
+
+    yaku = hai_yaku(counts, true, context);
+    yaku.push(Yaku::Chiitoitsu);
+    if context.tsumo {
+        yaku.push(Yaku::MenzenTsumo);
+    }
+    yaku.sort();
+    yaku
+}
+
+/// Counts of hai in juntehai and fuuro.
+pub fn all_counts(tehai: &Tehai) -> [u8; 34] {
+    let mut counts = tehai.to_counts();
+    for mentsu in &tehai.fuuro {
+        for hai in mentsu.to_vec() {
+            counts[hai.to_index()] += 1;
+        }
+    }
+    counts
+}
+
+impl Block {
+    /// Block of mentsu in juntehai, which is concealed.
+    pub fn new(mentsu: &Mentsu) -> Self {
+        let (index, juntsu, kantsu) = match mentsu {
+            Mentsu::Juntsu(a, b, c) => (
+                a.to_index().min(b.to_index()).min(c.to_index()),
+                true,
+                false,
+            ),
+            Mentsu::Koutsu(hai) => (hai.to_index(), false, false),
+            Mentsu::Kantsu(hai) => (hai.to_index(), false, true),
+        };
+        Self {
+            index,
+            juntsu,
+            kantsu,
+            concealed: true,
+        }
+    }
+
+    /// Blocks of fuuro of tehai, where only ankan is concealed.
+    pub fn fuuro_vec(tehai: &Tehai) -> Vec<Self> {
+        tehai
+            .fuuro
+            .iter()
+            .enumerate()
+            .map(|(index, mentsu)| Self {
+                concealed: tehai.fuuro_detail(index).ankan,
+                ..Self::new(mentsu)
+            })
+            .collect()
+    }
+
+    /// Whether the hai at `index` is in the block.
+    pub fn contains(&self, index: usize) -> bool {
+        if self.juntsu {
+            (self.index..self.index + 3).contains(&index)
+        } else {
+            self.index == index
+        }
+    }
+
+    /// Whether the block has yaochuuhai, that is juntsu of 1 to 3 or 7 to 9, or koutsu of
+    /// yaochuuhai.
+    pub fn has_yaochuu(&self) -> bool {
+        if self.juntsu {
+            is_yaochuu(self.index) || is_yaochuu(self.index + 2)
+        } else {
+            is_yaochuu(self.index)
+        }
+    }
+}
+
+/// Whether the hai at `index` of 34-index representation is yaochuuhai.
+fn is_yaochuu(index: usize) -> bool {
+    Hai::from_index(index).unwrap().is_yaochuu()
+}
+
+/// Yakuman decided by hai alone, except kokushimusou decided by hourakei. Chuurenpoutou
+/// needs tehai without any fuuro, even ankan.
+fn shape_yakuman(counts: &[u8; 34], no_fuuro: bool) -> Vec<Yaku> {
+    let used: Vec<usize> = (0..34).filter(|index| counts[*index] > 0).collect();
+    let mut yaku = vec![];
+    if used.iter().all(|index| *index >= 27) {
+        yaku.push(Yaku::Tsuuiisou);
+    }
+    // 2s, 3s, 4s, 6s, 8s and hatsu.
+    if used
+        .iter()
+        .all(|index| [19, 20, 21, 23, 25, 32].contains(index))
+    {
+        yaku.push(Yaku::Ryuuiisou);
+    }
+    if used.iter().all(|index| *index < 27 && is_yaochuu(*index)) {
+        yaku.push(Yaku::Chinroutou);
+    }
+    let suit = used[0] / 9;
+    if no_fuuro && suit < 3 && used.iter().all(|index| index / 9 == suit) {
+        let nine_gates = [3, 1, 1, 1, 1, 1, 1, 1, 3];
+        if (0..9).all(|number| counts[suit * 9 + number] >= nine_gates[number]) {
+            yaku.push(Yaku::Chuurenpoutou);
+        }
+    }
+    yaku
+}
+
+/// Yaku decided by hai alone and riichi, which both mentsute and chiitoitsu can have.
+fn hai_yaku(counts: &[u8; 34], menzen: bool, context: &YakuContext) -> Vec<Yaku> {
+    let used: Vec<usize> = (0..34).filter(|index| counts[*index] > 0).collect();
+    let mut yaku = vec![];
+    if context.riichi {
+        yaku.push(Yaku::Riichi);
+    }
+    if used.iter().all(|index| !is_yaochuu(*index)) && (menzen || !context.kuitan_nashi) {
+        yaku.push(Yaku::Tanyao);
+    }
+    if used.iter().all(|index| is_yaochuu(*index)) {
+        yaku.push(Yaku::Honroutou);
+    }
+    let suits: Vec<usize> = (0..3)
+        .filter(|suit| used.iter().any(|index| index / 9 == *suit))
+        .collect();
+    if suits.len() == 1 {
+        if used.iter().any(|index| *index >= 27) {
+            yaku.push(Yaku::Honitsu);
+        } else {
+            yaku.push(Yaku::Chinitsu);
+        }
+    }
+    yaku
+}
+
+impl Yaku {
+    /// Han of the yaku, less for some with fuuro.
+    pub fn han(&self, menzen: bool) -> u32 {
+        match self {
+            Yaku::Sanshoku | Yaku::Ittsu | Yaku::Chanta => 1 + menzen as u32,
+            Yaku::Honitsu | Yaku::Junchan => 2 + menzen as u32,
+            Yaku::Chinitsu => 5 + menzen as u32,
+            Yaku::Chiitoitsu
+            | Yaku::Toitoi
+            | Yaku::Sanankou
+            | Yaku::SanshokuDoukou
+            | Yaku::Sankantsu
+            | Yaku::Shousangen
+            | Yaku::Honroutou => 2,
+            Yaku::Ryanpeikou => 3,
+            _ if self.is_yakuman() => 13,
+            _ => 1,
+        }
+    }
+
+    pub fn is_yakuman(&self) -> bool {
+        *self >= Yaku::Kokushimusou
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Yaku::Riichi => "riichi",
+            Yaku::MenzenTsumo => "menzen_tsumo",
+            Yaku::Pinfu => "pinfu",
+            Yaku::Tanyao => "tanyao",
+            Yaku::Iipeikou => "iipeikou",
+            Yaku::Jikaze => "jikaze",
+            Yaku::Bakaze => "bakaze",
+            Yaku::Sangenpai(_) => "sangenpai",
+            Yaku::Sanshoku => "sanshoku",
+            Yaku::Ittsu => "ittsu",
+            Yaku::Chanta => "chanta",
+            Yaku::Chiitoitsu => "chiitoitsu",
+            Yaku::Toitoi => "toitoi",
+            Yaku::Sanankou => "sanankou",
+            Yaku::SanshokuDoukou => "sanshoku_doukou",
+            Yaku::Sankantsu => "sankantsu",
+            Yaku::Shousangen => "shousangen",
+            Yaku::Honroutou => "honroutou",
+            Yaku::Honitsu => "honitsu",
+            Yaku::Junchan => "junchan",
+            Yaku::Ryanpeikou => "ryanpeikou",
+            Yaku::Chinitsu => "chinitsu",
+            Yaku::Kokushimusou => "kokushimusou",
+            Yaku::Suuankou => "suuankou",
+            Yaku::Daisangen => "daisangen",
+            Yaku::Shousuushii => "shousuushii",
+            Yaku::Daisuushii => "daisuushii",
+            Yaku::Tsuuiisou => "tsuuiisou",
+            Yaku::Ryuuiisou => "ryuuiisou",
+            Yaku::Chinroutou => "chinroutou",
+            Yaku::Suukantsu => "suukantsu",
+            Yaku::Chuurenpoutou => "chuurenpoutou",
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Yaku::Sangenpai(hai) => json!(format!("{}_{}", self.name(), hai)),
+            _ => json!(self.name()),
+        }
+    }
+}
+
+impl std::fmt::Display for Yaku {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Yaku::Riichi => "立直",
+            Yaku::MenzenTsumo => "門前清自摸和",
+            Yaku::Pinfu => "平和",
+            Yaku::Tanyao => "断幺九",
+            Yaku::Iipeikou => "一盃口",
+            Yaku::Jikaze => "自風牌",
+            Yaku::Bakaze => "場風牌",
+            Yaku::Sangenpai(hai) => return write!(f, "役牌{}", hai),
+            Yaku::Sanshoku => "三色同順",
+            Yaku::Ittsu => "一気通貫",
+            Yaku::Chanta => "混全帯幺九",
+            Yaku::Chiitoitsu => "七対子",
+            Yaku::Toitoi => "対々和",
+            Yaku::Sanankou => "三暗刻",
+            Yaku::SanshokuDoukou => "三色同刻",
+            Yaku::Sankantsu => "三槓子",
+            Yaku::Shousangen => "小三元",
+            Yaku::Honroutou => "混老頭",
+            Yaku::Honitsu => "混一色",
+            Yaku::Junchan => "純全帯幺九",
+            Yaku::Ryanpeikou => "二盃口",
+            Yaku::Chinitsu => "清一色",
+            Yaku::Kokushimusou => "国士無双",
+            Yaku::Suuankou => "四暗刻",
+            Yaku::Daisangen => "大三元",
+            Yaku::Shousuushii => "小四喜",
+            Yaku::Daisuushii => "大四喜",
+            Yaku::Tsuuiisou => "字一色",
+            Yaku::Ryuuiisou => "緑一色",
+            Yaku::Chinroutou => "清老頭",
+            Yaku::Suukantsu => "四槓子",
+            Yaku::Chuurenpoutou => "九蓮宝燈",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn has_yaku_of(tehai: &str, agarihai: Hai, rules: &Rules) -> bool {
+        let tehai = Tehai::new(tehai.to_string(), PlayerNumber::Four).unwrap();
+        has_yaku(
+            &tehai,
+            &agarihai,
+            PlayerNumber::Four,
+            Some(Kaze::Nan),
+            Some(Kaze::Ton),
+            rules,
+        )
+    }
+
+    #[test]
+    fn kuitan_nashi() {
+        let kuitan_nashi = Rules {
+            kuitan_nashi: true,
+            ..Rules::default()
+        };
+        let tehai = "[234m]567p345s88m34s";
+        assert!(has_yaku_of(tehai, Hai::Souzu(5), &Rules::default()));
+        assert!(!has_yaku_of(tehai, Hai::Souzu(5), &kuitan_nashi));
+        // Menzen tehai can always declare riichi.
+        assert!(has_yaku_of(
+            "234m567p345s88m34s",
+            Hai::Souzu(5),
+            &kuitan_nashi
+        ));
+    }
+
+    #[test]
+    fn yakuhai_of_kaze() {
+        let rules = Rules::default();
+        // Ton is bakaze and nan is jikaze, but sha is not yakuhai.
+        for (fuuro, yaku) in [("[111z]", true), ("[222z]", true), ("[333z]", false)] {
+            let tehai = format!("{}234m567p789s8m", fuuro);
+            assert_eq!(has_yaku_of(&tehai, Hai::Manzu(8), &rules), yaku);
+        }
+    }
+}
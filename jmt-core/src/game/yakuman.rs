@@ -0,0 +1,230 @@
+//! Estimation of completing yakuman, so that big hands are not abandoned for a little more
+//! ukeire.
+
+use super::{shanten, simulate::Rng, Hai, Haiyama, PlayerNumber, Tehai};
+use serde_json::json;
+
+/// Yakuman estimated by this module.
+///
+/// # Japanese
+/// * Kokushimusou: 国士無双
+/// * Daisangen: 大三元
+/// * Suuankou: 四暗刻
+/// * Tsuuiisou: 字一色
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Yakuman {
+    Kokushimusou,
+    Daisangen,
+    Suuankou,
+    Tsuuiisou,
+}
+
+/// Chance of completing a yakuman.
+///
+/// # Member
+/// * yakuman: The yakuman.
+/// * shanten: Shanten toward the yakuman after the best discard if full with hai.
+/// * probability: Estimated probability of completing within the draws.
+#[derive(Copy, Clone, Debug)]
+pub struct YakumanChance {
+    pub yakuman: Yakuman,
+    pub shanten: i32,
+    pub probability: f64,
+}
+
+/// Number of iterations to estimate probability.
+const ITERATIONS: usize = 1000;
+
+/// Sangenpai in 34-index representation.
+const SANGENPAI_INDICES: [usize; 3] = [31, 32, 33];
+
+impl Yakuman {
+    pub fn all() -> [Yakuman; 4] {
+        [
+            Yakuman::Kokushimusou,
+            Yakuman::Daisangen,
+            Yakuman::Suuankou,
+            Yakuman::Tsuuiisou,
+        ]
+    }
+
+    /// Whether tehai shows potential of the yakuman, which is worth keeping.
+    ///
+    /// * Kokushimusou: At least 10 types of yaochuuhai.
+    /// * Daisangen: At least 6 sangenpai counted up to 3 of each, such as 3 toitsu.
+    /// * Suuankou: At most 2 shanten toward 4 koutsu.
+    /// * Tsuuiisou: At least 10 jihai.
+    pub fn has_potential(&self, counts: &[u8; 34]) -> bool {
+        match self {
+            Yakuman::Kokushimusou => {
+                // Every yaochuuhai is valid in sanma too.
+                Hai::iter_all(PlayerNumber::Four)
+                    .filter(|hai| hai.is_yaochuu() && counts[hai.to_index()] > 0)
+                    .count()
+                    >= 10
+            }
+            Yakuman::Daisangen => {
+                SANGENPAI_INDICES
+                    .iter()
+                    .map(|index| counts[*index].min(3))
+                    .sum::<u8>()
+                    >= 6
+            }
+            Yakuman::Suuankou => self.shanten(counts) <= 2,
+            Yakuman::Tsuuiisou => counts[27..].iter().sum::<u8>() >= 10,
+        }
+    }
+
+    /// Shanten toward the yakuman from 34-index counts without fuuro, -1 if completed.
+    pub fn shanten(&self, counts: &[u8; 34]) -> i32 {
+        match self {
+            Yakuman::Kokushimusou => shanten::calculate_kokushimusou(counts),
+            Yakuman::Daisangen => {
+                // Each sangenpai must be koutsu, and the rest must be a mentsu and jantou.
+                let mut rest = *counts;
+                let mut lack = 0;
+                for index in SANGENPAI_INDICES.iter() {
+                    let used = rest[*index].min(3);
+                    rest[*index] -= used;
+                    lack += 3 - used as i32;
+                }
+                lack + shanten::calculate_counts(&rest, 3)
+            }
+            Yakuman::Suuankou => {
+                let koutsu = counts.iter().filter(|count| **count >= 3).count().min(4) as i32;
+                let toitsu = counts.iter().filter(|count| **count == 2).count() as i32;
+                // One toitsu is jantou, others need one more hai to be koutsu, and ukihai
+                // need two more.
+                let lack = if toitsu > 0 {
+                    let upgrade = (toitsu - 1).min(4 - koutsu);
+                    upgrade + 2 * (4 - koutsu - upgrade)
+                } else {
+                    1 + 2 * (4 - koutsu)
+                };
+                lack - 1
+            }
+            Yakuman::Tsuuiisou => {
+                // Every suuhai must be replaced by a jihai.
+                let mut jihai = [0u8; 34];
+                jihai[27..].copy_from_slice(&counts[27..]);
+                let suuhai = counts[..27].iter().map(|count| *count as i32).sum::<i32>();
+                shanten::calculate_counts(&jihai, 0).max(suuhai - 1)
+            }
+        }
+    }
+}
+
+impl YakumanChance {
+    /// Estimate chances of all yakuman with potential for tehai without fuuro, by drawing
+    /// from shuffled haiyama at most `draws` times and discarding to keep the least shanten
+    /// toward each yakuman.
+    pub fn analyze(tehai: &Tehai, haiyama: &Haiyama, draws: usize) -> Vec<YakumanChance> {
+        if tehai.fuuro.len() > 0 {
+            return vec![];
+        }
+        let counts = tehai.to_counts();
+        let mut yama = vec![];
+        for (index, number) in haiyama.to_counts().iter().enumerate() {
+            for _ in 0..*number {
+                yama.push(index);
+            }
+        }
+
+        let mut chance_vec = vec![];
+        for yakuman in Yakuman::all().iter() {
+            if !yakuman.has_potential(&counts) {
+                continue;
+            }
+            let mut counts = counts;
+            if tehai.juntehai.len() % 3 == 2 {
+                discard(yakuman, &mut counts);
+            }
+            let mut rng = Rng::new(0);
+            let mut completed = 0;
+            for _ in 0..ITERATIONS {
+                rng.shuffle(&mut yama);
+                if play(yakuman, &counts, &yama, draws) {
+                    completed += 1;
+                }
+            }
+            chance_vec.push(YakumanChance {
+                yakuman: *yakuman,
+                shanten: yakuman.shanten(&counts),
+                probability: completed as f64 / ITERATIONS as f64,
+            });
+        }
+        chance_vec
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "yakuman": match self.yakuman {
+                Yakuman::Kokushimusou => "kokushimusou",
+                Yakuman::Daisangen => "daisangen",
+                Yakuman::Suuankou => "suuankou",
+                Yakuman::Tsuuiisou => "tsuuiisou",
+            },
+            "shanten_number": self.shanten,
+            "probability": self.probability,
+        })
+    }
+}
+
+/// Play one iteration, return whether the yakuman is completed.
+fn play(yakuman: &Yakuman, counts: &[u8; 34], yama: &[usize], draws: usize) -> bool {
+    let mut counts = *counts;
+    for index in yama.iter().take(draws) {
+        counts[*index] += 1;
+        if yakuman.shanten(&counts) == -1 {
+            return true;
+        }
+        discard(yakuman, &mut counts);
+    }
+    false
+}
+
+/// Discard the first hai keeping the least shanten toward the yakuman.
+fn discard(yakuman: &Yakuman, counts: &mut [u8; 34]) {
+    let mut best = None;
+    for index in 0..34 {
+        if counts[index] == 0 {
+            continue;
+        }
+        counts[index] -= 1;
+        let shanten = yakuman.shanten(counts);
+        counts[index] += 1;
+        if best.map_or(true, |(_, least)| shanten < least) {
+            best = Some((index, shanten));
+        }
+    }
+    if let Some((index, _)) = best {
+        counts[index] -= 1;
+    }
+}
+
+impl std::fmt::Display for Yakuman {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Yakuman::Kokushimusou => "国士無双",
+                Yakuman::Daisangen => "大三元",
+                Yakuman::Suuankou => "四暗刻",
+                Yakuman::Tsuuiisou => "字一色",
+            }
+        )
+    }
+}
+
+impl std::fmt::Display for YakumanChance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "役満 {} 向聴：{} 和了率 {:.1}%",
+            self.yakuman,
+            self.shanten,
+            self.probability * 100.0
+        )
+    }
+}
@@ -0,0 +1,345 @@
+//! Catalog of user-facing messages keyed by message ID, with a table for each locale.
+//!
+//! Placeholders `{}` in a message are replaced by arguments in order, and `{0}`, `{1}` by
+//! arguments at the index, since word order differs between languages, see `format()`. The
+//! locale is global, so errors deep in the game can be localized without passing it around.
+//! Errors that users should never see, such as broken invariants, are not in the catalog and
+//! stay in English.
+
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Language of messages.
+///
+/// English is the default, whose results keep Japanese terms of mahjong as before.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    Japanese,
+    Chinese,
+}
+
+/// ID of a user-facing message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// Name of the locale itself.
+    LocaleName,
+    /// Hai type character, index.
+    UnusedTypeCharacter,
+    /// Character, index.
+    UnknownCharacter,
+    /// Hai.
+    InvalidHai,
+    /// Index.
+    InvalidMeld,
+    /// Index.
+    SecondBracket,
+    /// Bracket, index.
+    MissingType,
+    /// Index.
+    UnmatchedBracket,
+    /// Index.
+    InvalidClaim,
+    /// Index.
+    InvalidAnkan,
+    /// Characters without type.
+    NoTypeAtEnd,
+    /// Hai.
+    FifthHai,
+    /// Number of fuuro, most juntehai.
+    TooManyJuntehai,
+    /// Command.
+    UnresolvedCommand,
+    OneHaiToAdd,
+    /// Operator.
+    OneHaiToDiscard,
+    OneDoraIndicator,
+    /// Hai string.
+    InvalidMentsu,
+    /// Capacity, hai.
+    HaiyamaFull,
+    /// Hai.
+    HaiyamaEmpty,
+    /// Hai.
+    NotEnoughInHaiyama,
+    AlreadyRiichi,
+    NoHistory,
+    UndoAfterRyuukyoku,
+    RyuukyokuNotLackOneHai,
+    /// Jikaze.
+    SelfTenpai,
+    HandOver,
+    NextHandBeforeRyuukyoku,
+    /// Seat.
+    NoSeat,
+    /// Points of a riichi stick, seat, points of the seat.
+    RiichiPoints,
+    NoRiichiStick,
+    SameWinnerLoser,
+    AgariNotLackOneHai,
+    NotTenpai,
+    /// Tehai before, tehai after.
+    CannotDiff,
+    /// Fuuro.
+    FuuroTakenBack,
+    /// Path, error.
+    KifuNotReplayed,
+    /// Hai string.
+    NotSingleHai,
+    /// Hai.
+    NotEnoughToDiscard,
+    NothingToAnnotate,
+    /// Seat.
+    OpponentAlreadyRiichi,
+    TooManyDoraIndicators,
+    NonInteractive,
+    NotInitialized,
+    NotFullHai,
+    RinshanhaiPrompt,
+    KanDoraPrompt,
+    Tehai,
+    Tenpai,
+    Shanten,
+    Agari,
+}
+
+static LOCALE: AtomicU8 = AtomicU8::new(0);
+
+impl Locale {
+    /// Parse a locale from its code like `en`, `ja` and `zh`.
+    pub fn from_string(string: &str) -> Result<Self, String> {
+        match string {
+            "en" | "english" => Ok(Locale::English),
+            "ja" | "japanese" => Ok(Locale::Japanese),
+            "zh" | "chinese" => Ok(Locale::Chinese),
+            _ => Err(format!(
+                "Unknown locale '{}', use 'en', 'ja' or 'zh'.",
+                string
+            )),
+        }
+    }
+}
+
+/// Set the locale of all messages.
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// Return the current locale.
+pub fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::Japanese,
+        2 => Locale::Chinese,
+        _ => Locale::English,
+    }
+}
+
+/// Message in the current locale, with placeholders unreplaced.
+pub fn text(message: Message) -> &'static str {
+    match locale() {
+        Locale::English => english(message),
+        Locale::Japanese => japanese(message),
+        Locale::Chinese => chinese(message),
+    }
+}
+
+/// Message in the current locale, with placeholders replaced by `arguments`. Placeholders
+/// without an argument are left empty.
+pub fn format(message: Message, arguments: &[&dyn Display]) -> String {
+    let mut output = String::new();
+    let mut next_index = 0;
+    let mut rest = text(message);
+    while let Some(start) = rest.find('{') {
+        output += &rest[..start];
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let index = match rest[start + 1..end].parse::<usize>() {
+            Ok(index) => index,
+            Err(_) => {
+                next_index += 1;
+                next_index - 1
+            }
+        };
+        if let Some(argument) = arguments.get(index) {
+            output += &argument.to_string();
+        }
+        rest = &rest[end + 1..];
+    }
+    output + rest
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::LocaleName => "English",
+        Message::UnusedTypeCharacter => "Unused type character '{}' at index {}.",
+        Message::UnknownCharacter => "Unknown character '{}' at index {}.",
+        Message::InvalidHai => "'{}' is invalid hai.",
+        Message::InvalidMeld => "Not a valid meld on '[]' before index {}.",
+        Message::SecondBracket => "Second '[' found at index {}.",
+        Message::MissingType => "Need 'm' 'p' 's' 'z' but find '{}' at index {}.",
+        Message::UnmatchedBracket => "Unmatched ']' found at index {}.",
+        Message::InvalidClaim => "Invalid called hai mark '\'' at index {}.",
+        Message::InvalidAnkan => "Invalid concealed kan '()' at index {}.",
+        Message::NoTypeAtEnd => "No type specified for '{}' at the end of input string.",
+        Message::FifthHai => "Fifth {} found.",
+        Message::TooManyJuntehai => "With {} melds, at most {} hai can be out of melds.",
+        Message::UnresolvedCommand => "Unresolved command: {}.",
+        Message::OneHaiToAdd => "Can only add one hai when use '+' operator.",
+        Message::OneHaiToDiscard => "Can only discard one hai when use '{}' operator.",
+        Message::OneDoraIndicator => "Can only reveal one dora indicator when use '*d' operator.",
+        Message::InvalidMentsu => "'{}' is not a valid mentsu.",
+        Message::HaiyamaFull => "Already {} '{}' in haiyama, cannot add more one.",
+        Message::HaiyamaEmpty => "Already no '{}' in haiyama, cannot discard more one.",
+        Message::NotEnoughInHaiyama => "Not enough '{}' in haiyama to discard.",
+        Message::AlreadyRiichi => "Already declared riichi.",
+        Message::NoHistory => "No more operation history.",
+        Message::UndoAfterRyuukyoku => "Cannot undo after ryuukyoku, which has been settled.",
+        Message::RyuukyokuNotLackOneHai => "Ryuukyoku can only happen when lack one hai.",
+        Message::SelfTenpai => "Tenpai of self ({}) is judged by tehai, only give seats of others.",
+        Message::HandOver => "The hand is over by ryuukyoku, start the next hand first.",
+        Message::NextHandBeforeRyuukyoku => "Next hand can only start after ryuukyoku.",
+        Message::NoSeat => "No seat of {} in game.",
+        Message::RiichiPoints => "Need {} points to declare riichi, but {} has only {}.",
+        Message::NoRiichiStick => "No riichi stick on the table.",
+        Message::SameWinnerLoser => "Winner and loser of ron must be different.",
+        Message::AgariNotLackOneHai => "Can only enumerate agari when lacking one hai.",
+        Message::NotTenpai => "Tehai is not tenpai.",
+        Message::CannotDiff => {
+            "Cannot turn tehai '{}' into '{}' by drawing, discarding and calling."
+        }
+        Message::FuuroTakenBack => "Fuuro '{}' cannot be taken back into tehai.",
+        Message::KifuNotReplayed => "Kifu '{}' is not replayed exactly: {}",
+        Message::NotSingleHai => "'{}' is not a single hai.",
+        Message::NotEnoughToDiscard => "No enough {} to discard.",
+        Message::NothingToAnnotate => "No operation to annotate.",
+        Message::OpponentAlreadyRiichi => "{} has already declared riichi.",
+        Message::TooManyDoraIndicators => "Cannot reveal more than 5 dora indicators.",
+        Message::NonInteractive => "Can not execute interactive command at non-interactive mode.",
+        Message::NotInitialized => "Not initialized.",
+        Message::NotFullHai => "Can only analyze tehai when full with hai.",
+        Message::RinshanhaiPrompt => "Draw rinshanhai by '+', such as '+5s'.",
+        Message::KanDoraPrompt => "Reveal the kan-dora indicator by '*d', such as '*d3p'.",
+        Message::Tehai => "手牌",
+        Message::Tenpai => "聴牌",
+        Message::Shanten => "向聴",
+        Message::Agari => "和了",
+    }
+}
+
+fn japanese(message: Message) -> &'static str {
+    match message {
+        Message::LocaleName => "日本語",
+        Message::UnusedTypeCharacter => "位置{1}の種類文字'{0}'の前に数字がありません。",
+        Message::UnknownCharacter => "位置{1}の'{0}'は認識できない文字です。",
+        Message::InvalidHai => "'{}'は存在しない牌です。",
+        Message::InvalidMeld => "位置{}の前の'[]'は面子ではありません。",
+        Message::SecondBracket => "位置{}に二つ目の'['があります。",
+        Message::MissingType => "位置{1}の'{0}'の前に'm' 'p' 's' 'z'が必要です。",
+        Message::UnmatchedBracket => "位置{}の']'に対応する'['がありません。",
+        Message::InvalidClaim => "位置{}の鳴き牌の印'\''が正しくありません。",
+        Message::InvalidAnkan => "位置{}の暗槓の'()'が正しくありません。",
+        Message::NoTypeAtEnd => "末尾の'{}'に種類がありません。",
+        Message::FifthHai => "五枚目の{}があります。",
+        Message::TooManyJuntehai => "副露が{}つの時、純手牌は{}枚までです。",
+        Message::UnresolvedCommand => "コマンド'{}'を解釈できません。",
+        Message::OneHaiToAdd => "'+'で加えられる牌は一枚だけです。",
+        Message::OneHaiToDiscard => "'{}'で打てる牌は一枚だけです。",
+        Message::OneDoraIndicator => "'*d'でめくれるドラ表示牌は一枚だけです。",
+        Message::InvalidMentsu => "'{}'は面子ではありません。",
+        Message::HaiyamaFull => "牌山に'{1}'が既に{0}枚あり、これ以上加えられません。",
+        Message::HaiyamaEmpty => "牌山に'{}'が残っておらず、これ以上除けません。",
+        Message::NotEnoughInHaiyama => "牌山の'{}'が足りず、除けません。",
+        Message::AlreadyRiichi => "既に立直しています。",
+        Message::NoHistory => "取り消せる操作がありません。",
+        Message::UndoAfterRyuukyoku => "流局は精算済みのため、取り消せません。",
+        Message::RyuukyokuNotLackOneHai => "流局は手牌が一枚少ない時にしかできません。",
+        Message::SelfTenpai => {
+            "自家（{}）の聴牌は手牌で判断されます。他家の席だけを指定してください。"
+        }
+        Message::HandOver => "流局で局が終わりました。先に次局を始めてください。",
+        Message::NextHandBeforeRyuukyoku => "次局は流局の後にしか始められません。",
+        Message::NoSeat => "{}家の席はありません。",
+        Message::RiichiPoints => "立直には{0}点が必要ですが、{1}家は{2}点しかありません。",
+        Message::NoRiichiStick => "供託の立直棒がありません。",
+        Message::SameWinnerLoser => "ロンの和了者と放銃者は別の席でなければなりません。",
+        Message::AgariNotLackOneHai => "和了を列挙できるのは手牌が一枚少ない時だけです。",
+        Message::NotTenpai => "手牌が聴牌していません。",
+        Message::CannotDiff => "手牌'{0}'をツモ、打牌と鳴きで'{1}'にできません。",
+        Message::FuuroTakenBack => "副露'{}'は手牌に戻せません。",
+        Message::KifuNotReplayed => "棋譜'{0}'を正しく再生できません：{1}",
+        Message::NotSingleHai => "'{}'は一枚の牌ではありません。",
+        Message::NotEnoughToDiscard => "打てる{}がありません。",
+        Message::NothingToAnnotate => "注釈を付ける操作がありません。",
+        Message::OpponentAlreadyRiichi => "{}は既に立直しています。",
+        Message::TooManyDoraIndicators => "ドラ表示牌は5枚までしかめくれません。",
+        Message::NonInteractive => "対話モードでしか実行できないコマンドです。",
+        Message::NotInitialized => "手牌が入力されていません。",
+        Message::NotFullHai => "手牌が揃っている時しか分析できません。",
+        Message::RinshanhaiPrompt => "嶺上牌を'+'で入力してください（例：'+5s'）。",
+        Message::KanDoraPrompt => "カンドラ表示牌を'*d'で入力してください（例：'*d3p'）。",
+        Message::Tehai => "手牌",
+        Message::Tenpai => "聴牌",
+        Message::Shanten => "向聴",
+        Message::Agari => "和了",
+    }
+}
+
+fn chinese(message: Message) -> &'static str {
+    match message {
+        Message::LocaleName => "中文",
+        Message::UnusedTypeCharacter => "位置{1}的花色'{0}'前没有数字。",
+        Message::UnknownCharacter => "位置{1}的字符'{0}'无法识别。",
+        Message::InvalidHai => "'{}'不是有效的牌。",
+        Message::InvalidMeld => "位置{}前的'[]'不是有效的面子。",
+        Message::SecondBracket => "位置{}出现了第二个'['。",
+        Message::MissingType => "位置{1}的'{0}'前需要'm' 'p' 's' 'z'。",
+        Message::UnmatchedBracket => "位置{}的']'没有对应的'['。",
+        Message::InvalidClaim => "位置{}的鸣牌标记'\''不正确。",
+        Message::InvalidAnkan => "位置{}的暗杠'()'不正确。",
+        Message::NoTypeAtEnd => "末尾的'{}'没有指定花色。",
+        Message::FifthHai => "出现了第五张{}。",
+        Message::TooManyJuntehai => "副露{}组时，纯手牌最多{}张。",
+        Message::UnresolvedCommand => "无法解析的命令：{}。",
+        Message::OneHaiToAdd => "'+'只能加入一张牌。",
+        Message::OneHaiToDiscard => "'{}'只能打出一张牌。",
+        Message::OneDoraIndicator => "'*d'只能翻开一张宝牌指示牌。",
+        Message::InvalidMentsu => "'{}'不是有效的面子。",
+        Message::HaiyamaFull => "牌山中已有{0}张'{1}'，无法再加入。",
+        Message::HaiyamaEmpty => "牌山中已没有'{}'，无法再移除。",
+        Message::NotEnoughInHaiyama => "牌山中的'{}'不够移除。",
+        Message::AlreadyRiichi => "已经立直了。",
+        Message::NoHistory => "没有可以撤销的操作。",
+        Message::UndoAfterRyuukyoku => "流局已结算，无法撤销。",
+        Message::RyuukyokuNotLackOneHai => "只能在手牌少一张时流局。",
+        Message::SelfTenpai => "自家（{}）是否听牌根据手牌判断，只需给出别家的座位。",
+        Message::HandOver => "本局已流局，请先开始下一局。",
+        Message::NextHandBeforeRyuukyoku => "只能在流局后开始下一局。",
+        Message::NoSeat => "没有{}家的座位。",
+        Message::RiichiPoints => "立直需要{0}点，但{1}家只有{2}点。",
+        Message::NoRiichiStick => "场上没有立直棒。",
+        Message::SameWinnerLoser => "荣和的和牌者与放铳者必须不同。",
+        Message::AgariNotLackOneHai => "只能在手牌少一张时列举和了。",
+        Message::NotTenpai => "手牌没有听牌。",
+        Message::CannotDiff => "无法通过摸牌、打牌和鸣牌将手牌'{0}'变为'{1}'。",
+        Message::FuuroTakenBack => "副露'{}'无法收回手牌。",
+        Message::KifuNotReplayed => "无法准确重放牌谱'{0}'：{1}",
+        Message::NotSingleHai => "'{}'不是一张牌。",
+        Message::NotEnoughToDiscard => "没有可以打出的{}。",
+        Message::NothingToAnnotate => "没有可以添加注释的操作。",
+        Message::OpponentAlreadyRiichi => "{}已经立直了。",
+        Message::TooManyDoraIndicators => "最多只能翻开5张宝牌指示牌。",
+        Message::NonInteractive => "该命令只能在交互模式下执行。",
+        Message::NotInitialized => "尚未输入手牌。",
+        Message::NotFullHai => "只能在手牌满时进行分析。",
+        Message::RinshanhaiPrompt => "请用'+'输入岭上牌，例如'+5s'。",
+        Message::KanDoraPrompt => "请用'*d'输入杠宝牌指示牌，例如'*d3p'。",
+        Message::Tehai => "手牌",
+        Message::Tenpai => "听牌",
+        Message::Shanten => "向听",
+        Message::Agari => "和了",
+    }
+}
@@ -0,0 +1,19 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+#![forbid(unsafe_code)]
+
+//! Analysis of tehai in Japanese mahjong, with the game around it as a feature. This crate
+//! does no I/O, the command line interface and the services are in `jmt-cli`.
+//!
+//! * core: Hai, tehai, shanten and machi, which are all an analyzer needs.
+//! * game: `GameManager` and analysis based on the state of the game, such as defense.
+//! * simd: Kernels on 34-index counts by `std::simd`, see `game::kernel`.
+//! * serde: `Serialize` of hai, tehai, mentsu, conditions and types in them, where hai is
+//!   serialized as a string like `4m`.
+//!
+//! Start with `prelude`, which gathers what typical use needs.
+
+#[cfg(feature = "core")]
+pub mod game;
+pub mod i18n;
+#[cfg(feature = "core")]
+pub mod prelude;
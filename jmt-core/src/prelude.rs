@@ -0,0 +1,18 @@
+//! Types and functions for typical use, so that `use jmt_core::prelude::*;`
+//! is enough to parse and analyze tehai.
+//!
+//! ```no_run
+//! use jmt_core::prelude::*;
+//!
+//! # fn main() -> Result<(), String> {
+//! let tehai = Tehai::new("123m456p78s1155z37z".to_string(), PlayerNumber::Four)?;
+//! let (shanten, conditions) = tehai.analyze(PlayerNumber::Four, None)?;
+//! let report = analyze(&tehai, PlayerNumber::Four)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::game::{shanten, Hai, MachiCondition, Mentsu, PlayerNumber, Tehai};
+
+#[cfg(feature = "game")]
+pub use crate::game::{analyzer::report_tehai as analyze, AnalysisReport, GameManager};
@@ -1,13 +0,0 @@
-mod combination;
-mod hai;
-mod haiyama;
-mod player_number;
-mod tehai;
-
-use super::{GameManager, Kan};
-
-pub use combination::{Mentsu, Taatsu, Toitsu, Ukihai};
-pub use hai::Hai;
-pub use haiyama::Haiyama;
-pub use player_number::PlayerNumber;
-pub use tehai::{MachiCondition, Tehai};
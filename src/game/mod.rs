@@ -1,9 +0,0 @@
-mod game_manager;
-mod mahjong;
-
-pub use game_manager::{
-    GameManager, HaiyamaOperation, Kan, Naku, Operation, State, TehaiOperation,
-};
-pub use mahjong::{
-    Hai, Haiyama, MachiCondition, Mentsu, PlayerNumber, Taatsu, Tehai, Toitsu, Ukihai,
-};
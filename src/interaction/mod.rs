@@ -1,5 +0,0 @@
-mod command;
-mod controller;
-
-use command::Command;
-pub use controller::{Controller, OutputFormat};